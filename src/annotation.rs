@@ -0,0 +1,200 @@
+use crate::font;
+use crate::lines::LineRenderer;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec2, Vec3, vec2, vec3};
+
+// Segments in each rounded end of the background pill
+const PILL_SEGMENTS: usize = 8;
+// Pulls labels slightly towards the camera so they win against the surface
+// they are anchored on when depth testing
+const DEPTH_BIAS: f32 = 1e-4;
+
+// Text label attached to a world-space point. It is drawn at a constant pixel
+// size regardless of distance and always faces the screen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub anchor: Vec3,
+    pub text: String,
+    pub color: Vec3,
+    // On-screen size of one font pixel; glyphs are 7 of these tall
+    pub scale: f32,
+    // Label center relative to the anchor, in pixels with +y up
+    pub offset: Vec2,
+    // Line from the anchor to the label when `offset` is non-zero
+    pub leader: bool,
+    // Rounded box drawn behind the text for contrast against busy geometry
+    pub background: Option<Vec3>,
+    // When true the label is hidden by geometry in front of the anchor;
+    // otherwise it is drawn on top of everything
+    pub depth_test: bool,
+}
+
+impl Annotation {
+    // White text on a dark pill, lifted above the anchor on a leader line
+    pub fn new(anchor: Vec3, text: impl Into<String>) -> Self {
+        Annotation {
+            anchor,
+            text: text.into(),
+            color: vec3(1.0, 1.0, 1.0),
+            scale: 2.0,
+            offset: vec2(0.0, 28.0),
+            leader: true,
+            background: Some(vec3(0.1, 0.1, 0.12)),
+            depth_test: false,
+        }
+    }
+}
+
+// Queues and draws `annotations` over the current frame. Labels whose anchor is
+// behind the camera or outside the depth range are skipped.
+pub(crate) fn render(
+    annotations: &[&Annotation],
+    lines: &mut LineRenderer,
+    view: &Mat4,
+    projection: &Mat4,
+    target: &FrameTarget,
+) {
+    if annotations.is_empty() {
+        return;
+    }
+    let view_projection = projection * view;
+    let size = vec2(target.width.max(1) as f32, target.height.max(1) as f32);
+    let identity = Mat4::identity();
+
+    unsafe {
+        gl::DepthMask(gl::FALSE);
+        gl::DepthFunc(gl::LEQUAL);
+    }
+    for depth_test in [true, false] {
+        for annotation in annotations.iter().filter(|a| a.depth_test == depth_test) {
+            queue(annotation, lines, &view_projection, &size);
+        }
+        unsafe {
+            if depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            } else {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+        }
+        lines.flush(&identity, &identity);
+    }
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+        gl::DepthFunc(gl::LESS);
+        gl::DepthMask(gl::TRUE);
+    }
+}
+
+// Builds the label directly in normalized device coordinates
+fn queue(annotation: &Annotation, lines: &mut LineRenderer, view_projection: &Mat4, size: &Vec2) {
+    let a = annotation.anchor;
+    let clip = view_projection * glm::vec4(a.x, a.y, a.z, 1.0);
+    if clip.w <= 1e-6 {
+        return;
+    }
+    let ndc = clip.xyz() / clip.w;
+    if ndc.z.abs() > 1.0 {
+        return;
+    }
+    let depth = (ndc.z - DEPTH_BIAS).max(-1.0);
+    let at = |p: Vec2| vec3(p.x / size.x * 2.0 - 1.0, p.y / size.y * 2.0 - 1.0, depth);
+
+    let anchor = vec2((ndc.x + 1.0) * 0.5 * size.x, (ndc.y + 1.0) * 0.5 * size.y);
+    let center = anchor + annotation.offset;
+    let scale = annotation.scale.max(0.5);
+    let (text_width, text_height) = font::text_size(&annotation.text);
+    let half = vec2(text_width as f32, text_height as f32) * scale * 0.5;
+
+    if annotation.leader && annotation.offset.norm() > 1.0 {
+        quad_line(&anchor, &center, scale, &annotation.color, lines, &at);
+    }
+
+    if let Some(background) = annotation.background {
+        let padding = 3.0 * scale;
+        pill(
+            &center,
+            &(half + vec2(padding, padding)),
+            &background,
+            lines,
+            &at,
+        );
+    }
+
+    let origin = center + vec2(-half.x, half.y);
+    font::queue_text(
+        &annotation.text,
+        &origin,
+        scale,
+        &annotation.color,
+        lines,
+        &at,
+    );
+}
+
+fn rect(
+    min: &Vec2,
+    max: &Vec2,
+    color: &Vec3,
+    lines: &mut LineRenderer,
+    at: &impl Fn(Vec2) -> Vec3,
+) {
+    let (a, b) = (at(*min), at(vec2(max.x, min.y)));
+    let (c, d) = (at(*max), at(vec2(min.x, max.y)));
+    lines.triangle(&a, &b, &c, color);
+    lines.triangle(&a, &c, &d, color);
+}
+
+// Segment drawn as a quad so its thickness doesn't depend on glLineWidth support
+fn quad_line(
+    from: &Vec2,
+    to: &Vec2,
+    thickness: f32,
+    color: &Vec3,
+    lines: &mut LineRenderer,
+    at: &impl Fn(Vec2) -> Vec3,
+) {
+    let direction = to - from;
+    if direction.norm() < 1e-3 {
+        return;
+    }
+    let normal = vec2(-direction.y, direction.x).normalize() * (thickness * 0.5);
+    let (a, b) = (at(from - normal), at(from + normal));
+    let (c, d) = (at(to + normal), at(to - normal));
+    lines.triangle(&a, &b, &c, color);
+    lines.triangle(&a, &c, &d, color);
+}
+
+// Rectangle with semicircular left and right ends, spanning center +- half
+fn pill(
+    center: &Vec2,
+    half: &Vec2,
+    color: &Vec3,
+    lines: &mut LineRenderer,
+    at: &impl Fn(Vec2) -> Vec3,
+) {
+    let radius = half.y;
+    let inner = (half.x - radius).max(0.0);
+    rect(
+        &(center - vec2(inner, radius)),
+        &(center + vec2(inner, radius)),
+        color,
+        lines,
+        at,
+    );
+    for side in [-1.0f32, 1.0] {
+        let hub = center + vec2(inner * side, 0.0);
+        for i in 0..PILL_SEGMENTS {
+            let angle = |i: usize| {
+                let t = i as f32 / PILL_SEGMENTS as f32;
+                (t - 0.5) * std::f32::consts::PI
+            };
+            let point = |theta: f32| hub + vec2(theta.cos() * side, theta.sin()) * radius;
+            lines.triangle(
+                &at(hub),
+                &at(point(angle(i))),
+                &at(point(angle(i + 1))),
+                color,
+            );
+        }
+    }
+}
@@ -0,0 +1,250 @@
+// Audio-reactive shader input (cargo feature `audio`).
+//
+// Decodes a WAV file up front and, each frame, analyzes the window of samples
+// at the current playback time: overall loudness plus a handful of
+// logarithmically spaced frequency bands. Playback time is the wall clock since
+// the source was set, so pair it with an external player started at the same
+// moment. Live system capture would need a platform audio backend and isn't
+// supported.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Number of frequency bands uploaded as `audioBands[]`
+pub const AUDIO_BANDS: usize = 8;
+
+// Samples per analysis window; must be a power of two for the FFT
+const WINDOW: usize = 1024;
+
+// Lowest band edge in Hz; everything below is mostly rumble
+const MIN_FREQUENCY: f32 = 40.0;
+
+pub enum AudioSource {
+    // PCM (8/16/24/32-bit) or 32-bit float WAV, looped
+    File(PathBuf),
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    Io(std::io::Error),
+    Format(String),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::Io(err) => write!(f, "failed to read audio file: {err}"),
+            AudioError::Format(msg) => write!(f, "unsupported audio file: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+impl From<std::io::Error> for AudioError {
+    fn from(err: std::io::Error) -> Self {
+        AudioError::Io(err)
+    }
+}
+
+// Values uploaded to shaders each frame, all roughly in [0, 1]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AudioLevels {
+    pub bands: [f32; AUDIO_BANDS],
+    pub loudness: f32,
+}
+
+pub(crate) struct AudioAnalyzer {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    levels: AudioLevels,
+}
+
+impl AudioAnalyzer {
+    pub(crate) fn new(source: AudioSource) -> Result<Self, AudioError> {
+        match source {
+            AudioSource::File(path) => {
+                let (samples, sample_rate) = read_wav(&path)?;
+                if samples.len() < WINDOW {
+                    return Err(AudioError::Format(format!(
+                        "{}: too short to analyze",
+                        path.display()
+                    )));
+                }
+                Ok(AudioAnalyzer {
+                    samples,
+                    sample_rate,
+                    levels: AudioLevels::default(),
+                })
+            }
+        }
+    }
+
+    pub(crate) fn levels(&self) -> AudioLevels {
+        self.levels
+    }
+
+    // Re-analyzes the window at `time` seconds into the (looping) track
+    pub(crate) fn update(&mut self, time: f32, delta_time: f32) {
+        let start = (time as f64 * self.sample_rate as f64) as usize % self.samples.len();
+        let window: Vec<f32> = (0..WINDOW)
+            .map(|i| self.samples[(start + i) % self.samples.len()])
+            .collect();
+
+        let rms = (window.iter().map(|s| s * s).sum::<f32>() / WINDOW as f32).sqrt();
+
+        // Hann window to reduce spectral leakage, then magnitude spectrum
+        let mut re: Vec<f32> = window
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let w = 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / WINDOW as f32).cos();
+                s * w
+            })
+            .collect();
+        let mut im = vec![0.0; WINDOW];
+        fft(&mut re, &mut im);
+        let magnitudes: Vec<f32> = (0..WINDOW / 2)
+            .map(|i| (re[i] * re[i] + im[i] * im[i]).sqrt() / (WINDOW as f32 / 4.0))
+            .collect();
+
+        // Log-spaced band edges from MIN_FREQUENCY up to Nyquist
+        let nyquist = self.sample_rate as f32 / 2.0;
+        let bin_hz = nyquist / (WINDOW / 2) as f32;
+        let ratio = (nyquist / MIN_FREQUENCY).powf(1.0 / AUDIO_BANDS as f32);
+        let mut target = [0.0; AUDIO_BANDS];
+        for (band, value) in target.iter_mut().enumerate() {
+            let lo = MIN_FREQUENCY * ratio.powi(band as i32);
+            let hi = lo * ratio;
+            let lo_bin = ((lo / bin_hz) as usize).clamp(1, WINDOW / 2 - 1);
+            let hi_bin = ((hi / bin_hz) as usize).clamp(lo_bin + 1, WINDOW / 2);
+            let peak = magnitudes[lo_bin..hi_bin]
+                .iter()
+                .copied()
+                .fold(0.0f32, f32::max);
+            *value = peak.sqrt().min(1.0);
+        }
+
+        // Fast attack, slow release so visuals pulse without flickering
+        let smooth = |current: f32, target: f32| {
+            let rate = if target > current { 30.0 } else { 4.0 };
+            current + (target - current) * (rate * delta_time).min(1.0)
+        };
+        for (band, value) in self.levels.bands.iter_mut().enumerate() {
+            *value = smooth(*value, target[band]);
+        }
+        self.levels.loudness = smooth(self.levels.loudness, (rms * 2.0).min(1.0));
+    }
+}
+
+// In-place iterative radix-2 Cooley-Tukey FFT; length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let bits = n.trailing_zeros();
+
+    for i in 0..n {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -std::f32::consts::TAU / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let tr = re[b] * cos - im[b] * sin;
+                let ti = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - tr;
+                im[b] = im[a] - ti;
+                re[a] += tr;
+                im[a] += ti;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+// Decodes a WAV file into mono f32 samples in [-1, 1]
+fn read_wav(path: &Path) -> Result<(Vec<f32>, u32), AudioError> {
+    let bytes = fs::read(path)?;
+    let bad = |msg: &str| AudioError::Format(format!("{}: {}", path.display(), msg));
+
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err(bad("not a RIFF/WAVE file"));
+    }
+
+    let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+    let u32_at =
+        |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+    let mut format = None;
+    let mut data = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32_at(offset + 4) as usize;
+        let body = offset + 8;
+        let end = (body + size).min(bytes.len());
+        match id {
+            b"fmt " if size >= 16 && body + 16 <= bytes.len() => {
+                format = Some((
+                    u16_at(body),
+                    u16_at(body + 2),
+                    u32_at(body + 4),
+                    u16_at(body + 14),
+                ));
+            }
+            b"data" => data = Some(&bytes[body..end]),
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        offset = body + size + (size & 1);
+    }
+
+    let (tag, channels, sample_rate, bits) = format.ok_or_else(|| bad("missing fmt chunk"))?;
+    let data = data.ok_or_else(|| bad("missing data chunk"))?;
+    if channels == 0 || sample_rate == 0 {
+        return Err(bad("invalid channel count or sample rate"));
+    }
+
+    // WAVE_FORMAT_EXTENSIBLE (0xFFFE) is treated as integer PCM, which covers
+    // what common tools write for 16/24-bit output
+    let float = match tag {
+        1 | 0xFFFE => false,
+        3 if bits == 32 => true,
+        _ => return Err(bad("only PCM and 32-bit float WAV are supported")),
+    };
+
+    let bytes_per_sample = (bits as usize).div_ceil(8);
+    if !(1..=4).contains(&bytes_per_sample) {
+        return Err(bad("unsupported bit depth"));
+    }
+    let frame = bytes_per_sample * channels as usize;
+
+    let decode = |s: &[u8]| -> f32 {
+        match (float, bytes_per_sample) {
+            (true, 4) => f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+            (_, 1) => (s[0] as f32 - 128.0) / 128.0,
+            (_, 2) => i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0,
+            (_, 3) => (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8_388_608.0,
+            _ => i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2_147_483_648.0,
+        }
+    };
+
+    let samples = data
+        .chunks_exact(frame)
+        .map(|f| {
+            let sum: f32 = f.chunks_exact(bytes_per_sample).map(decode).sum();
+            sum / channels as f32
+        })
+        .collect();
+
+    Ok((samples, sample_rate))
+}
@@ -0,0 +1,161 @@
+use crate::render_target::{FrameTarget, RenderTarget};
+use crate::shader::{ShaderError, build_program};
+
+// Glow around bright pixels. The scene is drawn into a floating-point target
+// so emissive surfaces can go above 1.0. Pixels past `threshold` are then
+// blurred at half resolution and added back over the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bloom {
+    // Brightness (max of r, g, b) where pixels start to glow; values below 1.0
+    // make ordinary lit surfaces bloom too
+    pub threshold: f32,
+    // Strength of the glow added back to the scene
+    pub intensity: f32,
+    // Horizontal + vertical blur passes; each one widens the glow
+    pub radius: u32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom {
+            threshold: 1.0,
+            intensity: 0.8,
+            radius: 4,
+        }
+    }
+}
+
+pub(crate) struct BloomPass {
+    bright_program: u32,
+    blur_program: u32,
+    composite_program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+    // Full-resolution HDR scene and the half-resolution blur ping-pong pair
+    targets: Option<(RenderTarget, [RenderTarget; 2])>,
+}
+
+impl BloomPass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = |fragment: &str| unsafe {
+            build_program(include_str!("shaders/fullscreen_vertex.glsl"), fragment)
+        };
+        let bright_program = program(include_str!("shaders/bloom_bright_fragment.glsl"))?;
+        let blur_program = program(include_str!("shaders/blur_fragment.glsl"))
+            .inspect_err(|_| unsafe { gl::DeleteProgram(bright_program) })?;
+        let composite_program = program(include_str!("shaders/bloom_composite_fragment.glsl"))
+            .inspect_err(|_| unsafe {
+                gl::DeleteProgram(bright_program);
+                gl::DeleteProgram(blur_program);
+            })?;
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+        Ok(BloomPass {
+            bright_program,
+            blur_program,
+            composite_program,
+            empty_vao,
+            targets: None,
+        })
+    }
+
+    // HDR target the size of `output` to draw the scene into, reallocated when
+    // that changes. None if it can't be created.
+    pub(crate) fn begin(&mut self, output: &FrameTarget) -> Option<FrameTarget> {
+        let (width, height) = (output.width.max(1), output.height.max(1));
+        let stale = self.targets.as_ref().is_none_or(|(scene, _)| {
+            (scene.frame_target().width, scene.frame_target().height) != (width, height)
+        });
+        if stale {
+            self.targets = None;
+            let (half_width, half_height) = ((width / 2).max(1), (height / 2).max(1));
+            let targets = RenderTarget::hdr(width, height).and_then(|scene| {
+                let ping = RenderTarget::hdr(half_width, half_height)?;
+                let pong = RenderTarget::hdr(half_width, half_height)?;
+                Ok((scene, [ping, pong]))
+            });
+            match targets {
+                Ok(targets) => self.targets = Some(targets),
+                Err(err) => {
+                    eprintln!("Bloom disabled: {}", err);
+                    return None;
+                }
+            }
+        }
+        self.targets.as_ref().map(|(scene, _)| scene.frame_target())
+    }
+
+    pub(crate) fn scene_texture(&self) -> Option<u32> {
+        self.targets
+            .as_ref()
+            .map(|(scene, _)| scene.color_texture())
+    }
+
+    // Extracts and blurs the bright pixels, then writes scene + glow to `output`
+    pub(crate) fn composite(&self, params: &Bloom, output: &FrameTarget) {
+        let Some((scene, blur)) = &self.targets else {
+            return;
+        };
+        let half = blur[0].frame_target();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::BindVertexArray(self.empty_vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            half.bind();
+            gl::UseProgram(self.bright_program);
+            gl::BindTexture(gl::TEXTURE_2D, scene.color_texture());
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.bright_program, name.as_ptr());
+            gl::Uniform1i(location(c"scene"), 0);
+            gl::Uniform1f(location(c"threshold"), params.threshold.max(0.0));
+            gl::Uniform1f(location(c"knee"), params.threshold.max(0.0) * 0.25);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+
+            gl::UseProgram(self.blur_program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.blur_program, name.as_ptr());
+            gl::Uniform1i(location(c"image"), 0);
+            let texel = (1.0 / half.width as f32, 1.0 / half.height as f32);
+            for _ in 0..params.radius.max(1) {
+                // Horizontal into the second target, vertical back into the first
+                for (from, to, direction) in [(0, 1, (texel.0, 0.0)), (1, 0, (0.0, texel.1))] {
+                    blur[to].frame_target().bind();
+                    gl::BindTexture(gl::TEXTURE_2D, blur[from].color_texture());
+                    gl::Uniform2f(location(c"direction"), direction.0, direction.1);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 3);
+                }
+            }
+
+            output.bind();
+            gl::UseProgram(self.composite_program);
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.composite_program, name.as_ptr())
+            };
+            gl::BindTexture(gl::TEXTURE_2D, scene.color_texture());
+            gl::Uniform1i(location(c"scene"), 0);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, blur[0].color_texture());
+            gl::Uniform1i(location(c"bloom"), 1);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Uniform1f(location(c"intensity"), params.intensity.max(0.0));
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for BloomPass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.bright_program);
+            gl::DeleteProgram(self.blur_program);
+            gl::DeleteProgram(self.composite_program);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use glm::{Mat4, Vec3, Vec4, vec3};
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Aabb { min, max }
+    }
+
+    // Smallest box containing every point; None for an empty iterator
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Aabb::new(first, first), |aabb, p| Aabb {
+            min: glm::min2(&aabb.min, &p),
+            max: glm::max2(&aabb.max, &p),
+        }))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn extents(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            vec3(a.x, a.y, a.z),
+            vec3(b.x, a.y, a.z),
+            vec3(b.x, b.y, a.z),
+            vec3(a.x, b.y, a.z),
+            vec3(a.x, a.y, b.z),
+            vec3(b.x, a.y, b.z),
+            vec3(b.x, b.y, b.z),
+            vec3(a.x, b.y, b.z),
+        ]
+    }
+
+    // World-space box enclosing this box after `transform` (re-fitted, so it
+    // grows under rotation rather than rotating with the object)
+    pub fn transformed(&self, transform: &Mat4) -> Aabb {
+        let corners = self
+            .corners()
+            .map(|c| glm::vec4_to_vec3(&(transform * glm::vec4(c.x, c.y, c.z, 1.0))));
+        Aabb::from_points(corners).unwrap()
+    }
+
+    // Distance along the ray `origin + t * direction` (t >= 0) where it enters
+    // the box, 0 if it starts inside; None if it misses. `direction` needn't
+    // be normalized, t is in its units.
+    pub fn ray_hit(&self, origin: &Vec3, direction: &Vec3) -> Option<f32> {
+        let (mut near, mut far) = (0.0f32, f32::INFINITY);
+        for axis in 0..3 {
+            let (o, d) = (origin[axis], direction[axis]);
+            if d.abs() < f32::EPSILON {
+                // Parallel to this pair of faces: inside the slab or never
+                if o < self.min[axis] || o > self.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let t0 = (self.min[axis] - o) / d;
+            let t1 = (self.max[axis] - o) / d;
+            near = near.max(t0.min(t1));
+            far = far.min(t0.max(t1));
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+
+    // The 12 edges as pairs of corner indices, for wireframe drawing
+    pub(crate) const EDGES: [(usize, usize); 12] = [
+        (0, 1),
+        (1, 2),
+        (2, 3),
+        (3, 0),
+        (4, 5),
+        (5, 6),
+        (6, 7),
+        (7, 4),
+        (0, 4),
+        (1, 5),
+        (2, 6),
+        (3, 7),
+    ];
+}
+
+// View frustum as six inward-facing planes (ax + by + cz + d >= 0 is inside)
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    // Gribb-Hartmann plane extraction from a combined projection * view matrix
+    pub fn from_matrix(view_projection: &Mat4) -> Self {
+        let m = view_projection;
+        let row = |i: usize| glm::vec4(m[(i, 0)], m[(i, 1)], m[(i, 2)], m[(i, 3)]);
+        let (r0, r1, r2, r3) = (row(0), row(1), row(2), row(3));
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|p| {
+            let len = glm::vec3(p.x, p.y, p.z).norm();
+            if len > 0.0 { p / len } else { p }
+        });
+        Frustum { planes }
+    }
+
+    // Conservative test: false only when the box is entirely outside one plane
+    pub fn intersects(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner furthest along the plane normal
+            let p = vec3(
+                if plane.x >= 0.0 {
+                    aabb.max.x
+                } else {
+                    aabb.min.x
+                },
+                if plane.y >= 0.0 {
+                    aabb.max.y
+                } else {
+                    aabb.min.y
+                },
+                if plane.z >= 0.0 {
+                    aabb.max.z
+                } else {
+                    aabb.min.z
+                },
+            );
+            plane.x * p.x + plane.y * p.y + plane.z * p.z + plane.w >= 0.0
+        })
+    }
+}
@@ -0,0 +1,705 @@
+use crate::bounds::Aabb;
+use crate::random::gradient_noise;
+use glm::{Mat4, Vec3, vec3};
+
+// Default vertical field of view of the perspective projection
+const FOV_Y: f32 = std::f32::consts::FRAC_PI_4;
+// Default clip plane distances
+const NEAR: f32 = 0.1;
+const FAR: f32 = 100.0;
+// Default seconds taken by animated view changes
+const TRANSITION_DURATION: f32 = 0.35;
+// Default radians of orbit or look rotation per pixel of mouse drag
+const MOUSE_SENSITIVITY: f32 = 0.005;
+// First-person look stops short of straight up/down so the view can't flip
+const MAX_PITCH: f32 = 89.0 * std::f32::consts::PI / 180.0;
+// Default first-person speed in world units per second
+const MOVE_SPEED: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    // Parallel projection sized to match the perspective view at the target
+    Orthographic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    // Dragging orbits the eye around the target; scrolling zooms
+    #[default]
+    Orbit,
+    // Dragging turns the view in place, W/A/S/D walk along it and the target
+    // moves along in front; scrolling changes the walking speed
+    FirstPerson,
+}
+
+// Canonical viewing directions; the name is the side of the scene seen, so
+// Front looks down -Z from the +Z side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewPreset {
+    Front,
+    Back,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    // From the (1, 1, 1) diagonal, like the default view
+    Isometric,
+}
+
+impl ViewPreset {
+    // Unit offset of the eye from the target and the up vector
+    fn direction(self) -> (Vec3, Vec3) {
+        let y_up = vec3(0.0, 1.0, 0.0);
+        match self {
+            ViewPreset::Front => (vec3(0.0, 0.0, 1.0), y_up),
+            ViewPreset::Back => (vec3(0.0, 0.0, -1.0), y_up),
+            // Plan views keep -Z pointing up the screen, as axis views do
+            ViewPreset::Top => (vec3(0.0, 1.0, 0.0), vec3(0.0, 0.0, -1.0)),
+            ViewPreset::Bottom => (vec3(0.0, -1.0, 0.0), vec3(0.0, 0.0, 1.0)),
+            ViewPreset::Left => (vec3(-1.0, 0.0, 0.0), y_up),
+            ViewPreset::Right => (vec3(1.0, 0.0, 0.0), y_up),
+            ViewPreset::Isometric => (vec3(1.0, 1.0, 1.0).normalize(), y_up),
+        }
+    }
+}
+
+// Procedural handheld-style shake layered on top of the view, e.g. for
+// recorded turntables and flythroughs. Smooth noise moves the eye up to
+// `amplitude` world units and turns the view up to `rotation` radians along
+// the camera's own axes, changing direction about `frequency` times a second.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShakeParams {
+    pub amplitude: f32,
+    pub rotation: f32,
+    pub frequency: f32,
+    // Different seeds give cameras different, uncorrelated shakes
+    pub seed: u32,
+}
+
+impl Default for ShakeParams {
+    fn default() -> Self {
+        ShakeParams {
+            amplitude: 0.02,
+            rotation: 0.01,
+            frequency: 1.5,
+            seed: 1,
+        }
+    }
+}
+
+impl ShakeParams {
+    // View-space (translation, rotation angles) at `time` seconds
+    fn offset(&self, time: f32) -> (Vec3, Vec3) {
+        let t = time * self.frequency;
+        // Two octaves: a slow sway plus finer jitter
+        let noise = |channel: u32| {
+            let seed = self.seed.wrapping_mul(8).wrapping_add(channel);
+            gradient_noise(t, seed) * 0.7 + gradient_noise(t * 2.3, seed ^ 0xABCD) * 0.3
+        };
+        (
+            vec3(noise(0), noise(1), noise(2)) * self.amplitude,
+            vec3(noise(3), noise(4), noise(5)) * self.rotation,
+        )
+    }
+}
+
+// Viewpoint the camera returns to when reset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HomeView {
+    pub position: Vec3,
+    pub target: Vec3,
+}
+
+impl HomeView {
+    // Looks at the center of `bounds` from the (1, 1, 1) diagonal, just far
+    // enough back for its bounding sphere to fill the vertical field of view
+    pub fn framing(bounds: &Aabb) -> Self {
+        let radius = (bounds.extents().norm() * 0.5).max(1e-3);
+        let distance = radius / (FOV_Y * 0.5).sin();
+        let target = bounds.center();
+        HomeView {
+            position: target + vec3(1.0, 1.0, 1.0).normalize() * distance,
+            target,
+        }
+    }
+}
+
+impl Default for HomeView {
+    fn default() -> Self {
+        HomeView {
+            position: vec3(2.0, 2.0, 2.0),
+            target: vec3(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// Orbit state that view toggles save and restore
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CameraPose {
+    position: Vec3,
+    target: Vec3,
+    up: Vec3,
+    zoom: f32,
+    // 0 = perspective, 1 = orthographic
+    ortho_blend: f32,
+}
+
+// Animates between two poses, easing out
+#[derive(Debug, Clone, Copy)]
+struct Transition {
+    from: CameraPose,
+    to: CameraPose,
+    elapsed: f32,
+}
+
+pub struct Camera {
+    pub(crate) position: Vec3,
+    pub(crate) target: Vec3,
+    pub(crate) up: Vec3,
+    pub(crate) zoom: f32,
+    pub(crate) last_mouse_pos: (f64, f64),
+    pub(crate) is_rotating: bool,
+    mode: CameraMode,
+    // First-person speed in world units per second
+    move_speed: f32,
+    // Radians of rotation per pixel of mouse drag, for yaw and pitch alike
+    mouse_sensitivity: f32,
+    // Dragging down turns the view up instead of down
+    invert_y: bool,
+    ortho_blend: f32,
+    // Vertical field of view in radians
+    fov_y: f32,
+    // Clip plane distances, 0 < near < far
+    near: f32,
+    far: f32,
+    transition: Option<Transition>,
+    // Seconds animated view changes take; 0 snaps
+    transition_duration: f32,
+    // Perspective pose to return to while an axis view is shown
+    saved_pose: Option<CameraPose>,
+    home: HomeView,
+    shake: Option<ShakeParams>,
+    // Seconds of shake played so far
+    shake_time: f32,
+    // View-projection of the last presented frame, for motion blur
+    pub(crate) previous_view_projection: Option<Mat4>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        let home = HomeView::default();
+        Camera {
+            position: home.position,
+            target: home.target,
+            up: vec3(0.0, 1.0, 0.0),
+            zoom: 1.0,
+            last_mouse_pos: (0.0, 0.0),
+            is_rotating: false,
+            mode: CameraMode::Orbit,
+            move_speed: MOVE_SPEED,
+            mouse_sensitivity: MOUSE_SENSITIVITY,
+            invert_y: false,
+            ortho_blend: 0.0,
+            fov_y: FOV_Y,
+            near: NEAR,
+            far: FAR,
+            transition: None,
+            transition_duration: TRANSITION_DURATION,
+            saved_pose: None,
+            home,
+            shake: None,
+            shake_time: 0.0,
+            previous_view_projection: None,
+        }
+    }
+
+    // Perspective camera at `position` orbiting `target`, which is also its home
+    pub fn looking_at(position: Vec3, target: Vec3) -> Self {
+        Camera {
+            position,
+            target,
+            home: HomeView { position, target },
+            ..Camera::new()
+        }
+    }
+
+    pub fn home(&self) -> HomeView {
+        self.home
+    }
+
+    // Changes where go_home returns to without moving the camera
+    pub fn set_home(&mut self, home: HomeView) {
+        self.home = home;
+    }
+
+    // Animates back to the home view in perspective at the default zoom
+    pub fn go_home(&mut self) {
+        self.saved_pose = None;
+        self.transition = Some(Transition {
+            from: self.pose(),
+            to: CameraPose {
+                position: self.home.position,
+                target: self.home.target,
+                up: vec3(0.0, 1.0, 0.0),
+                zoom: 1.0,
+                ortho_blend: 0.0,
+            },
+            elapsed: 0.0,
+        });
+    }
+
+    pub fn get_view_matrix(&self) -> Mat4 {
+        let view = glm::look_at(&self.eye(), &self.target, &self.up);
+        let Some(shake) = &self.shake else {
+            return view;
+        };
+        let (offset, angles) = shake.offset(self.shake_time);
+        let rotation = glm::rotation(angles.x, &Vec3::x())
+            * glm::rotation(angles.y, &Vec3::y())
+            * glm::rotation(angles.z, &Vec3::z());
+        rotation * glm::translation(&-offset) * view
+    }
+
+    // None turns the shake off, leaving the exact orbit view for stills
+    pub fn set_shake(&mut self, shake: Option<ShakeParams>) {
+        self.shake = shake;
+    }
+
+    pub fn shake(&self) -> Option<ShakeParams> {
+        self.shake
+    }
+
+    pub fn fov_y(&self) -> f32 {
+        self.fov_y
+    }
+
+    // Vertical field of view in radians, clamped to 1-170 degrees. Orthographic
+    // views size themselves from it too, so they keep matching the perspective.
+    pub fn set_fov_y(&mut self, fov_y: f32) {
+        self.fov_y = fov_y.clamp(1f32.to_radians(), 170f32.to_radians());
+    }
+
+    // (near, far) clip plane distances
+    pub fn clip_planes(&self) -> (f32, f32) {
+        (self.near, self.far)
+    }
+
+    // Depth precision is spread between the planes, favouring the near one,
+    // so raising near does much more against z-fighting than lowering far.
+    // Fails unless 0 < near < far, leaving the planes unchanged.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), String> {
+        if !(near > 0.0 && far > near && far.is_finite()) {
+            return Err(format!(
+                "invalid clip planes: need 0 < near < far, got near {near}, far {far}"
+            ));
+        }
+        self.near = near;
+        self.far = far;
+        Ok(())
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    // Switching to first person keeps the current eye, folding the zoom into
+    // the position and levelling the up vector; switching back orbits around
+    // the point that was in front of the camera.
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == CameraMode::FirstPerson && self.mode != mode {
+            self.transition = None;
+            self.position = self.eye();
+            self.zoom = 1.0;
+            self.up = vec3(0.0, 1.0, 0.0);
+            // Re-aim so the pitch starts inside the first-person limits
+            let distance = glm::distance(&self.position, &self.target);
+            let (yaw, pitch) = self.look_angles();
+            self.target = self.position + look_direction(yaw, pitch) * distance.max(1e-3);
+        }
+        self.mode = mode;
+    }
+
+    pub fn move_speed(&self) -> f32 {
+        self.move_speed
+    }
+
+    pub fn set_move_speed(&mut self, speed: f32) {
+        self.move_speed = speed.max(0.0);
+    }
+
+    pub fn mouse_sensitivity(&self) -> f32 {
+        self.mouse_sensitivity
+    }
+
+    // Radians of orbit or look rotation per pixel dragged (default 0.005);
+    // negative values are clamped to 0
+    pub fn set_mouse_sensitivity(&mut self, sensitivity: f32) {
+        self.mouse_sensitivity = sensitivity.max(0.0);
+    }
+
+    pub fn invert_y(&self) -> bool {
+        self.invert_y
+    }
+
+    // Flips the vertical drag direction only; horizontal turning is unchanged
+    pub fn set_invert_y(&mut self, invert: bool) {
+        self.invert_y = invert;
+    }
+
+    pub fn transition_duration(&self) -> f32 {
+        self.transition_duration
+    }
+
+    // How long go_home, view presets, axis and projection toggles and move_to
+    // take to ease into place; 0 makes them snap
+    pub fn set_transition_duration(&mut self, seconds: f32) {
+        self.transition_duration = seconds.max(0.0);
+    }
+
+    // Eases the eye and target to new positions, keeping the zoom, up vector
+    // and projection
+    pub fn move_to(&mut self, position: Vec3, target: Vec3) {
+        self.saved_pose = None;
+        let current = self.pose();
+        let start = self.transition.map_or(current, |t| t.to);
+        self.transition = Some(Transition {
+            from: current,
+            to: CameraPose {
+                position,
+                target,
+                ..start
+            },
+            elapsed: 0.0,
+        });
+    }
+
+    // Moves straight to a pose, cancelling any transition or axis view, e.g.
+    // to follow a camera path
+    pub(crate) fn set_pose(&mut self, position: Vec3, target: Vec3, up: Vec3) {
+        self.transition = None;
+        self.saved_pose = None;
+        self.apply_pose(&CameraPose {
+            position,
+            target,
+            up,
+            zoom: 1.0,
+            ortho_blend: 0.0,
+        });
+    }
+
+    // Zoom scales the distance from the target
+    pub fn eye(&self) -> Vec3 {
+        self.target + (self.position - self.target) * self.zoom
+    }
+
+    // Mid-transition this reports the projection being animated towards
+    pub fn projection(&self) -> Projection {
+        let blend = self
+            .transition
+            .map_or(self.ortho_blend, |t| t.to.ortho_blend);
+        if blend >= 0.5 {
+            Projection::Orthographic
+        } else {
+            Projection::Perspective
+        }
+    }
+
+    // While switching projections the two matrices are blended, which reads as
+    // a dolly-zoom rather than a pop
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        let perspective = glm::perspective(aspect, self.fov_y, self.near, self.far);
+        if self.ortho_blend <= 0.0 {
+            return perspective;
+        }
+        let half_height = glm::distance(&self.eye(), &self.target) * (self.fov_y * 0.5).tan();
+        let half_width = half_height * aspect;
+        let ortho = glm::ortho(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            self.near,
+            self.far,
+        );
+        if self.ortho_blend >= 1.0 {
+            return ortho;
+        }
+        perspective * (1.0 - self.ortho_blend) + ortho * self.ortho_blend
+    }
+
+    // Switches projection without moving the view, animated like an axis view
+    // toggle. The orthographic view is sized to match the perspective one at
+    // the target, so zooming scales its bounds.
+    pub fn set_projection(&mut self, projection: Projection) {
+        let current = self.pose();
+        let mut destination = self.transition.map_or(current, |t| t.to);
+        destination.ortho_blend = match projection {
+            Projection::Perspective => 0.0,
+            Projection::Orthographic => 1.0,
+        };
+        self.transition = Some(Transition {
+            from: current,
+            to: destination,
+            elapsed: 0.0,
+        });
+    }
+
+    // World-space ray through a point in normalized device coordinates
+    // (-1..1, +y up) as (origin, unit direction). The origin is on the near
+    // plane, so orthographic views give parallel rays from across the screen.
+    pub fn ray(&self, ndc_x: f32, ndc_y: f32, aspect: f32) -> (Vec3, Vec3) {
+        let inverse = (self.projection_matrix(aspect) * self.get_view_matrix())
+            .try_inverse()
+            .unwrap_or_else(Mat4::identity);
+        let unproject = |z: f32| {
+            let p = inverse * glm::vec4(ndc_x, ndc_y, z, 1.0);
+            p.xyz() / p.w
+        };
+        let near = unproject(-1.0);
+        let far = unproject(1.0);
+        let direction = far - near;
+        if direction.norm() > 0.0 {
+            (near, direction.normalize())
+        } else {
+            (near, (self.target - self.eye()).normalize())
+        }
+    }
+
+    // From free perspective, animates to the orthographic front/side/top view
+    // closest to the current viewing direction and remembers the pose; from an
+    // axis view, animates back to that pose.
+    pub fn toggle_axis_view(&mut self) {
+        let current = self.pose();
+        let destination = match self.saved_pose.take() {
+            Some(saved) => saved,
+            None => {
+                // Don't save a half-finished transition as the pose to return to
+                let start = self.transition.map_or(current, |t| t.to);
+                self.saved_pose = Some(start);
+                self.nearest_axis_pose(&start)
+            }
+        };
+        self.transition = Some(Transition {
+            from: current,
+            to: destination,
+            elapsed: 0.0,
+        });
+    }
+
+    // Animates around the current target to a canonical view at the same
+    // orbit distance, keeping the zoom and projection. Leaves any axis view.
+    pub fn set_view_preset(&mut self, preset: ViewPreset) {
+        self.saved_pose = None;
+        let current = self.pose();
+        let start = self.transition.map_or(current, |t| t.to);
+        let (direction, up) = preset.direction();
+        let distance = glm::distance(&start.position, &start.target).max(1e-3);
+        self.transition = Some(Transition {
+            from: current,
+            to: CameraPose {
+                position: start.target + direction * distance,
+                up,
+                ..start
+            },
+            elapsed: 0.0,
+        });
+    }
+
+    // Advances the shake and any running view transition; called once per
+    // frame by the engine. Without a transition the pose is left alone, so
+    // mouse orbiting stays immediate.
+    pub fn update(&mut self, delta_time: f32) {
+        if self.shake.is_some() {
+            self.shake_time += delta_time;
+        }
+        let Some(transition) = &mut self.transition else {
+            return;
+        };
+        transition.elapsed += delta_time;
+        let t = if self.transition_duration > 0.0 {
+            (transition.elapsed / self.transition_duration).min(1.0)
+        } else {
+            1.0
+        };
+        let (from, to) = (transition.from, transition.to);
+        if t >= 1.0 {
+            self.transition = None;
+        }
+        // Cubic ease-out: starts moving at once and settles gently
+        let eased = 1.0 - (1.0 - t).powi(3);
+        self.apply_pose(&interpolate(&from, &to, eased));
+    }
+
+    fn pose(&self) -> CameraPose {
+        CameraPose {
+            position: self.position,
+            target: self.target,
+            up: self.up,
+            zoom: self.zoom,
+            ortho_blend: self.ortho_blend,
+        }
+    }
+
+    fn apply_pose(&mut self, pose: &CameraPose) {
+        self.position = pose.position;
+        self.target = pose.target;
+        self.up = pose.up;
+        self.zoom = pose.zoom;
+        self.ortho_blend = pose.ortho_blend;
+    }
+
+    fn nearest_axis_pose(&self, pose: &CameraPose) -> CameraPose {
+        let offset = pose.position - pose.target;
+        let abs = offset.abs();
+        let (axis, up) = if abs.y >= abs.x && abs.y >= abs.z {
+            // Top/bottom: keep -Z pointing up the screen like a plan view
+            let sign = offset.y.signum();
+            (vec3(0.0, sign, 0.0), vec3(0.0, 0.0, -sign))
+        } else if abs.x >= abs.z {
+            (vec3(offset.x.signum(), 0.0, 0.0), vec3(0.0, 1.0, 0.0))
+        } else {
+            (vec3(0.0, 0.0, offset.z.signum()), vec3(0.0, 1.0, 0.0))
+        };
+        CameraPose {
+            position: pose.target + axis * offset.norm(),
+            target: pose.target,
+            up,
+            zoom: pose.zoom,
+            ortho_blend: 1.0,
+        }
+    }
+
+    // First-person step: `forward` and `right` are -1, 0 or 1 from the held
+    // keys, moving along the view direction and its horizontal right vector
+    pub(crate) fn walk(&mut self, forward: f32, right: f32, delta_time: f32) {
+        if self.mode != CameraMode::FirstPerson || self.transition.is_some() {
+            return;
+        }
+        let direction = (self.target - self.position).normalize();
+        let side = glm::cross(&direction, &self.up);
+        let step = direction * forward + side.normalize() * right;
+        if step.norm() < 1e-6 || side.norm() < 1e-6 {
+            return;
+        }
+        let offset = step.normalize() * self.move_speed * delta_time;
+        self.position += offset;
+        self.target += offset;
+    }
+
+    // Moves the eye and target together by the given world-space distances
+    // along the view's right and up directions, keeping the orbit distance
+    // and zoom
+    pub fn pan(&mut self, right_amount: f32, up_amount: f32) {
+        if self.transition.is_some() {
+            return;
+        }
+        let forward = self.target - self.position;
+        let right = glm::cross(&forward, &self.up);
+        if right.norm() < 1e-6 {
+            return;
+        }
+        let right = right.normalize();
+        let up = glm::cross(&right, &forward).normalize();
+        let offset = right * right_amount + up * up_amount;
+        self.position += offset;
+        self.target += offset;
+    }
+
+    // Yaw around +Y (0 looks down -Z) and pitch of the viewing direction
+    fn look_angles(&self) -> (f32, f32) {
+        let direction = (self.target - self.position).normalize();
+        let yaw = direction.x.atan2(-direction.z);
+        let pitch = direction
+            .y
+            .clamp(-1.0, 1.0)
+            .asin()
+            .clamp(-MAX_PITCH, MAX_PITCH);
+        (yaw, pitch)
+    }
+
+    pub(crate) fn process_mouse(&mut self, xpos: f64, ypos: f64) {
+        if self.is_rotating && self.transition.is_none() {
+            let dx = (xpos - self.last_mouse_pos.0) as f32 * self.mouse_sensitivity;
+            let mut dy = (self.last_mouse_pos.1 - ypos) as f32 * self.mouse_sensitivity;
+            if self.invert_y {
+                dy = -dy;
+            }
+            if self.mode == CameraMode::FirstPerson {
+                // Turn the view in place, keeping the target's distance
+                let (yaw, pitch) = self.look_angles();
+                let pitch = (pitch + dy).clamp(-MAX_PITCH, MAX_PITCH);
+                let distance = glm::distance(&self.position, &self.target);
+                self.target = self.position + look_direction(yaw + dx, pitch) * distance;
+                self.last_mouse_pos = (xpos, ypos);
+                return;
+            }
+
+            // Rotate around target
+            let right = glm::cross(&(self.position - self.target).normalize(), &self.up);
+
+            // Vertical rotation (pitch)
+            let pitch = glm::rotate(&Mat4::identity(), dy, &right);
+            let pos_vec4 = glm::vec3_to_vec4(&(self.position - self.target));
+            self.position = glm::vec4_to_vec3(&(pitch * pos_vec4)) + self.target;
+
+            // Horizontal rotation (yaw)
+            let yaw = glm::rotate(&Mat4::identity(), dx, &self.up);
+            let pos_vec4 = glm::vec3_to_vec4(&(self.position - self.target));
+            self.position = glm::vec4_to_vec3(&(yaw * pos_vec4)) + self.target;
+        }
+        self.last_mouse_pos = (xpos, ypos);
+    }
+
+    pub(crate) fn process_scroll(&mut self, yoffset: f64) {
+        if self.mode == CameraMode::FirstPerson {
+            self.move_speed = (self.move_speed * 1.1f32.powf(yoffset as f32)).clamp(0.01, 100.0);
+            return;
+        }
+        self.zoom -= yoffset as f32 * 0.1;
+        self.zoom = self.zoom.clamp(0.1, 5.0);
+    }
+}
+
+// Unit viewing direction for a yaw around +Y (0 looks down -Z, positive
+// turns toward +X) and a pitch above the horizon
+fn look_direction(yaw: f32, pitch: f32) -> Vec3 {
+    vec3(
+        yaw.sin() * pitch.cos(),
+        pitch.sin(),
+        -yaw.cos() * pitch.cos(),
+    )
+}
+
+// Moves the target linearly and orbits the offset from it by normalized lerp,
+// so the distance to the target changes linearly rather than cutting through it
+fn interpolate(from: &CameraPose, to: &CameraPose, t: f32) -> CameraPose {
+    let target = glm::lerp(&from.target, &to.target, t);
+    let (a, b) = (from.position - from.target, to.position - to.target);
+    let length = glm::lerp_scalar(a.norm(), b.norm(), t);
+    let direction = glm::lerp(&a, &b, t);
+    let position = if direction.norm() > 1e-6 {
+        target + direction.normalize() * length
+    } else {
+        to.position
+    };
+    let up = glm::lerp(&from.up, &to.up, t);
+    CameraPose {
+        position,
+        target,
+        up: if up.norm() > 1e-6 {
+            up.normalize()
+        } else {
+            to.up
+        },
+        zoom: glm::lerp_scalar(from.zoom, to.zoom, t),
+        ortho_blend: glm::lerp_scalar(from.ortho_blend, to.ortho_blend, t),
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
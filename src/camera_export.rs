@@ -0,0 +1,144 @@
+use crate::camera::{Camera, Projection};
+use glm::Vec3;
+
+// Sensor width the exported focal length is based on (full-frame 35mm, the
+// default in Blender and Maya), with the horizontal field of view fitted to it
+const SENSOR_WIDTH_MM: f32 = 36.0;
+
+// File layout for X3D::export_camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraFormat {
+    // A minimal glTF 2.0 scene holding one camera node. glTF is always Y-up;
+    // importers convert to their own convention.
+    Gltf,
+    // Flat JSON with the position/target/up vectors, field of view and focal
+    // length, in the given up-axis convention
+    Json(UpAxis),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpAxis {
+    // The engine's own convention (Maya, most game engines)
+    #[default]
+    Y,
+    // Blender, 3ds Max: (x, y, z) becomes (x, -z, y)
+    Z,
+}
+
+impl UpAxis {
+    fn convert(self, v: &Vec3) -> [f32; 3] {
+        match self {
+            UpAxis::Y => [v.x, v.y, v.z],
+            UpAxis::Z => [v.x, -v.z, v.y],
+        }
+    }
+}
+
+// The camera's unshaken pose and lens for a view of the given aspect ratio
+pub(crate) fn export(camera: &Camera, aspect: f32, format: CameraFormat) -> String {
+    match format {
+        CameraFormat::Gltf => gltf(camera, aspect),
+        CameraFormat::Json(up_axis) => json(camera, aspect, up_axis),
+    }
+}
+
+fn gltf(camera: &Camera, aspect: f32) -> String {
+    let eye = camera.eye();
+    // glTF cameras look down their local -Z with +Y up, like a view matrix
+    let q = glm::to_quat(&camera_to_world(camera)).coords;
+    let fov_y = camera.fov_y();
+    let (near, far) = camera.clip_planes();
+    let lens = match camera.projection() {
+        Projection::Perspective => format!(
+            r#""type": "perspective",
+      "perspective": {{ "yfov": {fov_y}, "aspectRatio": {aspect}, "znear": {near}, "zfar": {far} }}"#
+        ),
+        Projection::Orthographic => {
+            let (xmag, ymag) = ortho_half_size(camera, aspect);
+            format!(
+                r#""type": "orthographic",
+      "orthographic": {{ "xmag": {xmag}, "ymag": {ymag}, "znear": {near}, "zfar": {far} }}"#
+            )
+        }
+    };
+    format!(
+        r#"{{
+  "asset": {{ "version": "2.0", "generator": "x3d" }},
+  "scene": 0,
+  "scenes": [{{ "nodes": [0] }}],
+  "nodes": [
+    {{
+      "name": "Camera",
+      "camera": 0,
+      "translation": [{}, {}, {}],
+      "rotation": [{}, {}, {}, {}]
+    }}
+  ],
+  "cameras": [
+    {{
+      {lens}
+    }}
+  ]
+}}
+"#,
+        eye.x, eye.y, eye.z, q.x, q.y, q.z, q.w
+    )
+}
+
+fn json(camera: &Camera, aspect: f32, up_axis: UpAxis) -> String {
+    let vector = |v: &Vec3| {
+        let [x, y, z] = up_axis.convert(v);
+        format!("[{x}, {y}, {z}]")
+    };
+    let fov_y = camera.fov_y();
+    let (near, far) = camera.clip_planes();
+    let fov_x = 2.0 * ((fov_y * 0.5).tan() * aspect).atan();
+    let focal_length = SENSOR_WIDTH_MM / (2.0 * (fov_x * 0.5).tan());
+    let (projection, ortho) = match camera.projection() {
+        Projection::Perspective => ("perspective", String::new()),
+        Projection::Orthographic => {
+            let (_, half_height) = ortho_half_size(camera, aspect);
+            let ortho = format!(",\n  \"ortho_height\": {}", half_height * 2.0);
+            ("orthographic", ortho)
+        }
+    };
+    format!(
+        r#"{{
+  "up_axis": "{}",
+  "position": {},
+  "target": {},
+  "up": {},
+  "projection": "{projection}",
+  "fov_y_degrees": {},
+  "fov_x_degrees": {},
+  "aspect": {aspect},
+  "focal_length_mm": {focal_length},
+  "sensor_width_mm": {SENSOR_WIDTH_MM},
+  "near": {near},
+  "far": {far}{ortho}
+}}
+"#,
+        match up_axis {
+            UpAxis::Y => "Y",
+            UpAxis::Z => "Z",
+        },
+        vector(&camera.eye()),
+        vector(&camera.target),
+        // The up hint may lean along the view direction; export the true one
+        vector(&camera_to_world(camera).column(1).xyz()),
+        fov_y.to_degrees(),
+        fov_x.to_degrees(),
+    )
+}
+
+// Inverse of the unshaken view matrix
+fn camera_to_world(camera: &Camera) -> glm::Mat4 {
+    glm::inverse(&glm::look_at(&camera.eye(), &camera.target, &camera.up))
+}
+
+// Half width and height of the orthographic view volume, as in
+// Camera::projection_matrix
+fn ortho_half_size(camera: &Camera, aspect: f32) -> (f32, f32) {
+    let half_height = glm::distance(&camera.eye(), &camera.target) * (camera.fov_y() * 0.5).tan();
+    (half_height * aspect, half_height)
+}
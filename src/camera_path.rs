@@ -0,0 +1,185 @@
+use glm::{Quat, Vec3, vec3};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// One camera state along a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    // Seconds from the start of the path
+    pub time: f32,
+    pub position: Vec3,
+    pub target: Vec3,
+    // Vertical field of view in degrees
+    pub fov_y: f32,
+}
+
+// Keyframed camera flythrough. Between keyframes the position follows a
+// Catmull-Rom curve through all of them, the viewing direction is slerped,
+// and the distance to the target and the field of view are interpolated
+// linearly. Keyframes are kept sorted by time.
+//
+// Path files are plain text with one keyframe per line, blank lines and
+// '#' comments ignored:
+//
+//     key = <time> <px py pz> <tx ty tz> <fov degrees>
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CameraPath {
+    keyframes: Vec<CameraKeyframe>,
+}
+
+// Interpolated state at a point along the path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PathPose {
+    pub(crate) position: Vec3,
+    pub(crate) target: Vec3,
+    pub(crate) up: Vec3,
+    // Radians
+    pub(crate) fov_y: f32,
+}
+
+impl CameraPath {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>) -> Self {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraPath { keyframes }
+    }
+
+    pub fn keyframes(&self) -> &[CameraKeyframe] {
+        &self.keyframes
+    }
+
+    pub fn add_keyframe(&mut self, keyframe: CameraKeyframe) {
+        let index = self.keyframes.partition_point(|k| k.time <= keyframe.time);
+        self.keyframes.insert(index, keyframe);
+    }
+
+    // Time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        Ok(Self::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))?)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut keyframes = Vec::new();
+        for (number, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            if key.trim() != "key" {
+                continue;
+            }
+            let floats: Option<Vec<f32>> =
+                value.split_whitespace().map(|w| w.parse().ok()).collect();
+            match floats.as_deref() {
+                Some(&[time, px, py, pz, tx, ty, tz, fov_y]) => keyframes.push(CameraKeyframe {
+                    time,
+                    position: vec3(px, py, pz),
+                    target: vec3(tx, ty, tz),
+                    fov_y,
+                }),
+                _ => {
+                    return Err(format!(
+                        "line {}: invalid keyframe `{}`",
+                        number + 1,
+                        value.trim()
+                    ));
+                }
+            }
+        }
+        if keyframes.is_empty() {
+            return Err("no keyframes".to_string());
+        }
+        Ok(CameraPath::new(keyframes))
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::from("# key = <time> <px py pz> <tx ty tz> <fov degrees>\n");
+        for k in &self.keyframes {
+            let (p, t) = (&k.position, &k.target);
+            let _ = writeln!(
+                text,
+                "key = {} {} {} {} {} {} {} {}",
+                k.time, p.x, p.y, p.z, t.x, t.y, t.z, k.fov_y
+            );
+        }
+        text
+    }
+
+    // State at `time` seconds, held at the first/last keyframe outside the
+    // path. None for an empty path.
+    pub(crate) fn sample(&self, time: f32) -> Option<PathPose> {
+        let keys = &self.keyframes;
+        let last = keys.len().checked_sub(1)?;
+        let next = keys
+            .partition_point(|k| k.time <= time)
+            .clamp(1, last.max(1));
+        let (i, j) = (next - 1, next.min(last));
+        let (a, b) = (&keys[i], &keys[j]);
+        let span = b.time - a.time;
+        let t = if span > 0.0 {
+            ((time - a.time) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        // Neighbors for the curve's tangents, repeating the ends
+        let before = &keys[i.saturating_sub(1)];
+        let after = &keys[(j + 1).min(last)];
+        let position = catmull_rom(
+            &before.position,
+            &a.position,
+            &b.position,
+            &after.position,
+            t,
+        );
+
+        let (from, to) = (orientation(a), orientation(b));
+        // Take the short way around
+        let to = if glm::quat_dot(&from, &to) < 0.0 {
+            -to
+        } else {
+            to
+        };
+        let rotation = glm::quat_normalize(&glm::quat_slerp(&from, &to, t));
+        let forward = glm::quat_rotate_vec3(&rotation, &vec3(0.0, 0.0, -1.0));
+        let up = glm::quat_rotate_vec3(&rotation, &Vec3::y());
+        let distance = glm::lerp_scalar(
+            glm::distance(&a.position, &a.target),
+            glm::distance(&b.position, &b.target),
+            t,
+        )
+        .max(1e-3);
+
+        Some(PathPose {
+            position,
+            target: position + forward * distance,
+            up,
+            fov_y: glm::lerp_scalar(a.fov_y, b.fov_y, t).to_radians(),
+        })
+    }
+}
+
+// Camera-to-world rotation looking from the keyframe's position at its target
+fn orientation(keyframe: &CameraKeyframe) -> Quat {
+    let view = glm::look_at(&keyframe.position, &keyframe.target, &Vec3::y());
+    glm::to_quat(&glm::inverse(&view))
+}
+
+// Uniform Catmull-Rom between p1 and p2
+fn catmull_rom(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, t: f32) -> Vec3 {
+    let (t2, t3) = (t * t, t * t * t);
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (-p0 + p1 * 3.0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
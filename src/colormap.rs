@@ -0,0 +1,170 @@
+use crate::lines::LineRenderer;
+use crate::mesh::Mesh;
+use crate::shader::{ShaderError, build_program, with_morph_targets};
+use glm::{Mat4, Vec3, vec3};
+
+// Quads used to draw the legend's color ramp
+const LEGEND_STEPS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Colormap {
+    // Perceptually uniform dark blue -> green -> yellow
+    #[default]
+    Viridis,
+    // Classic blue -> cyan -> yellow -> red rainbow
+    Jet,
+    Grayscale,
+}
+
+impl Colormap {
+    // Integer id matching the `colormap` switch in colormap_fragment.glsl
+    fn shader_id(self) -> i32 {
+        match self {
+            Colormap::Viridis => 0,
+            Colormap::Jet => 1,
+            Colormap::Grayscale => 2,
+        }
+    }
+
+    // Color at `t` in [0, 1]; same ramps as the shader
+    pub fn sample(self, t: f32) -> Vec3 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Colormap::Viridis => {
+                let c = [
+                    vec3(0.27773, 0.00541, 0.33410),
+                    vec3(0.10509, 1.40461, 1.38459),
+                    vec3(-0.33086, 0.21485, 0.09510),
+                    vec3(-4.63423, -5.79910, -19.33244),
+                    vec3(6.22827, 14.17993, 56.69055),
+                    vec3(4.77638, -13.74515, -65.35303),
+                    vec3(-5.43546, 4.64585, 26.31244),
+                ];
+                c.iter().rev().fold(Vec3::zeros(), |acc, ci| acc * t + ci)
+            }
+            Colormap::Jet => {
+                let ramp = |offset: f32| (1.5 - (4.0 * t - offset).abs()).clamp(0.0, 1.0);
+                vec3(ramp(3.0), ramp(2.0), ramp(1.0))
+            }
+            Colormap::Grayscale => vec3(t, t, t),
+        }
+    }
+}
+
+// Per-vertex scalar in the custom attribute at `attribute`, mapped linearly from
+// [min, max] onto the colormap. Values outside the range saturate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScalarField {
+    pub attribute: u32,
+    pub min: f32,
+    pub max: f32,
+    pub colormap: Colormap,
+    // Draws the ramp as a vertical bar on the right edge of the window
+    pub show_legend: bool,
+}
+
+// The colormap program is compiled for one attribute location at a time
+pub(crate) struct ColormapShader {
+    program: u32,
+    location: u32,
+}
+
+impl ColormapShader {
+    pub(crate) fn new(location: u32) -> Result<Self, ShaderError> {
+        // GLSL needs the location as a constant, so define it after #version
+        let source = include_str!("shaders/colormap_vertex.glsl").replacen(
+            '\n',
+            &format!("\n#define SCALAR_LOCATION {}\n", location),
+            1,
+        );
+        let program = unsafe {
+            build_program(
+                &with_morph_targets(&source),
+                include_str!("shaders/colormap_fragment.glsl"),
+            )?
+        };
+        Ok(ColormapShader { program, location })
+    }
+
+    pub(crate) fn location(&self) -> u32 {
+        self.location
+    }
+
+    pub(crate) fn draw(
+        &self,
+        mesh: &Mesh,
+        field: &ScalarField,
+        model: &Mat4,
+        view: &Mat4,
+        projection: &Mat4,
+        light_pos: &Vec3,
+    ) {
+        unsafe {
+            gl::UseProgram(self.program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.program, name.as_ptr());
+            gl::UniformMatrix4fv(location(c"model"), 1, gl::FALSE, model.as_ptr());
+            gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(location(c"projection"), 1, gl::FALSE, projection.as_ptr());
+            gl::Uniform3f(location(c"lightPos"), light_pos.x, light_pos.y, light_pos.z);
+            gl::Uniform1f(location(c"scalarMin"), field.min);
+            gl::Uniform1f(location(c"scalarMax"), field.max);
+            gl::Uniform1i(location(c"colormap"), field.colormap.shader_id());
+        }
+        mesh.draw();
+    }
+}
+
+impl Drop for ColormapShader {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+// Vertical ramp from min (bottom) to max (top) in normalized device coordinates,
+// with tick marks at the quartiles. The range values are printed when the field
+// is set rather than drawn.
+pub(crate) fn render_legend(colormap: Colormap, lines: &mut LineRenderer) {
+    let (left, right) = (0.86, 0.92);
+    let (bottom, top) = (-0.6, 0.6);
+    let y = |t: f32| bottom + (top - bottom) * t;
+
+    for i in 0..LEGEND_STEPS {
+        let t0 = i as f32 / LEGEND_STEPS as f32;
+        let t1 = (i + 1) as f32 / LEGEND_STEPS as f32;
+        let color = colormap.sample((t0 + t1) * 0.5);
+        let (a, b) = (vec3(left, y(t0), 0.0), vec3(right, y(t0), 0.0));
+        let (c, d) = (vec3(right, y(t1), 0.0), vec3(left, y(t1), 0.0));
+        lines.triangle(&a, &b, &c, &color);
+        lines.triangle(&a, &c, &d, &color);
+    }
+
+    let frame = vec3(0.9, 0.9, 0.9);
+    let corners = [
+        vec3(left, bottom, 0.0),
+        vec3(right, bottom, 0.0),
+        vec3(right, top, 0.0),
+        vec3(left, top, 0.0),
+    ];
+    for i in 0..4 {
+        lines.line(&corners[i], &corners[(i + 1) % 4], &frame);
+    }
+    for t in [0.25, 0.5, 0.75] {
+        lines.line(
+            &vec3(left - 0.015, y(t), 0.0),
+            &vec3(left, y(t), 0.0),
+            &frame,
+        );
+    }
+
+    let identity = Mat4::identity();
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+    }
+    lines.flush(&identity, &identity);
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::bounds::Aabb;
+use crate::lines::LineRenderer;
+use glm::{Mat4, Vec3};
+
+// Immediate-mode gizmos: lines and points queued during a frame are drawn
+// unlit with the main camera's view and projection, then forgotten. Queue
+// them every frame they should stay visible, e.g. from the run_with closure
+// through X3D::debug_draw.
+#[derive(Debug, Clone, Default)]
+pub struct DebugDraw {
+    // (start, end, color)
+    lines: Vec<(Vec3, Vec3, Vec3)>,
+    // (position, color)
+    points: Vec<(Vec3, Vec3)>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        DebugDraw::default()
+    }
+
+    pub fn line(&mut self, a: Vec3, b: Vec3, color: Vec3) {
+        self.lines.push((a, b, color));
+    }
+
+    // A square dot a few pixels wide, the same size at any distance
+    pub fn point(&mut self, p: Vec3, color: Vec3) {
+        self.points.push((p, color));
+    }
+
+    // The twelve edges of `aabb`
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec3) {
+        let corners = aabb.corners();
+        for (a, b) in Aabb::EDGES {
+            self.line(corners[a], corners[b], color);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.points.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.points.clear();
+    }
+
+    // Draws everything queued in one batch per primitive type. The queue is
+    // kept; the frame loop clears it once the frame is finished, so every
+    // pane of a split view shows the same gizmos.
+    pub(crate) fn render(&self, renderer: &mut LineRenderer, view: &Mat4, projection: &Mat4) {
+        if self.is_empty() {
+            return;
+        }
+        for (a, b, color) in &self.lines {
+            renderer.line(a, b, color);
+        }
+        for (p, color) in &self.points {
+            renderer.point(p, color);
+        }
+        renderer.flush(view, projection);
+    }
+}
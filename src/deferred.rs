@@ -0,0 +1,361 @@
+use crate::fog::{self, FogParams};
+use crate::light::Light;
+use crate::material::Material;
+use crate::render_target::FrameTarget;
+use crate::scene;
+use crate::shader::{ShaderError, build_program, with_fog, with_morph_targets};
+use crate::texture::{DIFFUSE_TEXTURE_UNIT, Texture};
+use crate::transform::{normal_matrix, view_position};
+use glm::{Mat4, Vec3};
+use std::ptr;
+
+// Must match MAX_LIGHTS in deferred_lighting_fragment.glsl
+pub(crate) const MAX_DEFERRED_LIGHTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pipeline {
+    // Shade each fragment while drawing geometry
+    #[default]
+    Forward,
+    // Write albedo/normal/position to a G-buffer, then light every pixel once
+    // per light in a fullscreen pass. Cost scales with pixels x lights instead
+    // of fragments drawn x lights. Shadows and toon shading are forward-only.
+    Deferred,
+}
+
+// Multiple-render-target framebuffer holding the geometry pass outputs
+pub(crate) struct GBuffer {
+    fbo: u32,
+    position: u32,
+    normal: u32,
+    albedo: u32,
+    emissive: u32,
+    depth: u32,
+    width: i32,
+    height: i32,
+    geometry_program: u32,
+    lighting_program: u32,
+    // Attribute-less VAO for the fullscreen triangle (core profile requires one bound)
+    empty_vao: u32,
+}
+
+impl GBuffer {
+    pub(crate) fn new(width: i32, height: i32) -> Result<Self, ShaderError> {
+        let geometry_program = unsafe {
+            build_program(
+                &with_morph_targets(include_str!("shaders/gbuffer_vertex.glsl")),
+                include_str!("shaders/gbuffer_fragment.glsl"),
+            )?
+        };
+        let lighting_program = unsafe {
+            build_program(
+                include_str!("shaders/fullscreen_vertex.glsl"),
+                &with_fog(include_str!("shaders/deferred_lighting_fragment.glsl")),
+            )
+        }
+        .inspect_err(|_| unsafe { gl::DeleteProgram(geometry_program) })?;
+
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+
+        let mut gbuffer = GBuffer {
+            fbo: 0,
+            position: 0,
+            normal: 0,
+            albedo: 0,
+            emissive: 0,
+            depth: 0,
+            width: 0,
+            height: 0,
+            geometry_program,
+            lighting_program,
+            empty_vao,
+        };
+        gbuffer.allocate(width, height);
+        Ok(gbuffer)
+    }
+
+    // Recreates the attachments if the framebuffer size changed
+    pub(crate) fn resize(&mut self, width: i32, height: i32) {
+        if (width, height) != (self.width, self.height) && width > 0 && height > 0 {
+            self.free_targets();
+            self.allocate(width, height);
+        }
+    }
+
+    fn allocate(&mut self, width: i32, height: i32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut self.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+
+            // Alpha holds material parameters: ambient share, specular
+            // strength and shininess, in that order
+            self.position = self.color_target(gl::RGBA16F, gl::FLOAT, 0);
+            self.normal = self.color_target(gl::RGBA16F, gl::FLOAT, 1);
+            self.albedo = self.color_target(gl::RGBA8, gl::UNSIGNED_BYTE, 2);
+            // Float so emissive values above 1.0 reach bloom
+            self.emissive = self.color_target(gl::RGBA16F, gl::FLOAT, 3);
+
+            let attachments = [
+                gl::COLOR_ATTACHMENT0,
+                gl::COLOR_ATTACHMENT1,
+                gl::COLOR_ATTACHMENT2,
+                gl::COLOR_ATTACHMENT3,
+            ];
+            gl::DrawBuffers(attachments.len() as i32, attachments.as_ptr());
+
+            // A texture rather than a renderbuffer so the lighting pass can
+            // sample it and hand it on through gl_FragDepth
+            gl::GenTextures(1, &mut self.depth);
+            gl::BindTexture(gl::TEXTURE_2D, self.depth);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH_COMPONENT24 as i32,
+                self.width,
+                self.height,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::UNSIGNED_INT,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                self.depth,
+                0,
+            );
+
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("G-buffer framebuffer is incomplete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    unsafe fn color_target(
+        &self,
+        internal_format: gl::types::GLenum,
+        ty: gl::types::GLenum,
+        attachment: u32,
+    ) -> u32 {
+        unsafe {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                self.width,
+                self.height,
+                0,
+                gl::RGBA,
+                ty,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0 + attachment,
+                gl::TEXTURE_2D,
+                texture,
+                0,
+            );
+            texture
+        }
+    }
+
+    // Binds the G-buffer and geometry program; the caller sets `model` and the
+    // material via `set_model` and `set_material` and draws every opaque mesh.
+    pub(crate) fn begin_geometry_pass(&self, view: &Mat4, projection: &Mat4, flip_normals: bool) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            gl::UseProgram(self.geometry_program);
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.geometry_program, name.as_ptr())
+            };
+            gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(location(c"projection"), 1, gl::FALSE, projection.as_ptr());
+            gl::Uniform1i(location(c"flipNormals"), flip_normals as i32);
+        }
+    }
+
+    pub(crate) fn set_model(&self, model: &Mat4) {
+        unsafe {
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.geometry_program, name.as_ptr())
+            };
+            gl::UniformMatrix4fv(location(c"model"), 1, gl::FALSE, model.as_ptr());
+            let normal_matrix = normal_matrix(model);
+            gl::UniformMatrix3fv(
+                location(c"normalMatrix"),
+                1,
+                gl::FALSE,
+                normal_matrix.as_ptr(),
+            );
+        }
+    }
+
+    // Texture multiplied into the albedo of everything drawn after this call
+    pub(crate) fn set_diffuse_texture(&self, texture: Option<&Texture>) {
+        unsafe {
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.geometry_program, name.as_ptr())
+            };
+            gl::Uniform1i(location(c"useTexture"), texture.is_some() as i32);
+            if let Some(texture) = texture {
+                texture.bind(DIFFUSE_TEXTURE_UNIT);
+                gl::Uniform1i(location(c"diffuseTexture"), DIFFUSE_TEXTURE_UNIT as i32);
+            }
+        }
+    }
+
+    // Whether textures hold sRGB colors to decode before lighting
+    pub(crate) fn set_srgb_textures(&self, enabled: bool) {
+        unsafe {
+            let loc = gl::GetUniformLocation(self.geometry_program, c"srgbTextures".as_ptr());
+            gl::Uniform1i(loc, enabled as i32);
+        }
+    }
+
+    pub(crate) fn set_material(&self, material: &Material) {
+        material.apply_uniforms(self.geometry_program);
+    }
+
+    pub(crate) fn set_tint(&self, tint: Option<(Vec3, f32)>) {
+        scene::set_tint_uniforms(self.geometry_program, tint);
+    }
+
+    pub(crate) fn set_two_sided_lighting(&self, enabled: bool) {
+        unsafe {
+            let loc = gl::GetUniformLocation(self.geometry_program, c"twoSidedLighting".as_ptr());
+            gl::Uniform1i(loc, enabled as i32);
+        }
+    }
+
+    // Shades every pixel into `target` and writes the G-buffer depth along with
+    // it, so forward-rendered extras (foliage, outlines) still depth test. The
+    // depth goes through the shader rather than a blit, which would fail
+    // against a window framebuffer with a different depth format or samples.
+    pub(crate) fn lighting_pass(
+        &self,
+        target: &FrameTarget,
+        lights: &[Light],
+        view: &Mat4,
+        // (ambient, exposure)
+        levels: (f32, f32),
+        fog: Option<&FogParams>,
+        clear_mask: gl::types::GLbitfield,
+    ) {
+        let lights = &lights[..lights.len().min(MAX_DEFERRED_LIGHTS)];
+
+        // The caller sets the clear color
+        target.bind();
+        unsafe {
+            if clear_mask != 0 {
+                target.clear(clear_mask);
+            }
+
+            gl::UseProgram(self.lighting_program);
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.lighting_program, name.as_ptr())
+            };
+
+            for (unit, (texture, name)) in [
+                (self.position, c"gPosition"),
+                (self.normal, c"gNormal"),
+                (self.albedo, c"gAlbedo"),
+                (self.emissive, c"gEmissive"),
+                (self.depth, c"gDepth"),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                gl::ActiveTexture(gl::TEXTURE0 + unit as u32);
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::Uniform1i(location(name), unit as i32);
+            }
+            gl::ActiveTexture(gl::TEXTURE0);
+
+            let positions: Vec<f32> = lights
+                .iter()
+                .flat_map(|l| [l.position.x, l.position.y, l.position.z])
+                .collect();
+            let colors: Vec<f32> = lights
+                .iter()
+                .flat_map(|l| {
+                    let c = l.color * l.intensity;
+                    [c.x, c.y, c.z]
+                })
+                .collect();
+            gl::Uniform1i(location(c"lightCount"), lights.len() as i32);
+            if !lights.is_empty() {
+                gl::Uniform3fv(
+                    location(c"lightPositions"),
+                    lights.len() as i32,
+                    positions.as_ptr(),
+                );
+                gl::Uniform3fv(
+                    location(c"lightColors"),
+                    lights.len() as i32,
+                    colors.as_ptr(),
+                );
+            }
+
+            gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, view.as_ptr());
+            let eye = view_position(view);
+            gl::Uniform3f(location(c"viewPos"), eye.x, eye.y, eye.z);
+            gl::Uniform1f(location(c"ambientStrength"), levels.0);
+            gl::Uniform1f(location(c"exposure"), levels.1);
+            fog::apply_uniforms(self.lighting_program, fog);
+
+            // Depth writes need the test enabled; ALWAYS lets every covered
+            // pixel replace what the target held
+            gl::DepthFunc(gl::ALWAYS);
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+
+    fn free_targets(&mut self) {
+        unsafe {
+            let textures = [
+                self.position,
+                self.normal,
+                self.albedo,
+                self.emissive,
+                self.depth,
+            ];
+            gl::DeleteTextures(textures.len() as i32, textures.as_ptr());
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+impl Drop for GBuffer {
+    fn drop(&mut self) {
+        self.free_targets();
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.geometry_program);
+            gl::DeleteProgram(self.lighting_program);
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::render_target::FrameTarget;
+use crate::shader::{ShaderError, build_program, with_morph_targets};
+use glm::{Mat4, Vec3, vec3};
+use std::ptr;
+
+// Screen-space outlines for a technical-illustration look. The opaque geometry
+// is re-drawn into a view-space normal + depth buffer, and a Sobel filter over
+// it marks depth discontinuities (silhouettes) and normal changes (creases).
+// Unlike the inverted-hull Outline it catches interior edges and doesn't depend
+// on the mesh having smooth normals or closed topology.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EdgeDetection {
+    pub color: Vec3,
+    // Line width in pixels; fractional values give softer, thinner lines
+    pub thickness: f32,
+    // Relative depth change (0.1 = 10% of the distance) that counts as an edge
+    pub depth_threshold: f32,
+    // Normal change, as 1 - cos(angle), that counts as a crease
+    pub normal_threshold: f32,
+}
+
+impl Default for EdgeDetection {
+    fn default() -> Self {
+        EdgeDetection {
+            color: vec3(0.0, 0.0, 0.0),
+            thickness: 1.0,
+            depth_threshold: 0.05,
+            normal_threshold: 0.3,
+        }
+    }
+}
+
+pub(crate) struct EdgePass {
+    fbo: u32,
+    // RGB = view-space normal, A = view-space distance (0 for background)
+    normal_depth: u32,
+    depth: u32,
+    width: i32,
+    height: i32,
+    geometry_program: u32,
+    edge_program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+}
+
+impl EdgePass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let geometry_program = unsafe {
+            build_program(
+                &with_morph_targets(include_str!("shaders/normal_depth_vertex.glsl")),
+                include_str!("shaders/normal_depth_fragment.glsl"),
+            )?
+        };
+        let edge_program = unsafe {
+            build_program(
+                include_str!("shaders/fullscreen_vertex.glsl"),
+                include_str!("shaders/edge_fragment.glsl"),
+            )
+        }
+        .inspect_err(|_| unsafe { gl::DeleteProgram(geometry_program) })?;
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+        Ok(EdgePass {
+            fbo: 0,
+            normal_depth: 0,
+            depth: 0,
+            width: 0,
+            height: 0,
+            geometry_program,
+            edge_program,
+            empty_vao,
+        })
+    }
+
+    // Binds the normal/depth buffer (reallocated to match `target`) and the
+    // geometry program; the caller sets `model` via `set_model` and draws every
+    // opaque mesh.
+    pub(crate) fn begin(&mut self, target: &FrameTarget, view: &Mat4, projection: &Mat4) {
+        if (target.width, target.height) != (self.width, self.height) {
+            self.free_buffers();
+            self.allocate(target.width.max(1), target.height.max(1));
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            gl::UseProgram(self.geometry_program);
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.geometry_program, name.as_ptr())
+            };
+            gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(location(c"projection"), 1, gl::FALSE, projection.as_ptr());
+        }
+    }
+
+    pub(crate) fn set_model(&self, model: &Mat4) {
+        unsafe {
+            let loc = gl::GetUniformLocation(self.geometry_program, c"model".as_ptr());
+            gl::UniformMatrix4fv(loc, 1, gl::FALSE, model.as_ptr());
+        }
+    }
+
+    // Blends the detected edges over `target`, leaving its depth untouched
+    pub(crate) fn composite(&self, params: &EdgeDetection, target: &FrameTarget) {
+        target.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+
+            gl::UseProgram(self.edge_program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.edge_program, name.as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.normal_depth);
+            gl::Uniform1i(location(c"normalDepth"), 0);
+            gl::Uniform2f(
+                location(c"texelSize"),
+                1.0 / self.width as f32,
+                1.0 / self.height as f32,
+            );
+            gl::Uniform3f(
+                location(c"edgeColor"),
+                params.color.x,
+                params.color.y,
+                params.color.z,
+            );
+            gl::Uniform1f(location(c"thickness"), params.thickness.max(0.25));
+            gl::Uniform1f(
+                location(c"depthThreshold"),
+                params.depth_threshold.max(1e-4),
+            );
+            gl::Uniform1f(
+                location(c"normalThreshold"),
+                params.normal_threshold.max(1e-4),
+            );
+
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+
+            gl::Disable(gl::BLEND);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn allocate(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        unsafe {
+            gl::GenFramebuffers(1, &mut self.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+
+            // Filterable float texture so fractional thickness samples smoothly
+            gl::GenTextures(1, &mut self.normal_depth);
+            gl::BindTexture(gl::TEXTURE_2D, self.normal_depth);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA16F as i32,
+                width,
+                height,
+                0,
+                gl::RGBA,
+                gl::FLOAT,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.normal_depth,
+                0,
+            );
+
+            gl::GenRenderbuffers(1, &mut self.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.depth,
+            );
+
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("Edge detection framebuffer is incomplete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    fn free_buffers(&mut self) {
+        if self.fbo == 0 {
+            return;
+        }
+        unsafe {
+            gl::DeleteTextures(1, &self.normal_depth);
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+        self.fbo = 0;
+    }
+}
+
+impl Drop for EdgePass {
+    fn drop(&mut self) {
+        self.free_buffers();
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.geometry_program);
+            gl::DeleteProgram(self.edge_program);
+        }
+    }
+}
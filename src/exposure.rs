@@ -0,0 +1,224 @@
+use crate::render_target::{FrameTarget, RenderTarget};
+use crate::shader::{ShaderError, build_program};
+
+// Edge length of the luminance texture the scene is reduced into; a power
+// of two so its mip chain ends in a single texel
+const METER_SIZE: i32 = 64;
+
+// Automatic exposure (eye adaptation). Each frame the rendered scene's
+// average luminance is metered and the exposure eases toward the value that
+// would bring it to `key`, so moving between bright and dark areas brightens
+// or darkens the view gradually, like eyes adjusting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoExposure {
+    // Average luminance to aim for; 0.18 is photographic middle grey
+    pub key: f32,
+    // How quickly exposure follows the scene, per second; about 1/speed
+    // seconds to cover most of a change
+    pub speed: f32,
+    // Limits on the adapted exposure, so a black or blown-out view can't
+    // push it to extremes
+    pub min_exposure: f32,
+    pub max_exposure: f32,
+}
+
+impl Default for AutoExposure {
+    fn default() -> Self {
+        AutoExposure {
+            key: 0.18,
+            speed: 1.5,
+            min_exposure: 0.25,
+            max_exposure: 8.0,
+        }
+    }
+}
+
+pub(crate) struct ExposurePass {
+    luminance_program: u32,
+    resolve_program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+    // Mipmapped single-channel float texture and the framebuffer writing level 0
+    meter_fbo: u32,
+    meter_texture: u32,
+    // HDR scene target, used when bloom doesn't already provide one
+    target: Option<RenderTarget>,
+    // Elapsed time of the previous adaptation step
+    last_time: Option<f32>,
+}
+
+impl ExposurePass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = |fragment: &str| unsafe {
+            build_program(include_str!("shaders/fullscreen_vertex.glsl"), fragment)
+        };
+        let luminance_program = program(include_str!("shaders/luminance_fragment.glsl"))?;
+        let resolve_program = program(include_str!("shaders/hdr_resolve_fragment.glsl"))
+            .inspect_err(|_| unsafe { gl::DeleteProgram(luminance_program) })?;
+        let (mut empty_vao, mut meter_fbo, mut meter_texture) = (0, 0, 0);
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+
+            gl::GenTextures(1, &mut meter_texture);
+            gl::BindTexture(gl::TEXTURE_2D, meter_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R16F as i32,
+                METER_SIZE,
+                METER_SIZE,
+                0,
+                gl::RED,
+                gl::FLOAT,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR_MIPMAP_LINEAR as i32,
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::GenFramebuffers(1, &mut meter_fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, meter_fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                meter_texture,
+                0,
+            );
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        Ok(ExposurePass {
+            luminance_program,
+            resolve_program,
+            empty_vao,
+            meter_fbo,
+            meter_texture,
+            target: None,
+            last_time: None,
+        })
+    }
+
+    // HDR target the size of `output` to draw the scene into, reallocated when
+    // that changes. None if it can't be created.
+    pub(crate) fn begin(&mut self, output: &FrameTarget) -> Option<FrameTarget> {
+        let (width, height) = (output.width.max(1), output.height.max(1));
+        let stale = self
+            .target
+            .as_ref()
+            .is_none_or(|t| (t.frame_target().width, t.frame_target().height) != (width, height));
+        if stale {
+            self.target = None;
+            match RenderTarget::hdr(width, height) {
+                Ok(target) => self.target = Some(target),
+                Err(err) => {
+                    eprintln!("Auto exposure disabled: {}", err);
+                    return None;
+                }
+            }
+        }
+        self.target.as_ref().map(RenderTarget::frame_target)
+    }
+
+    pub(crate) fn scene_texture(&self) -> Option<u32> {
+        self.target.as_ref().map(RenderTarget::color_texture)
+    }
+
+    // Copies the HDR scene from `begin` into `output`, clamped to displayable range
+    pub(crate) fn resolve(&self, output: &FrameTarget) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        output.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(self.resolve_program);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, target.color_texture());
+            let location = gl::GetUniformLocation(self.resolve_program, c"scene".as_ptr());
+            gl::Uniform1i(location, 0);
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    // Meters `scene` (drawn with `exposure`) and returns the exposure eased
+    // toward the one that brings its average luminance to the key. `time` is
+    // the elapsed time in seconds, used to step the adaptation.
+    pub(crate) fn adapt(
+        &mut self,
+        params: &AutoExposure,
+        scene: u32,
+        exposure: f32,
+        time: f32,
+    ) -> f32 {
+        let delta_time = self.last_time.map_or(0.0, |last| (time - last).max(0.0));
+        self.last_time = Some(time);
+
+        let average = self.average_luminance(scene);
+        let (min, max) = (
+            params.min_exposure.max(1e-4),
+            params.max_exposure.max(params.min_exposure.max(1e-4)),
+        );
+        // The scene was already scaled by `exposure`, so scale that by how far
+        // off the key the result is
+        let target = (exposure * params.key.max(1e-4) / average.max(1e-4)).clamp(min, max);
+        // Ease in log space, so brightening and darkening feel equally fast
+        let blend = 1.0 - (-params.speed.max(0.0) * delta_time).exp();
+        let current = exposure.clamp(min, max).ln();
+        (current + (target.ln() - current) * blend).exp()
+    }
+
+    // Geometric mean luminance of `scene`: log luminance rendered into the
+    // meter texture, averaged down its mip chain and read back from the
+    // single-texel level
+    fn average_luminance(&self, scene: u32) -> f32 {
+        let levels = METER_SIZE.ilog2() as i32;
+        let mut log_average = 0.0f32;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.meter_fbo);
+            gl::Viewport(0, 0, METER_SIZE, METER_SIZE);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(self.luminance_program);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, scene);
+            let location = gl::GetUniformLocation(self.luminance_program, c"scene".as_ptr());
+            gl::Uniform1i(location, 0);
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+
+            gl::BindTexture(gl::TEXTURE_2D, self.meter_texture);
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            gl::GetTexImage(
+                gl::TEXTURE_2D,
+                levels,
+                gl::RED,
+                gl::FLOAT,
+                &mut log_average as *mut f32 as *mut _,
+            );
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+        }
+        log_average.exp()
+    }
+}
+
+impl Drop for ExposurePass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.meter_fbo);
+            gl::DeleteTextures(1, &self.meter_texture);
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.luminance_program);
+            gl::DeleteProgram(self.resolve_program);
+        }
+    }
+}
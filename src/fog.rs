@@ -0,0 +1,64 @@
+use glm::Vec3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FogMode {
+    // Ramps from clear at `start` to fully fogged at `end`
+    #[default]
+    Linear,
+    // 1 - e^(-density * d), thickening quickly then leveling off
+    Exponential,
+    // 1 - e^(-(density * d)^2), clearer up close with a sharper falloff
+    ExponentialSquared,
+}
+
+impl FogMode {
+    // Integer id matching the `fogMode` switch in the shaders; 0 means no fog
+    fn shader_id(self) -> i32 {
+        match self {
+            FogMode::Linear => 1,
+            FogMode::Exponential => 2,
+            FogMode::ExponentialSquared => 3,
+        }
+    }
+}
+
+// Distance fog blended over lit geometry with `mix(color, fog.color, factor)`,
+// where the factor grows with the fragment's view-space distance. `density` is
+// only used by the exponential modes; they measure distance from `start`,
+// and `end` only applies to `Linear`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FogParams {
+    pub color: Vec3,
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+    pub mode: FogMode,
+}
+
+impl Default for FogParams {
+    fn default() -> Self {
+        FogParams {
+            color: glm::vec3(0.6, 0.65, 0.7),
+            start: 2.0,
+            end: 12.0,
+            density: 0.15,
+            mode: FogMode::default(),
+        }
+    }
+}
+
+// Uploads the fog uniforms to `program`, which must be in use
+pub(crate) unsafe fn apply_uniforms(program: u32, fog: Option<&FogParams>) {
+    unsafe {
+        let location = |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+        let Some(fog) = fog else {
+            gl::Uniform1i(location(c"fogMode"), 0);
+            return;
+        };
+        gl::Uniform1i(location(c"fogMode"), fog.mode.shader_id());
+        gl::Uniform3f(location(c"fogColor"), fog.color.x, fog.color.y, fog.color.z);
+        gl::Uniform1f(location(c"fogStart"), fog.start);
+        gl::Uniform1f(location(c"fogEnd"), fog.end.max(fog.start + 1e-3));
+        gl::Uniform1f(location(c"fogDensity"), fog.density.max(0.0));
+    }
+}
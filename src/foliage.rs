@@ -0,0 +1,255 @@
+use crate::fog::{self, FogParams};
+use crate::random::XorShift;
+use crate::shader::{ShaderError, build_program, with_fog};
+use glm::{Mat4, Vec2, vec2, vec3};
+use std::mem;
+use std::ptr;
+
+// Upper bound on generated blades so a careless density can't exhaust memory
+const MAX_BLADES: usize = 250_000;
+
+// A field of instanced grass blades scattered over a square patch of ground.
+// Every blade shares one small mesh; only the per-instance model matrix differs,
+// so the whole field is a single DrawArraysInstanced call. The vertex shader
+// bends each blade with a time-driven wind function proportional to its height.
+pub struct Foliage {
+    shader_program: u32,
+    vao: u32,
+    blade_vbo: u32,
+    instance_vbo: u32,
+    blade_vertex_count: i32,
+    instance_count: i32,
+    extent: f32,
+    ground_height: f32,
+    density: f32,
+    wind_strength: f32,
+    wind_direction: Vec2,
+}
+
+impl Foliage {
+    // `extent` is the side length of the square patch centered on the origin,
+    // `density` the number of blades per square unit. Fails if the built-in
+    // grass shader doesn't compile.
+    pub fn new(extent: f32, ground_height: f32, density: f32) -> Result<Self, ShaderError> {
+        let shader_program = unsafe {
+            build_program(
+                include_str!("shaders/foliage_vertex.glsl"),
+                &with_fog(include_str!("shaders/foliage_fragment.glsl")),
+            )
+        }?;
+
+        let blade = create_blade_vertices();
+
+        let (vao, blade_vbo, instance_vbo) = unsafe {
+            let mut vao = 0;
+            let mut blade_vbo = 0;
+            let mut instance_vbo = 0;
+
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut blade_vbo);
+            gl::GenBuffers(1, &mut instance_vbo);
+
+            gl::BindVertexArray(vao);
+
+            // Blade geometry, shared by every instance
+            gl::BindBuffer(gl::ARRAY_BUFFER, blade_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (blade.len() * mem::size_of::<f32>()) as isize,
+                blade.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                3 * mem::size_of::<f32>() as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Per-instance model matrix, one vec4 column per attribute slot (1-4)
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = mem::size_of::<Mat4>() as i32;
+            for column in 0..4 {
+                let location = 1 + column;
+                gl::VertexAttribPointer(
+                    location,
+                    4,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (column as usize * 4 * mem::size_of::<f32>()) as *const _,
+                );
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 1);
+            }
+
+            gl::BindVertexArray(0);
+
+            (vao, blade_vbo, instance_vbo)
+        };
+
+        let mut foliage = Foliage {
+            shader_program,
+            vao,
+            blade_vbo,
+            instance_vbo,
+            blade_vertex_count: (blade.len() / 3) as i32,
+            instance_count: 0,
+            extent,
+            ground_height,
+            density: density.max(0.0),
+            wind_strength: 0.15,
+            wind_direction: vec2(1.0, 0.3).normalize(),
+        };
+        foliage.upload_instances();
+        Ok(foliage)
+    }
+
+    pub fn density(&self) -> f32 {
+        self.density
+    }
+
+    // Regenerates the instance buffer, so avoid calling this every frame
+    pub fn set_density(&mut self, density: f32) {
+        self.density = density.max(0.0);
+        self.upload_instances();
+    }
+
+    pub fn wind_strength(&self) -> f32 {
+        self.wind_strength
+    }
+
+    pub fn set_wind_strength(&mut self, strength: f32) {
+        self.wind_strength = strength.max(0.0);
+    }
+
+    pub fn set_wind_direction(&mut self, x: f32, z: f32) {
+        let direction = vec2(x, z);
+        if direction.norm() > f32::EPSILON {
+            self.wind_direction = direction.normalize();
+        }
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.instance_count as usize
+    }
+
+    fn upload_instances(&mut self) {
+        let transforms = scatter_blades(self.extent, self.ground_height, self.density);
+        self.instance_count = transforms.len() as i32;
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (transforms.len() * mem::size_of::<Mat4>()) as isize,
+                transforms.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+    }
+
+    pub(crate) fn render(
+        &self,
+        view: &Mat4,
+        projection: &Mat4,
+        time: f32,
+        fog: Option<&FogParams>,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+
+        unsafe {
+            gl::UseProgram(self.shader_program);
+            gl::BindVertexArray(self.vao);
+
+            let view_loc = gl::GetUniformLocation(self.shader_program, c"view".as_ptr());
+            let projection_loc =
+                gl::GetUniformLocation(self.shader_program, c"projection".as_ptr());
+            let time_loc = gl::GetUniformLocation(self.shader_program, c"uTime".as_ptr());
+            let strength_loc =
+                gl::GetUniformLocation(self.shader_program, c"windStrength".as_ptr());
+            let direction_loc =
+                gl::GetUniformLocation(self.shader_program, c"windDirection".as_ptr());
+
+            gl::UniformMatrix4fv(view_loc, 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(projection_loc, 1, gl::FALSE, projection.as_ptr());
+            gl::Uniform1f(time_loc, time);
+            gl::Uniform1f(strength_loc, self.wind_strength);
+            gl::Uniform2f(direction_loc, self.wind_direction.x, self.wind_direction.y);
+            fog::apply_uniforms(self.shader_program, fog);
+
+            gl::DrawArraysInstanced(
+                gl::TRIANGLES,
+                0,
+                self.blade_vertex_count,
+                self.instance_count,
+            );
+
+            gl::BindVertexArray(0);
+        }
+    }
+}
+
+impl Drop for Foliage {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteBuffers(1, &self.blade_vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.shader_program);
+        }
+    }
+}
+
+// Jittered grid placement so blades cover the patch evenly without visible rows.
+// Uses a fixed seed, so the same density always produces the same field.
+fn scatter_blades(extent: f32, ground_height: f32, density: f32) -> Vec<Mat4> {
+    let target = ((extent * extent * density) as usize).min(MAX_BLADES);
+    if target == 0 {
+        return Vec::new();
+    }
+
+    let per_side = (target as f32).sqrt().ceil() as usize;
+    let cell = extent / per_side as f32;
+    let half = extent * 0.5;
+    let mut rng = XorShift::new(0x9E37_79B9);
+    let mut transforms = Vec::with_capacity(per_side * per_side);
+
+    for i in 0..per_side {
+        for j in 0..per_side {
+            let x = -half + (i as f32 + rng.next_f32()) * cell;
+            let z = -half + (j as f32 + rng.next_f32()) * cell;
+            let angle = rng.next_f32() * std::f32::consts::TAU;
+            let height = 0.15 + rng.next_f32() * 0.2;
+            let width = 0.015 + rng.next_f32() * 0.01;
+
+            let translation = glm::translate(&Mat4::identity(), &vec3(x, ground_height, z));
+            let rotation = glm::rotate(&Mat4::identity(), angle, &vec3(0.0, 1.0, 0.0));
+            let scale = glm::scale(&Mat4::identity(), &vec3(width, height, width));
+            transforms.push(translation * rotation * scale);
+        }
+    }
+
+    transforms
+}
+
+fn create_blade_vertices() -> Vec<f32> {
+    // Unit blade: base centered at the origin, tip at y = 1, tapering as it rises.
+    // Positions only; the y coordinate doubles as the sway weight in the shader.
+    vec![
+        // Lower segment
+        -0.5, 0.0, 0.0, 0.5, 0.0, 0.0, 0.4, 0.4, 0.0, //
+        -0.5, 0.0, 0.0, 0.4, 0.4, 0.0, -0.4, 0.4, 0.0, //
+        // Middle segment
+        -0.4, 0.4, 0.0, 0.4, 0.4, 0.0, 0.25, 0.75, 0.0, //
+        -0.4, 0.4, 0.0, 0.25, 0.75, 0.0, -0.25, 0.75, 0.0, //
+        // Tip
+        -0.25, 0.75, 0.0, 0.25, 0.75, 0.0, 0.0, 1.0, 0.0,
+    ]
+}
@@ -0,0 +1,111 @@
+// Built-in 5x7 bitmap font for on-screen labels. Each glyph is seven rows,
+// top first, with the five low bits of each row as pixels (bit 4 = leftmost).
+// Covers digits, A-Z (lowercase is drawn as uppercase) and common punctuation.
+
+use crate::lines::LineRenderer;
+use glm::{Vec2, Vec3, vec2};
+
+pub(crate) const GLYPH_WIDTH: usize = 5;
+pub(crate) const GLYPH_HEIGHT: usize = 7;
+// Horizontal/vertical distance between character cells, including spacing
+pub(crate) const ADVANCE: usize = 6;
+pub(crate) const LINE_HEIGHT: usize = 9;
+
+pub(crate) fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0, 0, 0, 0, 0, 0, 0],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '.' => [0, 0, 0, 0, 0, 0x0C, 0x0C],
+        ',' => [0, 0, 0, 0, 0x0C, 0x04, 0x08],
+        '-' => [0, 0, 0, 0x1F, 0, 0, 0],
+        '+' => [0, 0x04, 0x04, 0x1F, 0x04, 0x04, 0],
+        '=' => [0, 0, 0x1F, 0, 0x1F, 0, 0],
+        ':' => [0, 0x0C, 0x0C, 0, 0x0C, 0x0C, 0],
+        '/' => [0, 0x01, 0x02, 0x04, 0x08, 0x10, 0],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '_' => [0, 0, 0, 0, 0, 0, 0x1F],
+        '°' => [0x0C, 0x12, 0x12, 0x0C, 0, 0, 0],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0, 0x04],
+    }
+}
+
+// Size of `text` in font pixels (before scaling)
+pub(crate) fn text_size(text: &str) -> (usize, usize) {
+    let lines = text.lines().count().max(1);
+    let columns = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let width = (columns * ADVANCE).saturating_sub(ADVANCE - GLYPH_WIDTH);
+    let height = lines * LINE_HEIGHT - (LINE_HEIGHT - GLYPH_HEIGHT);
+    (width, height)
+}
+
+// Queues `text` as one quad per lit font pixel, starting at the top-left corner
+// `origin` in pixels (+y up). `at` maps pixel positions to the space `lines` is
+// flushed in.
+pub(crate) fn queue_text(
+    text: &str,
+    origin: &Vec2,
+    scale: f32,
+    color: &Vec3,
+    lines: &mut LineRenderer,
+    at: &impl Fn(Vec2) -> Vec3,
+) {
+    for (row, line) in text.lines().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let cell = origin
+                + vec2(
+                    (column * ADVANCE) as f32 * scale,
+                    -((row * LINE_HEIGHT) as f32) * scale,
+                );
+            for (y, bits) in glyph(c).iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) == 0 {
+                        continue;
+                    }
+                    let min = cell + vec2(x as f32 * scale, -((y + 1) as f32) * scale);
+                    let max = min + vec2(scale, scale);
+                    let (a, b) = (at(min), at(vec2(max.x, min.y)));
+                    let (c, d) = (at(max), at(vec2(min.x, max.y)));
+                    lines.triangle(&a, &b, &c, color);
+                    lines.triangle(&a, &c, &d, color);
+                }
+            }
+        }
+    }
+}
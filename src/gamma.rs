@@ -0,0 +1,134 @@
+use crate::render_target::FrameTarget;
+use crate::shader::{ShaderError, build_program};
+
+// Gamma correction for outputs without an sRGB framebuffer: the finished
+// frame is copied aside and written back through the sRGB curve
+pub(crate) struct GammaPass {
+    program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+    copy_fbo: u32,
+    copy: u32,
+    width: i32,
+    height: i32,
+}
+
+impl GammaPass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = unsafe {
+            build_program(
+                include_str!("shaders/fullscreen_vertex.glsl"),
+                include_str!("shaders/gamma_fragment.glsl"),
+            )?
+        };
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+        Ok(GammaPass {
+            program,
+            empty_vao,
+            copy_fbo: 0,
+            copy: 0,
+            width: 0,
+            height: 0,
+        })
+    }
+
+    // Re-encodes `target`'s linear color as sRGB in place
+    pub(crate) fn apply(&mut self, target: &FrameTarget) {
+        if (target.width, target.height) != (self.width, self.height) {
+            self.free_buffers();
+            self.allocate(target.width.max(1), target.height.max(1));
+        }
+        unsafe {
+            // The scissor test would clip the blit, and target.bind restores it
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, target.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.copy_fbo);
+            gl::BlitFramebuffer(
+                target.x,
+                target.y,
+                target.x + target.width,
+                target.y + target.height,
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+        target.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(self.program);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.copy);
+            let location = gl::GetUniformLocation(self.program, c"scene".as_ptr());
+            gl::Uniform1i(location, 0);
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn allocate(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        unsafe {
+            gl::GenTextures(1, &mut self.copy);
+            gl::BindTexture(gl::TEXTURE_2D, self.copy);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width,
+                height,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                std::ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            gl::GenFramebuffers(1, &mut self.copy_fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.copy_fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.copy,
+                0,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("Gamma correction copy framebuffer is incomplete");
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    fn free_buffers(&mut self) {
+        if self.copy_fbo == 0 {
+            return;
+        }
+        unsafe {
+            gl::DeleteTextures(1, &self.copy);
+            gl::DeleteFramebuffers(1, &self.copy_fbo);
+        }
+        self.copy_fbo = 0;
+    }
+}
+
+impl Drop for GammaPass {
+    fn drop(&mut self) {
+        self.free_buffers();
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
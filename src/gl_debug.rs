@@ -0,0 +1,163 @@
+use std::ffi::{CStr, c_void};
+use std::fmt;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugSeverity {
+    // Informational, e.g. where a buffer was placed
+    Notification,
+    Low,
+    Medium,
+    // Errors and undefined behavior
+    High,
+}
+
+// One message from the driver's debug output
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlDebugMessage {
+    // What raised it: "API", "shader compiler", "window system", ...
+    pub source: &'static str,
+    // "error", "deprecated", "undefined behavior", "performance", ...
+    pub kind: &'static str,
+    // Driver-specific message id
+    pub id: u32,
+    pub severity: DebugSeverity,
+    pub message: String,
+}
+
+impl fmt::Display for GlDebugMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:?}] {} {} ({}): {}",
+            self.severity, self.source, self.kind, self.id, self.message
+        )
+    }
+}
+
+pub type DebugCallback = Box<dyn Fn(&GlDebugMessage)>;
+
+// The default callback: everything above notification level to stderr
+pub(crate) fn log(message: &GlDebugMessage) {
+    if message.severity > DebugSeverity::Notification {
+        eprintln!("GL debug: {}", message);
+    }
+}
+
+// Keeps the callback registered with the driver. The callback is boxed
+// again so the user pointer handed to GL is a thin pointer to a stable heap
+// address; it is unregistered before that allocation is freed.
+pub(crate) struct DebugOutput {
+    callback: Box<DebugCallback>,
+}
+
+impl DebugOutput {
+    // None if the context has no debug output (GL 4.3 or KHR_debug)
+    pub(crate) fn install(callback: DebugCallback) -> Option<Self> {
+        if !gl::DebugMessageCallback::is_loaded() {
+            return None;
+        }
+        let output = DebugOutput {
+            callback: Box::new(callback),
+        };
+        unsafe {
+            gl::Enable(gl::DEBUG_OUTPUT);
+            // Report during the offending call, so a breakpoint in the callback
+            // shows who made it
+            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+            output.register();
+        }
+        Some(output)
+    }
+
+    pub(crate) fn set_callback(&mut self, callback: DebugCallback) {
+        let previous = std::mem::replace(&mut self.callback, Box::new(callback));
+        unsafe {
+            self.register();
+        }
+        // Only freed once GL no longer points at it
+        drop(previous);
+    }
+
+    unsafe fn register(&self) {
+        let user = &*self.callback as *const DebugCallback as *const c_void;
+        unsafe {
+            gl::DebugMessageCallback(Some(receive), user);
+        }
+    }
+}
+
+impl Drop for DebugOutput {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DebugMessageCallback(None, std::ptr::null());
+            gl::Disable(gl::DEBUG_OUTPUT);
+        }
+    }
+}
+
+extern "system" fn receive(
+    source: gl::types::GLenum,
+    kind: gl::types::GLenum,
+    id: gl::types::GLuint,
+    severity: gl::types::GLenum,
+    length: gl::types::GLsizei,
+    message: *const gl::types::GLchar,
+    user: *mut c_void,
+) {
+    if user.is_null() || message.is_null() {
+        return;
+    }
+    // Some drivers pass a negative length for NUL-terminated text
+    let text = unsafe {
+        match usize::try_from(length) {
+            Ok(length) => {
+                String::from_utf8_lossy(std::slice::from_raw_parts(message.cast::<u8>(), length))
+            }
+            Err(_) => CStr::from_ptr(message).to_string_lossy(),
+        }
+    };
+    let message = GlDebugMessage {
+        source: source_name(source),
+        kind: kind_name(kind),
+        id,
+        severity: match severity {
+            gl::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+            gl::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+            _ => DebugSeverity::Notification,
+        },
+        message: text.trim_end().to_string(),
+    };
+    // Set by DebugOutput::register and valid until it's unregistered
+    let callback = unsafe { &*(user as *const DebugCallback) };
+    // Unwinding into the driver is undefined, so a panic stops here
+    if catch_unwind(AssertUnwindSafe(|| callback(&message))).is_err() {
+        eprintln!("GL debug callback panicked on: {}", message);
+    }
+}
+
+fn source_name(source: gl::types::GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "API",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    }
+}
+
+fn kind_name(kind: gl::types::GLenum) -> &'static str {
+    match kind {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        gl::DEBUG_TYPE_PUSH_GROUP => "push group",
+        gl::DEBUG_TYPE_POP_GROUP => "pop group",
+        _ => "other",
+    }
+}
@@ -0,0 +1,1026 @@
+use crate::image;
+use crate::json::{self, Value};
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::scene::{Scene, SceneNode};
+use crate::texture::Texture;
+use crate::transform::Transform;
+use glm::{Mat4, Vec3, vec3};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+
+// GLB container: a 12-byte header, then length-prefixed chunks
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const JSON_CHUNK: u32 = 0x4E4F_534A;
+const BIN_CHUNK: u32 = 0x004E_4942;
+
+// Primitive mode for triangle lists, the default
+const TRIANGLES: usize = 4;
+
+// Accessor component types
+const BYTE: usize = 5120;
+const UNSIGNED_BYTE: usize = 5121;
+const SHORT: usize = 5122;
+const UNSIGNED_SHORT: usize = 5123;
+const UNSIGNED_INT: usize = 5125;
+const FLOAT: usize = 5126;
+
+#[derive(Debug)]
+pub enum GltfError {
+    Io(std::io::Error),
+    // Malformed JSON or GLB container
+    Parse(String),
+    // Well-formed, but refers to missing or inconsistent data
+    Invalid(String),
+    // Valid glTF using something the loader doesn't handle
+    Unsupported(String),
+}
+
+impl fmt::Display for GltfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GltfError::Io(err) => write!(f, "failed to read glTF file: {err}"),
+            GltfError::Parse(message) => write!(f, "malformed glTF: {message}"),
+            GltfError::Invalid(message) => write!(f, "invalid glTF: {message}"),
+            GltfError::Unsupported(message) => write!(f, "unsupported glTF: {message}"),
+        }
+    }
+}
+
+impl Error for GltfError {}
+
+impl From<std::io::Error> for GltfError {
+    fn from(err: std::io::Error) -> Self {
+        GltfError::Io(err)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> GltfError {
+    GltfError::Invalid(message.into())
+}
+
+// Reads a glTF 2.0 file (.gltf with external or embedded base64 buffers, or
+// binary .glb) into a Scene whose top-level nodes are the default scene's
+// roots. Positions, normals, the first texture coordinate set, node
+// transforms, base colors (with their alpha for the BLEND alpha mode) and
+// base-color textures are imported; skins, animations, morph targets,
+// cameras and lights are ignored. Primitives that aren't triangle lists are
+// skipped, and missing normals are made flat.
+//
+// A node's first primitive becomes its own mesh and any further ones become
+// untransformed children. Meshes used by several nodes are uploaded once per
+// node; textures are shared. Images the built-in decoder can't read (such as
+// progressive JPEG) leave their materials untextured.
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<Scene, GltfError> {
+    let path = path.as_ref();
+    let data = std::fs::read(path)?;
+    let base = path.parent().unwrap_or(Path::new(""));
+    let (json, buffers) = read_gltf(&data, base)?;
+    let mut document = Document {
+        json: &json,
+        buffers,
+        base,
+        textures: HashMap::new(),
+    };
+    let mut scene = Scene::new();
+    for root in document.root_nodes()? {
+        scene.add(document.node(root, &mut Vec::new())?);
+    }
+    Ok(scene)
+}
+
+// The JSON document and buffers of a .gltf or .glb file's contents, with
+// external files resolved against `base`
+fn read_gltf(data: &[u8], base: &Path) -> Result<(Value, Vec<Vec<u8>>), GltfError> {
+    let (json, bin) = if data.starts_with(GLB_MAGIC) {
+        split_glb(data)?
+    } else {
+        let text = std::str::from_utf8(data)
+            .map_err(|_| GltfError::Parse("JSON is not valid UTF-8".to_string()))?;
+        (text, None)
+    };
+    let json = json::parse(json).map_err(GltfError::Parse)?;
+
+    let version = json
+        .get("asset")
+        .and_then(|asset| asset.get("version"))
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    if !version.starts_with("2.") {
+        return Err(GltfError::Unsupported(format!(
+            "version `{version}`, only 2.x is read"
+        )));
+    }
+    if let Some(required) = json.get("extensionsRequired").and_then(Value::as_array)
+        && let Some(name) = required.first().and_then(Value::as_str)
+    {
+        return Err(GltfError::Unsupported(format!("required extension {name}")));
+    }
+
+    let buffers = load_buffers(&json, bin, base)?;
+    Ok((json, buffers))
+}
+
+// The JSON text and binary chunk of a GLB file
+fn split_glb(data: &[u8]) -> Result<(&str, Option<&[u8]>), GltfError> {
+    let word = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or_else(|| GltfError::Parse("truncated GLB file".to_string()))
+    };
+    let version = word(4)?;
+    if version != 2 {
+        return Err(GltfError::Unsupported(format!("GLB version {version}")));
+    }
+    let length = (word(8)? as usize).min(data.len());
+
+    let (mut json, mut bin) = (None, None);
+    let mut pos = 12;
+    while pos + 8 <= length {
+        let chunk_length = word(pos)? as usize;
+        let kind = word(pos + 4)?;
+        let chunk = data
+            .get(pos + 8..pos + 8 + chunk_length)
+            .ok_or_else(|| GltfError::Parse("truncated GLB chunk".to_string()))?;
+        match kind {
+            JSON_CHUNK if json.is_none() => json = Some(chunk),
+            BIN_CHUNK if bin.is_none() => bin = Some(chunk),
+            // Unknown chunks are to be skipped
+            _ => {}
+        }
+        // Chunks are padded to four bytes
+        pos += 8 + chunk_length.next_multiple_of(4);
+    }
+    let json = json.ok_or_else(|| GltfError::Parse("GLB file has no JSON chunk".to_string()))?;
+    let text = std::str::from_utf8(json)
+        .map_err(|_| GltfError::Parse("JSON chunk is not valid UTF-8".to_string()))?;
+    Ok((text, bin))
+}
+
+fn load_buffers(json: &Value, bin: Option<&[u8]>, base: &Path) -> Result<Vec<Vec<u8>>, GltfError> {
+    let mut bin = bin;
+    let mut buffers = Vec::new();
+    for (index, buffer) in array(json, "buffers").iter().enumerate() {
+        let data = match buffer.get("uri").and_then(Value::as_str) {
+            Some(uri) => read_uri(uri, base)?,
+            // Only the first buffer may live in the GLB binary chunk
+            None => bin
+                .take()
+                .ok_or_else(|| invalid(format!("buffer {index} has no data")))?
+                .to_vec(),
+        };
+        let length = usize_field(buffer, "byteLength")?.unwrap_or(0);
+        if data.len() < length {
+            return Err(invalid(format!(
+                "buffer {index} holds {} bytes, expected {length}",
+                data.len()
+            )));
+        }
+        buffers.push(data);
+    }
+    Ok(buffers)
+}
+
+// Contents of a base64 `data:` URI, or of a file relative to the glTF file
+fn read_uri(uri: &str, base: &Path) -> Result<Vec<u8>, GltfError> {
+    if let Some(rest) = uri.strip_prefix("data:") {
+        let (header, payload) = rest
+            .split_once(',')
+            .ok_or_else(|| invalid("malformed data URI"))?;
+        if !header.ends_with(";base64") {
+            return Err(GltfError::Unsupported(
+                "data URI without base64".to_string(),
+            ));
+        }
+        return decode_base64(payload).ok_or_else(|| invalid("malformed base64 in data URI"));
+    }
+    Ok(std::fs::read(base.join(percent_decode(uri)))?)
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    };
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|c| !c.is_ascii_whitespace() && *c != b'=')
+        .map(value)
+        .collect::<Option<_>>()?;
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    for group in digits.chunks(4) {
+        let bits = group
+            .iter()
+            .enumerate()
+            .fold(0u32, |bits, (i, &d)| bits | (d as u32) << (18 - 6 * i));
+        let [_, a, b, c] = bits.to_be_bytes();
+        // n digits carry n - 1 whole bytes; a single trailing digit is malformed
+        match group.len() {
+            4 => bytes.extend_from_slice(&[a, b, c]),
+            3 => bytes.extend_from_slice(&[a, b]),
+            2 => bytes.push(a),
+            _ => return None,
+        }
+    }
+    Some(bytes)
+}
+
+// Relative URIs may escape spaces and other characters as %XX
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+// `key` of `value` as an array; missing means empty
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).unwrap_or(&[])
+}
+
+fn usize_field(value: &Value, key: &str) -> Result<Option<usize>, GltfError> {
+    value
+        .get(key)
+        .map(|v| {
+            v.as_usize()
+                .ok_or_else(|| invalid(format!("`{key}` must be a non-negative integer")))
+        })
+        .transpose()
+}
+
+// `key` as exactly N numbers, if present
+fn floats<const N: usize>(value: &Value, key: &str) -> Result<Option<[f32; N]>, GltfError> {
+    let Some(field) = value.get(key) else {
+        return Ok(None);
+    };
+    let numbers: Option<Vec<f32>> = field
+        .as_array()
+        .and_then(|items| items.iter().map(|n| n.as_f64().map(|n| n as f32)).collect());
+    numbers
+        .and_then(|numbers| numbers.try_into().ok())
+        .map(Some)
+        .ok_or_else(|| invalid(format!("`{key}` must be {N} numbers")))
+}
+
+// A typed view into a buffer: `count` elements of `components` values each,
+// the first at `offset` and each `stride` bytes after the previous
+struct Accessor<'a> {
+    // None for an accessor without a buffer view, which reads as zeros
+    data: Option<&'a [u8]>,
+    offset: usize,
+    stride: usize,
+    count: usize,
+    components: usize,
+    component_type: usize,
+    normalized: bool,
+}
+
+impl Accessor<'_> {
+    fn component_size(component_type: usize) -> Option<usize> {
+        match component_type {
+            BYTE | UNSIGNED_BYTE => Some(1),
+            SHORT | UNSIGNED_SHORT => Some(2),
+            UNSIGNED_INT | FLOAT => Some(4),
+            _ => None,
+        }
+    }
+
+    fn raw(&self, element: usize, component: usize) -> &[u8] {
+        let size = Self::component_size(self.component_type).unwrap_or(0);
+        let start = self.offset + element * self.stride + component * size;
+        self.data.map_or(&[], |data| &data[start..start + size])
+    }
+
+    // Every value as f32, integer types mapped to [0, 1] or [-1, 1] when the
+    // accessor is normalized
+    fn floats(&self) -> Vec<f32> {
+        let mut values = Vec::with_capacity(self.count * self.components);
+        for element in 0..self.count {
+            for component in 0..self.components {
+                let raw = self.raw(element, component);
+                let value = match (self.component_type, raw) {
+                    (_, []) => 0.0,
+                    (FLOAT, &[a, b, c, d]) => f32::from_le_bytes([a, b, c, d]),
+                    (UNSIGNED_BYTE, &[a]) if self.normalized => a as f32 / 255.0,
+                    (UNSIGNED_BYTE, &[a]) => a as f32,
+                    (BYTE, &[a]) if self.normalized => (a as i8 as f32 / 127.0).max(-1.0),
+                    (BYTE, &[a]) => a as i8 as f32,
+                    (UNSIGNED_SHORT, &[a, b]) if self.normalized => {
+                        u16::from_le_bytes([a, b]) as f32 / 65535.0
+                    }
+                    (UNSIGNED_SHORT, &[a, b]) => u16::from_le_bytes([a, b]) as f32,
+                    (SHORT, &[a, b]) if self.normalized => {
+                        (i16::from_le_bytes([a, b]) as f32 / 32767.0).max(-1.0)
+                    }
+                    (SHORT, &[a, b]) => i16::from_le_bytes([a, b]) as f32,
+                    (_, &[a, b, c, d]) => u32::from_le_bytes([a, b, c, d]) as f32,
+                    _ => 0.0,
+                };
+                values.push(value);
+            }
+        }
+        values
+    }
+
+    fn indices(&self) -> Result<Vec<u32>, GltfError> {
+        if self.components != 1 || self.component_type == FLOAT {
+            return Err(invalid("indices must be unsigned integer scalars"));
+        }
+        Ok((0..self.count)
+            .map(|element| match *self.raw(element, 0) {
+                [a] => a as u32,
+                [a, b] => u16::from_le_bytes([a, b]) as u32,
+                [a, b, c, d] => u32::from_le_bytes([a, b, c, d]),
+                _ => 0,
+            })
+            .collect())
+    }
+}
+
+// A primitive unrolled into triangle corners, ready for Mesh::with_uvs
+#[derive(Debug)]
+struct Geometry {
+    // Position and normal per corner, as Mesh::from_vertices takes them
+    vertices: Vec<f32>,
+    uvs: Option<Vec<f32>>,
+}
+
+struct Document<'a> {
+    json: &'a Value,
+    buffers: Vec<Vec<u8>>,
+    base: &'a Path,
+    // By image index; None where the image couldn't be decoded
+    textures: HashMap<usize, Option<Rc<Texture>>>,
+}
+
+impl<'a> Document<'a> {
+    // Element `index` of the top-level array `collection`
+    fn element(&self, collection: &str, index: usize) -> Result<&'a Value, GltfError> {
+        array(self.json, collection)
+            .get(index)
+            .ok_or_else(|| invalid(format!("{collection} {index} doesn't exist")))
+    }
+
+    // Nodes of the default scene; without any scenes, every node that isn't
+    // another's child
+    fn root_nodes(&self) -> Result<Vec<usize>, GltfError> {
+        let nodes = array(self.json, "nodes");
+        if array(self.json, "scenes").is_empty() {
+            let mut is_child = vec![false; nodes.len()];
+            for node in nodes {
+                for child in array(node, "children") {
+                    if let Some(flag) = child.as_usize().and_then(|c| is_child.get_mut(c)) {
+                        *flag = true;
+                    }
+                }
+            }
+            return Ok((0..nodes.len()).filter(|&i| !is_child[i]).collect());
+        }
+        let scene = usize_field(self.json, "scene")?.unwrap_or(0);
+        array(self.element("scenes", scene)?, "nodes")
+            .iter()
+            .map(|node| {
+                node.as_usize()
+                    .ok_or_else(|| invalid("invalid scene node index"))
+            })
+            .collect()
+    }
+
+    // Node `index` and its subtree. `ancestors` is the path from the root,
+    // used to reject cycles.
+    fn node(&mut self, index: usize, ancestors: &mut Vec<usize>) -> Result<SceneNode, GltfError> {
+        if ancestors.contains(&index) {
+            return Err(invalid(format!("node {index} is its own ancestor")));
+        }
+        let json = self.element("nodes", index)?;
+        let transform = node_transform(json)?;
+        let children: Vec<usize> = array(json, "children")
+            .iter()
+            .map(|child| {
+                child
+                    .as_usize()
+                    .ok_or_else(|| invalid("invalid child index"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let mut primitives = Vec::new();
+        if let Some(mesh) = usize_field(json, "mesh")? {
+            for primitive in array(self.element("meshes", mesh)?, "primitives") {
+                if let Some(node) = self.primitive(primitive)? {
+                    primitives.push(node);
+                }
+            }
+        }
+        let mut primitives = primitives.into_iter();
+        // Grouping nodes still need a mesh; an empty one draws nothing
+        let mut node = primitives
+            .next()
+            .unwrap_or_else(|| SceneNode::new(Mesh::from_vertices(&[])));
+        node.transform = transform;
+        node.children.extend(primitives);
+
+        ancestors.push(index);
+        for child in children {
+            let child = self.node(child, ancestors)?;
+            node.children.push(child);
+        }
+        ancestors.pop();
+        Ok(node)
+    }
+
+    // A triangle-list primitive as an untransformed node; None for other modes
+    fn primitive(&mut self, primitive: &Value) -> Result<Option<SceneNode>, GltfError> {
+        let Some(geometry) = self.geometry(primitive)? else {
+            return Ok(None);
+        };
+        let mesh = match geometry.uvs {
+            Some(uvs) => Mesh::with_uvs(&geometry.vertices, &uvs),
+            None => Mesh::from_vertices(&geometry.vertices),
+        };
+
+        let (material, texture) = match usize_field(primitive, "material")? {
+            Some(index) => self.material(index)?,
+            // The glTF default material is plain white
+            None => (Material::new(vec3(1.0, 1.0, 1.0)), None),
+        };
+        let mut node = SceneNode::new(mesh).with_material(material);
+        node.texture = texture;
+        Ok(Some(node))
+    }
+
+    // A triangle-list primitive's corners; None for other modes
+    fn geometry(&self, primitive: &Value) -> Result<Option<Geometry>, GltfError> {
+        if usize_field(primitive, "mode")?.unwrap_or(TRIANGLES) != TRIANGLES {
+            return Ok(None);
+        }
+        let attributes = primitive
+            .get("attributes")
+            .ok_or_else(|| invalid("primitive has no attributes"))?;
+        let attribute = |name: &str, components: usize| -> Result<Option<Vec<f32>>, GltfError> {
+            let Some(index) = usize_field(attributes, name)? else {
+                return Ok(None);
+            };
+            let accessor = self.accessor(index)?;
+            if accessor.components != components {
+                return Err(invalid(format!("{name} must have {components} components")));
+            }
+            Ok(Some(accessor.floats()))
+        };
+        let positions =
+            attribute("POSITION", 3)?.ok_or_else(|| invalid("primitive has no POSITION"))?;
+        let normals = attribute("NORMAL", 3)?;
+        let uvs = attribute("TEXCOORD_0", 2)?;
+        let vertex_count = positions.len() / 3;
+        // Indices are checked against POSITION, so the others must match it
+        for (name, values, components) in [("NORMAL", &normals, 3), ("TEXCOORD_0", &uvs, 2)] {
+            if let Some(values) = values
+                && values.len() / components != vertex_count
+            {
+                return Err(invalid(format!(
+                    "{name} has {} elements but POSITION has {vertex_count}",
+                    values.len() / components
+                )));
+            }
+        }
+        let indices = match usize_field(primitive, "indices")? {
+            Some(index) => self.accessor(index)?.indices()?,
+            None => (0..vertex_count as u32).collect(),
+        };
+        if indices.iter().any(|&i| i as usize >= vertex_count) {
+            return Err(invalid("primitive index out of range"));
+        }
+
+        let position = |i: u32| {
+            let i = i as usize * 3;
+            vec3(positions[i], positions[i + 1], positions[i + 2])
+        };
+        let mut vertices = Vec::with_capacity(indices.len() * 6);
+        let mut corner_uvs = Vec::with_capacity(indices.len() * 2);
+        // Trailing indices that don't make a whole triangle are dropped
+        for triangle in indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| position(triangle[corner]));
+            let flat = (b - a).cross(&(c - a));
+            let flat = if flat.norm() > f32::EPSILON {
+                flat.normalize()
+            } else {
+                Vec3::zeros()
+            };
+            for &i in triangle {
+                let p = position(i);
+                let n = normals.as_ref().map_or(flat, |normals| {
+                    let i = i as usize * 3;
+                    vec3(normals[i], normals[i + 1], normals[i + 2])
+                });
+                vertices.extend_from_slice(&[p.x, p.y, p.z, n.x, n.y, n.z]);
+                if let Some(uvs) = &uvs {
+                    let i = i as usize * 2;
+                    // glTF puts v = 0 at the top of the image, GL at the bottom
+                    corner_uvs.extend_from_slice(&[uvs[i], 1.0 - uvs[i + 1]]);
+                }
+            }
+        }
+        Ok(Some(Geometry {
+            vertices,
+            uvs: uvs.map(|_| corner_uvs),
+        }))
+    }
+
+    fn accessor(&self, index: usize) -> Result<Accessor<'_>, GltfError> {
+        let json = self.element("accessors", index)?;
+        if json.get("sparse").is_some() {
+            return Err(GltfError::Unsupported("sparse accessors".to_string()));
+        }
+        let count = usize_field(json, "count")?.unwrap_or(0);
+        let components = match json.get("type").and_then(Value::as_str) {
+            Some("SCALAR") => 1,
+            Some("VEC2") => 2,
+            Some("VEC3") => 3,
+            Some("VEC4") => 4,
+            other => {
+                return Err(GltfError::Unsupported(format!(
+                    "accessor type {}",
+                    other.unwrap_or("(none)")
+                )));
+            }
+        };
+        let component_type = usize_field(json, "componentType")?.unwrap_or(0);
+        let size = Accessor::component_size(component_type)
+            .ok_or_else(|| invalid(format!("component type {component_type}")))?
+            * components;
+        if count.checked_mul(size).is_none() {
+            return Err(invalid(format!("accessor {index} is too large")));
+        }
+        let normalized = json
+            .get("normalized")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let mut accessor = Accessor {
+            data: None,
+            offset: 0,
+            stride: size,
+            count,
+            components,
+            component_type,
+            normalized,
+        };
+        let Some(view_index) = usize_field(json, "bufferView")? else {
+            return Ok(accessor);
+        };
+
+        let view = self.element("bufferViews", view_index)?;
+        let view_data = self.buffer_view(view_index)?;
+        accessor.stride = usize_field(view, "byteStride")?.unwrap_or(size);
+        accessor.offset = usize_field(json, "byteOffset")?.unwrap_or(0);
+        // Every value here comes from the file, so overflow counts as out of range
+        let end = match count {
+            0 => Some(accessor.offset),
+            _ => (count - 1)
+                .checked_mul(accessor.stride)
+                .and_then(|n| n.checked_add(size))
+                .and_then(|n| n.checked_add(accessor.offset)),
+        };
+        if end.is_none_or(|end| end > view_data.len()) {
+            return Err(invalid(format!(
+                "accessor {index} reads past the end of its buffer view"
+            )));
+        }
+        accessor.data = Some(view_data);
+        Ok(accessor)
+    }
+
+    fn buffer_view(&self, index: usize) -> Result<&[u8], GltfError> {
+        let view = self.element("bufferViews", index)?;
+        let buffer = usize_field(view, "buffer")?
+            .ok_or_else(|| invalid(format!("buffer view {index} has no buffer")))?;
+        let data = self
+            .buffers
+            .get(buffer)
+            .ok_or_else(|| invalid(format!("buffers {buffer} doesn't exist")))?;
+        let offset = usize_field(view, "byteOffset")?.unwrap_or(0);
+        let length = usize_field(view, "byteLength")?.unwrap_or(0);
+        offset
+            .checked_add(length)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid(format!("buffer view {index} is out of range")))
+    }
+
+    fn material(&mut self, index: usize) -> Result<(Material, Option<Rc<Texture>>), GltfError> {
+        let json = self.element("materials", index)?;
+        let pbr = json.get("pbrMetallicRoughness");
+        let [r, g, b, a] = match pbr {
+            Some(pbr) => floats::<4>(pbr, "baseColorFactor")?.unwrap_or([1.0; 4]),
+            None => [1.0; 4],
+        };
+        let [er, eg, eb] = floats::<3>(json, "emissiveFactor")?.unwrap_or([0.0; 3]);
+        let texture = match pbr
+            .and_then(|pbr| pbr.get("baseColorTexture"))
+            .map(|info| usize_field(info, "index"))
+            .transpose()?
+            .flatten()
+        {
+            Some(texture) => self.texture(texture)?,
+            None => None,
+        };
+        // Alpha only applies in BLEND mode; MASK cutouts are drawn opaque
+        let blended = json.get("alphaMode").and_then(Value::as_str) == Some("BLEND");
+        let material = Material {
+            color: vec3(r, g, b),
+            emissive: vec3(er, eg, eb),
+            alpha: if blended { a } else { 1.0 },
+            ..Material::default()
+        };
+        Ok((material, texture))
+    }
+
+    fn texture(&mut self, index: usize) -> Result<Option<Rc<Texture>>, GltfError> {
+        let source = usize_field(self.element("textures", index)?, "source")?;
+        let Some(source) = source else {
+            return Ok(None);
+        };
+        if let Some(texture) = self.textures.get(&source) {
+            return Ok(texture.clone());
+        }
+
+        let json = self.element("images", source)?;
+        let data = match (
+            json.get("uri").and_then(Value::as_str),
+            usize_field(json, "bufferView")?,
+        ) {
+            (Some(uri), _) => read_uri(uri, self.base)?,
+            (None, Some(view)) => self.buffer_view(view)?.to_vec(),
+            (None, None) => return Err(invalid(format!("image {source} has no data"))),
+        };
+        let texture = match image::decode_bytes(&data) {
+            Ok(decoded) => Some(Rc::new(Texture::from_rgba(
+                decoded.width,
+                decoded.height,
+                &decoded.pixels,
+            ))),
+            Err(err) => {
+                eprintln!("glTF image {source} left untextured: {err}");
+                None
+            }
+        };
+        self.textures.insert(source, texture.clone());
+        Ok(texture)
+    }
+}
+
+// A node's local transform, from `matrix` or translation/rotation/scale
+fn node_transform(node: &Value) -> Result<Mat4, GltfError> {
+    if let Some(matrix) = floats::<16>(node, "matrix")? {
+        // Stored column by column, as nalgebra expects
+        return Ok(Mat4::from_column_slice(&matrix));
+    }
+    let mut transform = Transform::new();
+    if let Some([x, y, z]) = floats::<3>(node, "translation")? {
+        transform.position = vec3(x, y, z);
+    }
+    if let Some([x, y, z, w]) = floats::<4>(node, "rotation")? {
+        transform.rotation = glm::quat_normalize(&glm::quat(x, y, z, w));
+    }
+    if let Some([x, y, z]) = floats::<3>(node, "scale")? {
+        transform.scale = vec3(x, y, z);
+    }
+    Ok(transform.to_matrix())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One triangle: POSITION (36 bytes), TEXCOORD_0 (24) and u16 indices (6,
+    // padded to 8)
+    fn buffer() -> Vec<u8> {
+        let floats = [
+            0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0,
+        ];
+        let mut data: Vec<u8> = floats.iter().flat_map(|f| f.to_le_bytes()).collect();
+        data.extend([0u16, 1, 2, 0].iter().flat_map(|i| i.to_le_bytes()));
+        data
+    }
+
+    const VIEWS: &str = r#"[
+        {"buffer": 0, "byteLength": 36},
+        {"buffer": 0, "byteOffset": 36, "byteLength": 24},
+        {"buffer": 0, "byteOffset": 60, "byteLength": 6}
+    ]"#;
+    const ACCESSORS: &str = r#"[
+        {"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"},
+        {"bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2"},
+        {"bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR"}
+    ]"#;
+    const PRIMITIVE: &str = r#"{"attributes": {"POSITION": 0, "TEXCOORD_0": 1}, "indices": 2}"#;
+
+    fn gltf(buffer: &str, views: &str, accessors: &str, primitive: &str) -> String {
+        format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "buffers": [{buffer}],
+                "bufferViews": {views},
+                "accessors": {accessors},
+                "meshes": [{{"primitives": [{primitive}]}}],
+                "nodes": [{{"mesh": 0}}]
+            }}"#
+        )
+    }
+
+    fn base64(data: &[u8]) -> String {
+        const DIGITS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut text = String::new();
+        for group in data.chunks(3) {
+            let bits = group
+                .iter()
+                .enumerate()
+                .fold(0u32, |bits, (i, &b)| bits | (b as u32) << (16 - 8 * i));
+            for i in 0..4 {
+                text.push(if i <= group.len() {
+                    DIGITS[(bits >> (18 - 6 * i) & 63) as usize] as char
+                } else {
+                    '='
+                });
+            }
+        }
+        text
+    }
+
+    fn embedded_buffer() -> String {
+        format!(
+            r#"{{"byteLength": 68, "uri": "data:application/octet-stream;base64,{}"}}"#,
+            base64(&buffer())
+        )
+    }
+
+    // A .gltf with the buffer embedded as base64
+    fn embedded(views: &str, accessors: &str, primitive: &str) -> Vec<u8> {
+        gltf(&embedded_buffer(), views, accessors, primitive).into_bytes()
+    }
+
+    // ACCESSORS with `extra` appended as accessor 3
+    fn with_accessor(extra: &str) -> String {
+        format!("[{}, {extra}]", &ACCESSORS[1..ACCESSORS.len() - 1])
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let chunk = |kind: u32, data: &[u8], pad: u8| {
+            let mut chunk = Vec::new();
+            let length = data.len().next_multiple_of(4);
+            chunk.extend((length as u32).to_le_bytes());
+            chunk.extend(kind.to_le_bytes());
+            chunk.extend(data);
+            chunk.resize(8 + length, pad);
+            chunk
+        };
+        let mut chunks = chunk(JSON_CHUNK, json.as_bytes(), b' ');
+        chunks.extend(chunk(BIN_CHUNK, bin, 0));
+        let mut data = GLB_MAGIC.to_vec();
+        data.extend(2u32.to_le_bytes());
+        data.extend((12 + chunks.len() as u32).to_le_bytes());
+        data.extend(chunks);
+        data
+    }
+
+    // The first primitive of the first mesh, read from a file's contents
+    fn geometry(data: &[u8], base: &Path) -> Result<Geometry, GltfError> {
+        let (json, buffers) = read_gltf(data, base)?;
+        let document = Document {
+            json: &json,
+            buffers,
+            base,
+            textures: HashMap::new(),
+        };
+        let primitive = &array(document.element("meshes", 0)?, "primitives")[0];
+        Ok(document.geometry(primitive)?.expect("a triangle list"))
+    }
+
+    fn assert_triangle(geometry: Geometry) {
+        // Flat normals face +Z for this counter-clockwise triangle
+        #[rustfmt::skip]
+        let vertices = [
+            0.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+            1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+            0.0, 1.0, 0.0, 0.0, 0.0, 1.0,
+        ];
+        assert_eq!(geometry.vertices, vertices);
+        // V is flipped to GL's bottom-up convention
+        assert_eq!(geometry.uvs, Some(vec![0.0, 1.0, 1.0, 1.0, 0.0, 0.0]));
+    }
+
+    fn error(data: &[u8]) -> GltfError {
+        geometry(data, Path::new("")).unwrap_err()
+    }
+
+    fn assert_invalid(data: &[u8], message: &str) {
+        match error(data) {
+            GltfError::Invalid(m) if m.contains(message) => {}
+            err => panic!("expected an invalid glTF error about `{message}`, got {err:?}"),
+        }
+    }
+
+    #[test]
+    fn reads_embedded_buffers() {
+        let data = embedded(VIEWS, ACCESSORS, PRIMITIVE);
+        assert_triangle(geometry(&data, Path::new("")).unwrap());
+    }
+
+    #[test]
+    fn reads_glb_files() {
+        let json = gltf(r#"{"byteLength": 68}"#, VIEWS, ACCESSORS, PRIMITIVE);
+        assert_triangle(geometry(&glb(&json, &buffer()), Path::new("")).unwrap());
+    }
+
+    #[test]
+    fn reads_external_buffers_relative_to_the_file() {
+        let dir = std::env::temp_dir().join(format!("x3d-gltf-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("tri angle.bin"), buffer()).unwrap();
+        let json = gltf(
+            r#"{"byteLength": 68, "uri": "tri%20angle.bin"}"#,
+            VIEWS,
+            ACCESSORS,
+            PRIMITIVE,
+        );
+        let result = geometry(json.as_bytes(), &dir);
+
+        // The loader itself fails on the same file before touching GL
+        let path = dir.join("short.gltf");
+        let accessors = with_accessor(
+            r#"{"bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3"}"#,
+        );
+        let primitive = r#"{"attributes": {"POSITION": 0, "NORMAL": 3}}"#;
+        std::fs::write(&path, embedded(VIEWS, &accessors, primitive)).unwrap();
+        let loaded = load_gltf(&path);
+        let missing = load_gltf(dir.join("missing.gltf"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_triangle(result.unwrap());
+        assert!(matches!(loaded, Err(GltfError::Invalid(_))));
+        assert!(matches!(missing, Err(GltfError::Io(_))));
+    }
+
+    #[test]
+    fn rejects_attributes_shorter_than_position() {
+        let short_normals = with_accessor(
+            r#"{"bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3"}"#,
+        );
+        let primitive = r#"{"attributes": {"POSITION": 0, "NORMAL": 3}}"#;
+        assert_invalid(
+            &embedded(VIEWS, &short_normals, primitive),
+            "NORMAL has 2 elements but POSITION has 3",
+        );
+
+        let short_uvs = with_accessor(
+            r#"{"bufferView": 1, "componentType": 5126, "count": 2, "type": "VEC2"}"#,
+        );
+        let primitive = r#"{"attributes": {"POSITION": 0, "TEXCOORD_0": 3}}"#;
+        assert_invalid(
+            &embedded(VIEWS, &short_uvs, primitive),
+            "TEXCOORD_0 has 2 elements but POSITION has 3",
+        );
+    }
+
+    #[test]
+    fn rejects_indices_past_the_vertices() {
+        let two_positions = with_accessor(
+            r#"{"bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3"}"#,
+        );
+        let primitive = r#"{"attributes": {"POSITION": 3}, "indices": 2}"#;
+        assert_invalid(
+            &embedded(VIEWS, &two_positions, primitive),
+            "index out of range",
+        );
+    }
+
+    #[test]
+    fn rejects_accessors_outside_their_views() {
+        let primitive = r#"{"attributes": {"POSITION": 3}}"#;
+        for (accessor, message) in [
+            // count * element size overflows
+            (
+                r#"{"bufferView": 0, "componentType": 5126, "count": 4611686018427387904, "type": "VEC3"}"#,
+                "too large",
+            ),
+            // byteOffset + the elements overflows
+            (
+                r#"{"bufferView": 0, "byteOffset": 18446744073709551615, "componentType": 5126, "count": 3, "type": "VEC3"}"#,
+                "reads past the end",
+            ),
+            (
+                r#"{"bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3"}"#,
+                "reads past the end",
+            ),
+            (
+                r#"{"bufferView": 0, "byteOffset": 4, "componentType": 5126, "count": 3, "type": "VEC3"}"#,
+                "reads past the end",
+            ),
+        ] {
+            assert_invalid(
+                &embedded(VIEWS, &with_accessor(accessor), primitive),
+                message,
+            );
+        }
+
+        // (count - 1) * byteStride overflows
+        let views = r#"[{"buffer": 0, "byteLength": 36, "byteStride": 4611686018427387904}]"#;
+        let accessors = r#"[{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}]"#;
+        let primitive = r#"{"attributes": {"POSITION": 0}}"#;
+        assert_invalid(&embedded(views, accessors, primitive), "reads past the end");
+    }
+
+    #[test]
+    fn rejects_views_outside_their_buffers() {
+        let accessors = r#"[{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3"}]"#;
+        let primitive = r#"{"attributes": {"POSITION": 0}}"#;
+        for (view, message) in [
+            (r#"{"buffer": 0, "byteLength": 100}"#, "out of range"),
+            (
+                r#"{"buffer": 0, "byteOffset": 40, "byteLength": 36}"#,
+                "out of range",
+            ),
+            (
+                r#"{"buffer": 0, "byteOffset": 18446744073709551615, "byteLength": 36}"#,
+                "out of range",
+            ),
+            (r#"{"buffer": 1, "byteLength": 36}"#, "doesn't exist"),
+            (r#"{"byteLength": 36}"#, "has no buffer"),
+        ] {
+            assert_invalid(
+                &embedded(&format!("[{view}]"), accessors, primitive),
+                message,
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_bad_buffers() {
+        let short = r#"{"byteLength": 100, "uri": "data:;base64,AAAA"}"#;
+        let data = gltf(short, VIEWS, ACCESSORS, PRIMITIVE);
+        assert_invalid(data.as_bytes(), "holds 3 bytes, expected 100");
+
+        let malformed = r#"{"byteLength": 1, "uri": "data:;base64,A!AA"}"#;
+        let data = gltf(malformed, VIEWS, ACCESSORS, PRIMITIVE);
+        assert_invalid(data.as_bytes(), "malformed base64");
+
+        let missing = r#"{"byteLength": 68}"#;
+        let data = gltf(missing, VIEWS, ACCESSORS, PRIMITIVE);
+        assert_invalid(data.as_bytes(), "buffer 0 has no data");
+    }
+
+    #[test]
+    fn rejects_malformed_containers() {
+        let json = gltf(r#"{"byteLength": 68}"#, VIEWS, ACCESSORS, PRIMITIVE);
+        let file = glb(&json, &buffer());
+        for len in [8, 20, file.len() - 80] {
+            assert!(
+                matches!(error(&file[..len]), GltfError::Parse(_)),
+                "accepted {len} bytes"
+            );
+        }
+
+        let mut no_json = file.clone();
+        no_json[16..20].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        assert!(matches!(error(&no_json), GltfError::Parse(m) if m.contains("no JSON chunk")));
+
+        let mut version = file;
+        version[4] = 1;
+        assert!(matches!(error(&version), GltfError::Unsupported(_)));
+
+        assert!(matches!(error(b"{\"asset\": "), GltfError::Parse(_)));
+        assert!(matches!(error(b"\xff{}"), GltfError::Parse(_)));
+    }
+
+    #[test]
+    fn rejects_unsupported_documents() {
+        let old = br#"{"asset": {"version": "1.0"}}"#;
+        assert!(matches!(error(old), GltfError::Unsupported(m) if m.contains("1.0")));
+
+        let extension = br#"{"asset": {"version": "2.0"}, "extensionsRequired": ["KHR_draco_mesh_compression"]}"#;
+        assert!(matches!(
+            error(extension),
+            GltfError::Unsupported(m) if m.contains("KHR_draco_mesh_compression")
+        ));
+    }
+}
@@ -0,0 +1,45 @@
+use crate::lines::LineRenderer;
+use glm::{Mat4, Vec3, vec3};
+
+// Reference floor for judging orientation while orbiting: an NxN grid of
+// lines on the y = 0 plane, centered on the origin. It is depth tested, so
+// geometry below the floor hides it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    // Cells along each side
+    pub cells: u32,
+    // World units between neighboring lines
+    pub spacing: f32,
+    pub color: Vec3,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid {
+            cells: 20,
+            spacing: 0.5,
+            color: vec3(0.45, 0.45, 0.45),
+        }
+    }
+}
+
+impl Grid {
+    // Endpoints of every line: cells + 1 running along x, then as many along z
+    pub fn line_vertices(&self) -> Vec<(Vec3, Vec3)> {
+        let spacing = self.spacing.max(1e-4);
+        let half = self.cells as f32 * spacing * 0.5;
+        let offsets = (0..=self.cells).map(|i| i as f32 * spacing - half);
+        let along_x = offsets
+            .clone()
+            .map(|z| (vec3(-half, 0.0, z), vec3(half, 0.0, z)));
+        let along_z = offsets.map(|x| (vec3(x, 0.0, -half), vec3(x, 0.0, half)));
+        along_x.chain(along_z).collect()
+    }
+}
+
+pub(crate) fn render(grid: &Grid, lines: &mut LineRenderer, view: &Mat4, projection: &Mat4) {
+    for (start, end) in grid.line_vertices() {
+        lines.line(&start, &end, &grid.color);
+    }
+    lines.flush(view, projection);
+}
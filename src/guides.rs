@@ -0,0 +1,81 @@
+use crate::lines::LineRenderer;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec3, vec3};
+
+// Screen-space composition overlay for framing captures. Guides are drawn
+// inside the safe frame, so thirds and center match the cropped output.
+// They are left out of screenshots.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompositionGuides {
+    pub center_cross: bool,
+    pub rule_of_thirds: bool,
+    // Output aspect ratio (width / height) to outline; None uses the whole pane
+    pub safe_frame: Option<f32>,
+    pub color: Vec3,
+}
+
+impl Default for CompositionGuides {
+    fn default() -> Self {
+        CompositionGuides {
+            center_cross: true,
+            rule_of_thirds: true,
+            safe_frame: Some(16.0 / 9.0),
+            color: vec3(0.85, 0.85, 0.85),
+        }
+    }
+}
+
+impl CompositionGuides {
+    // The largest rectangle of the safe-frame aspect centered in a
+    // width x height frame, as (min, max) NDC corners
+    fn frame(&self, width: f32, height: f32) -> ([f32; 2], [f32; 2]) {
+        let Some(aspect) = self.safe_frame.filter(|a| *a > 0.0) else {
+            return ([-1.0, -1.0], [1.0, 1.0]);
+        };
+        let current = width / height;
+        let (x, y) = if aspect > current {
+            (1.0, current / aspect)
+        } else {
+            (aspect / current, 1.0)
+        };
+        ([-x, -y], [x, y])
+    }
+}
+
+pub(crate) fn render(guides: &CompositionGuides, lines: &mut LineRenderer, target: &FrameTarget) {
+    let (width, height) = (target.width.max(1) as f32, target.height.max(1) as f32);
+    let ([x0, y0], [x1, y1]) = guides.frame(width, height);
+    let color = &guides.color;
+    let at = |x: f32, y: f32| vec3(x, y, 0.0);
+
+    if guides.safe_frame.is_some() {
+        lines.line(&at(x0, y0), &at(x1, y0), color);
+        lines.line(&at(x1, y0), &at(x1, y1), color);
+        lines.line(&at(x1, y1), &at(x0, y1), color);
+        lines.line(&at(x0, y1), &at(x0, y0), color);
+    }
+    if guides.rule_of_thirds {
+        for t in [1.0 / 3.0, 2.0 / 3.0] {
+            let x = x0 + (x1 - x0) * t;
+            let y = y0 + (y1 - y0) * t;
+            lines.line(&at(x, y0), &at(x, y1), color);
+            lines.line(&at(x0, y), &at(x1, y), color);
+        }
+    }
+    if guides.center_cross {
+        // 12 pixels each way from the center of the frame
+        let (cx, cy) = ((x0 + x1) * 0.5, (y0 + y1) * 0.5);
+        let (dx, dy) = (24.0 / width, 24.0 / height);
+        lines.line(&at(cx - dx, cy), &at(cx + dx, cy), color);
+        lines.line(&at(cx, cy - dy), &at(cx, cy + dy), color);
+    }
+
+    let identity = Mat4::identity();
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+    }
+    lines.flush(&identity, &identity);
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
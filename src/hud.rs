@@ -0,0 +1,51 @@
+use crate::font;
+use crate::lines::LineRenderer;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec2, vec2, vec3};
+
+// On-screen size of one font pixel
+const SCALE: f32 = 2.0;
+// Distance from the corner of the frame, in pixels
+const MARGIN: f32 = 8.0;
+
+// Status lines stacked in the top-left corner of the frame on a dark backing,
+// e.g. inspection modes that change what's on screen
+pub(crate) fn render(status: &[String], lines: &mut LineRenderer, target: &FrameTarget) {
+    if status.is_empty() {
+        return;
+    }
+    let size = vec2(target.width.max(1) as f32, target.height.max(1) as f32);
+    let at = |p: Vec2| vec3(p.x / size.x * 2.0 - 1.0, p.y / size.y * 2.0 - 1.0, 0.0);
+
+    let text = status.join("\n");
+    let (width, height) = font::text_size(&text);
+    let padding = 2.0 * SCALE;
+    let top = size.y - MARGIN;
+    let (min, max) = (
+        vec2(MARGIN - padding, top - height as f32 * SCALE - padding),
+        vec2(MARGIN + width as f32 * SCALE + padding, top + padding),
+    );
+    let backing = vec3(0.0, 0.0, 0.0);
+    let (a, b) = (at(min), at(vec2(max.x, min.y)));
+    let (c, d) = (at(max), at(vec2(min.x, max.y)));
+    lines.triangle(&a, &b, &c, &backing);
+    lines.triangle(&a, &c, &d, &backing);
+
+    font::queue_text(
+        &text,
+        &vec2(MARGIN, top),
+        SCALE,
+        &vec3(1.0, 0.85, 0.3),
+        lines,
+        &at,
+    );
+
+    let identity = Mat4::identity();
+    unsafe {
+        gl::Disable(gl::DEPTH_TEST);
+    }
+    lines.flush(&identity, &identity);
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
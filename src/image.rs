@@ -0,0 +1,181 @@
+use crate::{jpeg, png};
+use std::error::Error;
+use std::path::Path;
+
+// Reads an image file into RGBA8 pixels; runs on the texture worker thread
+pub type ImageDecoder = fn(&Path) -> Result<Image, Box<dyn Error>>;
+
+// Tightly packed RGBA8 pixels, bottom row first as Texture::from_rgba expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    // Builds an image from rows stored top first, the usual file order
+    pub(crate) fn from_top_rows(width: u32, height: u32, rgba: Vec<u8>) -> Image {
+        let row = width as usize * 4;
+        let pixels = rgba.chunks_exact(row).rev().flatten().copied().collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+}
+
+// Built-in decoder, chosen by the file's signature: PNG (non-interlaced),
+// baseline JPEG, binary PPM/PGM (P6/P5, 8-bit) and uncompressed or RLE TGA
+// (true-color and grayscale)
+pub fn decode(path: &Path) -> Result<Image, Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    decode_bytes(&data).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+pub(crate) fn decode_bytes(data: &[u8]) -> Result<Image, String> {
+    match data.get(..4) {
+        Some(b"\x89PNG") => png::decode(data),
+        Some([0xFF, 0xD8, 0xFF, _]) => jpeg::decode(data),
+        _ => match data.get(..2) {
+            Some(b"P5") | Some(b"P6") => decode_netpbm(data),
+            _ => decode_tga(data),
+        },
+    }
+}
+
+fn decode_netpbm(data: &[u8]) -> Result<Image, String> {
+    let channels = if data[1] == b'6' { 3 } else { 1 };
+
+    // Header: magic, width, height, maxval separated by whitespace, with
+    // '#' comments running to the end of the line
+    let mut fields = [0u32; 3];
+    let mut pos = 2;
+    for field in &mut fields {
+        loop {
+            match data.get(pos) {
+                Some(b'#') => {
+                    while data.get(pos).is_some_and(|&c| c != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(u8::is_ascii_digit) {
+            pos += 1;
+        }
+        *field = std::str::from_utf8(&data[start..pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or("malformed PNM header")?;
+    }
+    let [width, height, maxval] = fields;
+    if width == 0 || height == 0 {
+        return Err("empty image".to_string());
+    }
+    if maxval == 0 || maxval > 255 {
+        return Err(format!("unsupported PNM maxval {maxval}"));
+    }
+    // Exactly one whitespace byte separates the header from the pixels
+    pos += 1;
+
+    let count = width as usize * height as usize;
+    let body = data
+        .get(pos..pos + count * channels)
+        .ok_or("truncated PNM pixel data")?;
+    let scale = |v: u8| (v as u32 * 255 / maxval) as u8;
+    let rgba = body
+        .chunks_exact(channels)
+        .flat_map(|p| match *p {
+            [r, g, b] => [scale(r), scale(g), scale(b), 255],
+            [l] => [scale(l), scale(l), scale(l), 255],
+            _ => unreachable!(),
+        })
+        .collect();
+    Ok(Image::from_top_rows(width, height, rgba))
+}
+
+fn decode_tga(data: &[u8]) -> Result<Image, String> {
+    let header = data.get(..18).ok_or("not a PNM or TGA file")?;
+    let id_length = header[0] as usize;
+    let color_map_type = header[1];
+    let image_type = header[2];
+    let width = u16::from_le_bytes([header[12], header[13]]) as u32;
+    let height = u16::from_le_bytes([header[14], header[15]]) as u32;
+    let bits = header[16];
+    let top_first = header[17] & 0x20 != 0;
+    if width == 0 || height == 0 {
+        return Err("empty image".to_string());
+    }
+
+    let (rle, gray) = match image_type {
+        2 => (false, false),
+        3 => (false, true),
+        10 => (true, false),
+        11 => (true, true),
+        _ => return Err(format!("unsupported TGA image type {image_type}")),
+    };
+    if color_map_type != 0 {
+        return Err("color-mapped TGA is not supported".to_string());
+    }
+    let bytes = match (gray, bits) {
+        (true, 8) => 1,
+        (false, 24) => 3,
+        (false, 32) => 4,
+        _ => return Err(format!("unsupported TGA pixel depth {bits}")),
+    };
+
+    // Pixels are BGR(A) or gray
+    let to_rgba = |p: &[u8]| match *p {
+        [l] => [l, l, l, 255],
+        [b, g, r] => [r, g, b, 255],
+        [b, g, r, a] => [r, g, b, a],
+        _ => unreachable!(),
+    };
+
+    let count = width as usize * height as usize;
+    let mut body = data.get(18 + id_length..).ok_or("truncated TGA header")?;
+    let mut rgba = Vec::with_capacity(count * 4);
+    if rle {
+        // Packets: a run of one repeated pixel or a span of raw pixels
+        while rgba.len() < count * 4 {
+            let (&packet, rest) = body.split_first().ok_or("truncated TGA pixel data")?;
+            let length = (packet & 0x7F) as usize + 1;
+            let payload = if packet & 0x80 != 0 {
+                bytes
+            } else {
+                length * bytes
+            };
+            let pixels = rest.get(..payload).ok_or("truncated TGA pixel data")?;
+            if packet & 0x80 != 0 {
+                let pixel = to_rgba(pixels);
+                for _ in 0..length {
+                    rgba.extend_from_slice(&pixel);
+                }
+            } else {
+                rgba.extend(pixels.chunks_exact(bytes).flat_map(to_rgba));
+            }
+            body = &rest[payload..];
+        }
+        rgba.truncate(count * 4);
+    } else {
+        let pixels = body
+            .get(..count * bytes)
+            .ok_or("truncated TGA pixel data")?;
+        rgba.extend(pixels.chunks_exact(bytes).flat_map(to_rgba));
+    }
+
+    if top_first {
+        Ok(Image::from_top_rows(width, height, rgba))
+    } else {
+        Ok(Image {
+            width,
+            height,
+            pixels: rgba,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use glfw::{Action, Key, MouseButton, WindowEvent};
+use std::collections::HashSet;
+
+// Keyboard and mouse state gathered from the window's events, for custom
+// controls written against held keys and cursor motion instead of
+// individual events. Refreshed once per frame, before the update closure.
+#[derive(Debug, Clone, Default)]
+pub struct InputState {
+    keys_down: HashSet<Key>,
+    // Went down this frame, not counting key-repeat
+    keys_pressed: HashSet<Key>,
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    // Window coordinates in screen units, origin top-left; None until the
+    // cursor first moves over the window
+    cursor: Option<(f64, f64)>,
+    mouse_delta: (f64, f64),
+    scroll_delta: (f64, f64),
+}
+
+impl InputState {
+    // True every frame `key` is held
+    pub fn is_key_down(&self, key: Key) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    // True only in the frame `key` went down
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    pub fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    pub fn cursor_position(&self) -> Option<(f64, f64)> {
+        self.cursor
+    }
+
+    // Cursor movement since the previous frame, +y down
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    // Scroll wheel movement since the previous frame
+    pub fn scroll_delta(&self) -> (f64, f64) {
+        self.scroll_delta
+    }
+
+    // Forgets the previous frame's edges and motion; call before its events
+    pub(crate) fn begin_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.buttons_pressed.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll_delta = (0.0, 0.0);
+    }
+
+    pub(crate) fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, _, Action::Press, _) => {
+                self.keys_down.insert(key);
+                self.keys_pressed.insert(key);
+            }
+            WindowEvent::Key(key, _, Action::Release, _) => {
+                self.keys_down.remove(&key);
+            }
+            WindowEvent::MouseButton(button, Action::Press, _) => {
+                self.buttons_down.insert(button);
+                self.buttons_pressed.insert(button);
+            }
+            WindowEvent::MouseButton(button, Action::Release, _) => {
+                self.buttons_down.remove(&button);
+            }
+            WindowEvent::CursorPos(x, y) => {
+                if let Some((last_x, last_y)) = self.cursor {
+                    self.mouse_delta.0 += x - last_x;
+                    self.mouse_delta.1 += y - last_y;
+                }
+                self.cursor = Some((x, y));
+            }
+            WindowEvent::Scroll(x, y) => {
+                self.scroll_delta.0 += x;
+                self.scroll_delta.1 += y;
+            }
+            // Releases that happen while unfocused never arrive
+            WindowEvent::Focus(false) => {
+                self.keys_down.clear();
+                self.buttons_down.clear();
+            }
+            _ => {}
+        }
+    }
+}
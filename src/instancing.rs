@@ -0,0 +1,119 @@
+use crate::bounds::{Aabb, Frustum};
+use crate::material::Material;
+use crate::mesh::{INSTANCE_ATTRIBUTE, Mesh};
+use glm::Mat4;
+use std::mem;
+
+// An InstancedMesh added with X3D::add_instanced_mesh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InstancedMeshId(pub(crate) usize);
+
+// Many copies of one mesh drawn with a single instanced draw call per pass.
+// Each copy's model matrix is read from a per-instance vertex attribute
+// (INSTANCE_ATTRIBUTE and the three locations after it) instead of the
+// `model` uniform. Copies share the material and are frustum culled as a
+// group. Drawn by the forward, deferred and shadow passes with the built-in
+// shaders; per-node shader overrides, outlines, edge detection and motion
+// blur don't see them.
+pub struct InstancedMesh {
+    mesh: Mesh,
+    instance_vbo: u32,
+    count: usize,
+    // World-space bounds of every copy; None without instances
+    bounds: Option<Aabb>,
+    pub material: Material,
+}
+
+impl InstancedMesh {
+    pub fn new(mesh: Mesh) -> Self {
+        let mut instance_vbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut instance_vbo);
+            gl::BindVertexArray(mesh.vao());
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            // A mat4 attribute is four vec4 columns, advanced once per instance
+            let stride = mem::size_of::<Mat4>() as i32;
+            for column in 0..4 {
+                let location = INSTANCE_ATTRIBUTE + column;
+                gl::VertexAttribPointer(
+                    location,
+                    4,
+                    gl::FLOAT,
+                    gl::FALSE,
+                    stride,
+                    (column as usize * 4 * mem::size_of::<f32>()) as *const _,
+                );
+                gl::EnableVertexAttribArray(location);
+                gl::VertexAttribDivisor(location, 1);
+            }
+            gl::BindVertexArray(0);
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        InstancedMesh {
+            mesh,
+            instance_vbo,
+            count: 0,
+            bounds: None,
+            material: Material::default(),
+        }
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    // Replaces every copy's model matrix, re-uploading the whole buffer
+    pub fn set_instances(&mut self, transforms: &[Mat4]) {
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(transforms) as isize,
+                transforms.as_ptr() as *const _,
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+        }
+        self.count = transforms.len();
+        let local = self.mesh.bounds();
+        self.bounds = Aabb::from_points(
+            transforms
+                .iter()
+                .flat_map(|transform| local.transformed(transform).corners()),
+        );
+    }
+
+    pub fn instance_count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn bounds(&self) -> Option<Aabb> {
+        self.bounds
+    }
+
+    pub(crate) fn is_drawn(&self, frustum: &Frustum) -> bool {
+        self.bounds
+            .is_some_and(|bounds| frustum.intersects(&bounds))
+    }
+
+    // Every copy in one call; the `model` uniform should be the identity
+    pub(crate) fn draw(&self) {
+        if self.count > 0 {
+            self.mesh
+                .draw_instanced(i32::try_from(self.count).unwrap_or(i32::MAX));
+        }
+    }
+}
+
+impl Drop for InstancedMesh {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.instance_vbo);
+        }
+    }
+}
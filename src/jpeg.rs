@@ -0,0 +1,785 @@
+use crate::image::Image;
+
+// Position in an 8x8 block of each coefficient, in the order they're coded
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+struct Component {
+    id: u8,
+    // Sampling factors relative to the other components
+    h: usize,
+    v: usize,
+    quant: usize,
+    // Decoded samples, padded to whole MCUs; `stride` samples per row
+    plane: Vec<u8>,
+    stride: usize,
+    // Entropy tables for the current scan, and the running DC value
+    dc_table: usize,
+    ac_table: usize,
+    dc: i32,
+}
+
+// Decodes a baseline or extended sequential Huffman-coded JPEG (the kind
+// cameras and most tools write) into RGBA8, with 1 (gray) or 3 (YCbCr, or
+// RGB if an Adobe marker says so) components and any chroma subsampling.
+// Progressive, lossless, arithmetic-coded and 12-bit files are rejected.
+// Chroma is upsampled to the nearest sample; EXIF orientation is ignored.
+pub(crate) fn decode(data: &[u8]) -> Result<Image, String> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err("not a JPEG file".to_string());
+    }
+    let mut quant = [[0u16; 64]; 4];
+    let mut dc_tables: [Option<Huffman>; 4] = Default::default();
+    let mut ac_tables: [Option<Huffman>; 4] = Default::default();
+    let mut frame: Option<(usize, usize, Vec<Component>)> = None;
+    let mut restart_interval = 0;
+    let mut adobe_rgb = false;
+    let mut scanned = false;
+
+    let mut pos = 2;
+    loop {
+        let marker = next_marker(data, pos).ok_or("truncated JPEG")?;
+        pos = marker + 2;
+        let kind = data[marker + 1];
+        if kind == 0xD9 {
+            break;
+        }
+        let length = data
+            .get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+            .ok_or("truncated JPEG segment")?;
+        let body = data
+            .get(pos + 2..pos + length.max(2))
+            .ok_or("truncated JPEG segment")?;
+        pos += length;
+        match kind {
+            // Baseline and extended sequential, Huffman coded
+            0xC0 | 0xC1 => {
+                if frame.is_some() {
+                    return Err("more than one frame".to_string());
+                }
+                frame = Some(read_frame(body)?);
+            }
+            0xC2 => return Err("progressive JPEGs aren't supported".to_string()),
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                return Err(format!("unsupported JPEG coding (SOF{})", kind - 0xC0));
+            }
+            0xC4 => read_huffman_tables(body, &mut dc_tables, &mut ac_tables)?,
+            0xDB => read_quant_tables(body, &mut quant)?,
+            0xDD => {
+                restart_interval = body
+                    .get(..2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                    .ok_or("short DRI segment")?;
+            }
+            // Adobe's marker says whether 3 components are YCbCr (1) or RGB (0)
+            0xEE if body.starts_with(b"Adobe") && body.len() >= 12 => {
+                adobe_rgb = body[11] == 0;
+            }
+            0xDA => {
+                let (width, height, components) =
+                    frame.as_mut().ok_or("scan before the frame header")?;
+                let scan = ScanTables {
+                    quant: &quant,
+                    dc: &dc_tables,
+                    ac: &ac_tables,
+                };
+                pos = decode_scan(
+                    data,
+                    pos,
+                    body,
+                    (*width, *height),
+                    components,
+                    &scan,
+                    restart_interval,
+                )?;
+                scanned = true;
+            }
+            _ => {}
+        }
+    }
+
+    let (width, height, components) = frame.ok_or("missing frame header")?;
+    if !scanned {
+        return Err("no image data".to_string());
+    }
+    Ok(Image::from_top_rows(
+        width as u32,
+        height as u32,
+        to_rgba(width, height, &components, adobe_rgb),
+    ))
+}
+
+// Offset of the next marker's 0xFF at or after `pos`, skipping fill bytes
+// and the stuffed zeros and restart markers inside entropy-coded data
+fn next_marker(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let offset = data.get(pos..)?.iter().position(|&b| b == 0xFF)?;
+        pos += offset;
+        match *data.get(pos + 1)? {
+            0x00 | 0xD0..=0xD7 | 0xFF => pos += 1,
+            _ => return Some(pos),
+        }
+    }
+}
+
+fn read_frame(body: &[u8]) -> Result<(usize, usize, Vec<Component>), String> {
+    let header = body.get(..6).ok_or("short frame header")?;
+    if header[0] != 8 {
+        return Err(format!("unsupported sample precision {}", header[0]));
+    }
+    let height = u16::from_be_bytes([header[1], header[2]]) as usize;
+    let width = u16::from_be_bytes([header[3], header[4]]) as usize;
+    if width == 0 || height == 0 {
+        return Err(format!("unsupported size {}x{}", width, height));
+    }
+    let count = header[5] as usize;
+    if count != 1 && count != 3 {
+        return Err(format!("unsupported component count {}", count));
+    }
+    let specs = body.get(6..6 + count * 3).ok_or("short frame header")?;
+    let mut components: Vec<Component> = specs
+        .chunks_exact(3)
+        .map(|spec| Component {
+            id: spec[0],
+            h: (spec[1] >> 4) as usize,
+            v: (spec[1] & 15) as usize,
+            quant: spec[2] as usize,
+            plane: Vec::new(),
+            stride: 0,
+            dc_table: 0,
+            ac_table: 0,
+            dc: 0,
+        })
+        .collect();
+    if components
+        .iter()
+        .any(|c| !(1..=4).contains(&c.h) || !(1..=4).contains(&c.v) || c.quant > 3)
+    {
+        return Err("invalid component parameters".to_string());
+    }
+
+    // Planes cover whole MCUs, so every block written lands inside them
+    let (h_max, v_max) = sampling_max(&components);
+    let mcus_x = width.div_ceil(8 * h_max);
+    let mcus_y = height.div_ceil(8 * v_max);
+    for component in &mut components {
+        component.stride = mcus_x * component.h * 8;
+        component.plane = vec![0; component.stride * mcus_y * component.v * 8];
+    }
+    Ok((width, height, components))
+}
+
+fn sampling_max(components: &[Component]) -> (usize, usize) {
+    let h = components.iter().map(|c| c.h).max().unwrap_or(1);
+    let v = components.iter().map(|c| c.v).max().unwrap_or(1);
+    (h, v)
+}
+
+fn read_huffman_tables(
+    mut body: &[u8],
+    dc_tables: &mut [Option<Huffman>; 4],
+    ac_tables: &mut [Option<Huffman>; 4],
+) -> Result<(), String> {
+    while let Some((&info, rest)) = body.split_first() {
+        let counts = rest.get(..16).ok_or("short DHT segment")?;
+        let total: usize = counts.iter().map(|&c| c as usize).sum();
+        let symbols = rest.get(16..16 + total).ok_or("short DHT segment")?;
+        let (class, id) = (info >> 4, (info & 15) as usize);
+        let table = Huffman::new(counts, symbols);
+        match (class, dc_tables.get_mut(id)) {
+            (0, Some(slot)) => *slot = Some(table),
+            (1, Some(_)) => ac_tables[id] = Some(table),
+            _ => return Err(format!("invalid Huffman table {:#04x}", info)),
+        }
+        body = &rest[16 + total..];
+    }
+    Ok(())
+}
+
+fn read_quant_tables(mut body: &[u8], quant: &mut [[u16; 64]; 4]) -> Result<(), String> {
+    while let Some((&info, rest)) = body.split_first() {
+        let (precision, id) = (info >> 4, (info & 15) as usize);
+        let table = quant
+            .get_mut(id)
+            .ok_or(format!("invalid quantization table {}", id))?;
+        let size = if precision == 0 { 64 } else { 128 };
+        let values = rest.get(..size).ok_or("short DQT segment")?;
+        for (k, value) in table.iter_mut().enumerate() {
+            *value = match precision {
+                0 => values[k] as u16,
+                _ => u16::from_be_bytes([values[k * 2], values[k * 2 + 1]]),
+            };
+        }
+        body = &rest[size..];
+    }
+    Ok(())
+}
+
+struct ScanTables<'a> {
+    quant: &'a [[u16; 64]; 4],
+    dc: &'a [Option<Huffman>; 4],
+    ac: &'a [Option<Huffman>; 4],
+}
+
+// Decodes the entropy-coded data after a scan header into the components'
+// planes. Returns the offset of the marker that ends the scan.
+fn decode_scan(
+    data: &[u8],
+    start: usize,
+    header: &[u8],
+    (width, height): (usize, usize),
+    components: &mut [Component],
+    tables: &ScanTables,
+    restart_interval: usize,
+) -> Result<usize, String> {
+    let count = *header.first().ok_or("short scan header")? as usize;
+    let specs = header.get(1..1 + count * 2).ok_or("short scan header")?;
+    let mut members = Vec::with_capacity(count);
+    for spec in specs.chunks_exact(2) {
+        let index = components
+            .iter()
+            .position(|c| c.id == spec[0])
+            .ok_or(format!("scan names unknown component {}", spec[0]))?;
+        let component = &mut components[index];
+        component.dc_table = (spec[1] >> 4) as usize;
+        component.ac_table = (spec[1] & 15) as usize;
+        component.dc = 0;
+        if tables
+            .dc
+            .get(component.dc_table)
+            .is_none_or(Option::is_none)
+            || tables
+                .ac
+                .get(component.ac_table)
+                .is_none_or(Option::is_none)
+        {
+            return Err("scan uses a missing Huffman table".to_string());
+        }
+        members.push(index);
+    }
+    if members.is_empty() {
+        return Err("empty scan".to_string());
+    }
+
+    let (h_max, v_max) = sampling_max(components);
+    // A single-component scan isn't interleaved: its blocks run in raster
+    // order over just the area that component covers
+    let (units_x, units_y) = if members.len() == 1 {
+        let c = &components[members[0]];
+        (
+            (width * c.h).div_ceil(h_max).div_ceil(8),
+            (height * c.v).div_ceil(v_max).div_ceil(8),
+        )
+    } else {
+        (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max))
+    };
+
+    let mut reader = BitReader::new(data, start);
+    let mut coefficients = [0i32; 64];
+    let table = idct_table();
+    for unit in 0..units_x * units_y {
+        if restart_interval > 0 && unit > 0 && unit % restart_interval == 0 {
+            reader.restart();
+            for &index in &members {
+                components[index].dc = 0;
+            }
+        }
+        let (unit_x, unit_y) = (unit % units_x, unit / units_x);
+        for &index in &members {
+            let component = &mut components[index];
+            let (blocks_x, blocks_y) = if members.len() == 1 {
+                (1, 1)
+            } else {
+                (component.h, component.v)
+            };
+            for block in 0..blocks_x * blocks_y {
+                let block_x = unit_x * blocks_x + block % blocks_x;
+                let block_y = unit_y * blocks_y + block / blocks_x;
+                decode_block(&mut reader, component, tables, &mut coefficients)?;
+                let offset = block_y * 8 * component.stride + block_x * 8;
+                let stride = component.stride;
+                idct(
+                    &table,
+                    &coefficients,
+                    &mut component.plane[offset..],
+                    stride,
+                );
+            }
+        }
+    }
+    next_marker(data, reader.pos.min(data.len())).ok_or("truncated JPEG scan".to_string())
+}
+
+fn decode_block(
+    reader: &mut BitReader,
+    component: &mut Component,
+    tables: &ScanTables,
+    coefficients: &mut [i32; 64],
+) -> Result<(), String> {
+    let quant = &tables.quant[component.quant];
+    let dc_table = tables.dc[component.dc_table].as_ref().unwrap();
+    let ac_table = tables.ac[component.ac_table].as_ref().unwrap();
+
+    coefficients.fill(0);
+    let size = dc_table.decode(reader)?;
+    if size > 11 {
+        return Err("invalid DC coefficient size".to_string());
+    }
+    component.dc += extend(reader.bits(size as u32), size);
+    coefficients[0] = component.dc * quant[0] as i32;
+
+    let mut k = 1;
+    while k < 64 {
+        let symbol = ac_table.decode(reader)?;
+        let (run, size) = ((symbol >> 4) as usize, symbol & 15);
+        if size == 0 {
+            if run != 15 {
+                // End of block
+                break;
+            }
+            k += 16;
+            continue;
+        }
+        k += run;
+        if k > 63 {
+            return Err("AC coefficients overrun the block".to_string());
+        }
+        coefficients[ZIGZAG[k]] = extend(reader.bits(size as u32), size) * quant[k] as i32;
+        k += 1;
+    }
+    Ok(())
+}
+
+// A `size`-bit magnitude category value as a signed coefficient
+fn extend(value: u32, size: u8) -> i32 {
+    if size == 0 {
+        0
+    } else if value < 1 << (size - 1) {
+        value as i32 - (1 << size) + 1
+    } else {
+        value as i32
+    }
+}
+
+// cos((2x + 1) u pi / 16) indexed by [x][u], scaled by 1/sqrt(2) for u = 0
+fn idct_table() -> [[f32; 8]; 8] {
+    let mut table = [[0f32; 8]; 8];
+    for (x, row) in table.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            let c = ((2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0).cos();
+            *value = if u == 0 {
+                c * std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                c
+            };
+        }
+    }
+    table
+}
+
+// Inverse DCT of one block into 8x8 samples of `out`, `stride` apart,
+// level-shifted back to 0-255
+fn idct(table: &[[f32; 8]; 8], coefficients: &[i32; 64], out: &mut [u8], stride: usize) {
+    // Rows, then columns
+    let mut rows = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8)
+                .map(|u| table[x][u] * coefficients[v * 8 + u] as f32)
+                .sum();
+        }
+    }
+    for y in 0..8 {
+        for x in 0..8 {
+            let sum: f32 = (0..8).map(|v| table[y][v] * rows[v * 8 + x]).sum();
+            out[y * stride + x] = (sum / 4.0 + 128.0).round().clamp(0.0, 255.0) as u8;
+        }
+    }
+}
+
+fn to_rgba(width: usize, height: usize, components: &[Component], rgb: bool) -> Vec<u8> {
+    let (h_max, v_max) = sampling_max(components);
+    let sample = |c: &Component, x: usize, y: usize| {
+        c.plane[(y * c.v / v_max) * c.stride + x * c.h / h_max] as f32
+    };
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = match components {
+                [gray] => {
+                    let l = sample(gray, x, y) as u8;
+                    [l, l, l, 255]
+                }
+                [a, b, c] if rgb => [
+                    sample(a, x, y) as u8,
+                    sample(b, x, y) as u8,
+                    sample(c, x, y) as u8,
+                    255,
+                ],
+                [luma, blue, red] => {
+                    let l = sample(luma, x, y);
+                    let cb = sample(blue, x, y) - 128.0;
+                    let cr = sample(red, x, y) - 128.0;
+                    let channel = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+                    [
+                        channel(l + 1.402 * cr),
+                        channel(l - 0.344_136 * cb - 0.714_136 * cr),
+                        channel(l + 1.772 * cb),
+                        255,
+                    ]
+                }
+                _ => unreachable!("frames have 1 or 3 components"),
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+    }
+    rgba
+}
+
+// Reads entropy-coded data most significant bit first, dropping the zero
+// stuffed after 0xFF bytes. At a marker it stops consuming input and feeds
+// zeros, which a well-formed scan never gets far enough to decode.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        BitReader {
+            data,
+            pos,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn bit(&mut self) -> u32 {
+        if self.count == 0 {
+            let byte = match self.data.get(self.pos..self.pos + 2) {
+                Some([0xFF, 0x00]) => {
+                    self.pos += 2;
+                    0xFF
+                }
+                Some([0xFF, _]) => 0,
+                _ => match self.data.get(self.pos) {
+                    Some(&byte) => {
+                        self.pos += 1;
+                        byte
+                    }
+                    None => 0,
+                },
+            };
+            self.buffer = byte as u32;
+            self.count = 8;
+        }
+        self.count -= 1;
+        (self.buffer >> self.count) & 1
+    }
+
+    fn bits(&mut self, n: u32) -> u32 {
+        (0..n).fold(0, |value, _| value << 1 | self.bit())
+    }
+
+    // Drops the partial byte and steps over the restart marker that should
+    // follow; decoding carries on even if it's missing
+    fn restart(&mut self) {
+        self.count = 0;
+        if let Some([0xFF, 0xD0..=0xD7]) = self.data.get(self.pos..self.pos + 2) {
+            self.pos += 2;
+        }
+    }
+}
+
+// Canonical Huffman code from a DHT segment: the number of codes of each
+// length 1-16 and the symbols in code order
+#[derive(Default)]
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8], symbols: &[u8]) -> Self {
+        let mut table = Huffman {
+            counts: [0; 16],
+            symbols: symbols.to_vec(),
+        };
+        for (count, &c) in table.counts.iter_mut().zip(counts) {
+            *count = c as u16;
+        }
+        table
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, String> {
+        // First code of the current length, and index of its symbol
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts {
+            code |= reader.bit() as i32;
+            let count = count as i32;
+            if (0..count).contains(&(code - first)) {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image;
+
+    const PYTHON_JPG: &[u8] = include_bytes!("../tests/data/python.jpg");
+    const PYTHON_PPM: &[u8] = include_bytes!("../tests/data/python.ppm");
+
+    // MSB-first bit writer that stuffs a zero after every 0xFF byte
+    #[derive(Default)]
+    struct Bits {
+        bytes: Vec<u8>,
+        current: u32,
+        count: u32,
+    }
+
+    impl Bits {
+        fn push(&mut self, value: u32, n: u32) {
+            for i in (0..n).rev() {
+                self.current = self.current << 1 | (value >> i) & 1;
+                self.count += 1;
+                if self.count == 8 {
+                    self.bytes.push(self.current as u8);
+                    if self.current == 0xFF {
+                        self.bytes.push(0);
+                    }
+                    self.current = 0;
+                    self.count = 0;
+                }
+            }
+        }
+
+        // Pads the last byte with ones, as encoders do before a marker
+        fn flush(&mut self) -> Vec<u8> {
+            if self.count > 0 {
+                self.push(0xFF, 8 - self.count);
+            }
+            std::mem::take(&mut self.bytes)
+        }
+    }
+
+    fn segment(out: &mut Vec<u8>, marker: u8, body: &[u8]) {
+        out.extend_from_slice(&[0xFF, marker]);
+        out.extend_from_slice(&(body.len() as u16 + 2).to_be_bytes());
+        out.extend_from_slice(body);
+    }
+
+    // A JPEG whose blocks are all flat: `level(component, block_x, block_y)`
+    // gives each block's sample value. DC sizes are coded with fixed 4-bit
+    // codes and the quantizer is 8, so a level is exactly its DC difference.
+    fn encode(
+        (width, height): (usize, usize),
+        sampling: &[(usize, usize)],
+        interleaved: bool,
+        restart_interval: usize,
+        level: impl Fn(usize, usize, usize) -> u8,
+    ) -> Vec<u8> {
+        let mut out = vec![0xFF, 0xD8];
+        let mut quant = vec![0];
+        quant.extend([8; 64]);
+        segment(&mut out, 0xDB, &quant);
+
+        let mut frame = vec![8];
+        frame.extend_from_slice(&(height as u16).to_be_bytes());
+        frame.extend_from_slice(&(width as u16).to_be_bytes());
+        frame.push(sampling.len() as u8);
+        for (id, &(h, v)) in sampling.iter().enumerate() {
+            frame.extend_from_slice(&[id as u8 + 1, (h << 4 | v) as u8, 0]);
+        }
+        segment(&mut out, 0xC0, &frame);
+
+        // DC: sizes 0-11 with 4-bit codes; AC: only end-of-block, as "0"
+        let mut tables = vec![0x00];
+        tables.extend([0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        tables.extend(0..12);
+        tables.extend([0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00]);
+        segment(&mut out, 0xC4, &tables);
+        if restart_interval > 0 {
+            segment(&mut out, 0xDD, &(restart_interval as u16).to_be_bytes());
+        }
+
+        let h_max = sampling.iter().map(|s| s.0).max().unwrap();
+        let v_max = sampling.iter().map(|s| s.1).max().unwrap();
+        let scans: Vec<Vec<usize>> = if interleaved {
+            vec![(0..sampling.len()).collect()]
+        } else {
+            (0..sampling.len()).map(|c| vec![c]).collect()
+        };
+        for scan in scans {
+            let mut header = vec![scan.len() as u8];
+            for &c in &scan {
+                header.extend_from_slice(&[c as u8 + 1, 0x00]);
+            }
+            header.extend_from_slice(&[0, 63, 0]);
+            segment(&mut out, 0xDA, &header);
+
+            let (units_x, units_y) = if let [c] = scan[..] {
+                let (h, v) = sampling[c];
+                (
+                    (width * h).div_ceil(h_max).div_ceil(8),
+                    (height * v).div_ceil(v_max).div_ceil(8),
+                )
+            } else {
+                (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max))
+            };
+            let mut bits = Bits::default();
+            let mut predictors = vec![0i32; sampling.len()];
+            for unit in 0..units_x * units_y {
+                if restart_interval > 0 && unit > 0 && unit % restart_interval == 0 {
+                    out.extend(bits.flush());
+                    out.extend_from_slice(&[0xFF, 0xD0 + (unit / restart_interval - 1) as u8 % 8]);
+                    predictors.fill(0);
+                }
+                for &c in &scan {
+                    let (h, v) = if scan.len() == 1 { (1, 1) } else { sampling[c] };
+                    for block in 0..h * v {
+                        let x = unit % units_x * h + block % h;
+                        let y = unit / units_x * v + block / h;
+                        let value = level(c, x, y) as i32 - 128;
+                        let diff = value - predictors[c];
+                        predictors[c] = value;
+                        let size = 32 - diff.unsigned_abs().leading_zeros();
+                        let coded = if diff < 0 {
+                            diff + (1 << size) - 1
+                        } else {
+                            diff
+                        };
+                        bits.push(size, 4);
+                        bits.push(coded as u32, size);
+                        bits.push(0, 1);
+                    }
+                }
+            }
+            out.extend(bits.flush());
+        }
+        out.extend_from_slice(&[0xFF, 0xD9]);
+        out
+    }
+
+    fn pixel(image: &Image, x: usize, y: usize) -> [u8; 4] {
+        // Rows are stored bottom first
+        let row = image.height as usize - 1 - y;
+        let i = (row * image.width as usize + x) * 4;
+        image.pixels[i..i + 4].try_into().unwrap()
+    }
+
+    #[test]
+    fn decodes_grayscale_blocks() {
+        let levels = [[0, 40, 255], [128, 200, 7]];
+        let data = encode((20, 12), &[(1, 1)], true, 0, |_, x, y| levels[y][x]);
+        let image = decode(&data).unwrap();
+        assert_eq!((image.width, image.height), (20, 12));
+        for y in 0..12 {
+            for x in 0..20 {
+                let l = levels[y / 8][x / 8];
+                assert_eq!(pixel(&image, x, y), [l, l, l, 255], "at {x},{y}");
+            }
+        }
+    }
+
+    #[test]
+    fn upsamples_subsampled_chroma() {
+        for (sampling, interleaved) in [
+            ([(2, 2), (1, 1), (1, 1)], true),
+            ([(2, 2), (1, 1), (1, 1)], false),
+            ([(2, 1), (1, 1), (1, 1)], true),
+        ] {
+            let (h, v) = (sampling[0].0 * 8, sampling[0].1 * 8);
+            // Luma differs per block; the single chroma block is reddish
+            let data = encode((h * 2, v), &sampling, interleaved, 0, |c, x, y| match c {
+                0 => 60 + (x + y * 4) as u8 * 10,
+                1 => 128,
+                _ => 178,
+            });
+            let image = decode(&data).unwrap();
+            for y in 0..v {
+                for x in 0..h * 2 {
+                    let l = 60.0 + (x / 8 + y / 8 * 4) as f32 * 10.0;
+                    let expected = [
+                        (l + 1.402 * 50.0).round() as u8,
+                        (l - 0.714_136 * 50.0).round() as u8,
+                        l as u8,
+                        255,
+                    ];
+                    assert_eq!(pixel(&image, x, y), expected, "{sampling:?} at {x},{y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn resets_predictors_at_restart_markers() {
+        for interval in [1, 2, 3] {
+            let data = encode((40, 16), &[(1, 1)], true, interval, |_, x, y| {
+                (x * 50 + y * 20) as u8
+            });
+            let image = decode(&data).unwrap();
+            for y in 0..16 {
+                for x in 0..40 {
+                    let l = (x / 8 * 50 + y / 8 * 20) as u8;
+                    assert_eq!(pixel(&image, x, y), [l, l, l, 255], "at {x},{y}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decodes_a_real_file_close_to_its_source() {
+        let jpeg = image::decode_bytes(PYTHON_JPG).unwrap();
+        let source = image::decode_bytes(PYTHON_PPM).unwrap();
+        assert_eq!((jpeg.width, jpeg.height), (source.width, source.height));
+        let total: u32 = jpeg
+            .pixels
+            .iter()
+            .zip(&source.pixels)
+            .map(|(&a, &b)| a.abs_diff(b) as u32)
+            .sum();
+        let mean = total as f32 / jpeg.pixels.len() as f32;
+        // Lossy, and 4:2:0 smears the icon's hard color edges; a decoding
+        // mistake is off by far more than this
+        assert!(mean < 8.0, "mean difference {mean}");
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        for len in 0..PYTHON_JPG.len() {
+            assert!(decode(&PYTHON_JPG[..len]).is_err(), "accepted {len} bytes");
+        }
+    }
+
+    #[test]
+    fn rejects_unsupported_codings() {
+        let sof = PYTHON_JPG
+            .windows(2)
+            .position(|w| w == [0xFF, 0xC0])
+            .unwrap();
+        let patched = |offset: usize, value: u8| {
+            let mut data = PYTHON_JPG.to_vec();
+            data[sof + offset] = value;
+            decode(&data).unwrap_err()
+        };
+        assert!(patched(1, 0xC2).contains("progressive"));
+        assert!(patched(1, 0xC9).contains("SOF9"));
+        assert!(patched(4, 12).contains("precision"));
+        assert!(patched(9, 4).contains("component count"));
+        assert!(decode(b"\xFF\xD8\xFF\xD9").is_err());
+        assert!(decode(b"not a jpeg").is_err());
+    }
+}
@@ -0,0 +1,371 @@
+use std::collections::BTreeMap;
+
+// Nesting deeper than this is rejected rather than risking the stack
+const MAX_DEPTH: usize = 128;
+
+// A parsed JSON document. Numbers are kept as f64, which holds every integer
+// up to 2^53 exactly, plenty for the indices and offsets in model files.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
+}
+
+impl Value {
+    // Member `key` of an object; None for missing keys and non-objects
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    // Non-negative whole numbers only
+    pub(crate) fn as_usize(&self) -> Option<usize> {
+        self.as_f64()
+            .filter(|n| *n >= 0.0 && n.fract() == 0.0 && *n <= usize::MAX as f64)
+            .map(|n| n as usize)
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+// Parses a complete JSON document (RFC 8259). Errors give the byte offset.
+pub(crate) fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        bytes: text.as_bytes(),
+        pos: 0,
+    };
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("unexpected data after the document"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        format!("byte {}: {}", self.pos, message)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected `{}`", byte as char)));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Value, String> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(depth),
+            Some(b'[') => self.array(depth),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(b't') => self.literal("true", Value::Bool(true)),
+            Some(b'f') => self.literal("false", Value::Bool(false)),
+            Some(b'n') => self.literal("null", Value::Null),
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: Value) -> Result<Value, String> {
+        if !self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            return Err(self.error(&format!("expected `{}`", word)));
+        }
+        self.pos += word.len();
+        Ok(value)
+    }
+
+    fn object(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect(b'{')?;
+        let mut members = BTreeMap::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(b':')?;
+            // A repeated key keeps the last value, as most parsers do
+            members.insert(key, self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<Value, String> {
+        self.expect(b'[')?;
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+        loop {
+            values.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while matches!(
+            self.peek(),
+            Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+        ) {
+            self.pos += 1;
+        }
+        // The input is a &str and these are all ASCII, so the slice is valid
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or("");
+        text.parse()
+            .map(Value::Number)
+            .map_err(|_| format!("byte {}: invalid number `{}`", start, text))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let Some(byte) = self.peek() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.pos += 1;
+                    let decoded = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    };
+                    let mut buffer = [0; 4];
+                    bytes.extend_from_slice(decoded.encode_utf8(&mut buffer).as_bytes());
+                }
+                0..0x20 => return Err(self.error("control character in string")),
+                _ => bytes.push(byte),
+            }
+        }
+        // Only whole characters were copied out of a &str
+        String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    // The character after `\u`, combining a UTF-16 surrogate pair if needed.
+    // Unpaired surrogates become U+FFFD.
+    fn unicode_escape(&mut self) -> Result<char, String> {
+        let first = self.hex4()?;
+        if (0xD800..0xDC00).contains(&first) && self.bytes[self.pos..].starts_with(b"\\u") {
+            let saved = self.pos;
+            self.pos += 2;
+            let second = self.hex4()?;
+            if (0xDC00..0xE000).contains(&second) {
+                let code = 0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00);
+                return Ok(char::from_u32(code).unwrap_or('\u{FFFD}'));
+            }
+            self.pos = saved;
+        }
+        Ok(char::from_u32(first).unwrap_or('\u{FFFD}'))
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .filter(|d| d.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|d| std::str::from_utf8(d).ok())
+            .and_then(|d| u32::from_str_radix(d, 16).ok())
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(members: &[(&str, Value)]) -> Value {
+        Value::Object(
+            members
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn parses_every_kind_of_value() {
+        let value = parse(
+            r#" { "a": [1, -2.5, 3e2, 0], "b": {"c": null}, "d": true, "e": false,
+                  "f": "text", "g": [], "h": {} } "#,
+        )
+        .unwrap();
+        let expected = object(&[
+            (
+                "a",
+                Value::Array(vec![
+                    Value::Number(1.0),
+                    Value::Number(-2.5),
+                    Value::Number(300.0),
+                    Value::Number(0.0),
+                ]),
+            ),
+            ("b", object(&[("c", Value::Null)])),
+            ("d", Value::Bool(true)),
+            ("e", Value::Bool(false)),
+            ("f", Value::String("text".to_string())),
+            ("g", Value::Array(Vec::new())),
+            ("h", object(&[])),
+        ]);
+        assert_eq!(value, expected);
+        assert_eq!(
+            value.get("a").and_then(Value::as_array).map(<[_]>::len),
+            Some(4)
+        );
+        assert_eq!(value.get("f").and_then(Value::as_str), Some("text"));
+        assert_eq!(value.get("d").and_then(Value::as_bool), Some(true));
+        assert_eq!(value.get("missing"), None);
+    }
+
+    #[test]
+    fn converts_only_whole_non_negative_numbers_to_usize() {
+        let values = parse("[0, 7, 7.5, -1, 1e3, \"7\"]").unwrap();
+        let converted: Vec<_> = values
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(Value::as_usize)
+            .collect();
+        assert_eq!(converted, [Some(0), Some(7), None, None, Some(1000), None]);
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        let value = parse(r#""\"\\\/\b\f\n\r\t \u00e9 \u20AC é""#).unwrap();
+        assert_eq!(value.as_str(), Some("\"\\/\u{8}\u{c}\n\r\t é € é"));
+    }
+
+    #[test]
+    fn combines_surrogate_pairs() {
+        assert_eq!(parse(r#""\ud83d\ude00""#).unwrap().as_str(), Some("😀"));
+        // Unpaired halves become replacement characters
+        assert_eq!(parse(r#""\ud83d!""#).unwrap().as_str(), Some("\u{FFFD}!"));
+        assert_eq!(parse(r#""\ude00""#).unwrap().as_str(), Some("\u{FFFD}"));
+        assert_eq!(
+            parse(r#""\ud83d\u0041""#).unwrap().as_str(),
+            Some("\u{FFFD}A")
+        );
+    }
+
+    #[test]
+    fn limits_nesting_depth() {
+        let nested = |depth: usize| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_ok());
+        let err = parse(&nested(MAX_DEPTH + 2)).unwrap_err();
+        assert!(err.contains("nested too deeply"), "{err}");
+    }
+
+    #[test]
+    fn reports_errors_with_their_offset() {
+        for (text, message) in [
+            ("", "byte 0: unexpected end of input"),
+            ("[1, 2", "byte 5: expected `,` or `]`"),
+            ("[1 2]", "byte 3: expected `,` or `]`"),
+            ("{\"a\" 1}", "byte 5: expected `:`"),
+            ("{\"a\": 1,}", "byte 8: expected `\"`"),
+            ("{1: 2}", "byte 1: expected `\"`"),
+            ("tru", "byte 0: expected `true`"),
+            ("nul", "byte 0: expected `null`"),
+            ("1.2.3", "byte 0: invalid number `1.2.3`"),
+            ("-", "byte 0: invalid number `-`"),
+            ("\"abc", "byte 4: unterminated string"),
+            ("\"a\nb\"", "byte 3: control character in string"),
+            ("\"\\x\"", "byte 3: invalid escape"),
+            ("\"\\u12g4\"", "byte 3: invalid \\u escape"),
+            ("{} {}", "byte 3: unexpected data after the document"),
+            ("@", "byte 0: expected a value"),
+        ] {
+            assert_eq!(parse(text), Err(message.to_string()), "parsing {text:?}");
+        }
+    }
+}
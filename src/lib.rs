@@ -0,0 +1,3830 @@
+extern crate gl;
+extern crate glfw;
+extern crate nalgebra_glm as glm;
+
+use bloom::BloomPass;
+use colormap::ColormapShader;
+use deferred::GBuffer;
+use edges::EdgePass;
+use exposure::ExposurePass;
+use gamma::GammaPass;
+use gl_debug::DebugOutput;
+use glfw::GlfwReceiver;
+use glfw::{Action, Context};
+use glm::{Mat4, Vec2, Vec3, vec3};
+use lines::LineRenderer;
+use measure::Measurement;
+use mesh::MeshSource;
+use motion_blur::MotionBlurPass;
+use pacing::{FramePacer, FrameTimer, LatencyMonitor};
+use render_target::{FrameTarget, RenderTarget};
+use retro::PixelationPass;
+#[cfg(feature = "audio")]
+use shader::set_float_array;
+use shader::{
+    Uniforms, build_program, set_float, set_int, set_mat4, set_vec3, set_vec3_array, with_fog,
+    with_morph_targets,
+};
+use shader_watch::ShaderWatch;
+use shadow::ShadowMap;
+use skybox::SkyboxPass;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use text::TextRenderer;
+use texture::DIFFUSE_TEXTURE_UNIT;
+
+pub mod annotation;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod bloom;
+pub mod bounds;
+pub mod camera;
+pub mod camera_export;
+pub mod camera_path;
+pub mod colormap;
+pub mod debug_draw;
+pub mod deferred;
+pub mod edges;
+pub mod exposure;
+pub mod fog;
+pub mod foliage;
+mod font;
+mod gamma;
+pub mod gl_debug;
+pub mod gltf;
+pub mod grid;
+pub mod guides;
+mod hud;
+pub mod image;
+pub mod input;
+pub mod instancing;
+mod jpeg;
+mod json;
+pub mod light;
+pub mod lighting;
+mod lines;
+pub mod material;
+pub mod measure;
+pub mod mesh;
+pub mod motion_blur;
+mod obj;
+pub mod pacing;
+pub mod particles;
+mod png;
+mod random;
+mod render_target;
+pub mod retro;
+pub mod scene;
+pub mod settings;
+mod shader;
+mod shader_watch;
+pub mod shadow;
+pub mod skybox;
+pub mod test_pattern;
+mod text;
+pub mod texture;
+pub mod transform;
+pub mod uv_overlay;
+pub mod viewport;
+
+pub use annotation::Annotation;
+pub use bloom::Bloom;
+pub use bounds::{Aabb, Frustum};
+pub use camera::{Camera, CameraMode, HomeView, Projection, ShakeParams, ViewPreset};
+pub use camera_export::{CameraFormat, UpAxis};
+pub use camera_path::{CameraKeyframe, CameraPath};
+pub use colormap::{Colormap, ScalarField};
+pub use debug_draw::DebugDraw;
+pub use deferred::Pipeline;
+pub use edges::EdgeDetection;
+pub use exposure::AutoExposure;
+pub use fog::{FogMode, FogParams};
+pub use foliage::Foliage;
+pub use gl_debug::{DebugSeverity, GlDebugMessage};
+pub use glfw::{Key, MouseButton};
+pub use gltf::{GltfError, load_gltf};
+pub use grid::Grid;
+pub use guides::CompositionGuides;
+pub use image::{Image, ImageDecoder};
+pub use input::InputState;
+pub use instancing::{InstancedMesh, InstancedMeshId};
+pub use light::Light;
+pub use lighting::{LightingModel, Outline, StudioLighting};
+pub use material::Material;
+pub use measure::MeasureTool;
+pub use mesh::{Mesh, MeshError, MeshLoader};
+pub use motion_blur::MotionBlur;
+pub use pacing::{FrameStats, SwapMode};
+pub use particles::{BlendMode, ParticleEmitter};
+pub use retro::Pixelation;
+pub use scene::{NodeId, Scene, SceneNode, ShaderHandle};
+pub use settings::RenderSettings;
+pub use shader::ShaderError;
+pub use shadow::{ShadowParams, SoftShadows};
+pub use skybox::Skybox;
+pub use test_pattern::TestPattern;
+pub use texture::{Texture, TextureError, TextureHandle, TextureQuality};
+pub use transform::Transform;
+pub use uv_overlay::UvOverlayMode;
+pub use viewport::{Rect, ViewportLayout};
+
+const DEFAULT_WINDOW_TITLE: &str = "X3D - Camera Control";
+// Resolution of the square shadow depth texture
+const SHADOW_MAP_SIZE: i32 = 2048;
+// Half-extent of the light's orthographic frustum around the scene center
+const SHADOW_RADIUS: f32 = 5.0;
+// Texture unit reserved for the shadow map; unit 0 is left for material textures
+const SHADOW_TEXTURE_UNIT: u32 = 1;
+// Must match MAX_LIGHTS in fragment.glsl
+const MAX_FORWARD_LIGHTS: usize = 8;
+// Seconds a press of , or . moves camera path playback
+const PATH_SCRUB_STEP: f32 = 0.5;
+// Change in explode factor per press of [ or ]
+const EXPLODE_STEP: f32 = 0.25;
+// Arrow-key panning, in multiples of the eye's distance from the target per second
+const PAN_SPEED: f32 = 1.0;
+// Most fixed-timestep updates run in one frame; time beyond that is dropped
+// so a slow frame can't snowball into ever more catch-up work
+const MAX_CATCH_UP_STEPS: u32 = 5;
+// Longest frame time passed to updates by default, so a stall (a debugger
+// break, a dragged window) doesn't teleport everything forward
+const DEFAULT_MAX_DELTA: f32 = 0.1;
+// How long a paused engine sleeps waiting for events, in seconds
+const PAUSED_WAIT: f64 = 0.1;
+// How quickly the explode view eases toward its target (per second); the
+// remaining distance halves about every 0.09s
+const EXPLODE_RATE: f32 = 8.0;
+
+// Why the engine couldn't start
+#[derive(Debug)]
+pub enum EngineError {
+    GlfwInit(glfw::InitError),
+    WindowCreation,
+    Shader(ShaderError),
+    // The headless render target couldn't be created
+    Framebuffer(String),
+}
+
+impl fmt::Display for EngineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EngineError::GlfwInit(err) => write!(f, "failed to initialize GLFW: {err}"),
+            EngineError::WindowCreation => write!(f, "failed to create an OpenGL 3.3 window"),
+            EngineError::Shader(err) => write!(f, "built-in shader failed: {err}"),
+            EngineError::Framebuffer(err) => write!(f, "offscreen framebuffer failed: {err}"),
+        }
+    }
+}
+
+impl Error for EngineError {}
+
+impl From<ShaderError> for EngineError {
+    fn from(err: ShaderError) -> Self {
+        EngineError::Shader(err)
+    }
+}
+
+pub struct X3D {
+    shader_program: u32,
+    uniforms: Uniforms,
+    // Source files of shader_program, rebuilt when they change
+    shader_watch: ShaderWatch,
+    outline_program: u32,
+    mesh: Mesh,
+    mesh_source: Option<MeshSource>,
+    rotation_angle: f32,
+    // Main mesh spin in radians per second
+    rotation_speed: f32,
+    camera: Camera,
+    // Flythrough driving the main camera, with its playhead in seconds
+    camera_path: Option<CameraPath>,
+    path_time: f32,
+    path_playing: bool,
+    last_frame_time: Instant,
+    // Upper bound on the delta time of one frame, in seconds
+    max_delta: f32,
+    elapsed_time: f32,
+    foliage: Option<Foliage>,
+    lighting_model: LightingModel,
+    outline: Option<Outline>,
+    light_position: Vec3,
+    studio_lighting: Option<StudioLighting>,
+    shadow_map: Option<ShadowMap>,
+    shadow_params: ShadowParams,
+    clear_color: [f32; 4],
+    ambient: f32,
+    exposure: f32,
+    auto_exposure: Option<(AutoExposure, ExposurePass)>,
+    // Exposure the auto-exposure controller has settled on so far
+    adapted_exposure: f32,
+    // Cycled with P; starts with the built-in presets
+    presets: Vec<RenderSettings>,
+    current_preset: Option<usize>,
+    fog: Option<FogParams>,
+    pipeline: Pipeline,
+    gbuffer: Option<GBuffer>,
+    lights: Vec<Light>,
+    emitters: Vec<ParticleEmitter>,
+    line_renderer: LineRenderer,
+    show_bounds: bool,
+    guides: CompositionGuides,
+    show_guides: bool,
+    grid: Grid,
+    show_grid: bool,
+    // Gizmos queued this frame, cleared after it's presented
+    debug_draw: DebugDraw,
+    // Screen text queued with draw_text this frame
+    text: TextRenderer,
+    inside_out: bool,
+    backface_culling: bool,
+    // Whether materials with alpha below 1.0 blend, and whether those are
+    // drawn farthest first
+    blending: bool,
+    sort_transparent: bool,
+    wireframe: bool,
+    two_sided_lighting: bool,
+    depth_clamp: bool,
+    uv_overlay: Option<UvOverlayMode>,
+    clear_mask: gl::types::GLbitfield,
+    measurement: Option<Measurement>,
+    // Cursor position (fractions of the window, origin bottom-left) of a click
+    pending_pick: Option<(f32, f32)>,
+    // Offscreen framebuffer used instead of the window while capturing
+    frame_target: Option<FrameTarget>,
+    // Window framebuffer size in pixels, kept current from resize events
+    framebuffer_size: (i32, i32),
+    // (x, y, width, height) in screen coordinates to restore when leaving
+    // full screen
+    windowed_placement: (i32, i32, u32, u32),
+    // Stands in for the window's framebuffer when created with new_headless
+    headless_target: Option<RenderTarget>,
+    // Samples per pixel the window's framebuffer was actually given
+    msaa_samples: u32,
+    // Whether the window's framebuffer can encode sRGB on write
+    srgb_framebuffer: bool,
+    // Output encoded as sRGB; the pass does it where the framebuffer can't
+    gamma_correction: bool,
+    gamma_pass: Option<GammaPass>,
+    // Driver debug messages, when requested; unregistered on drop, before
+    // the context goes away
+    gl_debug: Option<DebugOutput>,
+    // Window title, before any frame stats are appended
+    title: String,
+    // Set while a screenshot is rendered, outside the regular frame loop
+    capturing: bool,
+    axis_view_key: Option<Key>,
+    // Held keys and buttons and this frame's presses and cursor motion
+    input: InputState,
+    // Home set for the current mesh; None frames its bounds automatically
+    home_view: Option<HomeView>,
+    layout: ViewportLayout,
+    // Cameras for panes after the first, which uses `camera`
+    pane_cameras: Vec<Camera>,
+    // Part of the window being drawn by render_frame
+    current_pane: Rect,
+    // Part of the window drawing is scissored to; None draws all of it
+    render_region: Option<Rect>,
+    // While rendering one tile of capture_screenshot_tiled: the matrix that
+    // maps the tile's part of clip space to the whole, and the full image's
+    // aspect ratio
+    tile: Option<(Mat4, f32)>,
+    pixelation: Option<(Pixelation, PixelationPass)>,
+    bloom: Option<(Bloom, BloomPass)>,
+    skybox: Option<(Skybox, SkyboxPass)>,
+    material: Material,
+    test_texture: Option<(TestPattern, Texture)>,
+    texture: Option<Texture>,
+    edge_detection: Option<(EdgeDetection, EdgePass)>,
+    motion_blur: Option<(MotionBlur, MotionBlurPass)>,
+    // Main mesh transform in the last presented frame, for motion blur
+    previous_model: Option<Mat4>,
+    swap_mode: SwapMode,
+    frame_pacer: FramePacer,
+    // Frame rate cap enforced by sleeping; None runs uncapped
+    target_fps: Option<u32>,
+    // Seconds per update step when fixed, and time not yet stepped through
+    fixed_timestep: Option<f32>,
+    step_accumulator: f32,
+    latency_monitor: LatencyMonitor,
+    // Whether losing focus pauses the loop, and whether it's paused now
+    auto_pause_on_blur: bool,
+    paused: bool,
+    frame_timer: FrameTimer,
+    // Whether the title shows the frame stats, and when it last changed
+    stats_in_title: bool,
+    title_updated: Instant,
+    show_latency: bool,
+    scalar_field: Option<ScalarField>,
+    annotations: Vec<Annotation>,
+    scene: Scene,
+    // Target explode factor and the value currently shown, easing toward it
+    explode_factor: f32,
+    explode_current: f32,
+    // Programs from add_shader, indexed by ShaderHandle
+    custom_shaders: Vec<u32>,
+    // Indexed by InstancedMeshId
+    instanced_meshes: Vec<InstancedMesh>,
+    colormap_shader: Option<ColormapShader>,
+    #[cfg(feature = "audio")]
+    audio: Option<audio::AudioAnalyzer>,
+    #[cfg(feature = "audio")]
+    audio_start: f32,
+    // Fields drop in declaration order, and everything above may free GL
+    // objects, so the window and its context must come last
+    events: GlfwReceiver<(f64, glfw::WindowEvent)>,
+    window: glfw::PWindow,
+    glfw: glfw::Glfw,
+}
+
+impl Default for X3D {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Programs owned directly by X3D; meshes, textures and passes free their own
+// objects when the fields drop afterwards
+impl Drop for X3D {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.shader_program);
+            gl::DeleteProgram(self.outline_program);
+            for &program in &self.custom_shaders {
+                gl::DeleteProgram(program);
+            }
+        }
+        self.shader_program = 0;
+        self.outline_program = 0;
+        self.custom_shaders.clear();
+    }
+}
+
+// Window options that have to be known before the window is created, e.g.
+// `X3D::builder().size(1280, 720).title("Viewer").msaa(4).build()`
+#[derive(Debug, Clone, PartialEq)]
+pub struct X3DBuilder {
+    width: u32,
+    height: u32,
+    title: String,
+    fullscreen: bool,
+    gl_version: (u32, u32),
+    depth_bits: u32,
+    msaa_samples: u32,
+    debug: bool,
+}
+
+impl Default for X3DBuilder {
+    fn default() -> Self {
+        X3DBuilder {
+            width: 800,
+            height: 600,
+            title: DEFAULT_WINDOW_TITLE.to_string(),
+            fullscreen: false,
+            gl_version: (3, 3),
+            depth_bits: 24,
+            msaa_samples: 0,
+            debug: false,
+        }
+    }
+}
+
+impl X3DBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Initial window size in screen coordinates
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    // Full screen on the primary monitor at its current resolution, so the
+    // display mode isn't switched; the size is then ignored. Falls back to a
+    // window when there's no monitor.
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    // Core-profile OpenGL context version to request. The built-in shaders
+    // need GLSL 3.30, so anything older is raised to 3.3.
+    pub fn gl_version(mut self, major: u32, minor: u32) -> Self {
+        self.gl_version = (major, minor).max((3, 3));
+        self
+    }
+
+    // Depth buffer precision to ask for (default 24 bits), requested
+    // explicitly so it doesn't vary with the driver's default
+    pub fn depth_bits(mut self, bits: u32) -> Self {
+        self.depth_bits = bits;
+        self
+    }
+
+    // Multisample anti-aliasing for the window: 0 (off), 2, 4 or 8 samples
+    // per pixel. Other counts round down to the nearest of those. Only
+    // drawing straight to the window is smoothed; passes that render through
+    // an offscreen target (bloom, exposure, pixelation, captures) aren't.
+    pub fn msaa(mut self, samples: u32) -> Self {
+        self.msaa_samples = match samples {
+            0..=1 => 0,
+            2..=3 => 2,
+            4..=7 => 4,
+            _ => 8,
+        };
+        self
+    }
+
+    // Requests a debug context and logs the driver's debug messages (errors,
+    // undefined behavior, performance warnings) to stderr; see
+    // X3D::set_gl_debug_callback to handle them instead. Needs GL 4.3 or the
+    // KHR_debug extension, and may slow rendering down.
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.debug = debug;
+        self
+    }
+
+    // Opens the window and sets up the OpenGL context and built-in shaders
+    pub fn build(self) -> Result<X3D, EngineError> {
+        X3D::create(&self, true)
+    }
+}
+
+impl X3D {
+    // Panics if the engine can't start; see try_new
+    pub fn new() -> Self {
+        Self::try_new().unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    // An 800x600 window with a GL 3.3 context and no anti-aliasing; see
+    // builder for other options
+    pub fn try_new() -> Result<Self, EngineError> {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> X3DBuilder {
+        X3DBuilder::new()
+    }
+
+    // Sends the driver's debug messages to `callback` instead of stderr,
+    // including notifications. Turns debug output on if the builder didn't;
+    // a context without the debug flag may report little, though. Returns
+    // false if the context has no debug output at all.
+    pub fn set_gl_debug_callback(&mut self, callback: impl Fn(&GlDebugMessage) + 'static) -> bool {
+        match &mut self.gl_debug {
+            Some(output) => output.set_callback(Box::new(callback)),
+            None => self.gl_debug = DebugOutput::install(Box::new(callback)),
+        }
+        self.gl_debug.is_some()
+    }
+
+    // Samples per pixel of the window's framebuffer, as granted by the driver;
+    // 0 without anti-aliasing
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    // Converts the linear colors lighting produces to sRGB on output, so
+    // midtones no longer look dark and washed out, and decodes textures from
+    // sRGB before lighting them. Off by default, which keeps the look colors
+    // were picked for. Material and clear colors are treated as linear.
+    pub fn set_gamma_correction(&mut self, enabled: bool) {
+        self.gamma_correction = enabled;
+    }
+
+    pub fn gamma_correction(&self) -> bool {
+        self.gamma_correction
+    }
+
+    // Whether the window's framebuffer encodes sRGB itself, which makes gamma
+    // correction free (GL_FRAMEBUFFER_SRGB). Otherwise, and for headless and
+    // offscreen renders, a fullscreen pass re-encodes each finished frame.
+    pub fn srgb_framebuffer(&self) -> bool {
+        self.srgb_framebuffer
+    }
+
+    // Renders into a width x height offscreen framebuffer instead of a window,
+    // e.g. for automated checks in CI. A hidden window still provides the GL
+    // context, so a display (or a virtual one such as Xvfb) is needed. Draw
+    // with render_once and read the result with read_pixels.
+    pub fn new_headless(width: u32, height: u32) -> Result<Self, EngineError> {
+        let mut x3d = Self::create(&X3DBuilder::new().size(width, height), false)?;
+        let size = |n: u32| i32::try_from(n.max(1)).unwrap_or(i32::MAX);
+        let target =
+            RenderTarget::new(size(width), size(height)).map_err(EngineError::Framebuffer)?;
+        let frame = target.frame_target();
+        x3d.framebuffer_size = (frame.width, frame.height);
+        frame.bind();
+        x3d.headless_target = Some(target);
+        Ok(x3d)
+    }
+
+    fn create(config: &X3DBuilder, visible: bool) -> Result<Self, EngineError> {
+        // GLFW errors are reported through return values; just log the details
+        let mut glfw = glfw::init(|error, description| {
+            eprintln!("GLFW error {:?}: {}", error, description);
+        })
+        .map_err(EngineError::GlfwInit)?;
+
+        // Window hints for OpenGL
+        let (major, minor) = config.gl_version;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(major, minor));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
+            glfw::OpenGlProfileHint::Core,
+        ));
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+        glfw.window_hint(glfw::WindowHint::Visible(visible));
+        glfw.window_hint(glfw::WindowHint::DepthBits(Some(config.depth_bits)));
+        glfw.window_hint(glfw::WindowHint::Samples(Some(config.msaa_samples)));
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(config.debug));
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(true));
+
+        let title = config.title.clone();
+        let (mut window, events) = glfw
+            .with_primary_monitor(|glfw, monitor| {
+                let fullscreen = monitor
+                    .filter(|_| config.fullscreen && visible)
+                    .and_then(|m| Some((m.get_video_mode()?, m)));
+                match fullscreen {
+                    Some((mode, monitor)) => {
+                        glfw.window_hint(glfw::WindowHint::RefreshRate(Some(mode.refresh_rate)));
+                        glfw.create_window(
+                            mode.width,
+                            mode.height,
+                            &title,
+                            glfw::WindowMode::FullScreen(monitor),
+                        )
+                    }
+                    None => glfw.create_window(
+                        config.width.max(1),
+                        config.height.max(1),
+                        &title,
+                        glfw::WindowMode::Windowed,
+                    ),
+                }
+            })
+            .ok_or(EngineError::WindowCreation)?;
+
+        // Where leaving full screen puts the window: where it is now, or the
+        // requested size centered on the monitor if it started full screen
+        let windowed_placement =
+            if window.with_window_mode(|mode| matches!(mode, glfw::WindowMode::Windowed)) {
+                let ((x, y), (width, height)) = (window.get_pos(), window.get_size());
+                (x, y, width.max(1) as u32, height.max(1) as u32)
+            } else {
+                let (width, height) = (config.width.max(1), config.height.max(1));
+                let (x, y) = glfw.with_primary_monitor(|_, monitor| {
+                    monitor
+                        .and_then(|m| m.get_video_mode())
+                        .map_or((0, 0), |mode| {
+                            (
+                                (mode.width as i32 - width as i32).max(0) / 2,
+                                (mode.height as i32 - height as i32).max(0) / 2,
+                            )
+                        })
+                });
+                (x, y, width, height)
+            };
+
+        window.make_current();
+        let swap_mode = SwapMode::default();
+        glfw.set_swap_interval(swap_mode.interval());
+        window.set_key_polling(true);
+        window.set_focus_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_scroll_polling(true);
+        window.set_framebuffer_size_polling(true);
+        let framebuffer_size = window.get_framebuffer_size();
+
+        // Initialize OpenGL
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+        let gl_debug = if config.debug {
+            let output = DebugOutput::install(Box::new(gl_debug::log));
+            if output.is_none() {
+                eprintln!("GL debug output unavailable: needs GL 4.3 or KHR_debug");
+            }
+            output
+        } else {
+            None
+        };
+
+        // Set up shaders, read from disk in debug builds so they can be edited
+        // while running
+        let mut shader_watch = ShaderWatch::new();
+        let shader_program = shader_watch.build()?;
+
+        let outline_program = unsafe {
+            build_program(
+                &with_morph_targets(include_str!("shaders/outline_vertex.glsl")),
+                include_str!("shaders/outline_fragment.glsl"),
+            )?
+        };
+
+        // Upload the cube as the initial mesh
+        let mesh = Mesh::cube();
+
+        // The driver may grant a different sample count than requested
+        let mut granted_samples = 0;
+        unsafe {
+            gl::GetIntegerv(gl::SAMPLES, &mut granted_samples);
+            if granted_samples > 0 {
+                gl::Enable(gl::MULTISAMPLE);
+            } else {
+                gl::Disable(gl::MULTISAMPLE);
+            }
+        }
+        let msaa_samples = granted_samples.max(0) as u32;
+        // The hint is only a request; check what the window actually got
+        let mut color_encoding = 0;
+        unsafe {
+            gl::GetFramebufferAttachmentParameteriv(
+                gl::FRAMEBUFFER,
+                gl::BACK_LEFT,
+                gl::FRAMEBUFFER_ATTACHMENT_COLOR_ENCODING,
+                &mut color_encoding,
+            );
+        }
+        let srgb_framebuffer = color_encoding == gl::SRGB as i32;
+
+        let clear_color = [0.1, 0.1, 0.3, 1.0];
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::ClearColor(
+                clear_color[0],
+                clear_color[1],
+                clear_color[2],
+                clear_color[3],
+            );
+        }
+
+        Ok(X3D {
+            glfw,
+            window,
+            events,
+            shader_program,
+            uniforms: Uniforms::new(shader_program),
+            shader_watch,
+            outline_program,
+            mesh,
+            mesh_source: None,
+            rotation_angle: 0.0,
+            rotation_speed: 0.0,
+            camera: Camera::new(),
+            camera_path: None,
+            path_time: 0.0,
+            path_playing: false,
+            last_frame_time: Instant::now(),
+            max_delta: DEFAULT_MAX_DELTA,
+            elapsed_time: 0.0,
+            foliage: None,
+            lighting_model: LightingModel::default(),
+            outline: None,
+            light_position: vec3(1.2, 1.0, 2.0),
+            studio_lighting: None,
+            shadow_map: None,
+            shadow_params: ShadowParams::default(),
+            clear_color,
+            ambient: 0.1,
+            exposure: 1.0,
+            auto_exposure: None,
+            adapted_exposure: 1.0,
+            presets: RenderSettings::builtin(),
+            current_preset: None,
+            fog: None,
+            pipeline: Pipeline::default(),
+            gbuffer: None,
+            lights: Vec::new(),
+            emitters: Vec::new(),
+            line_renderer: LineRenderer::new()?,
+            show_bounds: false,
+            guides: CompositionGuides::default(),
+            show_guides: false,
+            grid: Grid::default(),
+            show_grid: false,
+            debug_draw: DebugDraw::new(),
+            text: TextRenderer::default(),
+            inside_out: false,
+            backface_culling: false,
+            blending: false,
+            sort_transparent: true,
+            wireframe: false,
+            two_sided_lighting: false,
+            depth_clamp: false,
+            uv_overlay: None,
+            clear_mask: gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT,
+            measurement: None,
+            pending_pick: None,
+            frame_target: None,
+            framebuffer_size,
+            windowed_placement,
+            headless_target: None,
+            msaa_samples,
+            srgb_framebuffer,
+            gamma_correction: false,
+            gamma_pass: None,
+            gl_debug,
+            title,
+            capturing: false,
+            axis_view_key: Some(Key::Kp5),
+            input: InputState::default(),
+            home_view: None,
+            layout: ViewportLayout::Single,
+            pane_cameras: Vec::new(),
+            current_pane: Rect::FULL,
+            render_region: None,
+            tile: None,
+            pixelation: None,
+            bloom: None,
+            skybox: None,
+            material: Material::default(),
+            test_texture: None,
+            texture: None,
+            edge_detection: None,
+            motion_blur: None,
+            previous_model: None,
+            swap_mode,
+            frame_pacer: FramePacer::new(),
+            target_fps: None,
+            fixed_timestep: None,
+            step_accumulator: 0.0,
+            latency_monitor: LatencyMonitor::new(),
+            auto_pause_on_blur: true,
+            paused: false,
+            frame_timer: FrameTimer::new(),
+            stats_in_title: false,
+            title_updated: Instant::now(),
+            show_latency: false,
+            scalar_field: None,
+            annotations: Vec::new(),
+            scene: Scene::new(),
+            explode_factor: 0.0,
+            explode_current: 0.0,
+            custom_shaders: Vec::new(),
+            instanced_meshes: Vec::new(),
+            colormap_shader: None,
+            #[cfg(feature = "audio")]
+            audio: None,
+            #[cfg(feature = "audio")]
+            audio_start: 0.0,
+        })
+    }
+
+    pub fn set_foliage(&mut self, foliage: Option<Foliage>) {
+        self.foliage = foliage;
+    }
+
+    pub fn foliage_mut(&mut self) -> Option<&mut Foliage> {
+        self.foliage.as_mut()
+    }
+
+    // Replaces the current mesh with one read from `path`, remembering the path
+    // and loader so the file can be reloaded later (F5) without restarting.
+    pub fn load_mesh(
+        &mut self,
+        path: impl AsRef<Path>,
+        loader: MeshLoader,
+    ) -> Result<(), Box<dyn Error>> {
+        let source = MeshSource {
+            path: path.as_ref().to_path_buf(),
+            loader,
+        };
+        self.mesh = source.load()?;
+        self.mesh_source = Some(source);
+
+        // A new scene gets a fresh auto-framed home, and the view moves there
+        self.home_view = None;
+        self.update_home();
+        for camera in self.cameras_mut() {
+            camera.go_home();
+        }
+        Ok(())
+    }
+
+    // Viewpoint the Home key returns to for the current mesh. None goes back to
+    // framing its bounding box. Setting it doesn't move the camera; loading
+    // another mesh discards it, while reloading (F5) keeps it.
+    pub fn set_home_view(&mut self, home: Option<HomeView>) {
+        self.home_view = home;
+        self.update_home();
+    }
+
+    pub fn home_view(&self) -> HomeView {
+        self.camera.home()
+    }
+
+    // Animates the camera under the cursor back to the home view
+    pub fn reset_camera(&mut self) {
+        let (index, _) = self.pane_at_cursor();
+        self.pane_camera_mut(index).go_home();
+    }
+
+    fn update_home(&mut self) {
+        let home = self
+            .home_view
+            .unwrap_or_else(|| HomeView::framing(&self.world_bounds()));
+        for camera in self.cameras_mut() {
+            camera.set_home(home);
+        }
+    }
+
+    // The mesh being drawn, e.g. to add custom attribute channels. Loading or
+    // reloading a mesh replaces it, dropping anything added this way.
+    pub fn mesh_mut(&mut self) -> &mut Mesh {
+        &mut self.mesh
+    }
+
+    // Blend weights for the main mesh's morph targets (see Mesh::add_morph_target)
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        self.mesh.set_morph_weights(weights);
+    }
+
+    // Additional meshes drawn with the main one, each with its subtree of
+    // children. Nodes without a shader use the built-in lit shader; the rest
+    // are grouped so each program is bound once.
+    pub fn add_node(&mut self, node: SceneNode) -> NodeId {
+        self.scene.add(node)
+    }
+
+    // Adds every top-level node of `scene`, e.g. one read by load_gltf
+    pub fn add_scene(&mut self, scene: Scene) -> Vec<NodeId> {
+        scene
+            .into_nodes()
+            .into_iter()
+            .map(|node| self.scene.add(node))
+            .collect()
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&SceneNode> {
+        self.scene.node(id)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut SceneNode> {
+        self.scene.node_mut(id)
+    }
+
+    // Draws every copy of the mesh in one call per pass, for scenes with
+    // thousands of identical objects; see InstancedMesh
+    pub fn add_instanced_mesh(&mut self, mesh: InstancedMesh) -> InstancedMeshId {
+        self.instanced_meshes.push(mesh);
+        InstancedMeshId(self.instanced_meshes.len() - 1)
+    }
+
+    pub fn instanced_mesh_mut(&mut self, id: InstancedMeshId) -> Option<&mut InstancedMesh> {
+        self.instanced_meshes.get_mut(id.0)
+    }
+
+    // Pushes nodes apart to show the parts of an assembly: each node moves
+    // along the direction from the center of all nodes' bounds to its own
+    // center, by that distance times `factor`. 0 (the default) is the
+    // assembled view and 1 doubles every node's distance from the center.
+    // The view eases to the new factor over a few frames; [ and ] step it.
+    // Node transforms are left as set; see SceneNode::world_transform.
+    pub fn set_explode_factor(&mut self, factor: f32) {
+        self.explode_factor = factor.max(0.0);
+    }
+
+    pub fn explode_factor(&self) -> f32 {
+        self.explode_factor
+    }
+
+    // Main vertex and fragment shader files. Debug builds default to the
+    // crate's src/shaders, or the copies built into the library if those
+    // can't be read; release builds use the built-in copies. While running,
+    // the files are checked once a second and the program is rebuilt when
+    // either changes; if that fails the compile log is printed and the
+    // previous program stays in use. Fails without changing anything if
+    // either file can't be read. A compile error is returned too, but the new
+    // files stay watched, so fixing them loads the program.
+    pub fn set_shader_paths(
+        &mut self,
+        vertex: impl AsRef<Path>,
+        fragment: impl AsRef<Path>,
+    ) -> Result<(), ShaderError> {
+        self.shader_watch
+            .set_paths(vertex.as_ref(), fragment.as_ref())?;
+        let program = self.shader_watch.build()?;
+        self.replace_shader_program(program);
+        Ok(())
+    }
+
+    pub fn shader_paths(&self) -> (&Path, &Path) {
+        self.shader_watch.paths()
+    }
+
+    // Replaces the main program with one built from the given sources, e.g.
+    // to try another lighting model. The vertex shader gets the morph target
+    // code and the fragment shader the fog code (fog.glsl) like the built-in
+    // ones, and they're handed the same uniforms and vertex attributes;
+    // uniforms they don't declare are skipped. If compiling or linking fails
+    // the current program stays in use and the error is returned. Editing the
+    // watched shader files replaces it again.
+    pub fn set_shader_program(
+        &mut self,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<(), ShaderError> {
+        let program = unsafe {
+            build_program(
+                &with_morph_targets(vertex_source),
+                &with_fog(fragment_source),
+            )?
+        };
+        self.replace_shader_program(program);
+        Ok(())
+    }
+
+    fn reload_changed_shaders(&mut self) {
+        match self.shader_watch.poll() {
+            Some(Ok(program)) => {
+                self.replace_shader_program(program);
+                eprintln!("Reloaded shaders");
+            }
+            Some(Err(err)) => eprintln!("Shader reload failed, keeping the old program: {}", err),
+            None => {}
+        }
+    }
+
+    fn replace_shader_program(&mut self, program: u32) {
+        unsafe {
+            gl::DeleteProgram(self.shader_program);
+        }
+        self.shader_program = program;
+        self.uniforms = Uniforms::new(program);
+    }
+
+    // Compiles a program for use as a per-node override. It is given the
+    // `model`, `view` and `projection` matrices, `lightPos`, `viewPos` and
+    // `time` (seconds) uniforms, and the vertex attributes described in mesh.rs.
+    // Compile and link errors are returned with the driver's log.
+    pub fn add_shader(
+        &mut self,
+        vertex_source: &str,
+        fragment_source: &str,
+    ) -> Result<ShaderHandle, ShaderError> {
+        let program = unsafe { build_program(vertex_source, fragment_source)? };
+        self.custom_shaders.push(program);
+        Ok(ShaderHandle(self.custom_shaders.len() - 1))
+    }
+
+    // Re-reads the current mesh file from disk. On failure the previous geometry
+    // stays on screen and the error is logged. The camera is left untouched.
+    pub fn reload_mesh(&mut self) {
+        let Some(source) = &self.mesh_source else {
+            eprintln!("Nothing to reload: no mesh was loaded from disk");
+            return;
+        };
+        match source.load() {
+            Ok(mesh) => {
+                self.mesh = mesh;
+                println!("Reloaded {}", source.path.display());
+                // Re-frame an automatic home, since the bounds may have changed
+                self.update_home();
+            }
+            Err(err) => eprintln!("Failed to reload {}: {}", source.path.display(), err),
+        }
+    }
+
+    pub fn set_lighting_model(&mut self, model: LightingModel) {
+        self.lighting_model = model;
+    }
+
+    pub fn lighting_model(&self) -> LightingModel {
+        self.lighting_model
+    }
+
+    // Camera-relative key/fill/rim lighting that replaces the fixed primary
+    // light; the key casts the shadows. None goes back to the fixed light.
+    pub fn set_studio_lighting(&mut self, rig: Option<StudioLighting>) {
+        self.studio_lighting = rig;
+    }
+
+    pub fn studio_lighting(&self) -> Option<StudioLighting> {
+        self.studio_lighting
+    }
+
+    // Every light shading the scene: the studio rig's, then any added ones.
+    // With neither, the fixed white light.
+    fn scene_lights(&self) -> Vec<Light> {
+        let mut lights: Vec<Light> = self
+            .studio_lighting
+            .map(|rig| rig.lights(&self.camera).to_vec())
+            .unwrap_or_default();
+        lights.extend_from_slice(&self.lights);
+        if lights.is_empty() {
+            lights.push(Light::white(self.light_position));
+        }
+        lights
+    }
+
+    // The shadow-casting light: the studio key when the rig is on, else the
+    // first added light
+    fn primary_light(&self) -> Light {
+        self.scene_lights()[0]
+    }
+
+    // Background color when fog is off, as RGBA with each component clamped
+    // to 0-1. Applied when each frame is cleared, so changes show at once.
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = color.map(|c| if c.is_nan() { 0.0 } else { c.clamp(0.0, 1.0) });
+    }
+
+    pub fn clear_color(&self) -> [f32; 4] {
+        self.clear_color
+    }
+
+    // Strength of the light every surface receives regardless of direction
+    pub fn set_ambient(&mut self, ambient: f32) {
+        self.ambient = ambient.max(0.0);
+    }
+
+    pub fn ambient(&self) -> f32 {
+        self.ambient
+    }
+
+    // Multiplier on the lit color, applied before fog
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.exposure = exposure.max(0.0);
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    // Adapts the exposure to the scene's brightness every frame (see
+    // AutoExposure), starting from the manual exposure. Metering needs the
+    // unclipped scene, so it renders to a floating-point target like bloom
+    // does. None goes back to the manual exposure.
+    pub fn set_auto_exposure(&mut self, auto_exposure: Option<AutoExposure>) {
+        match (auto_exposure, &mut self.auto_exposure) {
+            (Some(params), Some((current, _))) => *current = params,
+            (Some(params), None) => match ExposurePass::new() {
+                Ok(pass) => {
+                    self.adapted_exposure = self.exposure;
+                    self.auto_exposure = Some((params, pass));
+                }
+                Err(err) => eprintln!("Auto exposure disabled: {}", err),
+            },
+            (None, _) => self.auto_exposure = None,
+        }
+    }
+
+    pub fn auto_exposure(&self) -> Option<AutoExposure> {
+        self.auto_exposure.as_ref().map(|(params, _)| *params)
+    }
+
+    // The exposure being rendered with: the adapted value while auto exposure
+    // is on, else the manual one
+    pub fn current_exposure(&self) -> f32 {
+        match self.auto_exposure {
+            Some(_) => self.adapted_exposure,
+            None => self.exposure,
+        }
+    }
+
+    // Snapshot of the current look, e.g. to save as a preset file
+    pub fn render_settings(&self) -> RenderSettings {
+        RenderSettings {
+            name: self
+                .current_preset
+                .map_or("Custom".to_string(), |i| self.presets[i].name.clone()),
+            lighting_model: self.lighting_model,
+            ambient: self.ambient,
+            exposure: self.exposure,
+            clear_color: self.clear_color,
+            shadows: self.shadow_map.is_some(),
+            fog: self.fog,
+            outline: self.outline,
+            edge_detection: self.edge_detection(),
+            pixelation: self.pixelation(),
+        }
+    }
+
+    pub fn apply_render_settings(&mut self, settings: &RenderSettings) {
+        self.set_lighting_model(settings.lighting_model);
+        self.set_ambient(settings.ambient);
+        self.set_exposure(settings.exposure);
+        self.set_clear_color(settings.clear_color);
+        self.set_shadows_enabled(settings.shadows);
+        self.set_fog(settings.fog);
+        self.set_outline(settings.outline);
+        self.set_edge_detection(settings.edge_detection);
+        self.set_pixelation(settings.pixelation);
+        self.current_preset = None;
+    }
+
+    // Adds a preset to the P cycle and returns its index
+    pub fn add_preset(&mut self, settings: RenderSettings) -> usize {
+        self.presets.push(settings);
+        self.presets.len() - 1
+    }
+
+    // Reads a preset file (see RenderSettings) and adds it to the cycle
+    pub fn load_preset(&mut self, path: impl AsRef<Path>) -> Result<usize, Box<dyn Error>> {
+        let settings = RenderSettings::load(path)?;
+        Ok(self.add_preset(settings))
+    }
+
+    pub fn presets(&self) -> &[RenderSettings] {
+        &self.presets
+    }
+
+    pub fn apply_preset(&mut self, index: usize) {
+        let Some(settings) = self.presets.get(index).cloned() else {
+            return;
+        };
+        self.apply_render_settings(&settings);
+        self.current_preset = Some(index);
+    }
+
+    // Applies the preset after the current one, wrapping around
+    pub fn next_preset(&mut self) {
+        if self.presets.is_empty() {
+            return;
+        }
+        let next = self
+            .current_preset
+            .map_or(0, |i| (i + 1) % self.presets.len());
+        self.apply_preset(next);
+        println!("Render preset: {}", self.presets[next].name);
+    }
+
+    // Draws a silhouette outline around the geometry, or disables it with None
+    pub fn set_outline(&mut self, outline: Option<Outline>) {
+        self.outline = outline;
+    }
+
+    // Allocates (or frees) the shadow map for the primary light
+    pub fn set_shadows_enabled(&mut self, enabled: bool) {
+        if enabled && self.shadow_map.is_none() {
+            match ShadowMap::new(SHADOW_MAP_SIZE) {
+                Ok(shadow_map) => self.shadow_map = Some(shadow_map),
+                Err(err) => eprintln!("Shadows disabled: {}", err),
+            }
+        } else if !enabled {
+            self.shadow_map = None;
+        }
+    }
+
+    // Takes effect on the next frame, so values can be tuned live
+    pub fn set_shadow_params(&mut self, bias: f32, normal_bias: f32, pcf_samples: u32) {
+        self.shadow_params.bias = bias.max(0.0);
+        self.shadow_params.normal_bias = normal_bias.max(0.0);
+        self.shadow_params.pcf_samples = pcf_samples.clamp(1, ShadowParams::MAX_PCF_SAMPLES);
+    }
+
+    // Additional bias scaled by tan(angle) between the surface normal and the light
+    pub fn set_shadow_slope_bias(&mut self, slope_bias: f32) {
+        self.shadow_params.slope_bias = slope_bias.max(0.0);
+    }
+
+    // Contact-hardening soft shadows (see SoftShadows) instead of the fixed
+    // PCF kernel, or None to go back to it
+    pub fn set_soft_shadows(&mut self, soft_shadows: Option<SoftShadows>) {
+        self.shadow_params.soft_shadows = soft_shadows;
+    }
+
+    pub fn shadow_params(&self) -> ShadowParams {
+        self.shadow_params
+    }
+
+    // Switching back to Forward frees the G-buffer
+    pub fn set_pipeline(&mut self, pipeline: Pipeline) {
+        self.pipeline = pipeline;
+        if pipeline == Pipeline::Forward {
+            self.gbuffer = None;
+        }
+    }
+
+    pub fn pipeline(&self) -> Pipeline {
+        self.pipeline
+    }
+
+    // Point lights shading the scene, along with the studio rig when it's on.
+    // The forward pipeline uses the first MAX_FORWARD_LIGHTS (8), the
+    // deferred one up to 64. With none added, a fixed white light is used so
+    // the scene isn't plunged into darkness.
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    pub fn clear_lights(&mut self) {
+        self.lights.clear();
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn add_emitter(&mut self, emitter: ParticleEmitter) {
+        self.emitters.push(emitter);
+    }
+
+    pub fn emitters_mut(&mut self) -> &mut [ParticleEmitter] {
+        &mut self.emitters
+    }
+
+    pub fn clear_emitters(&mut self) {
+        self.emitters.clear();
+    }
+
+    // Splits the window into panes that each render the scene with their own
+    // camera, in the order listed on ViewportLayout. Missing cameras start at
+    // the default view; extra ones are ignored. Mouse input goes to the pane
+    // under the cursor.
+    pub fn set_viewports(&mut self, layout: ViewportLayout, cameras: Vec<Camera>) {
+        let mut cameras = cameras.into_iter();
+        if let Some(first) = cameras.next() {
+            self.camera = first;
+        }
+        self.pane_cameras = cameras
+            .chain(std::iter::repeat_with(Camera::new))
+            .take(layout.pane_count() - 1)
+            .collect();
+        self.layout = layout;
+    }
+
+    pub fn viewport_layout(&self) -> ViewportLayout {
+        self.layout
+    }
+
+    // Writes the main camera's pose and lens to `path` so the view can be
+    // recreated in a DCC tool such as Blender or Maya (see CameraFormat). Shake
+    // isn't included; the aspect ratio is that of the main camera's pane.
+    pub fn export_camera(
+        &self,
+        path: impl AsRef<Path>,
+        format: CameraFormat,
+    ) -> Result<(), Box<dyn Error>> {
+        let pane = self.layout.panes()[0].of(&self.frame_target());
+        std::fs::write(
+            path,
+            camera_export::export(&self.camera, pane.aspect(), format),
+        )?;
+        Ok(())
+    }
+
+    // Reads a keyframed flythrough (see CameraPath) to drive the main camera,
+    // starting paused at its first keyframe
+    pub fn load_camera_path(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let camera_path = CameraPath::load(path)?;
+        self.set_camera_path(Some(camera_path));
+        Ok(())
+    }
+
+    // While a path is set it positions the main camera every frame, taking
+    // over from mouse orbiting. Space plays/pauses and , and . scrub. None
+    // returns control to the mouse from wherever the path left the camera.
+    pub fn set_camera_path(&mut self, path: Option<CameraPath>) {
+        self.camera_path = path;
+        self.path_playing = false;
+        self.seek_camera_path(0.0);
+    }
+
+    pub fn camera_path(&self) -> Option<&CameraPath> {
+        self.camera_path.as_ref()
+    }
+
+    // Playback stops at the end of the path
+    pub fn play_camera_path(&mut self) {
+        if let Some(path) = &self.camera_path
+            && self.path_time >= path.duration()
+        {
+            self.path_time = 0.0;
+        }
+        self.path_playing = self.camera_path.is_some();
+    }
+
+    pub fn pause_camera_path(&mut self) {
+        self.path_playing = false;
+    }
+
+    pub fn camera_path_playing(&self) -> bool {
+        self.path_playing
+    }
+
+    // Moves the playhead to `time` seconds and poses the camera there
+    pub fn seek_camera_path(&mut self, time: f32) {
+        let Some(path) = &self.camera_path else {
+            return;
+        };
+        self.path_time = time.clamp(0.0, path.duration());
+        if let Some(pose) = path.sample(self.path_time) {
+            self.camera.set_pose(pose.position, pose.target, pose.up);
+            self.camera.set_fov_y(pose.fov_y);
+        }
+    }
+
+    pub fn camera_path_time(&self) -> f32 {
+        self.path_time
+    }
+
+    // Renders the camera path at `fps` frames per second into numbered PNGs
+    // (frame-00000.png, ...) in `dir`, each width x height, for assembling
+    // into a video with an external encoder. Returns the number of frames.
+    // Playback is paused and left at the end of the path.
+    pub fn export_camera_path_frames(
+        &mut self,
+        dir: impl AsRef<Path>,
+        fps: f32,
+        width: u32,
+        height: u32,
+    ) -> Result<usize, Box<dyn Error>> {
+        let duration = match &self.camera_path {
+            Some(path) => path.duration(),
+            None => return Err("no camera path is set".into()),
+        };
+        if fps <= 0.0 {
+            return Err(format!("invalid frame rate {fps}").into());
+        }
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        self.path_playing = false;
+        let frames = (duration * fps).floor() as usize + 1;
+        for frame in 0..frames {
+            self.seek_camera_path(frame as f32 / fps);
+            let path = dir.join(format!("frame-{frame:05}.png"));
+            self.capture_screenshot_hires(&path, width, height, 1)?;
+        }
+        Ok(frames)
+    }
+
+    // Procedural shake on every current viewport camera (see ShakeParams);
+    // None turns it off for still captures. Cameras passed to set_viewports
+    // later keep their own setting.
+    pub fn set_camera_shake(&mut self, shake: Option<ShakeParams>) {
+        for camera in self.cameras_mut() {
+            camera.set_shake(shake);
+        }
+    }
+
+    pub fn camera_shake(&self) -> Option<ShakeParams> {
+        self.camera.shake()
+    }
+
+    // Eases the main camera's eye and target to new positions over the
+    // transition duration
+    pub fn move_camera_to(&mut self, position: Vec3, target: Vec3) {
+        self.camera.move_to(position, target);
+    }
+
+    // Seconds every current viewport camera takes to ease into a new view
+    // (home, presets, axis views, move_camera_to); 0 snaps
+    pub fn set_camera_transition_duration(&mut self, seconds: f32) {
+        for camera in self.cameras_mut() {
+            camera.set_transition_duration(seconds);
+        }
+    }
+
+    pub fn camera_transition_duration(&self) -> f32 {
+        self.camera.transition_duration()
+    }
+
+    // Drag rotation speed in radians per pixel (default 0.005) for every
+    // current viewport camera
+    pub fn set_mouse_sensitivity(&mut self, sensitivity: f32) {
+        for camera in self.cameras_mut() {
+            camera.set_mouse_sensitivity(sensitivity);
+        }
+    }
+
+    pub fn mouse_sensitivity(&self) -> f32 {
+        self.camera.mouse_sensitivity()
+    }
+
+    // Inverted vertical mouse look for every current viewport camera
+    pub fn set_invert_y(&mut self, invert: bool) {
+        for camera in self.cameras_mut() {
+            camera.set_invert_y(invert);
+        }
+    }
+
+    pub fn invert_y(&self) -> bool {
+        self.camera.invert_y()
+    }
+
+    // Near and far clip plane distances for every current viewport camera
+    // (default 0.1 and 100). Fails unless 0 < near < far, changing nothing.
+    pub fn set_clip_planes(&mut self, near: f32, far: f32) -> Result<(), String> {
+        for camera in self.cameras_mut() {
+            camera.set_clip_planes(near, far)?;
+        }
+        Ok(())
+    }
+
+    pub fn clip_planes(&self) -> (f32, f32) {
+        self.camera.clip_planes()
+    }
+
+    // Orbit or first-person controls for every current viewport camera; F
+    // toggles the camera under the cursor
+    pub fn set_camera_mode(&mut self, mode: CameraMode) {
+        for camera in self.cameras_mut() {
+            camera.set_mode(mode);
+        }
+    }
+
+    pub fn camera_mode(&self) -> CameraMode {
+        self.camera.mode()
+    }
+
+    // Switches between the free perspective view and the nearest orthographic
+    // front/side/top view, animating the change
+    pub fn toggle_axis_view(&mut self) {
+        let (index, _) = self.pane_at_cursor();
+        self.pane_camera_mut(index).toggle_axis_view();
+    }
+
+    // Snaps the camera of the viewport pane under the cursor to a canonical
+    // view; keys 1-7 pick front, back, top, bottom, left, right and isometric
+    pub fn set_view_preset(&mut self, preset: ViewPreset) {
+        let (index, _) = self.pane_at_cursor();
+        self.pane_camera_mut(index).set_view_preset(preset);
+    }
+
+    // Key bound to toggle_axis_view (default: keypad 5), or None to unbind it
+    pub fn set_axis_view_key(&mut self, key: Option<Key>) {
+        self.axis_view_key = key;
+    }
+
+    // Perspective or orthographic for every current viewport camera, e.g. for
+    // CAD-style views; zooming still works, scaling the orthographic bounds
+    pub fn set_projection(&mut self, projection: Projection) {
+        for camera in self.cameras_mut() {
+            camera.set_projection(projection);
+        }
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.camera.projection()
+    }
+
+    // Draws world-space bounding boxes: green when visible, red when frustum culled
+    pub fn set_show_bounds(&mut self, show: bool) {
+        self.show_bounds = show;
+    }
+
+    pub fn show_bounds(&self) -> bool {
+        self.show_bounds
+    }
+
+    // Renders only the faces pointing away from the camera, with their normals
+    // negated, to look at the inside of closed meshes or spot inverted winding.
+    // Unlike double-sided rendering the outside faces are hidden. Toggled with I.
+    pub fn set_inside_out(&mut self, inside_out: bool) {
+        self.inside_out = inside_out;
+    }
+
+    pub fn inside_out(&self) -> bool {
+        self.inside_out
+    }
+
+    // Skips triangles facing away from the camera (wound clockwise on
+    // screen), roughly halving the fragments drawn for closed meshes. Open
+    // or single-sided surfaces vanish when seen from behind, so it is off by
+    // default; inside-out inspection takes precedence while it's on.
+    pub fn set_backface_culling(&mut self, on: bool) {
+        self.backface_culling = on;
+    }
+
+    pub fn backface_culling(&self) -> bool {
+        self.backface_culling
+    }
+
+    // Blends the main mesh and scene nodes whose Material::alpha is below 1.0
+    // over what's behind them (SRC_ALPHA, ONE_MINUS_SRC_ALPHA). They're drawn
+    // in a pass of their own after the opaque geometry, without writing
+    // depth. Off by default, which draws every material opaque. Instanced
+    // meshes and nodes with a custom shader always stay opaque.
+    pub fn set_blending(&mut self, on: bool) {
+        self.blending = on;
+    }
+
+    pub fn blending(&self) -> bool {
+        self.blending
+    }
+
+    // Orders the transparent pass back to front by each object's distance
+    // from the camera, so nearer surfaces blend over farther ones. On by
+    // default. Without it, overlapping transparent objects may render in the
+    // wrong order; sorting whole objects also can't fix ones that intersect.
+    pub fn set_transparency_sorting(&mut self, on: bool) {
+        self.sort_transparent = on;
+    }
+
+    pub fn transparency_sorting(&self) -> bool {
+        self.sort_transparent
+    }
+
+    // Draws the meshes' triangle edges instead of filled faces, with hidden
+    // edges removed: the faces still write depth, just no color. Overlays
+    // and post passes are unaffected. Toggled with Z.
+    pub fn set_wireframe(&mut self, on: bool) {
+        self.wireframe = on;
+    }
+
+    pub fn wireframe(&self) -> bool {
+        self.wireframe
+    }
+
+    // Two-sided lighting for the main mesh: faces seen from behind are shaded
+    // with the reversed normal, so a thin surface lit from one side is dark on
+    // the other instead of showing the lit side's shading through. Both sides
+    // are drawn either way; nodes set SceneNode::two_sided_lighting instead.
+    pub fn set_two_sided_lighting(&mut self, enabled: bool) {
+        self.two_sided_lighting = enabled;
+    }
+
+    pub fn two_sided_lighting(&self) -> bool {
+        self.two_sided_lighting
+    }
+
+    // Inside-out mode already shades back faces with flipped normals
+    fn lights_back_faces(&self, two_sided: bool) -> bool {
+        two_sided && !self.inside_out
+    }
+
+    // Clamps depth to the [near, far] range instead of clipping geometry that
+    // crosses the near or far plane, so surfaces the camera is inside of (or
+    // casters behind the light's near plane) don't leave holes. Off by default.
+    pub fn set_depth_clamp(&mut self, clamp: bool) {
+        self.depth_clamp = clamp;
+        unsafe {
+            if clamp {
+                gl::Enable(gl::DEPTH_CLAMP);
+            } else {
+                gl::Disable(gl::DEPTH_CLAMP);
+            }
+        }
+    }
+
+    pub fn depth_clamp(&self) -> bool {
+        self.depth_clamp
+    }
+
+    // Shows the current mesh's UV layout in a corner viewport, or hides it with None
+    pub fn set_uv_overlay(&mut self, mode: Option<UvOverlayMode>) {
+        self.uv_overlay = mode;
+    }
+
+    // Selects which buffers are cleared at the start of each frame (default:
+    // color + depth). Skipping the color clear keeps the previous frame's
+    // image, e.g. for motion trails; skipping depth is only safe if the caller
+    // clears or overwrites it some other way.
+    pub fn set_clear_flags(&mut self, color: bool, depth: bool, stencil: bool) {
+        let mut mask = 0;
+        if color {
+            mask |= gl::COLOR_BUFFER_BIT;
+        }
+        if depth {
+            mask |= gl::DEPTH_BUFFER_BIT;
+        }
+        if stencil {
+            mask |= gl::STENCIL_BUFFER_BIT;
+        }
+        self.clear_mask = mask;
+    }
+
+    // World-space (origin, unit direction) of the ray under the mouse cursor.
+    // Cursor coordinates are in window units with a top-left origin, so they
+    // are converted via the window size rather than the framebuffer size. With
+    // split viewports the ray comes from the camera of the pane under the cursor.
+    pub fn cursor_ray(&self) -> (Vec3, Vec3) {
+        let (index, pane) = self.pane_at_cursor();
+        let (u, v) = pane.local(self.cursor_fraction()).unwrap_or((0.5, 0.5));
+        let aspect = pane.of(&self.frame_target()).aspect();
+        self.pane_camera(index)
+            .ray(u * 2.0 - 1.0, v * 2.0 - 1.0, aspect)
+    }
+
+    // Right-clicking surfaces picks measurement points for `tool`; None turns the
+    // tool off and removes its annotation
+    pub fn set_measure_tool(&mut self, tool: Option<MeasureTool>) {
+        self.measurement = tool.map(Measurement::new);
+        self.pending_pick = None;
+    }
+
+    pub fn measure_tool(&self) -> Option<MeasureTool> {
+        self.measurement.as_ref().map(Measurement::tool)
+    }
+
+    // Removes the picked points but keeps the tool active
+    pub fn clear_measurement(&mut self) {
+        if let Some(measurement) = &mut self.measurement {
+            measurement.clear();
+        }
+    }
+
+    pub fn measured_distance(&self) -> Option<f32> {
+        self.measurement.as_ref().and_then(Measurement::distance)
+    }
+
+    // Angle in radians at the middle point; None until three points are picked
+    // or when a point coincides with the vertex
+    pub fn measured_angle(&self) -> Option<f32> {
+        self.measurement.as_ref().and_then(Measurement::angle)
+    }
+
+    // Screen-facing text labels anchored to world points, drawn every frame
+    pub fn add_annotation(&mut self, annotation: Annotation) {
+        self.annotations.push(annotation);
+    }
+
+    pub fn annotations_mut(&mut self) -> &mut Vec<Annotation> {
+        &mut self.annotations
+    }
+
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+    }
+
+    // Colors the mesh by the scalar in custom attribute `attribute` (see
+    // Mesh::add_attribute), mapping [min, max] onto `colormap` instead of the
+    // usual lighting. Forward pipeline only; meshes without the attribute are
+    // drawn normally.
+    pub fn set_scalar_field(&mut self, attribute: u32, min: f32, max: f32, colormap: Colormap) {
+        if self.colormap_shader.as_ref().map(ColormapShader::location) != Some(attribute) {
+            match ColormapShader::new(attribute) {
+                Ok(shader) => self.colormap_shader = Some(shader),
+                Err(err) => {
+                    eprintln!("Scalar field disabled: {}", err);
+                    self.clear_scalar_field();
+                    return;
+                }
+            }
+        }
+        let show_legend = self.scalar_field.is_none_or(|field| field.show_legend);
+        self.scalar_field = Some(ScalarField {
+            attribute,
+            min,
+            max,
+            colormap,
+            show_legend,
+        });
+        println!("Scalar field range: {} .. {}", min, max);
+    }
+
+    pub fn clear_scalar_field(&mut self) {
+        self.scalar_field = None;
+        self.colormap_shader = None;
+    }
+
+    pub fn scalar_field(&self) -> Option<ScalarField> {
+        self.scalar_field
+    }
+
+    // Shows or hides the color ramp bar while a scalar field is set (default: shown)
+    pub fn set_colormap_legend(&mut self, show: bool) {
+        if let Some(field) = &mut self.scalar_field {
+            field.show_legend = show;
+        }
+    }
+
+    // Drives the `audioBands[8]` and `audioLevel` shader uniforms from `source`,
+    // starting from its beginning now. None stops the analysis and zeroes them.
+    #[cfg(feature = "audio")]
+    pub fn set_audio_source(
+        &mut self,
+        source: Option<audio::AudioSource>,
+    ) -> Result<(), audio::AudioError> {
+        self.audio = source.map(audio::AudioAnalyzer::new).transpose()?;
+        self.audio_start = self.elapsed_time;
+        Ok(())
+    }
+
+    #[cfg(feature = "audio")]
+    pub fn audio_levels(&self) -> audio::AudioLevels {
+        self.audio
+            .as_ref()
+            .map(audio::AudioAnalyzer::levels)
+            .unwrap_or_default()
+    }
+
+    // Enables distance fog on lit geometry and foliage, or disables it with None.
+    // While fog is on the background is cleared to the fog color so distant
+    // surfaces fade into the horizon instead of standing out against it.
+    pub fn set_fog(&mut self, fog: Option<FogParams>) {
+        self.fog = fog;
+    }
+
+    pub fn fog(&self) -> Option<FogParams> {
+        self.fog
+    }
+
+    // Low-resolution retro look for the window, or None to render normally.
+    // Screenshots are captured at full resolution either way.
+    pub fn set_pixelation(&mut self, pixelation: Option<Pixelation>) {
+        match (pixelation, &mut self.pixelation) {
+            (Some(params), Some((current, _))) => *current = params,
+            (Some(params), None) => match PixelationPass::new() {
+                Ok(pass) => self.pixelation = Some((params, pass)),
+                Err(err) => eprintln!("Pixelation disabled: {}", err),
+            },
+            (None, _) => self.pixelation = None,
+        }
+    }
+
+    pub fn pixelation(&self) -> Option<Pixelation> {
+        self.pixelation.as_ref().map(|(params, _)| *params)
+    }
+
+    // Glow around pixels brighter than the threshold, e.g. emissive materials.
+    // While on, the scene renders to a floating-point target so those values
+    // aren't clipped at 1.0 before the bright pass.
+    pub fn set_bloom(&mut self, bloom: Option<Bloom>) {
+        match (bloom, &mut self.bloom) {
+            (Some(params), Some((current, _))) => *current = params,
+            (Some(params), None) => match BloomPass::new() {
+                Ok(pass) => self.bloom = Some((params, pass)),
+                Err(err) => eprintln!("Bloom disabled: {}", err),
+            },
+            (None, _) => self.bloom = None,
+        }
+    }
+
+    pub fn bloom(&self) -> Option<Bloom> {
+        self.bloom.as_ref().map(|(params, _)| *params)
+    }
+
+    // Environment drawn behind the scene in place of the background color
+    // wherever nothing else was drawn
+    pub fn set_skybox(&mut self, skybox: Option<Skybox>) {
+        match skybox {
+            Some(skybox) => match &mut self.skybox {
+                Some((current, _)) => *current = skybox,
+                None => match SkyboxPass::new() {
+                    Ok(pass) => self.skybox = Some((skybox, pass)),
+                    Err(err) => eprintln!("Skybox disabled: {}", err),
+                },
+            },
+            None => self.skybox = None,
+        }
+    }
+
+    pub fn skybox(&self) -> Option<&Skybox> {
+        self.skybox.as_ref().map(|(skybox, _)| skybox)
+    }
+
+    // Surface of the main mesh; nodes carry their own in SceneNode::material
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+    }
+
+    pub fn material(&self) -> Material {
+        self.material.clone()
+    }
+
+    // Spins the main mesh about its tilted axis; negative speeds turn it the
+    // other way. 0, the default, leaves it still.
+    pub fn set_rotation_speed(&mut self, radians_per_sec: f32) {
+        self.rotation_speed = radians_per_sec;
+    }
+
+    pub fn rotation_speed(&self) -> f32 {
+        self.rotation_speed
+    }
+
+    // Replaces the surface color of every mesh drawn with the built-in shaders
+    // with a procedural checker/UV-quadrant texture, to check UV layout and
+    // texel density; meshes without UVs come out a flat color. None turns it off.
+    pub fn set_test_texture(&mut self, pattern: Option<TestPattern>) {
+        self.test_texture = match (pattern, self.test_texture.take()) {
+            (Some(pattern), Some((current, texture))) if pattern == current => {
+                Some((current, texture))
+            }
+            (Some(pattern), _) => Some((pattern, pattern.texture())),
+            (None, _) => None,
+        };
+    }
+
+    pub fn test_texture(&self) -> Option<TestPattern> {
+        self.test_texture.as_ref().map(|(pattern, _)| *pattern)
+    }
+
+    // Image multiplied into the surface color of every mesh drawn with the
+    // built-in shaders, sampled with the meshes' UVs. A test texture takes
+    // precedence while one is set.
+    pub fn set_texture(&mut self, texture: Option<Texture>) {
+        self.texture = texture;
+    }
+
+    pub fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+
+    fn diffuse_texture(&self) -> Option<&Texture> {
+        self.test_texture
+            .as_ref()
+            .map(|(_, texture)| texture)
+            .or(self.texture.as_ref())
+    }
+
+    // A node's own texture stands in for the global one, but not for the
+    // UV test pattern
+    fn node_texture<'a>(&'a self, node: &'a SceneNode) -> Option<&'a Texture> {
+        self.test_texture
+            .as_ref()
+            .map(|(_, texture)| texture)
+            .or(node.texture.as_deref())
+            .or(self.texture.as_ref())
+    }
+
+    // Screen-space outlines from depth and normal discontinuities of the mesh
+    // and nodes, or None to turn them off. Works with either pipeline and can
+    // be combined with the inverted-hull outline.
+    pub fn set_edge_detection(&mut self, edges: Option<EdgeDetection>) {
+        match (edges, &mut self.edge_detection) {
+            (Some(params), Some((current, _))) => *current = params,
+            (Some(params), None) => match EdgePass::new() {
+                Ok(pass) => self.edge_detection = Some((params, pass)),
+                Err(err) => eprintln!("Edge detection disabled: {}", err),
+            },
+            (None, _) => self.edge_detection = None,
+        }
+    }
+
+    pub fn edge_detection(&self) -> Option<EdgeDetection> {
+        self.edge_detection.as_ref().map(|(params, _)| *params)
+    }
+
+    // Blurs moving geometry along its screen-space motion since the previous
+    // frame, from both camera and object movement (see MotionBlur), or None
+    // to turn it off. Motion is tracked from the frame after it's enabled.
+    // Left out of screenshots, which have no previous frame.
+    pub fn set_motion_blur(&mut self, motion_blur: Option<MotionBlur>) {
+        match (motion_blur, &mut self.motion_blur) {
+            (Some(params), Some((current, _))) => *current = params,
+            (Some(params), None) => match MotionBlurPass::new() {
+                Ok(pass) => {
+                    self.clear_motion_history();
+                    self.motion_blur = Some((params, pass));
+                }
+                Err(err) => eprintln!("Motion blur disabled: {}", err),
+            },
+            (None, _) => self.motion_blur = None,
+        }
+    }
+
+    pub fn motion_blur(&self) -> Option<MotionBlur> {
+        self.motion_blur.as_ref().map(|(params, _)| *params)
+    }
+
+    // Which composition guides to draw and the safe-frame aspect; shown or
+    // hidden with set_guides_visible or the G key
+    pub fn set_composition_guides(&mut self, guides: CompositionGuides) {
+        self.guides = guides;
+    }
+
+    pub fn composition_guides(&self) -> CompositionGuides {
+        self.guides
+    }
+
+    pub fn set_guides_visible(&mut self, visible: bool) {
+        self.show_guides = visible;
+    }
+
+    pub fn guides_visible(&self) -> bool {
+        self.show_guides
+    }
+
+    // Size, line spacing and color of the floor grid shown with
+    // set_grid_visible
+    pub fn set_grid(&mut self, grid: Grid) {
+        self.grid = grid;
+    }
+
+    pub fn grid(&self) -> Grid {
+        self.grid
+    }
+
+    pub fn set_grid_spacing(&mut self, spacing: f32) {
+        self.grid.spacing = spacing;
+    }
+
+    pub fn grid_spacing(&self) -> f32 {
+        self.grid.spacing
+    }
+
+    pub fn set_grid_visible(&mut self, visible: bool) {
+        self.show_grid = visible;
+    }
+
+    pub fn grid_visible(&self) -> bool {
+        self.show_grid
+    }
+
+    // Whether swaps wait for the display refresh (default: VSync)
+    pub fn set_swap_mode(&mut self, mode: SwapMode) {
+        self.swap_mode = mode;
+        self.glfw.set_swap_interval(mode.interval());
+    }
+
+    pub fn swap_mode(&self) -> SwapMode {
+        self.swap_mode
+    }
+
+    // Shorthand for set_swap_mode: VSync when on, Immediate when off
+    pub fn set_vsync(&mut self, on: bool) {
+        self.set_swap_mode(if on {
+            SwapMode::VSync
+        } else {
+            SwapMode::Immediate
+        });
+    }
+
+    // Switches between a window and full screen on the primary monitor at its
+    // current video mode (F11). Leaving full screen restores the window's
+    // earlier position and size. Does nothing for headless engines, or when
+    // there's no monitor to go full screen on.
+    pub fn set_fullscreen(&mut self, on: bool) {
+        if on == self.is_fullscreen() || self.headless_target.is_some() {
+            return;
+        }
+        if on {
+            let ((x, y), (width, height)) = (self.window.get_pos(), self.window.get_size());
+            let window = &mut self.window;
+            let switched = self.glfw.with_primary_monitor(|_, monitor| {
+                let monitor = monitor?;
+                let mode = monitor.get_video_mode()?;
+                window.set_monitor(
+                    glfw::WindowMode::FullScreen(monitor),
+                    0,
+                    0,
+                    mode.width,
+                    mode.height,
+                    Some(mode.refresh_rate),
+                );
+                Some(())
+            });
+            if switched.is_none() {
+                eprintln!("Full screen unavailable: no monitor found");
+                return;
+            }
+            self.windowed_placement = (x, y, width.max(1) as u32, height.max(1) as u32);
+        } else {
+            let (x, y, width, height) = self.windowed_placement;
+            self.window
+                .set_monitor(glfw::WindowMode::Windowed, x, y, width, height, None);
+        }
+
+        // Some platforms reset the swap interval with the mode; the resize
+        // event arrives later, so pick up the new size right away too
+        self.glfw.set_swap_interval(self.swap_mode.interval());
+        let (width, height) = self.window.get_framebuffer_size();
+        self.framebuffer_size = (width, height);
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+        }
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.window
+            .with_window_mode(|mode| matches!(mode, glfw::WindowMode::FullScreen(_)))
+    }
+
+    // Caps the frame rate by sleeping out the rest of each frame's budget,
+    // independent of the swap mode, e.g. to keep an uncapped (Immediate)
+    // engine from pegging the GPU. None, the default, runs uncapped.
+    pub fn set_target_fps(&mut self, fps: Option<u32>) {
+        self.target_fps = fps.filter(|&fps| fps > 0);
+    }
+
+    pub fn target_fps(&self) -> Option<u32> {
+        self.target_fps
+    }
+
+    // Whether the loop pauses (no updates or drawing, just waiting on events)
+    // while the window is unfocused. On by default; turn it off for engines
+    // that must keep running in the background, e.g. recorders or displays.
+    pub fn set_auto_pause_on_blur(&mut self, on: bool) {
+        self.auto_pause_on_blur = on;
+        if !on {
+            self.paused = false;
+        }
+    }
+
+    pub fn auto_pause_on_blur(&self) -> bool {
+        self.auto_pause_on_blur
+    }
+
+    // Runs run_with's update closure in fixed steps of `step` seconds, e.g.
+    // 1/60, instead of once per frame with the frame's delta time: zero or
+    // more times a frame as real time accumulates, at most 5, with the
+    // leftover carried into the next frame. Input is still gathered per
+    // frame, so key_pressed may be seen by several steps or by none. None,
+    // the default, passes the variable frame time.
+    pub fn set_fixed_timestep(&mut self, step: Option<f32>) {
+        self.fixed_timestep = step.filter(|step| step.is_finite() && *step > 0.0);
+        self.step_accumulator = 0.0;
+    }
+
+    pub fn fixed_timestep(&self) -> Option<f32> {
+        self.fixed_timestep
+    }
+
+    // Longest delta time a frame reports, in seconds (0.1 by default). Longer
+    // frames, e.g. after a breakpoint or while the window is dragged, count as
+    // this long, so animations slow down through the stall instead of jumping.
+    // f32::INFINITY turns the clamp off; zero, negative and NaN are ignored.
+    pub fn set_max_delta(&mut self, max_delta: f32) {
+        if is_valid_max_delta(max_delta) {
+            self.max_delta = max_delta;
+        }
+    }
+
+    pub fn max_delta(&self) -> f32 {
+        self.max_delta
+    }
+
+    // Caps how many frames the CPU may submit before the GPU finishes them,
+    // waiting on a fence after each swap; None (the default) leaves it to the
+    // driver, usually 2-3. Lower values cut input latency, higher ones let the
+    // CPU and GPU overlap more for throughput. 1 pairs well with VSync for the
+    // most responsive camera; 2 behaves like triple buffering.
+    pub fn set_max_frames_in_flight(&mut self, max: Option<u32>) {
+        self.frame_pacer.set_max_in_flight(max);
+    }
+
+    pub fn max_frames_in_flight(&self) -> Option<u32> {
+        self.frame_pacer.max_in_flight()
+    }
+
+    // Estimated time from polling input to the GPU finishing the frame that
+    // used it, swap included, averaged over recent frames. The display adds
+    // its own scanout and processing delay on top, which can't be measured
+    // without hardware, so this is a lower bound on input-to-photon latency.
+    // None until the first frames complete or without timer query support.
+    pub fn input_latency(&self) -> Option<Duration> {
+        self.latency_monitor.latency()
+    }
+
+    // Frame rate and frame time averaged over the last 60 frames
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_timer.stats()
+    }
+
+    // Appends the frame stats to the window title, refreshed once a second
+    pub fn set_stats_in_title(&mut self, show: bool) {
+        self.stats_in_title = show;
+        if !show {
+            self.window.set_title(&self.title);
+        }
+    }
+
+    pub fn stats_in_title(&self) -> bool {
+        self.stats_in_title
+    }
+
+    fn update_title(&mut self) {
+        if !self.stats_in_title || self.title_updated.elapsed() < Duration::from_secs(1) {
+            return;
+        }
+        self.title_updated = Instant::now();
+        let stats = self.frame_stats();
+        self.window.set_title(&format!(
+            "{} - {:.0} fps ({:.2} ms)",
+            self.title, stats.fps, stats.frame_time_ms
+        ));
+    }
+
+    // Shows the latency estimate in the HUD, for tuning the swap mode and
+    // frames in flight. Toggled with L.
+    pub fn set_latency_visible(&mut self, visible: bool) {
+        self.show_latency = visible;
+    }
+
+    pub fn latency_visible(&self) -> bool {
+        self.show_latency
+    }
+
+    // Applies filtering/anisotropy to every existing texture and to any created later
+    pub fn set_texture_quality(&mut self, quality: TextureQuality) {
+        texture::set_texture_quality(quality);
+    }
+
+    pub fn texture_quality(&self) -> TextureQuality {
+        texture::texture_quality()
+    }
+
+    // Saves exactly what the window shows, at its resolution, as a PNG. The
+    // frame is drawn into the window's back buffer and read from there, so it
+    // can be called at any point in the loop; the next swap presents it as
+    // usual. Fails while the window is minimized.
+    pub fn capture_screenshot(&mut self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let (width, height) = self.framebuffer_size;
+        if width <= 0 || height <= 0 {
+            return Err(format!("the window has no pixels ({width}x{height})").into());
+        }
+        self.render_once();
+        // GL's rows run bottom-up; a factor of 1 only flips them
+        let pixels = downsample(&self.read_pixels(), width as u32, height as u32, 1);
+        png::write_rgba(path.as_ref(), width as u32, height as u32, &pixels)?;
+        Ok(())
+    }
+
+    // Draws one frame into the window's back buffer (or the headless target)
+    // without handling input or swapping
+    pub fn render_once(&mut self) {
+        self.render_window_frame();
+    }
+
+    // The window's back buffer (or the headless target) as tightly packed
+    // RGBA8, bottom row first like Image. Empty while the window is minimized.
+    pub fn read_pixels(&self) -> Vec<u8> {
+        let target = self.frame_target();
+        let (width, height) = (target.width.max(0), target.height.max(0));
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        if pixels.is_empty() {
+            return pixels;
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, target.fbo);
+            if target.fbo == 0 {
+                gl::ReadBuffer(gl::BACK);
+            }
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                target.x,
+                target.y,
+                width,
+                height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        pixels
+    }
+
+    // Renders the current view offscreen at width x height and saves it as a
+    // PNG, independent of the window size. With `supersample` > 1 the scene is
+    // drawn that many times larger in each direction and box-filtered down,
+    // which anti-aliases edges. The render size is limited by the driver's
+    // maximum texture size.
+    pub fn capture_screenshot_hires(
+        &mut self,
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        supersample: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        let factor = supersample.max(1);
+        let render_width = i32::try_from(width as u64 * factor as u64)?;
+        let render_height = i32::try_from(height as u64 * factor as u64)?;
+        let target = RenderTarget::new(render_width, render_height)?;
+
+        // Composition guides are for framing the shot, not part of it
+        let show_guides = std::mem::replace(&mut self.show_guides, false);
+        self.capturing = true;
+        self.render_panes(target.frame_target());
+        self.apply_gamma_pass(&target.frame_target());
+        self.capturing = false;
+        self.show_guides = show_guides;
+        let pixels = target.read_rgba();
+        drop(target);
+
+        // Restore the window so the next frame isn't affected
+        self.frame_target().bind();
+
+        let pixels = downsample(&pixels, width, height, factor);
+        png::write_rgba(path.as_ref(), width, height, &pixels)?;
+        Ok(())
+    }
+
+    // Like capture_screenshot_hires, but renders the image as a grid of
+    // tiles of at most tile_size pixels square and stitches them together,
+    // so the output can exceed the driver's maximum render target size. Each
+    // tile narrows the projection to its part of the frame. Only the main
+    // camera is drawn, over the whole image, whatever the viewport layout.
+    // Composition guides and the HUD are left out. Screen-space effects
+    // (outlines, edge detection) may show seams at tile borders, and the
+    // stitched image is held in memory (4 bytes per pixel) before saving.
+    pub fn capture_screenshot_tiled(
+        &mut self,
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        tile_size: u32,
+    ) -> Result<(), Box<dyn Error>> {
+        if width == 0 || height == 0 {
+            return Err(format!("{width}x{height} is not a valid image size").into());
+        }
+        let tile_size = tile_size.clamp(1, width.max(height));
+        let side = i32::try_from(tile_size)?;
+        let target = RenderTarget::new(side, side)?;
+        let (width, height, tile_size) = (width as usize, height as usize, tile_size as usize);
+        let aspect = width as f32 / height as f32;
+        let mut pixels = vec![0u8; width * height * 4];
+
+        let show_guides = std::mem::replace(&mut self.show_guides, false);
+        self.capturing = true;
+        for y0 in (0..height).step_by(tile_size) {
+            for x0 in (0..width).step_by(tile_size) {
+                let (x1, y1) = ((x0 + tile_size).min(width), (y0 + tile_size).min(height));
+                // The tile's NDC rectangle, scaled and shifted to fill clip space
+                let ndc = |p: usize, size: usize| p as f32 / size as f32 * 2.0 - 1.0;
+                let (left, right) = (ndc(x0, width), ndc(x1, width));
+                let (bottom, top) = (ndc(y0, height), ndc(y1, height));
+                let tile_matrix =
+                    glm::scaling(&vec3(2.0 / (right - left), 2.0 / (top - bottom), 1.0))
+                        * glm::translation(&vec3(
+                            -(left + right) * 0.5,
+                            -(bottom + top) * 0.5,
+                            0.0,
+                        ));
+
+                self.frame_target = Some(FrameTarget {
+                    width: (x1 - x0) as i32,
+                    height: (y1 - y0) as i32,
+                    ..target.frame_target()
+                });
+                self.tile = Some((tile_matrix, aspect));
+                self.render_frame();
+                self.apply_gamma_pass(&self.frame_target());
+
+                // Tile rows come bottom first; the image is stored top first
+                let tile = target.read_rgba();
+                for row in 0..y1 - y0 {
+                    let source = row * tile_size * 4;
+                    let dest = ((height - 1 - (y0 + row)) * width + x0) * 4;
+                    let len = (x1 - x0) * 4;
+                    pixels[dest..dest + len].copy_from_slice(&tile[source..source + len]);
+                }
+            }
+        }
+        self.tile = None;
+        self.frame_target = None;
+        self.capturing = false;
+        self.show_guides = show_guides;
+        drop(target);
+        self.frame_target().bind();
+
+        png::write_rgba(path.as_ref(), width as u32, height as u32, &pixels)?;
+        Ok(())
+    }
+
+    // Limits drawing in the window to `region` (fractions of the window,
+    // origin bottom-left) with the scissor test, for looking at one area in
+    // isolation; the rest of the window is cleared to black. The HUD is
+    // clipped too. Screenshots ignore it. None draws the whole window.
+    pub fn set_render_region(&mut self, region: Option<Rect>) {
+        self.render_region = region;
+    }
+
+    pub fn render_region(&self) -> Option<Rect> {
+        self.render_region
+    }
+
+    // Queues a line segment for the current frame only. Call it from the
+    // run_with update closure every frame the line should stay visible.
+    pub fn debug_line(&mut self, start: Vec3, end: Vec3, color: Vec3) {
+        self.debug_draw.line(start, end, color);
+    }
+
+    // Queues an axis-aligned cross `size` world units across for the current frame
+    pub fn debug_point(&mut self, position: Vec3, color: Vec3, size: f32) {
+        for axis in [Vec3::x(), Vec3::y(), Vec3::z()] {
+            let offset = axis * (size * 0.5);
+            self.debug_draw
+                .line(position - offset, position + offset, color);
+        }
+    }
+
+    // This frame's gizmo queue, for points, boxes and lines alike
+    pub fn debug_draw(&mut self) -> &mut DebugDraw {
+        &mut self.debug_draw
+    }
+
+    // Queues `text` for the current frame only, drawn over everything in the
+    // built-in bitmap font. (x, y) is its top-left corner in framebuffer
+    // pixels from the top-left of the window; `scale` is the size of one font
+    // pixel, 2 matching the status HUD. Newlines start new lines.
+    pub fn draw_text(&mut self, x: f32, y: f32, text: &str, scale: f32, color: Vec3) {
+        self.text.queue(x, y, text, scale, color);
+    }
+
+    // Width and height in pixels draw_text would give `text` at `scale`, e.g.
+    // for right-aligning it
+    pub fn text_size(text: &str, scale: f32) -> Vec2 {
+        text::text_size(text, scale)
+    }
+
+    // True only in the frame `key` went down; holding it (and the key-repeat
+    // events that brings) doesn't report it again. Use this for toggles in a
+    // run_with closure.
+    pub fn key_pressed(&self, key: Key) -> bool {
+        self.input.key_pressed(key)
+    }
+
+    // True every frame `key` is held, for continuous actions
+    pub fn key_down(&self, key: Key) -> bool {
+        self.input.is_key_down(key)
+    }
+
+    // Keyboard and mouse state for this frame, e.g. for custom controls in a
+    // run_with closure
+    pub fn input(&self) -> &InputState {
+        &self.input
+    }
+
+    pub fn run(&mut self) {
+        self.run_with(|_, _| {});
+    }
+
+    // Like run, but calls `update` with the frame's delta time (seconds) after
+    // input has been handled and before drawing, e.g. to move nodes or queue
+    // debug lines. With set_fixed_timestep it's called in fixed steps instead.
+    pub fn run_with(&mut self, mut update: impl FnMut(&mut X3D, f32)) {
+        // Time spent on setup isn't part of the first frame
+        self.last_frame_time = Instant::now();
+        while !self.window.should_close() {
+            if self.paused {
+                self.wait_while_paused();
+                continue;
+            }
+
+            let current_time = Instant::now();
+            let delta_time = clamp_delta(
+                current_time
+                    .duration_since(self.last_frame_time)
+                    .as_secs_f32(),
+                self.max_delta,
+            );
+            self.last_frame_time = current_time;
+            self.elapsed_time += delta_time;
+            self.frame_timer.record(delta_time);
+            self.update_title();
+
+            // Process events
+            let input_time = Instant::now();
+            self.input.begin_frame();
+            self.glfw.poll_events();
+            let events: Vec<_> = glfw::flush_messages(&self.events).collect();
+            for (_, event) in events {
+                self.input.handle_event(&event);
+                self.handle_window_event(event);
+            }
+
+            // Wrapped so the angle keeps its precision over long runs
+            self.rotation_angle = (self.rotation_angle + self.rotation_speed * delta_time)
+                .rem_euclid(std::f32::consts::TAU);
+
+            self.reload_changed_shaders();
+
+            for camera in self.cameras_mut() {
+                camera.update(delta_time);
+            }
+            self.walk_camera(delta_time);
+            self.pan_camera(delta_time);
+            if self.path_playing {
+                self.seek_camera_path(self.path_time + delta_time);
+                if self
+                    .camera_path
+                    .as_ref()
+                    .is_some_and(|path| self.path_time >= path.duration())
+                {
+                    self.path_playing = false;
+                }
+            }
+
+            for emitter in &mut self.emitters {
+                emitter.update(delta_time);
+            }
+
+            #[cfg(feature = "audio")]
+            if let Some(audio) = &mut self.audio {
+                audio.update(self.elapsed_time - self.audio_start, delta_time);
+            }
+
+            match self.fixed_timestep {
+                Some(step) => {
+                    self.step_accumulator += delta_time;
+                    let mut steps = 0;
+                    while self.step_accumulator >= step && steps < MAX_CATCH_UP_STEPS {
+                        update(self, step);
+                        self.step_accumulator -= step;
+                        steps += 1;
+                    }
+                    self.step_accumulator %= step;
+                }
+                None => update(self, delta_time),
+            }
+            self.update_explode(delta_time);
+
+            self.render_window_frame();
+            self.debug_draw.clear();
+            self.text.clear();
+            if self.motion_blur.is_some() {
+                self.store_motion_history();
+            }
+
+            // Swap buffers
+            self.window.swap_buffers();
+            self.latency_monitor.frame_submitted(input_time);
+            self.frame_pacer.frame_submitted();
+            if let Some(fps) = self.target_fps {
+                pacing::limit_frame_rate(current_time, fps);
+            }
+        }
+    }
+
+    // While paused nothing is updated or drawn; this sleeps until events
+    // arrive (or PAUSED_WAIT passes), handles them, and restarts the frame
+    // clock so the first frame after resuming doesn't see the whole pause
+    fn wait_while_paused(&mut self) {
+        self.input.begin_frame();
+        self.glfw.wait_events_timeout(PAUSED_WAIT);
+        let events: Vec<_> = glfw::flush_messages(&self.events).collect();
+        for (_, event) in events {
+            self.input.handle_event(&event);
+            self.handle_window_event(event);
+        }
+        self.last_frame_time = Instant::now();
+    }
+
+    fn handle_window_event(&mut self, event: glfw::WindowEvent) {
+        match event {
+            glfw::WindowEvent::Focus(focused) => {
+                // A hidden headless window has nothing to be paused for
+                self.paused = !focused && self.auto_pause_on_blur && self.headless_target.is_none();
+            }
+            glfw::WindowEvent::Key(key, _, action, _) => {
+                self.handle_key(key, action);
+            }
+            glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
+                let (index, _) = self.pane_at_cursor();
+                self.pane_camera_mut(index).is_rotating = true;
+            }
+            glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
+                for camera in self.cameras_mut() {
+                    camera.is_rotating = false;
+                }
+            }
+            glfw::WindowEvent::MouseButton(MouseButton::Button2, Action::Press, _)
+                if self.measurement.is_some() =>
+            {
+                self.pending_pick = Some(self.cursor_fraction());
+            }
+            glfw::WindowEvent::CursorPos(xpos, ypos) => {
+                // Every camera tracks the cursor so none jumps when it starts dragging
+                for camera in self.cameras_mut() {
+                    camera.process_mouse(xpos, ypos);
+                }
+            }
+            glfw::WindowEvent::Scroll(_, yoffset) => {
+                let (index, _) = self.pane_at_cursor();
+                self.pane_camera_mut(index).process_scroll(yoffset);
+            }
+            glfw::WindowEvent::FramebufferSize(width, height) if self.headless_target.is_none() => {
+                // Zero while minimized; aspect ratios clamp it to 1
+                self.framebuffer_size = (width, height);
+                unsafe {
+                    gl::Viewport(0, 0, width, height);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Built-in key bindings. Toggles and one-shot actions fire on Press only:
+    // holding a key makes GLFW send Repeat events, which would otherwise flip
+    // a toggle back and forth. Continuous adjustments also follow Repeat.
+    fn handle_key(&mut self, key: Key, action: Action) {
+        if matches!(key, Key::Comma | Key::Period) && action != Action::Release {
+            let step = if key == Key::Period {
+                PATH_SCRUB_STEP
+            } else {
+                -PATH_SCRUB_STEP
+            };
+            self.seek_camera_path(self.path_time + step);
+            return;
+        }
+        if matches!(key, Key::LeftBracket | Key::RightBracket) && action != Action::Release {
+            let step = if key == Key::RightBracket {
+                EXPLODE_STEP
+            } else {
+                -EXPLODE_STEP
+            };
+            self.set_explode_factor(self.explode_factor + step);
+            return;
+        }
+        if matches!(key, Key::Equal | Key::Minus) && action != Action::Release {
+            if let Some(fog) = &mut self.fog {
+                let factor = if key == Key::Equal { 1.25 } else { 0.8 };
+                fog.density = (fog.density * factor).clamp(0.001, 10.0);
+                println!("Fog density: {:.3}", fog.density);
+            }
+            return;
+        }
+        if action != Action::Press {
+            return;
+        }
+        if key == Key::Escape {
+            self.window.set_should_close(true);
+            return;
+        }
+        if Some(key) == self.axis_view_key {
+            self.toggle_axis_view();
+            return;
+        }
+        let preset = match key {
+            Key::Num1 => Some(ViewPreset::Front),
+            Key::Num2 => Some(ViewPreset::Back),
+            Key::Num3 => Some(ViewPreset::Top),
+            Key::Num4 => Some(ViewPreset::Bottom),
+            Key::Num5 => Some(ViewPreset::Left),
+            Key::Num6 => Some(ViewPreset::Right),
+            Key::Num7 => Some(ViewPreset::Isometric),
+            _ => None,
+        };
+        if let Some(preset) = preset {
+            self.set_view_preset(preset);
+            return;
+        }
+        match key {
+            Key::Home => {
+                self.reset_camera();
+            }
+            Key::F5 => {
+                self.reload_mesh();
+            }
+            Key::F11 => {
+                self.set_fullscreen(!self.is_fullscreen());
+            }
+            Key::B => {
+                self.show_bounds = !self.show_bounds;
+            }
+            Key::F => {
+                let (index, _) = self.pane_at_cursor();
+                let camera = self.pane_camera_mut(index);
+                let mode = match camera.mode() {
+                    CameraMode::Orbit => CameraMode::FirstPerson,
+                    CameraMode::FirstPerson => CameraMode::Orbit,
+                };
+                camera.set_mode(mode);
+                println!("Camera mode: {:?}", mode);
+            }
+            Key::G => {
+                self.show_guides = !self.show_guides;
+            }
+            Key::Space => {
+                if self.path_playing {
+                    self.pause_camera_path();
+                } else {
+                    self.play_camera_path();
+                }
+            }
+            Key::L => {
+                self.show_latency = !self.show_latency;
+            }
+            Key::P => {
+                self.next_preset();
+            }
+            Key::I => {
+                self.inside_out = !self.inside_out;
+            }
+            Key::Z => {
+                self.wireframe = !self.wireframe;
+            }
+            Key::T => {
+                let pattern = match self.test_texture() {
+                    Some(_) => None,
+                    None => Some(TestPattern::default()),
+                };
+                self.set_test_texture(pattern);
+            }
+            Key::U => {
+                // Off -> wireframe -> distortion -> off
+                self.uv_overlay = match self.uv_overlay {
+                    None => Some(UvOverlayMode::Wireframe),
+                    Some(UvOverlayMode::Wireframe) => Some(UvOverlayMode::Distortion),
+                    Some(UvOverlayMode::Distortion) => None,
+                };
+            }
+            Key::F12 => {
+                // Twice the window resolution, 2x2 supersampled
+                let (width, height) = self.framebuffer_size;
+                let path = screenshot_path();
+                match self.capture_screenshot_hires(
+                    &path,
+                    width.max(1) as u32 * 2,
+                    height.max(1) as u32 * 2,
+                    2,
+                ) {
+                    Ok(()) => println!("Saved {}", path.display()),
+                    Err(err) => eprintln!("Screenshot failed: {}", err),
+                }
+            }
+            Key::M => {
+                // Off -> distance -> angle -> off
+                let next = match self.measure_tool() {
+                    None => Some(MeasureTool::Distance),
+                    Some(MeasureTool::Distance) => Some(MeasureTool::Angle),
+                    Some(MeasureTool::Angle) => None,
+                };
+                self.set_measure_tool(next);
+            }
+            Key::Backspace => {
+                self.clear_measurement();
+            }
+            _ => {}
+        }
+    }
+
+    // Draws the scene to the window, once per viewport pane, through the
+    // bloom and pixelation passes if they're enabled. Bloom works at the
+    // pixelated resolution so the glow is as chunky as everything else. Auto
+    // exposure meters the HDR scene, bloom's if it's on, before compositing.
+    fn render_window_frame(&mut self) {
+        let mut window = self.frame_target();
+        // Passes draw into linear targets, so only writes to the window encode
+        let srgb_output = self.gamma_correction && self.srgb_framebuffer && window.fbo == 0;
+        if srgb_output {
+            unsafe {
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            }
+        }
+        let region = self.render_region;
+        if let Some(region) = &region {
+            // Blank out whatever was outside the region in earlier frames
+            window.bind();
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+            }
+            window.clear(gl::COLOR_BUFFER_BIT);
+            window = window.cropped(region);
+        }
+        let crop = |target: FrameTarget| match &region {
+            Some(region) => target.cropped(region),
+            None => target,
+        };
+        let low_res = match &mut self.pixelation {
+            Some((params, pass)) => pass.begin(params, &window).map(crop),
+            None => None,
+        };
+        let output = low_res.unwrap_or(window);
+        let hdr = match &mut self.bloom {
+            Some((_, pass)) => pass.begin(&output).map(crop),
+            None => None,
+        };
+        let exposure_hdr = match &mut self.auto_exposure {
+            Some((_, pass)) if hdr.is_none() => pass.begin(&output).map(crop),
+            _ => None,
+        };
+        self.render_panes(hdr.or(exposure_hdr).unwrap_or(output));
+
+        if let Some((params, pass)) = &mut self.auto_exposure {
+            let scene = match &self.bloom {
+                Some((_, bloom)) if hdr.is_some() => bloom.scene_texture(),
+                _ if exposure_hdr.is_some() => pass.scene_texture(),
+                _ => None,
+            };
+            if let Some(scene) = scene {
+                self.adapted_exposure =
+                    pass.adapt(params, scene, self.adapted_exposure, self.elapsed_time);
+            }
+        }
+        if hdr.is_some()
+            && let Some((params, pass)) = &self.bloom
+        {
+            pass.composite(params, &output);
+        }
+        if exposure_hdr.is_some()
+            && let Some((_, pass)) = &self.auto_exposure
+        {
+            pass.resolve(&output);
+        }
+        if low_res.is_some()
+            && let Some((params, pass)) = &self.pixelation
+        {
+            pass.composite(params, &window);
+        }
+        // Screen text on the finished image, once rather than per pane
+        self.text.render(&mut self.line_renderer, &window);
+
+        if srgb_output {
+            unsafe {
+                gl::Disable(gl::FRAMEBUFFER_SRGB);
+            }
+        } else {
+            self.apply_gamma_pass(&window);
+        }
+    }
+
+    // Re-encodes `target` as sRGB when gamma correction is on; for outputs
+    // that can't do it as they're written
+    fn apply_gamma_pass(&mut self, target: &FrameTarget) {
+        if !self.gamma_correction {
+            return;
+        }
+        if self.gamma_pass.is_none() {
+            match GammaPass::new() {
+                Ok(pass) => self.gamma_pass = Some(pass),
+                Err(err) => {
+                    eprintln!("Gamma correction disabled: {}", err);
+                    self.gamma_correction = false;
+                    return;
+                }
+            }
+        }
+        if let Some(pass) = &mut self.gamma_pass {
+            pass.apply(target);
+        }
+    }
+
+    // Draws each viewport pane into its part of `base`
+    fn render_panes(&mut self, base: FrameTarget) {
+        for (index, pane) in self.layout.panes().into_iter().enumerate() {
+            self.frame_target = Some(pane.of(&base));
+            self.current_pane = pane;
+            if index == 0 {
+                self.render_frame();
+            } else {
+                // Swap the pane's camera in so every pass sees it as `camera`
+                std::mem::swap(&mut self.camera, &mut self.pane_cameras[index - 1]);
+                self.render_frame();
+                std::mem::swap(&mut self.camera, &mut self.pane_cameras[index - 1]);
+            }
+        }
+        self.frame_target = None;
+        self.current_pane = Rect::FULL;
+    }
+
+    // The top-level node under a window position in screen coordinates (as
+    // reported by cursor events, origin top-left), nearest first. Hits are
+    // tested against each node's mesh bounding box, so picks near the corners
+    // of rounded meshes can land on empty space. Reflects node transforms as
+    // of the last drawn frame.
+    pub fn pick(&self, mouse_x: f64, mouse_y: f64) -> Option<NodeId> {
+        let (width, height) = self.window.get_size();
+        let point = (
+            (mouse_x / width.max(1) as f64) as f32,
+            (1.0 - mouse_y / height.max(1) as f64) as f32,
+        );
+        let panes = self.layout.panes();
+        let (index, (u, v)) = panes
+            .iter()
+            .enumerate()
+            .find_map(|(index, pane)| Some((index, pane.local(point)?)))?;
+        let aspect = panes[index].of(&self.frame_target()).aspect();
+        let (origin, direction) = self
+            .pane_camera(index)
+            .ray(u * 2.0 - 1.0, v * 2.0 - 1.0, aspect);
+        self.scene.pick(&origin, &direction)
+    }
+
+    // Cursor position as fractions of the window, origin bottom-left
+    fn cursor_fraction(&self) -> (f32, f32) {
+        let (x, y) = self.window.get_cursor_pos();
+        let (width, height) = self.window.get_size();
+        (
+            (x / width.max(1) as f64) as f32,
+            (1.0 - y / height.max(1) as f64) as f32,
+        )
+    }
+
+    // Index and rectangle of the pane under the cursor (the first one if outside)
+    fn pane_at_cursor(&self) -> (usize, Rect) {
+        let cursor = self.cursor_fraction();
+        let panes = self.layout.panes();
+        panes
+            .iter()
+            .position(|pane| pane.local(cursor).is_some())
+            .map_or((0, panes[0]), |index| (index, panes[index]))
+    }
+
+    fn pane_camera(&self, index: usize) -> &Camera {
+        match index {
+            0 => &self.camera,
+            _ => &self.pane_cameras[index - 1],
+        }
+    }
+
+    fn pane_camera_mut(&mut self, index: usize) -> &mut Camera {
+        match index {
+            0 => &mut self.camera,
+            _ => &mut self.pane_cameras[index - 1],
+        }
+    }
+
+    fn cameras_mut(&mut self) -> impl Iterator<Item = &mut Camera> {
+        std::iter::once(&mut self.camera).chain(self.pane_cameras.iter_mut())
+    }
+
+    // Held W/A/S/D move the camera under the cursor if it's in first person
+    fn walk_camera(&mut self, delta_time: f32) {
+        let axis = |positive: Key, negative: Key| {
+            self.key_down(positive) as i32 as f32 - self.key_down(negative) as i32 as f32
+        };
+        let (forward, right) = (axis(Key::W, Key::S), axis(Key::D, Key::A));
+        if forward == 0.0 && right == 0.0 {
+            return;
+        }
+        let (index, _) = self.pane_at_cursor();
+        self.pane_camera_mut(index).walk(forward, right, delta_time);
+    }
+
+    // Arrow keys slide the camera under the cursor and its target sideways and
+    // vertically, to reframe an off-center model
+    fn pan_camera(&mut self, delta_time: f32) {
+        let axis = |positive: Key, negative: Key| {
+            self.key_down(positive) as i32 as f32 - self.key_down(negative) as i32 as f32
+        };
+        let (right, up) = (axis(Key::Right, Key::Left), axis(Key::Up, Key::Down));
+        if right == 0.0 && up == 0.0 {
+            return;
+        }
+        let (index, _) = self.pane_at_cursor();
+        let camera = self.pane_camera_mut(index);
+        let step = glm::distance(&camera.eye(), &camera.target) * PAN_SPEED * delta_time;
+        camera.pan(right * step, up * step);
+    }
+
+    // Remembers this frame's transforms as the next frame's previous ones
+    fn store_motion_history(&mut self) {
+        let window = self.frame_target();
+        for (index, pane) in self.layout.panes().into_iter().enumerate() {
+            let aspect = pane.of(&window).aspect();
+            let camera = self.pane_camera_mut(index);
+            camera.previous_view_projection =
+                Some(camera.projection_matrix(aspect) * camera.get_view_matrix());
+        }
+        self.previous_model = Some(self.model_matrix());
+        self.scene
+            .visit_mut(|node| node.previous_transform = Some(node.world_transform()));
+    }
+
+    fn clear_motion_history(&mut self) {
+        for camera in self.cameras_mut() {
+            camera.previous_view_projection = None;
+        }
+        self.previous_model = None;
+        self.scene.visit_mut(|node| node.previous_transform = None);
+    }
+
+    // Draws one frame of the scene into the current frame target, without
+    // presenting it
+    fn render_frame(&mut self) {
+        let target = self.frame_target();
+        self.scene.update_transforms();
+
+        // Depth from the light's point of view, used by the main pass
+        self.render_shadow_pass();
+        target.bind();
+
+        match self.pipeline {
+            Pipeline::Forward => {
+                // Clear the screen
+                if self.clear_mask != 0 {
+                    let [r, g, b, a] = self.background_color();
+                    unsafe {
+                        gl::ClearColor(r, g, b, a);
+                    }
+                    target.clear(self.clear_mask);
+                }
+
+                // Render cube
+                self.cull_faces(true);
+                self.draw_geometry(|| self.render_cube());
+                self.cull_faces(false);
+            }
+            Pipeline::Deferred => self.render_deferred(),
+        }
+        self.render_skybox();
+        self.render_outline();
+        self.render_edges();
+
+        // Render foliage with the same camera
+        if let Some(foliage) = &self.foliage {
+            let view = self.camera.get_view_matrix();
+            let projection = self.projection_matrix();
+            foliage.render(&view, &projection, self.elapsed_time, self.fog.as_ref());
+        }
+
+        // Depth now holds all opaque geometry, so a queued click can be resolved
+        // in whichever pane was clicked
+        if let Some(local) = self.pending_pick.and_then(|p| self.current_pane.local(p)) {
+            self.pending_pick = None;
+            self.resolve_pick(local);
+        }
+
+        self.render_motion_blur();
+
+        // Floor grid after the opaque geometry, depth tested against it
+        if self.show_grid {
+            let view = self.camera.get_view_matrix();
+            let projection = self.projection_matrix();
+            grid::render(&self.grid, &mut self.line_renderer, &view, &projection);
+        }
+
+        if self.show_bounds {
+            self.render_bounds();
+        }
+
+        self.render_measurement();
+        self.render_debug_draw();
+
+        if let Some(field) = &self.scalar_field
+            && field.show_legend
+        {
+            colormap::render_legend(field.colormap, &mut self.line_renderer);
+        }
+
+        self.render_transparent();
+
+        // Transparent particles last, over all opaque geometry
+        if !self.emitters.is_empty() {
+            let view = self.camera.get_view_matrix();
+            let projection = self.projection_matrix();
+            let eye = self.camera.eye();
+            for emitter in &mut self.emitters {
+                emitter.render(&view, &projection, &eye);
+            }
+        }
+
+        // Labels over everything in the scene, including particles
+        let measurement_label = self.measurement.as_ref().and_then(Measurement::label);
+        let labels: Vec<&Annotation> = self.annotations.iter().chain(&measurement_label).collect();
+        if !labels.is_empty() {
+            let view = self.camera.get_view_matrix();
+            let projection = self.projection_matrix();
+            annotation::render(
+                &labels,
+                &mut self.line_renderer,
+                &view,
+                &projection,
+                &target,
+            );
+        }
+
+        // UV inspection overlay on top of everything
+        if let (Some(mode), Some(layout)) = (self.uv_overlay, self.mesh.uv_layout()) {
+            uv_overlay::render(layout, mode, &mut self.line_renderer, &target);
+            let [r, g, b, a] = self.background_color();
+            target.bind();
+            unsafe {
+                gl::ClearColor(r, g, b, a);
+            }
+        }
+
+        if self.show_guides {
+            guides::render(&self.guides, &mut self.line_renderer, &target);
+        }
+        // Per-tile overlays would repeat across a tiled capture
+        if self.tile.is_none() {
+            hud::render(&self.hud_status(), &mut self.line_renderer, &target);
+        }
+    }
+
+    // Eases the shown explode factor toward the target and offsets the nodes.
+    // Offsets are recomputed every frame so nodes moved in the update closure
+    // explode from their new positions.
+    fn update_explode(&mut self, delta_time: f32) {
+        let blend = 1.0 - (-EXPLODE_RATE * delta_time).exp();
+        self.explode_current += (self.explode_factor - self.explode_current) * blend;
+        if (self.explode_factor - self.explode_current).abs() < 1e-4 {
+            self.explode_current = self.explode_factor;
+        }
+
+        let bounds: Vec<Aabb> = self
+            .scene
+            .nodes()
+            .iter()
+            .map(|node| node.mesh.bounds().transformed(&node.transform))
+            .collect();
+        let Some(assembly) = Aabb::from_points(bounds.iter().flat_map(Aabb::corners)) else {
+            return;
+        };
+        let assembly_center = assembly.center();
+        for (node, bounds) in self.scene.nodes_mut().iter_mut().zip(bounds) {
+            node.explode_offset = (bounds.center() - assembly_center) * self.explode_current;
+        }
+    }
+
+    // Modes worth a reminder on screen because they change what is drawn
+    fn hud_status(&self) -> Vec<String> {
+        let mut status = Vec::new();
+        if self.inside_out {
+            status.push("Inside-out".to_string());
+        }
+        if self.wireframe {
+            status.push("Wireframe".to_string());
+        }
+        if self.depth_clamp {
+            status.push("Depth clamp".to_string());
+        }
+        if self.show_latency {
+            status.push(match self.input_latency() {
+                Some(latency) => format!("Latency ~{:.1} ms (est.)", latency.as_secs_f64() * 1e3),
+                None => "Latency n/a".to_string(),
+            });
+        }
+        status
+    }
+
+    // Where the scene is drawn: an offscreen target during captures, else the
+    // window (or its headless stand-in)
+    fn frame_target(&self) -> FrameTarget {
+        self.frame_target.unwrap_or_else(|| {
+            if let Some(target) = &self.headless_target {
+                return target.frame_target();
+            }
+            let (width, height) = self.framebuffer_size;
+            FrameTarget {
+                fbo: 0,
+                x: 0,
+                y: 0,
+                width,
+                height,
+                crop: None,
+            }
+        })
+    }
+
+    // Fog replaces the clear color, so fogged geometry fades into the background
+    fn background_color(&self) -> [f32; 4] {
+        match &self.fog {
+            Some(fog) => [fog.color.x, fog.color.y, fog.color.z, 1.0],
+            None => self.clear_color,
+        }
+    }
+
+    fn projection_matrix(&self) -> Mat4 {
+        match self.tile {
+            Some((tile_matrix, aspect)) => tile_matrix * self.camera.projection_matrix(aspect),
+            None => self.camera.projection_matrix(self.frame_target().aspect()),
+        }
+    }
+
+    fn model_matrix(&self) -> Mat4 {
+        let mut transform = Transform::new();
+        transform.rotate_axis_angle(&vec3(0.5, 1.0, 0.0), self.rotation_angle);
+        transform.to_matrix()
+    }
+
+    fn world_bounds(&self) -> Aabb {
+        self.mesh.bounds().transformed(&self.model_matrix())
+    }
+
+    // Frustum test against the current camera, used to skip drawing off-screen geometry
+    fn mesh_in_view(&self) -> bool {
+        let view_projection = self.projection_matrix() * self.camera.get_view_matrix();
+        Frustum::from_matrix(&view_projection).intersects(&self.world_bounds())
+    }
+
+    fn render_bounds(&mut self) {
+        let bounds = self.world_bounds();
+        let color = if self.mesh_in_view() {
+            vec3(0.0, 1.0, 0.0)
+        } else {
+            vec3(1.0, 0.0, 0.0)
+        };
+        let corners = bounds.corners();
+        for (a, b) in Aabb::EDGES {
+            self.line_renderer.line(&corners[a], &corners[b], &color);
+        }
+
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        self.line_renderer.flush(&view, &projection);
+    }
+
+    fn resolve_pick(&mut self, point: (f32, f32)) {
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        let hit = measure::pick_surface(point, &self.frame_target(), &view, &projection);
+        if let (Some(point), Some(measurement)) = (hit, &mut self.measurement) {
+            measurement.add_point(point);
+        }
+    }
+
+    fn render_debug_draw(&mut self) {
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        self.debug_draw
+            .render(&mut self.line_renderer, &view, &projection);
+    }
+
+    // Drawn without depth testing so the annotation stays visible behind geometry
+    fn render_measurement(&mut self) {
+        let Some(measurement) = &self.measurement else {
+            return;
+        };
+        measurement.draw(&mut self.line_renderer);
+
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        self.line_renderer.flush(&view, &projection);
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn light_space_matrix(&self) -> Mat4 {
+        let light = self.primary_light();
+        ShadowMap::light_space_matrix(&light.position, &vec3(0.0, 0.0, 0.0), SHADOW_RADIUS)
+    }
+
+    fn render_shadow_pass(&self) {
+        if let Some(shadow_map) = &self.shadow_map {
+            shadow_map.begin(&self.light_space_matrix());
+            shadow_map.set_model(&self.model_matrix());
+            self.mesh.draw();
+            for node in self.scene.iter().filter(|node| node.is_shown()) {
+                shadow_map.set_model(&node.world_transform());
+                node.mesh.draw();
+            }
+            shadow_map.set_model(&Mat4::identity());
+            for instanced in &self.instanced_meshes {
+                instanced.draw();
+            }
+            shadow_map.end(&self.frame_target());
+        }
+    }
+
+    fn render_cube(&self) {
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        let frustum = Frustum::from_matrix(&(projection * view));
+        let main_visible = self.mesh_in_view() && !self.is_blended(&self.material);
+
+        let colormapped = main_visible
+            && match (&self.scalar_field, &self.colormap_shader) {
+                (Some(field), Some(shader)) if self.mesh.has_attribute(field.attribute) => {
+                    shader.draw(
+                        &self.mesh,
+                        field,
+                        &self.model_matrix(),
+                        &view,
+                        &projection,
+                        &self.primary_light().position,
+                    );
+                    true
+                }
+                _ => false,
+            };
+
+        // The main mesh and every node without an override share the built-in program
+        let default_nodes: Vec<&SceneNode> = self
+            .scene
+            .iter()
+            .filter(|node| node.shader.is_none() && node.is_drawn(&frustum))
+            .filter(|node| !self.is_blended(&node.material))
+            .collect();
+        let instanced: Vec<&InstancedMesh> = self
+            .instanced_meshes
+            .iter()
+            .filter(|mesh| mesh.is_drawn(&frustum))
+            .collect();
+        if (main_visible && !colormapped) || !default_nodes.is_empty() || !instanced.is_empty() {
+            self.use_lit_program(&view, &projection);
+            let uniforms = &self.uniforms;
+            if main_visible && !colormapped {
+                uniforms.set_model(&self.model_matrix());
+                self.material.apply_uniforms(self.shader_program);
+                scene::set_tint_uniforms(self.shader_program, None);
+                let two_sided = self.lights_back_faces(self.two_sided_lighting);
+                set_int(uniforms.two_sided_lighting, two_sided as i32);
+                self.mesh.draw();
+            }
+            for node in default_nodes {
+                uniforms.set_model(&node.world_transform());
+                node.material.apply_uniforms(self.shader_program);
+                scene::set_tint_uniforms(self.shader_program, node.effective_tint());
+                self.bind_lit_texture(self.node_texture(node));
+                let two_sided = self.lights_back_faces(node.two_sided_lighting);
+                set_int(uniforms.two_sided_lighting, two_sided as i32);
+                node.mesh.draw();
+            }
+            if !instanced.is_empty() {
+                uniforms.set_model(&Mat4::identity());
+                self.bind_lit_texture(self.diffuse_texture());
+                scene::set_tint_uniforms(self.shader_program, None);
+                let two_sided = self.lights_back_faces(false);
+                set_int(uniforms.two_sided_lighting, two_sided as i32);
+            }
+            for mesh in instanced {
+                mesh.material.apply_uniforms(self.shader_program);
+                mesh.draw();
+            }
+        }
+
+        self.render_custom_shaded_nodes(&frustum, &view, &projection);
+    }
+
+    // Sets the lit program's (already in use) diffuse texture
+    fn bind_lit_texture(&self, texture: Option<&Texture>) {
+        set_int(self.uniforms.use_texture, texture.is_some() as i32);
+        if let Some(texture) = texture {
+            texture.bind(DIFFUSE_TEXTURE_UNIT);
+            set_int(self.uniforms.diffuse_texture, DIFFUSE_TEXTURE_UNIT as i32);
+        }
+    }
+
+    // Binds the built-in lit program and uploads everything except `model`
+    fn use_lit_program(&self, view: &Mat4, projection: &Mat4) {
+        let uniforms = &self.uniforms;
+        unsafe {
+            gl::UseProgram(self.shader_program);
+        }
+
+        set_mat4(uniforms.view, view);
+        set_mat4(uniforms.projection, projection);
+        set_vec3(uniforms.view_pos, &transform::view_position(view));
+        let lights = self.scene_lights();
+        let count = lights.len().min(MAX_FORWARD_LIGHTS);
+        let positions: Vec<f32> = lights[..count]
+            .iter()
+            .flat_map(|l| [l.position.x, l.position.y, l.position.z])
+            .collect();
+        let colors: Vec<f32> = lights[..count]
+            .iter()
+            .flat_map(|l| {
+                let c = l.color * l.intensity;
+                [c.x, c.y, c.z]
+            })
+            .collect();
+        set_int(uniforms.light_count, count as i32);
+        set_vec3_array(uniforms.light_positions, &positions);
+        set_vec3_array(uniforms.light_colors, &colors);
+
+        // Lighting model
+        set_int(uniforms.lighting_model, self.lighting_model.shader_id());
+        set_int(uniforms.toon_bands, self.lighting_model.bands());
+        set_int(uniforms.flip_normals, self.inside_out as i32);
+        set_float(uniforms.ambient_strength, self.ambient);
+        set_float(uniforms.exposure, self.current_exposure());
+
+        self.bind_lit_texture(self.diffuse_texture());
+        set_int(uniforms.srgb_textures, self.gamma_correction as i32);
+
+        // Audio-reactive input, for shaders that want it
+        #[cfg(feature = "audio")]
+        {
+            let levels = self.audio_levels();
+            set_float_array(uniforms.audio_bands, &levels.bands);
+            set_float(uniforms.audio_level, levels.loudness);
+        }
+
+        unsafe { fog::apply_uniforms(self.shader_program, self.fog.as_ref()) };
+        // Opaque until the transparent pass sets it per object
+        set_float(uniforms.alpha, 1.0);
+
+        // Shadows
+        set_int(uniforms.shadows_enabled, self.shadow_map.is_some() as i32);
+        if let Some(shadow_map) = &self.shadow_map {
+            shadow_map.bind_texture(SHADOW_TEXTURE_UNIT);
+            let params = &self.shadow_params;
+            set_int(uniforms.shadow_map, SHADOW_TEXTURE_UNIT as i32);
+            set_mat4(uniforms.light_space_matrix, &self.light_space_matrix());
+            set_float(uniforms.shadow_bias, params.bias);
+            set_float(uniforms.shadow_normal_bias, params.normal_bias);
+            set_float(uniforms.shadow_slope_bias, params.slope_bias);
+            set_int(uniforms.pcf_radius, params.pcf_radius());
+            set_int(uniforms.pcss_enabled, params.soft_shadows.is_some() as i32);
+            if let Some(soft) = &params.soft_shadows {
+                set_float(uniforms.pcss_scale, soft.pcss_scale(SHADOW_RADIUS));
+                set_int(
+                    uniforms.pcss_samples,
+                    soft.samples.clamp(1, SoftShadows::MAX_SAMPLES) as i32,
+                );
+            }
+        }
+    }
+
+    // Nodes with a shader override, sorted by handle so each program is bound once
+    fn render_custom_shaded_nodes(&self, frustum: &Frustum, view: &Mat4, projection: &Mat4) {
+        let mut nodes: Vec<(ShaderHandle, &SceneNode)> = self
+            .scene
+            .iter()
+            .filter(|node| node.is_drawn(frustum))
+            .filter_map(|node| node.shader.map(|shader| (shader, node)))
+            .collect();
+        nodes.sort_by_key(|(shader, _)| *shader);
+
+        let eye = self.camera.eye();
+        let mut bound = None;
+        for (handle, node) in nodes {
+            let Some(&program) = self.custom_shaders.get(handle.0) else {
+                continue;
+            };
+            let location =
+                |name: &std::ffi::CStr| unsafe { gl::GetUniformLocation(program, name.as_ptr()) };
+            unsafe {
+                if bound != Some(handle) {
+                    bound = Some(handle);
+                    gl::UseProgram(program);
+                    gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, view.as_ptr());
+                    gl::UniformMatrix4fv(
+                        location(c"projection"),
+                        1,
+                        gl::FALSE,
+                        projection.as_ptr(),
+                    );
+                    let light = self.primary_light().position;
+                    gl::Uniform3f(location(c"lightPos"), light.x, light.y, light.z);
+                    gl::Uniform3f(location(c"viewPos"), eye.x, eye.y, eye.z);
+                    gl::Uniform1f(location(c"time"), self.elapsed_time);
+                }
+                gl::UniformMatrix4fv(
+                    location(c"model"),
+                    1,
+                    gl::FALSE,
+                    node.world_transform().as_ptr(),
+                );
+            }
+            node.material.apply_uniforms(program);
+            scene::set_tint_uniforms(program, node.effective_tint());
+            node.mesh.draw();
+        }
+    }
+
+    // Drawn in the transparent pass instead of with the opaque geometry
+    fn is_blended(&self, material: &Material) -> bool {
+        self.blending && material.is_transparent()
+    }
+
+    // The main mesh and scene nodes with translucent materials, blended over
+    // the finished opaque frame through the built-in program. Depth is tested
+    // but not written, so they never hide each other outright.
+    fn render_transparent(&self) {
+        if !self.blending {
+            return;
+        }
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        let frustum = Frustum::from_matrix(&(projection * view));
+
+        // None stands for the main mesh
+        let mut objects: Vec<(Option<&SceneNode>, Aabb)> = self
+            .scene
+            .iter()
+            .filter(|node| node.shader.is_none() && node.is_drawn(&frustum))
+            .filter(|node| self.is_blended(&node.material))
+            .map(|node| (Some(node), node.world_bounds()))
+            .collect();
+        if self.is_blended(&self.material) && self.mesh_in_view() {
+            objects.push((None, self.world_bounds()));
+        }
+        if objects.is_empty() {
+            return;
+        }
+        if self.sort_transparent {
+            // Farthest first so nearer objects blend over them
+            let eye = self.camera.eye();
+            objects.sort_by(|(_, a), (_, b)| {
+                let da = glm::distance2(&a.center(), &eye);
+                let db = glm::distance2(&b.center(), &eye);
+                db.total_cmp(&da)
+            });
+        }
+
+        self.use_lit_program(&view, &projection);
+        unsafe {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DepthMask(gl::FALSE);
+        }
+        self.cull_faces(true);
+        self.draw_geometry(|| {
+            let uniforms = &self.uniforms;
+            for (node, _) in &objects {
+                let (material, two_sided) = match node {
+                    Some(node) => {
+                        uniforms.set_model(&node.world_transform());
+                        self.bind_lit_texture(self.node_texture(node));
+                        (&node.material, node.two_sided_lighting)
+                    }
+                    None => {
+                        uniforms.set_model(&self.model_matrix());
+                        self.bind_lit_texture(self.diffuse_texture());
+                        (&self.material, self.two_sided_lighting)
+                    }
+                };
+                material.apply_uniforms(self.shader_program);
+                set_float(uniforms.alpha, material.alpha.clamp(0.0, 1.0));
+                scene::set_tint_uniforms(
+                    self.shader_program,
+                    node.and_then(|n| n.effective_tint()),
+                );
+                set_int(
+                    uniforms.two_sided_lighting,
+                    self.lights_back_faces(two_sided) as i32,
+                );
+                match node {
+                    Some(node) => node.mesh.draw(),
+                    None => self.mesh.draw(),
+                }
+            }
+        });
+        self.cull_faces(false);
+        unsafe {
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+    }
+
+    // Drawn after the opaque geometry rather than before it: its depth sits on
+    // the far plane, so it only fills pixels nothing else covered, and the
+    // deferred lighting pass would otherwise clear it away
+    fn render_skybox(&self) {
+        let Some((skybox, pass)) = &self.skybox else {
+            return;
+        };
+        pass.render(
+            skybox,
+            &self.camera.get_view_matrix(),
+            &self.projection_matrix(),
+            self.current_exposure(),
+            self.gamma_correction,
+            self.fog.as_ref(),
+        );
+    }
+
+    fn render_outline(&self) {
+        let Some(outline) = &self.outline else {
+            return;
+        };
+        if !self.mesh_in_view() {
+            return;
+        }
+        let model = self.model_matrix();
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+
+        unsafe {
+            gl::UseProgram(self.outline_program);
+
+            let model_loc = gl::GetUniformLocation(self.outline_program, c"model".as_ptr());
+            let view_loc = gl::GetUniformLocation(self.outline_program, c"view".as_ptr());
+            let projection_loc =
+                gl::GetUniformLocation(self.outline_program, c"projection".as_ptr());
+            let width_loc = gl::GetUniformLocation(self.outline_program, c"outlineWidth".as_ptr());
+            let color_loc = gl::GetUniformLocation(self.outline_program, c"outlineColor".as_ptr());
+            let view_pos_loc = gl::GetUniformLocation(self.outline_program, c"viewPos".as_ptr());
+
+            let view_pos = self.camera.eye();
+            gl::UniformMatrix4fv(model_loc, 1, gl::FALSE, model.as_ptr());
+            gl::UniformMatrix4fv(view_loc, 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(projection_loc, 1, gl::FALSE, projection.as_ptr());
+            gl::Uniform1f(width_loc, outline.width);
+            gl::Uniform3f(color_loc, outline.color.x, outline.color.y, outline.color.z);
+            gl::Uniform3f(view_pos_loc, view_pos.x, view_pos.y, view_pos.z);
+
+            self.mesh.draw();
+        }
+    }
+
+    // Draws velocities for the mesh and every node, then blurs the frame along
+    // them. Anything without history yet (new nodes, the first frame) counts
+    // as not moving.
+    fn render_motion_blur(&mut self) {
+        // Captures have no previous frame to blur from
+        if self.capturing {
+            return;
+        }
+        let target = self.frame_target();
+        let view_projection = self.projection_matrix() * self.camera.get_view_matrix();
+        let previous_view_projection = self
+            .camera
+            .previous_view_projection
+            .unwrap_or(view_projection);
+        let frustum = Frustum::from_matrix(&view_projection);
+        let draw_mesh = self.mesh_in_view();
+        let model = self.model_matrix();
+        let previous_model = self.previous_model.unwrap_or(model);
+        let Some((params, pass)) = &mut self.motion_blur else {
+            return;
+        };
+
+        pass.begin(&target, &view_projection, &previous_view_projection);
+        if draw_mesh {
+            pass.set_model(&model, &previous_model);
+            self.mesh.draw();
+        }
+        for node in self.scene.iter().filter(|node| node.is_drawn(&frustum)) {
+            let transform = node.world_transform();
+            pass.set_model(&transform, &node.previous_transform.unwrap_or(transform));
+            node.mesh.draw();
+        }
+        pass.composite(params, &target);
+    }
+
+    // Re-draws the opaque geometry's normals and depth, then darkens the edges
+    // found in them over the shaded frame
+    fn render_edges(&mut self) {
+        let target = self.frame_target();
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        let frustum = Frustum::from_matrix(&(projection * view));
+        let draw_mesh = self.mesh_in_view();
+        let model = self.model_matrix();
+        let culled_faces = self.culled_faces();
+        let Some((params, pass)) = &mut self.edge_detection else {
+            return;
+        };
+
+        pass.begin(&target, &view, &projection);
+        set_face_culling(culled_faces);
+        if draw_mesh {
+            pass.set_model(&model);
+            self.mesh.draw();
+        }
+        for node in self.scene.iter().filter(|node| node.is_drawn(&frustum)) {
+            pass.set_model(&node.world_transform());
+            node.mesh.draw();
+        }
+        set_face_culling(None);
+        pass.composite(params, &target);
+
+        let [r, g, b, a] = self.background_color();
+        unsafe {
+            gl::ClearColor(r, g, b, a);
+        }
+    }
+
+    // Which faces of the opaque geometry to skip: front faces while inside-out
+    // inspection is on, else back faces if culling them is enabled
+    fn culled_faces(&self) -> Option<gl::types::GLenum> {
+        if self.inside_out {
+            Some(gl::FRONT)
+        } else if self.backface_culling {
+            Some(gl::BACK)
+        } else {
+            None
+        }
+    }
+
+    // Culls around the opaque geometry only; particles, foliage and overlays
+    // don't have consistent winding, so culling is otherwise left disabled
+    fn cull_faces(&self, enable: bool) {
+        set_face_culling(self.culled_faces().filter(|_| enable));
+    }
+
+    // Runs `draw` once normally, or for wireframe twice: first filling depth
+    // only, pushed back slightly so the edges on the surface pass the depth
+    // test, then as lines. The polygon mode is set and restored around every
+    // call, so toggling between frames takes effect at once.
+    fn draw_geometry(&self, draw: impl Fn()) {
+        if !self.wireframe {
+            draw();
+            return;
+        }
+        unsafe {
+            gl::ColorMask(gl::FALSE, gl::FALSE, gl::FALSE, gl::FALSE);
+            gl::Enable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonOffset(1.0, 1.0);
+        }
+        draw();
+        unsafe {
+            gl::Disable(gl::POLYGON_OFFSET_FILL);
+            gl::ColorMask(gl::TRUE, gl::TRUE, gl::TRUE, gl::TRUE);
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+        }
+        draw();
+        unsafe {
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+        }
+    }
+
+    fn render_deferred(&mut self) {
+        let target = self.frame_target();
+        if self.gbuffer.is_none() {
+            match GBuffer::new(target.width, target.height) {
+                Ok(gbuffer) => self.gbuffer = Some(gbuffer),
+                Err(err) => {
+                    eprintln!("Deferred pipeline unavailable, using forward: {}", err);
+                    self.pipeline = Pipeline::Forward;
+                    return;
+                }
+            }
+        }
+        let gbuffer = self.gbuffer.as_mut().unwrap();
+        gbuffer.resize(target.width, target.height);
+
+        let view = self.camera.get_view_matrix();
+        let projection = self.projection_matrix();
+        let gbuffer = self.gbuffer.as_ref().unwrap();
+        gbuffer.begin_geometry_pass(&view, &projection, self.inside_out);
+        gbuffer.set_diffuse_texture(self.diffuse_texture());
+        gbuffer.set_srgb_textures(self.gamma_correction);
+        self.cull_faces(true);
+        // Shader overrides don't apply here; every opaque node goes through the
+        // G-buffer
+        let frustum = Frustum::from_matrix(&(projection * view));
+        self.draw_geometry(|| {
+            if self.mesh_in_view() && !self.is_blended(&self.material) {
+                gbuffer.set_model(&self.model_matrix());
+                gbuffer.set_material(&self.material);
+                gbuffer.set_tint(None);
+                gbuffer.set_two_sided_lighting(self.lights_back_faces(self.two_sided_lighting));
+                self.mesh.draw();
+            }
+            for node in self
+                .scene
+                .iter()
+                .filter(|node| node.is_drawn(&frustum) && !self.is_blended(&node.material))
+            {
+                gbuffer.set_model(&node.world_transform());
+                gbuffer.set_material(&node.material);
+                gbuffer.set_tint(node.effective_tint());
+                gbuffer.set_diffuse_texture(self.node_texture(node));
+                gbuffer.set_two_sided_lighting(self.lights_back_faces(node.two_sided_lighting));
+                node.mesh.draw();
+            }
+            gbuffer.set_model(&Mat4::identity());
+            gbuffer.set_diffuse_texture(self.diffuse_texture());
+            gbuffer.set_tint(None);
+            gbuffer.set_two_sided_lighting(self.lights_back_faces(false));
+            for mesh in self
+                .instanced_meshes
+                .iter()
+                .filter(|m| m.is_drawn(&frustum))
+            {
+                gbuffer.set_material(&mesh.material);
+                mesh.draw();
+            }
+        });
+        self.cull_faces(false);
+
+        let lights = self.scene_lights();
+        let [r, g, b, a] = self.background_color();
+        unsafe {
+            gl::ClearColor(r, g, b, a);
+        }
+        gbuffer.lighting_pass(
+            &target,
+            &lights,
+            &view,
+            (self.ambient, self.current_exposure()),
+            self.fog.as_ref(),
+            self.clear_mask,
+        );
+    }
+}
+
+// Enables culling of `faces` (gl::FRONT or gl::BACK) of counter-clockwise
+// front-facing triangles, or disables culling for None
+// A frame's delta time given the wall time since the last frame
+fn clamp_delta(elapsed: f32, max_delta: f32) -> f32 {
+    elapsed.min(max_delta)
+}
+
+// Rejects zero, negative and NaN limits, which would freeze or break updates
+fn is_valid_max_delta(max_delta: f32) -> bool {
+    max_delta > 0.0
+}
+
+fn set_face_culling(faces: Option<gl::types::GLenum>) {
+    unsafe {
+        match faces {
+            Some(faces) => {
+                gl::Enable(gl::CULL_FACE);
+                gl::CullFace(faces);
+                gl::FrontFace(gl::CCW);
+            }
+            None => gl::Disable(gl::CULL_FACE),
+        }
+    }
+}
+
+// Averages each factor x factor block of bottom-up RGBA `pixels` into one
+// output pixel and flips the rows to top-first for image files
+fn downsample(pixels: &[u8], width: u32, height: u32, factor: u32) -> Vec<u8> {
+    let (width, height, factor) = (width as usize, height as usize, factor as usize);
+    let source_width = width * factor;
+    let samples = (factor * factor) as u32;
+    let mut out = Vec::with_capacity(width * height * 4);
+    for y in (0..height).rev() {
+        for x in 0..width {
+            let mut sum = [0u32; 4];
+            for sy in y * factor..(y + 1) * factor {
+                for sx in x * factor..(x + 1) * factor {
+                    let i = (sy * source_width + sx) * 4;
+                    for (channel, total) in sum.iter_mut().enumerate() {
+                        *total += pixels[i + channel] as u32;
+                    }
+                }
+            }
+            out.extend(sum.map(|total| ((total + samples / 2) / samples) as u8));
+        }
+    }
+    out
+}
+
+// screenshot-<unix seconds>.png in the working directory
+fn screenshot_path() -> std::path::PathBuf {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    format!("screenshot-{}.png", seconds).into()
+}
+
+// The unit cube as interleaved vertices (36, six per face), e.g. for building
+// per-vertex data that lines up with Mesh::cube
+pub fn create_cube_vertices() -> Vec<f32> {
+    // Positions + normals. Every face is wound counter-clockwise seen from
+    // outside, starting at its bottom-left corner, so back-face culling keeps
+    // the outside and textures aren't mirrored.
+    vec![
+        // Front face
+        -0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, 0.5, 0.5, 0.0, 0.0, 1.0,
+        0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, -0.5, 0.5, 0.0, 0.0,
+        1.0, // Back face
+        0.5, -0.5, -0.5, 0.0, 0.0, -1.0, -0.5, -0.5, -0.5, 0.0, 0.0, -1.0, -0.5, 0.5, -0.5, 0.0,
+        0.0, -1.0, -0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 0.5, -0.5,
+        -0.5, 0.0, 0.0, -1.0, // Left face
+        -0.5, -0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5, 0.5, -1.0,
+        0.0, 0.0, -0.5, 0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5,
+        -0.5, -1.0, 0.0, 0.0, // Right face
+        0.5, -0.5, 0.5, 1.0, 0.0, 0.0, 0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.5, 0.5, -0.5, 1.0, 0.0,
+        0.0, 0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 0.5, -0.5, 0.5, 1.0, 0.0,
+        0.0, // Bottom face
+        -0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5, -0.5, 0.5, 0.0,
+        -1.0, 0.0, 0.5, -0.5, 0.5, 0.0, -1.0, 0.0, -0.5, -0.5, 0.5, 0.0, -1.0, 0.0, -0.5, -0.5,
+        -0.5, 0.0, -1.0, 0.0, // Top face
+        -0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.5, 0.5, -0.5, 0.0, 1.0, 0.0,
+        0.5, 0.5, -0.5, 0.0, 1.0, 0.0, -0.5, 0.5, -0.5, 0.0, 1.0, 0.0, -0.5, 0.5, 0.5, 0.0, 1.0,
+        0.0,
+    ]
+}
+
+// The unit cube with each distinct vertex stored once (24: four corners per
+// face, since faces don't share normals) plus 36 indices, for Mesh::from_indexed
+pub fn create_cube_indexed() -> (Vec<f32>, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    let mut seen: HashMap<[u32; mesh::FLOATS_PER_VERTEX], u32> = HashMap::new();
+    for vertex in create_cube_vertices().chunks_exact(mesh::FLOATS_PER_VERTEX) {
+        let key: [u32; mesh::FLOATS_PER_VERTEX] = std::array::from_fn(|i| vertex[i].to_bits());
+        let index = *seen.entry(key).or_insert_with(|| {
+            vertices.extend_from_slice(vertex);
+            (vertices.len() / mesh::FLOATS_PER_VERTEX - 1) as u32
+        });
+        indices.push(index);
+    }
+    (vertices, indices)
+}
+
+// One RGB color per vertex of create_cube_vertices, a different one per face
+pub fn create_cube_face_colors() -> Vec<f32> {
+    // Front, back, left, right, bottom, top
+    let faces = [
+        [0.9, 0.3, 0.3],
+        [0.3, 0.9, 0.3],
+        [0.3, 0.3, 0.9],
+        [0.9, 0.9, 0.3],
+        [0.3, 0.9, 0.9],
+        [0.9, 0.3, 0.9],
+    ];
+    faces.iter().flat_map(|color| color.repeat(6)).collect()
+}
+
+fn create_cube_uvs() -> Vec<f32> {
+    // Each face maps the full [0, 1] square, matching create_cube_vertices order
+    let face = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 0.0];
+    face.repeat(6)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_long_frames_to_the_default_max_delta() {
+        assert_eq!(clamp_delta(3.5, DEFAULT_MAX_DELTA), 0.1);
+        assert_eq!(clamp_delta(0.016, DEFAULT_MAX_DELTA), 0.016);
+    }
+
+    #[test]
+    fn infinite_max_delta_disables_the_clamp() {
+        assert_eq!(clamp_delta(3.5, f32::INFINITY), 3.5);
+        assert!(is_valid_max_delta(f32::INFINITY));
+    }
+
+    #[test]
+    fn rejects_invalid_max_deltas() {
+        for max_delta in [0.0, -0.0, -1.0, f32::NAN, f32::NEG_INFINITY] {
+            assert!(!is_valid_max_delta(max_delta), "accepted {max_delta}");
+        }
+        assert!(is_valid_max_delta(0.25));
+    }
+}
@@ -0,0 +1,24 @@
+use glm::{Vec3, vec3};
+
+// Point light; `color` is scaled by `intensity` when shading
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub position: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+}
+
+impl Light {
+    pub fn new(position: Vec3, color: Vec3, intensity: f32) -> Self {
+        Light {
+            position,
+            color,
+            intensity,
+        }
+    }
+
+    // Plain white light of unit intensity
+    pub fn white(position: Vec3) -> Self {
+        Light::new(position, vec3(1.0, 1.0, 1.0), 1.0)
+    }
+}
@@ -0,0 +1,104 @@
+use crate::camera::Camera;
+use crate::light::Light;
+use glm::{Vec3, vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LightingModel {
+    // Smooth ambient + diffuse shading
+    #[default]
+    Lambert,
+    // Cel shading: the diffuse term is quantized into `bands` flat steps
+    Toon {
+        bands: u32,
+    },
+}
+
+impl LightingModel {
+    // Integer id matching the `lightingModel` switch in fragment.glsl
+    pub(crate) fn shader_id(self) -> i32 {
+        match self {
+            LightingModel::Lambert => 0,
+            LightingModel::Toon { .. } => 1,
+        }
+    }
+
+    pub(crate) fn bands(self) -> i32 {
+        match self {
+            LightingModel::Lambert => 0,
+            LightingModel::Toon { bands } => bands.max(1) as i32,
+        }
+    }
+}
+
+// Silhouette outline drawn with the inverted-hull technique: the mesh is drawn a
+// second time, pushed out along its normals by `width` world units, keeping only
+// the faces pointing away from the camera. Meshes with hard (per-face) normals
+// split at their edges when extruded, so corners show small notches.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outline {
+    pub width: f32,
+    pub color: Vec3,
+}
+
+impl Default for Outline {
+    fn default() -> Self {
+        Outline {
+            width: 0.02,
+            color: vec3(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+// Classic three-point product lighting placed relative to the camera, so it
+// follows as the view orbits: a key light above and to the right of the camera,
+// a dimmer fill low on the left to soften the key's shadows, and a rim light
+// behind the subject to separate its silhouette from the background.
+// Lights sit at the camera's distance from its target.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StudioLighting {
+    // Key light intensity; the other two are ratios of it
+    pub key: f32,
+    pub fill_ratio: f32,
+    pub rim_ratio: f32,
+    pub color: Vec3,
+}
+
+impl Default for StudioLighting {
+    fn default() -> Self {
+        StudioLighting {
+            key: 1.0,
+            fill_ratio: 0.5,
+            rim_ratio: 0.6,
+            color: vec3(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl StudioLighting {
+    // Key, fill and rim, in that order, for the camera's current view
+    pub fn lights(&self, camera: &Camera) -> [Light; 3] {
+        let eye = camera.eye();
+        let offset = eye - camera.target;
+        let distance = offset.norm().max(1e-3);
+        let back = offset / distance;
+        let right = camera
+            .up
+            .cross(&back)
+            .try_normalize(1e-6)
+            .unwrap_or(vec3(1.0, 0.0, 0.0));
+        let up = back.cross(&right);
+
+        let place = |direction: Vec3, intensity: f32| {
+            Light::new(
+                camera.target + direction.normalize() * distance,
+                self.color,
+                intensity,
+            )
+        };
+        [
+            place(back + right + up * 0.8, self.key),
+            place(back - right * 1.2 + up * 0.2, self.key * self.fill_ratio),
+            place(-back - right * 0.3 + up * 0.8, self.key * self.rim_ratio),
+        ]
+    }
+}
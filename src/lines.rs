@@ -0,0 +1,137 @@
+use crate::shader::{ShaderError, build_program};
+use glm::{Mat4, Vec3};
+use std::mem;
+use std::ptr;
+
+// Floats per line vertex: position (3) + color (3)
+const FLOATS_PER_VERTEX: usize = 6;
+// Size of queued points in pixels
+const POINT_SIZE: f32 = 6.0;
+
+// Batches colored line segments (and flat triangles and points) and draws them with an
+// unlit shader, one call per primitive type. Everything queued is cleared after
+// every flush (immediate mode).
+pub(crate) struct LineRenderer {
+    program: u32,
+    vao: u32,
+    vbo: u32,
+    vertices: Vec<f32>,
+    triangles: Vec<f32>,
+    points: Vec<f32>,
+}
+
+impl LineRenderer {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = unsafe {
+            build_program(
+                include_str!("shaders/line_vertex.glsl"),
+                include_str!("shaders/line_fragment.glsl"),
+            )?
+        };
+
+        let (vao, vbo) = unsafe {
+            let mut vao = 0;
+            let mut vbo = 0;
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = (FLOATS_PER_VERTEX * mem::size_of::<f32>()) as i32;
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (3 * mem::size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+            gl::BindVertexArray(0);
+
+            (vao, vbo)
+        };
+
+        Ok(LineRenderer {
+            program,
+            vao,
+            vbo,
+            vertices: Vec::new(),
+            triangles: Vec::new(),
+            points: Vec::new(),
+        })
+    }
+
+    pub(crate) fn line(&mut self, a: &Vec3, b: &Vec3, color: &Vec3) {
+        self.vertices
+            .extend_from_slice(&[a.x, a.y, a.z, color.x, color.y, color.z]);
+        self.vertices
+            .extend_from_slice(&[b.x, b.y, b.z, color.x, color.y, color.z]);
+    }
+
+    pub(crate) fn triangle(&mut self, a: &Vec3, b: &Vec3, c: &Vec3, color: &Vec3) {
+        for p in [a, b, c] {
+            self.triangles
+                .extend_from_slice(&[p.x, p.y, p.z, color.x, color.y, color.z]);
+        }
+    }
+
+    pub(crate) fn point(&mut self, p: &Vec3, color: &Vec3) {
+        self.points
+            .extend_from_slice(&[p.x, p.y, p.z, color.x, color.y, color.z]);
+    }
+
+    pub(crate) fn flush(&mut self, view: &Mat4, projection: &Mat4) {
+        if self.vertices.is_empty() && self.triangles.is_empty() && self.points.is_empty() {
+            return;
+        }
+
+        unsafe {
+            gl::UseProgram(self.program);
+            let view_projection = projection * view;
+            let loc = gl::GetUniformLocation(self.program, c"viewProjection".as_ptr());
+            gl::UniformMatrix4fv(loc, 1, gl::FALSE, view_projection.as_ptr());
+            gl::BindVertexArray(self.vao);
+
+            gl::PointSize(POINT_SIZE);
+
+            // Triangles first so lines and points stay visible on top of them
+            for (data, mode) in [
+                (&self.triangles, gl::TRIANGLES),
+                (&self.vertices, gl::LINES),
+                (&self.points, gl::POINTS),
+            ] {
+                if data.is_empty() {
+                    continue;
+                }
+                gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+                gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (data.len() * mem::size_of::<f32>()) as isize,
+                    data.as_ptr() as *const _,
+                    gl::STREAM_DRAW,
+                );
+                gl::DrawArrays(mode, 0, (data.len() / FLOATS_PER_VERTEX) as i32);
+            }
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+        }
+
+        self.vertices.clear();
+        self.triangles.clear();
+        self.points.clear();
+    }
+}
+
+impl Drop for LineRenderer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
@@ -1,344 +1,469 @@
-extern crate gl;
-extern crate glfw;
-extern crate nalgebra_glm as glm;
-
-use glfw::{Action, Context, Key, MouseButton};
-use glfw::{GlfwReceiver, fail_on_errors};
-use glm::{Mat4, Vec3, vec3};
-use std::ffi::CString;
-use std::mem;
-use std::ptr;
-use std::time::Instant;
-
-struct Camera {
-    position: Vec3,
-    target: Vec3,
-    up: Vec3,
-    zoom: f32,
-    last_mouse_pos: (f64, f64),
-    is_rotating: bool,
+use nalgebra_glm::{self as glm, vec3, vec4};
+use x3d::mesh::{FIRST_CUSTOM_ATTRIBUTE, read_obj};
+use x3d::{
+    Aabb, BlendMode, Bloom, Camera, Colormap, EdgeDetection, FogMode, FogParams, Foliage,
+    InstancedMesh, Light, LightingModel, Material, Mesh, MotionBlur, MouseButton, Outline,
+    ParticleEmitter, Pipeline, Pixelation, SceneNode, ShakeParams, SoftShadows, StudioLighting,
+    TestPattern, Texture, Transform, ViewportLayout, X3D, create_cube_face_colors, load_gltf,
+};
+
+const UNLIT_VERTEX: &str = r#"#version 330 core
+layout (location = 0) in vec3 aPos;
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+void main()
+{
+    gl_Position = projection * view * model * vec4(aPos, 1.0);
 }
+"#;
+
+const UNLIT_FRAGMENT: &str = r#"#version 330 core
+out vec4 FragColor;
+uniform float time;
+void main()
+{
+    FragColor = vec4(1.0, 0.4 + 0.3 * sin(time * 3.0), 0.2, 1.0);
+}
+"#;
 
-impl Camera {
-    fn new() -> Self {
-        Camera {
-            position: vec3(2.0, 2.0, 2.0),
-            target: vec3(0.0, 0.0, 0.0),
-            up: vec3(0.0, 1.0, 0.0),
-            zoom: 1.0,
-            last_mouse_pos: (0.0, 0.0),
-            is_rotating: false,
+fn main() {
+    let mut x3d = match X3D::builder().msaa(4).build() {
+        Ok(x3d) => x3d,
+        Err(err) => {
+            eprintln!("Failed to start: {err}");
+            std::process::exit(1);
         }
-    }
-
-    fn get_view_matrix(&self) -> Mat4 {
-        glm::look_at(&(self.position * self.zoom), &self.target, &self.up)
-    }
-
-    fn process_mouse(&mut self, window: &glfw::PWindow, xpos: f64, ypos: f64) {
-        if self.is_rotating {
-            let sensitivity = 0.005;
-            let dx = (xpos - self.last_mouse_pos.0) as f32 * sensitivity;
-            let dy = (self.last_mouse_pos.1 - ypos) as f32 * sensitivity;
-
-            // Rotate around target
-            let right = glm::cross(&(self.position - self.target).normalize(), &self.up);
-
-            // Vertical rotation (pitch)
-            let pitch = glm::rotate(&Mat4::identity(), dy, &right);
-            let pos_vec4 = glm::vec3_to_vec4(&(self.position - self.target));
-            self.position = glm::vec4_to_vec3(&(pitch * pos_vec4)) + self.target;
-
-            // Horizontal rotation (yaw)
-            let yaw = glm::rotate(&Mat4::identity(), dx, &self.up);
-            let pos_vec4 = glm::vec3_to_vec4(&(self.position - self.target));
-            self.position = glm::vec4_to_vec3(&(yaw * pos_vec4)) + self.target;
+    };
+
+    // Optional demo scenes, e.g. `cargo run -- foliage`
+    match std::env::args().nth(1).as_deref() {
+        Some("foliage") => match Foliage::new(6.0, -0.5, 400.0) {
+            Ok(foliage) => x3d.set_foliage(Some(foliage)),
+            Err(err) => eprintln!("Failed to create foliage: {}", err),
+        },
+        Some("toon") => {
+            x3d.set_lighting_model(LightingModel::Toon { bands: 3 });
+            x3d.set_outline(Some(Outline::default()));
         }
-        self.last_mouse_pos = (xpos, ypos);
-    }
-
-    fn process_scroll(&mut self, yoffset: f64) {
-        self.zoom -= yoffset as f32 * 0.1;
-        self.zoom = self.zoom.max(0.1).min(5.0);
-    }
-}
-
-pub struct X3D {
-    glfw: glfw::Glfw,
-    window: glfw::PWindow,
-    events: GlfwReceiver<(f64, glfw::WindowEvent)>,
-    shader_program: u32,
-    vao: u32,
-    vbo: u32,
-    rotation_angle: f32,
-    camera: Camera,
-    last_frame_time: Instant,
-}
-
-impl X3D {
-    pub fn new() -> Self {
-        let mut glfw = glfw::init(fail_on_errors!()).unwrap();
-
-        // Window hints for OpenGL
-        glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-        glfw.window_hint(glfw::WindowHint::OpenGlProfile(
-            glfw::OpenGlProfileHint::Core,
-        ));
-        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-        let (mut window, events) = glfw
-            .create_window(800, 600, "X3D - Camera Control", glfw::WindowMode::Windowed)
-            .expect("Failed to create GLFW window");
-
-        window.make_current();
-        window.set_key_polling(true);
-        window.set_mouse_button_polling(true);
-        window.set_cursor_pos_polling(true);
-        window.set_scroll_polling(true);
-
-        // Initialize OpenGL
-        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-        // Set up shaders (same as before)
-        let shader_program = unsafe {
-            let vertex_shader =
-                compile_shader(include_str!("shaders/vertex.glsl"), gl::VERTEX_SHADER);
-            let fragment_shader =
-                compile_shader(include_str!("shaders/fragment.glsl"), gl::FRAGMENT_SHADER);
-            link_program(vertex_shader, fragment_shader)
-        };
-
-        // Cube data (same as before)
-        let vertices = create_cube_vertices();
-
-        // Set up VAO and VBO (same as before)
-        let (vao, vbo) = unsafe {
-            let mut vao = 0;
-            let mut vbo = 0;
-
-            gl::GenVertexArrays(1, &mut vao);
-            gl::GenBuffers(1, &mut vbo);
-
-            gl::BindVertexArray(vao);
-            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
-
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                (vertices.len() * mem::size_of::<f32>()) as isize,
-                vertices.as_ptr() as *const _,
-                gl::STATIC_DRAW,
-            );
-
-            // Position attribute
-            gl::VertexAttribPointer(
-                0,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * mem::size_of::<f32>() as i32,
-                ptr::null(),
+        Some("retro") => {
+            // Chunky pixels and a 3-bit palette over cel shading
+            x3d.set_lighting_model(LightingModel::Toon { bands: 3 });
+            x3d.set_outline(Some(Outline::default()));
+            x3d.set_pixelation(Some(Pixelation {
+                pixel_size: 6,
+                color_bits: Some(3),
+                dither: true,
+            }));
+        }
+        Some("deferred") => {
+            x3d.set_pipeline(Pipeline::Deferred);
+            x3d.add_light(Light::new(vec3(2.0, 0.5, 0.0), vec3(1.0, 0.2, 0.2), 0.8));
+            x3d.add_light(Light::new(vec3(-2.0, 0.5, 0.0), vec3(0.2, 1.0, 0.2), 0.8));
+            x3d.add_light(Light::new(vec3(0.0, 2.0, 2.0), vec3(0.2, 0.2, 1.0), 0.8));
+        }
+        Some("particles") => {
+            // Sorted alpha-blended smoke next to unsorted additive sparks
+            let smoke =
+                ParticleEmitter::new(vec3(-0.6, 0.5, 0.0), 2000, BlendMode::Alpha { sort: true });
+            let sparks = ParticleEmitter::new(vec3(0.6, 0.5, 0.0), 4000, BlendMode::Additive);
+            match (smoke, sparks) {
+                (Ok(mut smoke), Ok(mut sparks)) => {
+                    smoke.size = 0.25;
+                    smoke.speed = 0.4;
+                    smoke.gravity = vec3(0.0, 0.1, 0.0);
+                    smoke.start_color = vec4(0.6, 0.6, 0.6, 0.6);
+                    smoke.end_color = vec4(0.3, 0.3, 0.3, 0.0);
+                    x3d.add_emitter(smoke);
+
+                    sparks.rate = 400.0;
+                    sparks.speed = 1.5;
+                    sparks.size = 0.04;
+                    sparks.start_color = vec4(1.0, 0.7, 0.2, 1.0);
+                    sparks.end_color = vec4(1.0, 0.1, 0.0, 0.0);
+                    x3d.add_emitter(sparks);
+                }
+                (Err(err), _) | (_, Err(err)) => eprintln!("Failed to create particles: {}", err),
+            }
+        }
+        Some("grid") => x3d.set_grid_visible(true),
+        Some("instances") => {
+            // 1000 small cubes in a 10x10x10 block, drawn with one call per pass
+            let transforms: Vec<glm::Mat4> = (0..1000)
+                .map(|i| {
+                    let cell = vec3((i % 10) as f32, (i / 10 % 10) as f32, (i / 100) as f32);
+                    glm::translation(&((cell - vec3(4.5, 4.5, 4.5)) * 0.3))
+                        * glm::scaling(&vec3(0.15, 0.15, 0.15))
+                })
+                .collect();
+            let mut cubes =
+                InstancedMesh::new(Mesh::cube()).with_material(Material::new(vec3(0.9, 0.6, 0.2)));
+            cubes.set_instances(&transforms);
+            x3d.add_instanced_mesh(cubes);
+        }
+        Some("stretch") => {
+            // A cube squashed to a slab and tilted; its shading should match
+            // the main cube's faces pointing the same way
+            let mut slab = Transform::new();
+            slab.position = vec3(0.0, -1.2, 0.0);
+            slab.scale = vec3(3.0, 0.3, 1.0);
+            slab.rotate_axis_angle(&vec3(0.0, 0.0, 1.0), 0.4);
+            x3d.add_node(
+                SceneNode::new(Mesh::cube())
+                    .with_transform(slab.into())
+                    .with_material(Material::new(vec3(0.4, 0.8, 0.5))),
             );
-            gl::EnableVertexAttribArray(0);
-
-            // Normal attribute
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * mem::size_of::<f32>() as i32,
-                (3 * mem::size_of::<f32>()) as *const _,
+        }
+        Some("gltf") => {
+            // A glTF/GLB file given after the demo name, e.g.
+            // `cargo run -- gltf model.glb`
+            match std::env::args().nth(2).map(load_gltf) {
+                Some(Ok(scene)) => {
+                    x3d.add_scene(scene);
+                }
+                Some(Err(err)) => eprintln!("Failed to load glTF: {err}"),
+                None => eprintln!("Usage: gltf <file.gltf|file.glb>"),
+            }
+        }
+        Some("materials") => {
+            // The same color matte, default and shiny, left to right
+            let color = vec3(0.8, 0.3, 0.3);
+            for (x, material) in [
+                (-1.5, Material::matte(color)),
+                (1.5, Material::shiny(color)),
+            ] {
+                x3d.add_node(
+                    SceneNode::new(Mesh::cube())
+                        .with_transform(glm::translation(&vec3(x, 0.0, 0.0)))
+                        .with_material(material),
+                );
+            }
+            x3d.set_material(Material::new(color));
+        }
+        Some("normalmap") => {
+            // Rivets pressed into every face, picked out by a light circling
+            // the cube; only the normals change, the geometry stays flat
+            x3d.set_material(
+                Material::shiny(vec3(0.7, 0.7, 0.75)).with_normal_map(rivet_normal_map(256)),
             );
-            gl::EnableVertexAttribArray(1);
-
-            (vao, vbo)
-        };
-
-        unsafe {
-            gl::Enable(gl::DEPTH_TEST);
-            gl::ClearColor(0.1, 0.1, 0.3, 1.0);
+            let mut time = 0.0f32;
+            x3d.run_with(move |x3d, dt| {
+                time += dt;
+                x3d.clear_lights();
+                let position = vec3(time.cos() * 2.0, 1.2, time.sin() * 2.0);
+                x3d.add_light(Light::white(position));
+            });
+            return;
         }
-
-        X3D {
-            glfw,
-            window,
-            events,
-            shader_program,
-            vao,
-            vbo,
-            rotation_angle: 0.0,
-            camera: Camera::new(),
-            last_frame_time: Instant::now(),
+        Some("fog") => {
+            // Grass fading into exponential fog; +/- adjust the density
+            match Foliage::new(20.0, -0.5, 150.0) {
+                Ok(foliage) => x3d.set_foliage(Some(foliage)),
+                Err(err) => eprintln!("Failed to create foliage: {}", err),
+            }
+            x3d.set_fog(Some(FogParams {
+                mode: FogMode::Exponential,
+                start: 1.0,
+                ..FogParams::default()
+            }));
         }
-    }
-
-    pub fn run(&mut self) {
-        while !self.window.should_close() {
-            let current_time = Instant::now();
-            let delta_time = current_time
-                .duration_since(self.last_frame_time)
-                .as_secs_f32();
-            self.last_frame_time = current_time;
-
-            // Process events
-            self.glfw.poll_events();
-            for (_, event) in glfw::flush_messages(&self.events) {
-                match event {
-                    glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => {
-                        self.window.set_should_close(true)
-                    }
-                    glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Press, _) => {
-                        self.camera.is_rotating = true;
-                    }
-                    glfw::WindowEvent::MouseButton(MouseButton::Button1, Action::Release, _) => {
-                        self.camera.is_rotating = false;
-                    }
-                    glfw::WindowEvent::CursorPos(xpos, ypos) => {
-                        self.camera.process_mouse(&self.window, xpos, ypos);
-                    }
-                    glfw::WindowEvent::Scroll(_, yoffset) => {
-                        self.camera.process_scroll(yoffset);
+        Some("nodes") => {
+            // A lit cube and an unlit, time-pulsing one side by side
+            x3d.add_node(
+                SceneNode::new(Mesh::cube())
+                    .with_transform(glm::translation(&vec3(-1.5, 0.0, 0.0))),
+            );
+            let mut pulsing =
+                SceneNode::new(Mesh::cube()).with_transform(glm::translation(&vec3(1.5, 0.0, 0.0)));
+            // A shader that fails to build leaves the cube on the lit shader
+            match x3d.add_shader(UNLIT_VERTEX, UNLIT_FRAGMENT) {
+                Ok(unlit) => pulsing = pulsing.with_shader(unlit),
+                Err(err) => eprintln!("Unlit shader: {err}"),
+            }
+            x3d.add_node(pulsing);
+        }
+        Some("select") => {
+            // Right-click a cube to highlight it; right-clicking empty space
+            // clears the selection
+            let cubes: Vec<_> = [-1.5, 1.5]
+                .into_iter()
+                .map(|x| {
+                    x3d.add_node(
+                        SceneNode::new(Mesh::cube())
+                            .with_transform(glm::translation(&vec3(x, 0.0, 0.0))),
+                    )
+                })
+                .collect();
+            x3d.run_with(move |x3d, _| {
+                if !x3d.input().mouse_pressed(MouseButton::Button2) {
+                    return;
+                }
+                let Some((x, y)) = x3d.input().cursor_position() else {
+                    return;
+                };
+                let picked = x3d.pick(x, y);
+                for &cube in &cubes {
+                    if let Some(node) = x3d.node_mut(cube) {
+                        node.set_tint((picked == Some(cube)).then(|| vec3(1.0, 0.6, 0.1)));
                     }
-                    _ => {}
                 }
+            });
+            return;
+        }
+        Some("glass") => {
+            // Three tinted glass panes in front of the spinning cube, blended
+            // back to front
+            x3d.set_blending(true);
+            for (i, color) in [
+                vec3(1.0, 0.3, 0.3),
+                vec3(0.3, 1.0, 0.3),
+                vec3(0.3, 0.3, 1.0),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let offset = vec3(i as f32 - 1.0, 0.0, 1.5 + i as f32 * 0.5);
+                x3d.add_node(
+                    SceneNode::new(Mesh::cube())
+                        .with_transform(
+                            glm::translation(&offset) * glm::scaling(&vec3(0.8, 1.2, 0.05)),
+                        )
+                        .with_material(Material::shiny(color).with_alpha(0.4)),
+                );
             }
-
-            // Update rotation
-            //self.rotation_angle += 0.5 * delta_time;
-
-            // Clear the screen
-            unsafe {
-                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+        Some("hierarchy") => {
+            // A planet circling the main cube with a moon circling it; only the
+            // top-level node is animated, the moon follows through the hierarchy
+            let moon = SceneNode::new(Mesh::cube())
+                .with_transform(
+                    glm::translation(&vec3(0.8, 0.0, 0.0)) * glm::scaling(&vec3(0.3, 0.3, 0.3)),
+                )
+                .with_material(Material::new(vec3(0.8, 0.8, 0.9)));
+            let planet = x3d.add_node(
+                SceneNode::new(Mesh::cube())
+                    .with_material(Material::new(vec3(0.3, 0.5, 1.0)))
+                    .with_child(moon),
+            );
+            let mut time = 0.0f32;
+            x3d.run_with(move |x3d, dt| {
+                time += dt;
+                if let Some(planet) = x3d.node_mut(planet) {
+                    planet.transform = glm::rotation(time * 0.8, &glm::Vec3::y())
+                        * glm::translation(&vec3(2.0, 0.0, 0.0))
+                        * glm::rotation(time * 3.0, &glm::Vec3::y())
+                        * glm::scaling(&vec3(0.4, 0.4, 0.4));
+                }
+            });
+            return;
+        }
+        Some("heatmap") => {
+            // One scalar per cube face, spread over the colormap
+            let mesh = x3d.mesh_mut();
+            let scalars: Vec<f32> = (0..mesh.vertex_count()).map(|i| (i / 6) as f32).collect();
+            mesh.add_attribute(FIRST_CUSTOM_ATTRIBUTE, 1, &scalars);
+            x3d.set_scalar_field(FIRST_CUSTOM_ATTRIBUTE, 0.0, 5.0, Colormap::Viridis);
+        }
+        Some("edges") => {
+            // Ink lines on creases and silhouettes over flat shading
+            x3d.set_lighting_model(LightingModel::Toon { bands: 2 });
+            x3d.set_edge_detection(Some(EdgeDetection {
+                thickness: 1.5,
+                ..EdgeDetection::default()
+            }));
+        }
+        Some("debug") => {
+            // World axes, a point circling the cube, the lights, the cube's
+            // bounds and the frame rate, re-queued every frame
+            let mut time = 0.0f32;
+            x3d.run_with(move |x3d, dt| {
+                time += dt;
+                let origin = vec3(0.0, 0.0, 0.0);
+                x3d.debug_line(origin, vec3(1.5, 0.0, 0.0), vec3(1.0, 0.0, 0.0));
+                x3d.debug_line(origin, vec3(0.0, 1.5, 0.0), vec3(0.0, 1.0, 0.0));
+                x3d.debug_line(origin, vec3(0.0, 0.0, 1.5), vec3(0.0, 0.0, 1.0));
+                let orbit = vec3(time.cos(), 0.8, time.sin()) * 1.2;
+                x3d.debug_line(origin, orbit, vec3(1.0, 1.0, 0.0));
+                x3d.debug_point(orbit, vec3(1.0, 1.0, 1.0), 0.15);
+                let lights: Vec<_> = x3d.lights().iter().map(|l| (l.position, l.color)).collect();
+                let gizmos = x3d.debug_draw();
+                for (position, color) in lights {
+                    gizmos.point(position, color);
+                }
+                gizmos.aabb(
+                    &Aabb::new(vec3(-0.5, -0.5, -0.5), vec3(0.5, 0.5, 0.5)),
+                    vec3(0.0, 1.0, 1.0),
+                );
+                let fps = format!("{:.0} FPS", x3d.frame_stats().fps);
+                x3d.draw_text(8.0, 8.0, &fps, 3.0, vec3(1.0, 1.0, 1.0));
+                x3d.draw_text(
+                    8.0,
+                    36.0,
+                    "Drag to orbit\nScroll to zoom",
+                    2.0,
+                    vec3(0.7, 0.7, 0.7),
+                );
+            });
+            return;
+        }
+        Some("motion") => {
+            // A small cube whipping around the main one, streaked by motion blur
+            let node = x3d.add_node(
+                SceneNode::new(Mesh::cube()).with_material(Material::new(vec3(1.0, 0.5, 0.2))),
+            );
+            x3d.set_motion_blur(Some(MotionBlur::default()));
+            let mut time = 0.0f32;
+            x3d.run_with(move |x3d, dt| {
+                time += dt;
+                let angle = time * 6.0;
+                if let Some(node) = x3d.node_mut(node) {
+                    node.transform =
+                        glm::translation(&vec3(angle.cos() * 1.5, 0.0, angle.sin() * 1.5))
+                            * glm::scaling(&vec3(0.3, 0.3, 0.3));
+                }
+            });
+            return;
+        }
+        Some("morph") => {
+            // The cube's top face pinching into a point and back
+            let vertices = x3d::create_cube_vertices();
+            let pinch: Vec<f32> = vertices
+                .chunks_exact(6)
+                .flat_map(|v| {
+                    if v[1] > 0.0 {
+                        [-v[0], 0.0, -v[2]]
+                    } else {
+                        [0.0; 3]
+                    }
+                })
+                .collect();
+            x3d.mesh_mut().add_morph_target(&pinch, None);
+            let mut time = 0.0f32;
+            x3d.run_with(move |x3d, dt| {
+                time += dt;
+                x3d.set_morph_weights(&[0.5 - 0.5 * (time * 1.5).cos()]);
+            });
+            return;
+        }
+        Some("studio") => {
+            // Three-point lighting that follows the camera, with the key casting soft,
+            // contact-hardening shadows
+            x3d.set_studio_lighting(Some(StudioLighting::default()));
+            x3d.set_shadows_enabled(true);
+            x3d.set_soft_shadows(Some(SoftShadows::default()));
+        }
+        Some("thin") => {
+            // Two thin cards lit from the front: orbit behind them and the left
+            // one (two-sided lighting) goes dark while the right one doesn't
+            for (x, two_sided) in [(-0.7, true), (0.7, false)] {
+                x3d.add_node(
+                    SceneNode::new(Mesh::quad())
+                        .with_transform(glm::translation(&vec3(x, 0.0, 1.2)))
+                        .with_two_sided_lighting(two_sided),
+                );
             }
-
-            // Render cube
-            self.render_cube();
-
-            // Swap buffers
-            self.window.swap_buffers();
         }
-    }
-
-    fn render_cube(&self) {
-        unsafe {
-            gl::UseProgram(self.shader_program);
-            gl::BindVertexArray(self.vao);
-
-            // Model matrix (rotation)
-            let model = glm::rotate(
-                &Mat4::identity(),
-                self.rotation_angle,
-                &vec3(0.5, 1.0, 0.0).normalize(),
+        Some("glow") => {
+            // A neon cube next to the plain one, blooming into the background
+            x3d.add_node(
+                SceneNode::new(Mesh::cube())
+                    .with_transform(glm::translation(&vec3(1.5, 0.0, 0.0)))
+                    .with_material(Material::neon(vec3(1.0, 0.2, 0.6))),
             );
-
-            // View matrix from camera
-            let view = self.camera.get_view_matrix();
-
-            // Projection matrix
-            let (width, height) = self.window.get_size();
-            let projection = glm::perspective(
-                width as f32 / height as f32,
-                45.0f32.to_radians(),
-                0.1,
-                100.0,
+            x3d.set_bloom(Some(Bloom::default()));
+        }
+        Some("colors") => {
+            // A different vertex color on each face of the cube, drawn
+            // under a white material so they aren't tinted
+            x3d.mesh_mut().set_colors(&create_cube_face_colors());
+            x3d.set_material(Material::new(vec3(1.0, 1.0, 1.0)));
+        }
+        Some("uvcheck") => {
+            // Numbered quadrants and checkers on the cube's faces; T toggles it
+            x3d.set_test_texture(Some(TestPattern {
+                tiles: 4,
+                ..TestPattern::default()
+            }));
+        }
+        Some("shake") => {
+            // Exaggerated handheld wobble so it's obvious at a glance
+            x3d.set_camera_shake(Some(ShakeParams {
+                amplitude: 0.05,
+                rotation: 0.02,
+                ..ShakeParams::default()
+            }));
+        }
+        Some("split") => {
+            // Front, side and top views around the default orbit camera
+            let center = vec3(0.0, 0.0, 0.0);
+            x3d.set_viewports(
+                ViewportLayout::Quad,
+                vec![
+                    Camera::new(),
+                    Camera::looking_at(vec3(0.0, 0.0, 3.5), center),
+                    Camera::looking_at(vec3(3.5, 0.0, 0.0), center),
+                    Camera::looking_at(vec3(0.0, 3.5, 0.01), center),
+                ],
             );
-
-            // Set matrices
-            let model_loc =
-                gl::GetUniformLocation(self.shader_program, b"model\0".as_ptr() as *const _);
-            let view_loc =
-                gl::GetUniformLocation(self.shader_program, b"view\0".as_ptr() as *const _);
-            let projection_loc =
-                gl::GetUniformLocation(self.shader_program, b"projection\0".as_ptr() as *const _);
-
-            gl::UniformMatrix4fv(model_loc, 1, gl::FALSE, model.as_ptr());
-            gl::UniformMatrix4fv(view_loc, 1, gl::FALSE, view.as_ptr());
-            gl::UniformMatrix4fv(projection_loc, 1, gl::FALSE, projection.as_ptr());
-
-            // Light position (fixed in world space)
-            let light_pos = vec3(1.2, 1.0, 2.0);
-            let light_pos_loc =
-                gl::GetUniformLocation(self.shader_program, b"lightPos\0".as_ptr() as *const _);
-            gl::Uniform3f(light_pos_loc, light_pos.x, light_pos.y, light_pos.z);
-
-            // Draw cube
-            gl::DrawArrays(gl::TRIANGLES, 0, 36);
         }
-    }
-}
-unsafe fn compile_shader(src: &str, ty: gl::types::GLenum) -> u32 {
-    let shader = gl::CreateShader(ty);
-    let c_str = CString::new(src.as_bytes()).unwrap();
-    gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
-    gl::CompileShader(shader);
-
-    // Check for compilation errors
-    let mut success = 0;
-    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
-    if success == 0 {
-        let mut len = 0;
-        gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
-        let mut buf = Vec::with_capacity(len as usize);
-        buf.set_len((len as usize) - 1);
-        gl::GetShaderInfoLog(shader, len, ptr::null_mut(), buf.as_mut_ptr() as *mut _);
-        panic!(
-            "Shader compilation failed: {}",
-            String::from_utf8_lossy(&buf)
-        );
+        Some("explode") => {
+            // Eight blocks at the corners of the main cube, eased apart on startup;
+            // [ and ] adjust the spread
+            for corner in 0..8 {
+                let sign = |bit: i32| if corner & bit == 0 { -0.8 } else { 0.8 };
+                let position = vec3(sign(1), sign(2), sign(4));
+                x3d.add_node(
+                    SceneNode::new(Mesh::cube())
+                        .with_transform(
+                            glm::translation(&position) * glm::scaling(&vec3(0.5, 0.5, 0.5)),
+                        )
+                        .with_material(Material::new(vec3(0.9, 0.6, 0.3))),
+                );
+            }
+            x3d.set_explode_factor(1.0);
+        }
+        // Any other argument ending in .obj is loaded in place of the cube; F5 reloads it
+        Some(path) if path.to_ascii_lowercase().ends_with(".obj") => {
+            if let Err(err) = x3d.load_mesh(path, read_obj) {
+                eprintln!("Failed to load mesh: {}", err);
+            }
+        }
+        // An image file (PNG, JPEG, PPM/PGM or TGA) textures the cube
+        Some(path)
+            if [".png", ".jpg", ".jpeg", ".ppm", ".pgm", ".tga"]
+                .iter()
+                .any(|ext| path.to_ascii_lowercase().ends_with(ext)) =>
+        {
+            match Texture::load(path) {
+                Ok(texture) => x3d.set_texture(Some(texture)),
+                Err(err) => eprintln!("Failed to load texture: {}", err),
+            }
+        }
+        _ => {}
     }
 
-    shader
+    x3d.run();
 }
 
-unsafe fn link_program(vertex_shader: u32, fragment_shader: u32) -> u32 {
-    let program = gl::CreateProgram();
-    gl::AttachShader(program, vertex_shader);
-    gl::AttachShader(program, fragment_shader);
-    gl::LinkProgram(program);
-
-    // Check for linking errors
-    let mut success = 0;
-    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
-    if success == 0 {
-        let mut len = 0;
-        gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
-        let mut buf = Vec::with_capacity(len as usize);
-        buf.set_len((len as usize) - 1);
-        gl::GetProgramInfoLog(program, len, ptr::null_mut(), buf.as_mut_ptr() as *mut _);
-        panic!("Program linking failed: {}", String::from_utf8_lossy(&buf));
+// A 4x4 grid of domed rivets as a tangent-space normal map, the slopes of a
+// height field taken by central differences
+fn rivet_normal_map(size: u32) -> Texture {
+    let height = |u: f32, v: f32| {
+        let (x, y) = ((u * 4.0).fract() - 0.5, (v * 4.0).fract() - 0.5);
+        (0.09 - x * x - y * y).max(0.0).sqrt()
+    };
+    let step = 1.0 / size as f32;
+    let mut pixels = Vec::with_capacity((size * size * 4) as usize);
+    for row in 0..size {
+        for column in 0..size {
+            let (u, v) = ((column as f32 + 0.5) * step, (row as f32 + 0.5) * step);
+            let du = (height(u + step, v) - height(u - step, v)) / (2.0 * step);
+            let dv = (height(u, v + step) - height(u, v - step)) / (2.0 * step);
+            // Slopes are in tile widths; scaled down so the rims aren't sheer
+            let normal = vec3(-du * 0.25, -dv * 0.25, 1.0).normalize();
+            let encode = |n: f32| ((n * 0.5 + 0.5) * 255.0).round() as u8;
+            pixels.extend_from_slice(&[encode(normal.x), encode(normal.y), encode(normal.z), 255]);
+        }
     }
-
-    gl::DeleteShader(vertex_shader);
-    gl::DeleteShader(fragment_shader);
-
-    program
-}
-fn create_cube_vertices() -> Vec<f32> {
-    // Positions + Normals
-    vec![
-        // Front face
-        -0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, 0.5, 0.5, 0.0, 0.0, 1.0,
-        0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, -0.5, 0.5, 0.0, 0.0,
-        1.0, // Back face
-        -0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.5, 0.5, -0.5, 0.0,
-        0.0, -1.0, 0.5, 0.5, -0.5, 0.0, 0.0, -1.0, -0.5, 0.5, -0.5, 0.0, 0.0, -1.0, -0.5, -0.5,
-        -0.5, 0.0, 0.0, -1.0, // Left face
-        -0.5, 0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5, -0.5, -1.0,
-        0.0, 0.0, -0.5, -0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5,
-        0.5, -1.0, 0.0, 0.0, // Right face
-        0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 0.5, -0.5, -0.5, 1.0, 0.0,
-        0.0, 0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.5, -0.5, 0.5, 1.0, 0.0, 0.0, 0.5, 0.5, 0.5, 1.0,
-        0.0, 0.0, // Bottom face
-        -0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5, -0.5, 0.5, 0.0,
-        -1.0, 0.0, 0.5, -0.5, 0.5, 0.0, -1.0, 0.0, -0.5, -0.5, 0.5, 0.0, -1.0, 0.0, -0.5, -0.5,
-        -0.5, 0.0, -1.0, 0.0, // Top face
-        -0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.5, 0.5, 0.5, 0.0, 1.0,
-        0.0, 0.5, 0.5, 0.5, 0.0, 1.0, 0.0, -0.5, 0.5, 0.5, 0.0, 1.0, 0.0, -0.5, 0.5, -0.5, 0.0,
-        1.0, 0.0,
-    ]
-}
-
-fn main() {
-    let mut x3d = X3D::new();
-    x3d.run();
+    Texture::from_rgba(size, size, &pixels)
 }
@@ -0,0 +1,117 @@
+use crate::texture::{NORMAL_MAP_TEXTURE_UNIT, Texture};
+use glm::{Vec3, vec3};
+use std::rc::Rc;
+
+// Surface parameters for the built-in lit shaders
+#[derive(Debug, Clone, PartialEq)]
+pub struct Material {
+    // Diffuse color, multiplied by the incoming light
+    pub color: Vec3,
+    // Light the surface gives off itself, added after lighting so it shows
+    // even in shadow. Values above 1.0 glow when bloom is enabled.
+    pub emissive: Vec3,
+    // Share of the scene's ambient light (X3D::set_ambient) the surface
+    // reflects; below 1.0 for recesses or dark materials
+    pub ambient: f32,
+    // Brightness of Blinn-Phong highlights, 0.0 for none. Highlights take
+    // the lights' color rather than the surface's.
+    pub specular: f32,
+    // Highlight exponent: higher gives smaller, sharper highlights
+    pub shininess: f32,
+    // Tangent-space normal map (OpenGL convention, +Y up) adding surface
+    // detail to the lighting. Needs a mesh with UVs, which get tangents;
+    // others are lit with their vertex normals. Shared between materials.
+    pub normal_map: Option<Rc<Texture>>,
+    // Opacity from 0.0 (invisible) to 1.0 (solid). Anything below 1.0 is
+    // blended over the scene once X3D::set_blending is on; until then it's
+    // drawn opaque. The deferred G-buffer has no room for it, so translucent
+    // objects are always drawn forward.
+    pub alpha: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            color: vec3(0.5, 0.8, 1.0),
+            emissive: vec3(0.0, 0.0, 0.0),
+            ambient: 1.0,
+            specular: 0.25,
+            shininess: 32.0,
+            normal_map: None,
+            alpha: 1.0,
+        }
+    }
+}
+
+impl Material {
+    pub fn new(color: Vec3) -> Self {
+        Material {
+            color,
+            ..Material::default()
+        }
+    }
+
+    // No highlights at all, like chalk or unfinished wood
+    pub fn matte(color: Vec3) -> Self {
+        Material {
+            color,
+            specular: 0.0,
+            ..Material::default()
+        }
+    }
+
+    // Small bright highlights, like polished plastic or lacquer
+    pub fn shiny(color: Vec3) -> Self {
+        Material {
+            color,
+            specular: 0.9,
+            shininess: 128.0,
+            ..Material::default()
+        }
+    }
+
+    // A sign-tube glow: dark base with an emissive well past the default
+    // bloom threshold
+    pub fn neon(color: Vec3) -> Self {
+        Material {
+            color: color * 0.2,
+            emissive: color * 4.0,
+            ..Material::default()
+        }
+    }
+
+    pub fn with_normal_map(mut self, normal_map: Texture) -> Self {
+        self.normal_map = Some(Rc::new(normal_map));
+        self
+    }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.alpha < 1.0
+    }
+
+    // Sets the material uniforms on `program`, which must be in use, and
+    // binds the normal map
+    pub(crate) fn apply_uniforms(&self, program: u32) {
+        unsafe {
+            let location = |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+            let (color, emissive) = (self.color, self.emissive);
+            gl::Uniform3f(location(c"albedo"), color.x, color.y, color.z);
+            gl::Uniform3f(location(c"emissive"), emissive.x, emissive.y, emissive.z);
+            gl::Uniform1f(location(c"materialAmbient"), self.ambient.max(0.0));
+            gl::Uniform1f(location(c"specularStrength"), self.specular.max(0.0));
+            // pow(x, 0) is 1 even facing away, so keep the exponent positive
+            gl::Uniform1f(location(c"shininess"), self.shininess.max(1.0));
+            gl::Uniform1i(location(c"useNormalMap"), self.normal_map.is_some() as i32);
+            gl::Uniform1i(location(c"normalMap"), NORMAL_MAP_TEXTURE_UNIT as i32);
+            if let Some(normal_map) = &self.normal_map {
+                normal_map.bind(NORMAL_MAP_TEXTURE_UNIT);
+                gl::ActiveTexture(gl::TEXTURE0);
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::annotation::Annotation;
+use crate::lines::LineRenderer;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec3, vec3};
+
+// Points closer together than this are treated as coincident
+const DEGENERATE_EPSILON: f32 = 1e-5;
+// Line segments used to approximate the angle arc
+const ARC_SEGMENTS: usize = 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeasureTool {
+    // Two clicks: straight-line distance between the picked points
+    Distance,
+    // Three clicks: angle at the second (middle) point
+    Angle,
+}
+
+impl MeasureTool {
+    fn points_needed(self) -> usize {
+        match self {
+            MeasureTool::Distance => 2,
+            MeasureTool::Angle => 3,
+        }
+    }
+}
+
+// Surface points picked for the active tool. Once complete the annotation stays
+// on screen until cleared; the next pick starts a new measurement.
+pub(crate) struct Measurement {
+    tool: MeasureTool,
+    points: Vec<Vec3>,
+}
+
+impl Measurement {
+    pub(crate) fn new(tool: MeasureTool) -> Self {
+        Measurement {
+            tool,
+            points: Vec::with_capacity(3),
+        }
+    }
+
+    pub(crate) fn tool(&self) -> MeasureTool {
+        self.tool
+    }
+
+    pub(crate) fn is_complete(&self) -> bool {
+        self.points.len() == self.tool.points_needed()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    pub(crate) fn add_point(&mut self, point: Vec3) {
+        if self.is_complete() {
+            self.points.clear();
+        }
+        self.points.push(point);
+        if self.is_complete() {
+            self.report();
+        }
+    }
+
+    pub(crate) fn distance(&self) -> Option<f32> {
+        match (self.tool, &self.points[..]) {
+            (MeasureTool::Distance, [a, b]) => Some(glm::distance(a, b)),
+            _ => None,
+        }
+    }
+
+    // Angle in radians at the middle point
+    pub(crate) fn angle(&self) -> Option<f32> {
+        match (self.tool, &self.points[..]) {
+            (MeasureTool::Angle, [a, b, c]) => angle_at(a, b, c),
+            _ => None,
+        }
+    }
+
+    fn report(&self) {
+        match self.tool {
+            MeasureTool::Distance => {
+                if let Some(distance) = self.distance() {
+                    println!("Distance: {:.4}", distance);
+                }
+            }
+            MeasureTool::Angle => match self.angle() {
+                Some(angle) => println!("Angle: {:.2}°", angle.to_degrees()),
+                None => println!("Angle: undefined (coincident points)"),
+            },
+        }
+    }
+
+    // Readout anchored at the midpoint (distance) or vertex (angle) once complete
+    pub(crate) fn label(&self) -> Option<Annotation> {
+        match (self.tool, &self.points[..]) {
+            (MeasureTool::Distance, [a, b]) => Some(Annotation::new(
+                (a + b) * 0.5,
+                format!("{:.3}", glm::distance(a, b)),
+            )),
+            (MeasureTool::Angle, [_, b, _]) => {
+                let text = match self.angle() {
+                    Some(angle) => format!("{:.1}°", angle.to_degrees()),
+                    None => "undefined".to_string(),
+                };
+                Some(Annotation::new(*b, text))
+            }
+            _ => None,
+        }
+    }
+
+    // Queues the picked points, segments and (for angles) the arc into `lines`
+    pub(crate) fn draw(&self, lines: &mut LineRenderer) {
+        let marker = vec3(1.0, 1.0, 0.0);
+        let segment = vec3(1.0, 0.6, 0.0);
+        let arc = vec3(0.2, 0.9, 1.0);
+
+        let scale = self
+            .points
+            .windows(2)
+            .map(|p| glm::distance(&p[0], &p[1]))
+            .fold(f32::INFINITY, f32::min);
+        let marker_size = if scale.is_finite() && scale > DEGENERATE_EPSILON {
+            scale * 0.03
+        } else {
+            0.02
+        };
+        for p in &self.points {
+            for axis in [Vec3::x(), Vec3::y(), Vec3::z()] {
+                let offset = axis * marker_size;
+                lines.line(&(p - offset), &(p + offset), &marker);
+            }
+        }
+
+        for pair in self.points.windows(2) {
+            lines.line(&pair[0], &pair[1], &segment);
+        }
+
+        if let (MeasureTool::Angle, [a, b, c]) = (self.tool, &self.points[..]) {
+            draw_arc(a, b, c, &arc, lines);
+        }
+    }
+}
+
+// Angle between `a - b` and `c - b`, or None if either arm has no length
+pub(crate) fn angle_at(a: &Vec3, b: &Vec3, c: &Vec3) -> Option<f32> {
+    let u = a - b;
+    let v = c - b;
+    if u.norm() < DEGENERATE_EPSILON || v.norm() < DEGENERATE_EPSILON {
+        return None;
+    }
+    Some(glm::angle(&u, &v))
+}
+
+// Arc around `b` from the `a` arm to the `c` arm, at a quarter of the shorter arm
+fn draw_arc(a: &Vec3, b: &Vec3, c: &Vec3, color: &Vec3, lines: &mut LineRenderer) {
+    let Some(angle) = angle_at(a, b, c) else {
+        return;
+    };
+    let u = a - b;
+    let v = c - b;
+    let radius = u.norm().min(v.norm()) * 0.25;
+    let from = u.normalize();
+
+    // Rotate about the plane normal; collinear arms need some perpendicular axis
+    let mut axis = glm::cross(&u, &v);
+    if axis.norm() < DEGENERATE_EPSILON {
+        axis = glm::cross(&from, &Vec3::y());
+        if axis.norm() < DEGENERATE_EPSILON {
+            axis = glm::cross(&from, &Vec3::x());
+        }
+    }
+    let axis = axis.normalize();
+
+    let point = |t: f32| b + glm::rotate_vec3(&from, angle * t, &axis) * radius;
+    let mut previous = point(0.0);
+    for i in 1..=ARC_SEGMENTS {
+        let next = point(i as f32 / ARC_SEGMENTS as f32);
+        lines.line(&previous, &next, color);
+        previous = next;
+    }
+}
+
+// Reads the depth buffer of `target` at `point` (fractions of the target, origin
+// bottom-left) and unprojects it back to world space. Must run after the opaque
+// geometry has been drawn; None when the point is over background.
+pub(crate) fn pick_surface(
+    point: (f32, f32),
+    target: &FrameTarget,
+    view: &Mat4,
+    projection: &Mat4,
+) -> Option<Vec3> {
+    if target.width <= 0 || target.height <= 0 {
+        return None;
+    }
+    let x = point.0 * target.width as f32;
+    let y = point.1 * target.height as f32;
+    let (px, py) = (x.floor() as i32, y.floor() as i32);
+    if px < 0 || py < 0 || px >= target.width || py >= target.height {
+        return None;
+    }
+
+    let mut depth = 1.0f32;
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, target.fbo);
+        gl::ReadPixels(
+            target.x + px,
+            target.y + py,
+            1,
+            1,
+            gl::DEPTH_COMPONENT,
+            gl::FLOAT,
+            &mut depth as *mut f32 as *mut _,
+        );
+    }
+    if depth >= 1.0 {
+        return None;
+    }
+
+    // Unproject within the target's own rectangle
+    let viewport = glm::vec4(0.0, 0.0, target.width as f32, target.height as f32);
+    Some(glm::unproject(
+        &vec3(x, y, depth),
+        view,
+        projection,
+        viewport,
+    ))
+}
@@ -0,0 +1,768 @@
+use crate::bounds::Aabb;
+use crate::uv_overlay::UvLayout;
+use glm::{Vec3, vec3};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::path::Path;
+use std::ptr;
+
+// Floats per interleaved vertex: position (3) + normal (3)
+pub const FLOATS_PER_VERTEX: usize = 6;
+
+// Attribute locations 0 (position), 1 (normal), 2 (uv) and 3 (color) are used
+// by the built-in shaders; custom channels start here and go up to
+// TANGENT_ATTRIBUTE - 1
+pub const FIRST_CUSTOM_ATTRIBUTE: u32 = 4;
+// Optional per-vertex RGB, multiplied into the surface color
+pub const COLOR_ATTRIBUTE: u32 = 3;
+// Per-vertex tangent (xyz) and bitangent sign (w) for normal mapping,
+// generated for meshes with UVs; reads as zero for the rest
+pub const TANGENT_ATTRIBUTE: u32 = 11;
+// Per-instance model matrix of an InstancedMesh, one column per location up
+// to MAX_ATTRIBUTES - 1; reads as the identity for ordinary draws
+pub const INSTANCE_ATTRIBUTE: u32 = 12;
+// GL 3.3 guarantees at least 16 vertex attributes
+pub const MAX_ATTRIBUTES: u32 = 16;
+
+// Blend shapes per mesh; matches MAX_MORPH_TARGETS in morph.glsl
+pub const MAX_MORPH_TARGETS: usize = 8;
+// Texture unit the morph deltas are bound to while drawing, kept clear of the
+// low units materials and the shadow map use
+const MORPH_TEXTURE_UNIT: u32 = 15;
+
+// Reads a mesh file and returns interleaved [px, py, pz, nx, ny, nz] vertices
+pub type MeshLoader = fn(&Path) -> Result<Vec<f32>, Box<dyn Error>>;
+
+#[derive(Debug)]
+pub enum MeshError {
+    Io(std::io::Error),
+    // `line` is 1-based
+    Parse { line: usize, message: String },
+    // The file parsed but held no faces
+    Empty,
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(err) => write!(f, "failed to read mesh file: {err}"),
+            MeshError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            MeshError::Empty => write!(f, "mesh file has no faces"),
+        }
+    }
+}
+
+impl Error for MeshError {}
+
+impl From<std::io::Error> for MeshError {
+    fn from(err: std::io::Error) -> Self {
+        MeshError::Io(err)
+    }
+}
+
+// GPU-side triangle list with interleaved position + normal attributes. The
+// CPU-side copy of the vertex data is retained for measuring, exporting and
+// re-uploading, which costs 28 bytes per vertex (positions, normals and
+// indices) on top of the GPU buffers.
+pub struct Mesh {
+    vao: u32,
+    vbo: u32,
+    // Element buffer of an indexed mesh; 0 draws the vertices in order
+    ebo: u32,
+    vertex_count: i32,
+    bounds: Aabb,
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    indices: Vec<u32>,
+    // Optional texture coordinates, bound at attribute location 2
+    uv_vbo: u32,
+    uv_layout: Option<UvLayout>,
+    // Tangents derived from the UVs, bound at TANGENT_ATTRIBUTE
+    tangent_vbo: u32,
+    // Optional vertex colors, bound at COLOR_ATTRIBUTE
+    color_vbo: u32,
+    // Extra per-vertex channels added with `add_attribute`, as (location, vbo)
+    custom_vbos: Vec<(u32, u32)>,
+    morph: Option<MorphTargets>,
+}
+
+// Per-vertex position/normal offsets stored in a buffer texture, two RGBA32F
+// texels per vertex per target, blended in the vertex shader
+struct MorphTargets {
+    buffer: u32,
+    texture: u32,
+    // Everything uploaded so far, kept to rebuild the buffer when a target is added
+    deltas: Vec<f32>,
+    count: usize,
+    weights: [f32; MAX_MORPH_TARGETS],
+    // Bounds of the base shape and every target fully applied
+    bounds: Aabb,
+}
+
+impl Mesh {
+    pub fn from_vertices(vertices: &[f32]) -> Mesh {
+        assert_eq!(
+            vertices.len() % FLOATS_PER_VERTEX,
+            0,
+            "vertex data must be interleaved position + normal"
+        );
+
+        let (vao, vbo) = unsafe {
+            let mut vao = 0;
+            let mut vbo = 0;
+
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(vertices) as isize,
+                vertices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+
+            // Position attribute
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (FLOATS_PER_VERTEX * mem::size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // Normal attribute
+            gl::VertexAttribPointer(
+                1,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (FLOATS_PER_VERTEX * mem::size_of::<f32>()) as i32,
+                (3 * mem::size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(1);
+
+            gl::BindVertexArray(0);
+
+            (vao, vbo)
+        };
+
+        let (positions, normals): (Vec<Vec3>, Vec<Vec3>) = vertices
+            .chunks_exact(FLOATS_PER_VERTEX)
+            .map(|v| (vec3(v[0], v[1], v[2]), vec3(v[3], v[4], v[5])))
+            .unzip();
+        let bounds = Aabb::from_points(positions.iter().copied())
+            .unwrap_or(Aabb::new(vec3(0.0, 0.0, 0.0), vec3(0.0, 0.0, 0.0)));
+        let vertex_count = positions.len();
+
+        Mesh {
+            vao,
+            vbo,
+            ebo: 0,
+            vertex_count: vertex_count as i32,
+            bounds,
+            positions,
+            normals,
+            indices: (0..vertex_count as u32).collect(),
+            uv_vbo: 0,
+            uv_layout: None,
+            tangent_vbo: 0,
+            color_vbo: 0,
+            custom_vbos: Vec::new(),
+            morph: None,
+        }
+    }
+
+    // Like `from_vertices`, but triangles are read from `indices` (three per
+    // triangle) so shared vertices are stored once, and drawn with
+    // glDrawElements
+    pub fn from_indexed(vertices: &[f32], indices: &[u32]) -> Mesh {
+        let mut mesh = Mesh::from_vertices(vertices);
+        assert_eq!(indices.len() % 3, 0, "expected three indices per triangle");
+        assert!(
+            indices.iter().all(|&i| i < mesh.vertex_count as u32),
+            "index out of range for {} vertices",
+            mesh.vertex_count
+        );
+
+        unsafe {
+            // The element buffer binding is part of the VAO's state
+            gl::BindVertexArray(mesh.vao);
+            gl::GenBuffers(1, &mut mesh.ebo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, mesh.ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                mem::size_of_val(indices) as isize,
+                indices.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::BindVertexArray(0);
+        }
+        mesh.indices = indices.to_vec();
+        mesh
+    }
+
+    // Unit cube centered on the origin, with a full [0, 1] UV square per face
+    pub fn cube() -> Mesh {
+        Mesh::with_uvs(&crate::create_cube_vertices(), &crate::create_cube_uvs())
+    }
+
+    // Unit square in the XY plane centered on the origin and facing +Z, with
+    // UVs spanning [0, 1]; a zero-thickness surface such as a leaf or card
+    pub fn quad() -> Mesh {
+        let corners = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+            (0.0, 0.0),
+        ];
+        let vertices: Vec<f32> = corners
+            .iter()
+            .flat_map(|&(u, v)| [u - 0.5, v - 0.5, 0.0, 0.0, 0.0, 1.0])
+            .collect();
+        let uvs: Vec<f32> = corners.iter().flat_map(|&(u, v)| [u, v]).collect();
+        Mesh::with_uvs(&vertices, &uvs)
+    }
+
+    // Mesh::cube with a different flat color on each face
+    pub fn colored_cube() -> Mesh {
+        let mut mesh = Mesh::cube();
+        mesh.set_colors(&crate::create_cube_face_colors());
+        mesh
+    }
+
+    // Triangles from a Wavefront OBJ file (see read_obj for what's supported)
+    pub fn load_obj(path: impl AsRef<Path>) -> Result<Mesh, MeshError> {
+        let text = std::fs::read_to_string(path)?;
+        Ok(Mesh::from_vertices(&crate::obj::parse(&text)?))
+    }
+
+    // Like `from_vertices`, plus one (u, v) pair per vertex at attribute location 2
+    // and tangents computed from them for normal mapping
+    pub fn with_uvs(vertices: &[f32], uvs: &[f32]) -> Mesh {
+        let mut mesh = Mesh::from_vertices(vertices);
+        assert_eq!(
+            uvs.len() / 2,
+            mesh.vertex_count as usize,
+            "expected one UV pair per vertex"
+        );
+
+        unsafe {
+            gl::BindVertexArray(mesh.vao);
+            gl::GenBuffers(1, &mut mesh.uv_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.uv_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(uvs) as isize,
+                uvs.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                2,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                (2 * mem::size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(2);
+
+            let tangents = compute_tangents(vertices, uvs);
+            gl::GenBuffers(1, &mut mesh.tangent_vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, mesh.tangent_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(tangents.as_slice()) as isize,
+                tangents.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                TANGENT_ATTRIBUTE,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                (4 * mem::size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(TANGENT_ATTRIBUTE);
+            gl::BindVertexArray(0);
+        }
+
+        mesh.uv_layout = Some(UvLayout::new(vertices, uvs));
+        mesh
+    }
+
+    // One (r, g, b) triple per vertex, multiplied into the material (and
+    // texture) color by the built-in shaders. Meshes without colors draw as
+    // if every vertex were white. Setting colors again replaces them.
+    pub fn set_colors(&mut self, colors: &[f32]) {
+        assert_eq!(
+            colors.len() / 3,
+            self.vertex_count as usize,
+            "expected one RGB color per vertex"
+        );
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            if self.color_vbo == 0 {
+                gl::GenBuffers(1, &mut self.color_vbo);
+            }
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.color_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(colors) as isize,
+                colors.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                COLOR_ATTRIBUTE,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                (3 * mem::size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(COLOR_ATTRIBUTE);
+            gl::BindVertexArray(0);
+        }
+    }
+
+    pub fn has_colors(&self) -> bool {
+        self.color_vbo != 0
+    }
+
+    // Uploads `components` floats per vertex to attribute `location` so custom
+    // shaders can read them (e.g. `layout (location = 4) in float scalar;`).
+    // Locations below FIRST_CUSTOM_ATTRIBUTE and from TANGENT_ATTRIBUTE up
+    // are reserved for the built-in attributes. Adding a location again
+    // replaces its data.
+    pub fn add_attribute(&mut self, location: u32, components: usize, data: &[f32]) {
+        assert!(
+            (FIRST_CUSTOM_ATTRIBUTE..TANGENT_ATTRIBUTE).contains(&location),
+            "custom attribute location must be in {}..{}",
+            FIRST_CUSTOM_ATTRIBUTE,
+            TANGENT_ATTRIBUTE
+        );
+        assert!(
+            (1..=4).contains(&components),
+            "attributes have 1 to 4 components"
+        );
+        assert_eq!(
+            data.len(),
+            components * self.vertex_count as usize,
+            "expected {} floats per vertex",
+            components
+        );
+
+        self.remove_attribute(location);
+        let mut vbo = 0;
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(data) as isize,
+                data.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                location,
+                components as i32,
+                gl::FLOAT,
+                gl::FALSE,
+                (components * mem::size_of::<f32>()) as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(location);
+            gl::BindVertexArray(0);
+        }
+        self.custom_vbos.push((location, vbo));
+    }
+
+    // Drops a channel added with `add_attribute`; does nothing if it isn't present
+    pub fn remove_attribute(&mut self, location: u32) {
+        let Some(index) = self.custom_vbos.iter().position(|(l, _)| *l == location) else {
+            return;
+        };
+        let (_, vbo) = self.custom_vbos.remove(index);
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DisableVertexAttribArray(location);
+            gl::BindVertexArray(0);
+            gl::DeleteBuffers(1, &vbo);
+        }
+    }
+
+    pub fn has_attribute(&self, location: u32) -> bool {
+        self.custom_vbos.iter().any(|(l, _)| *l == location)
+    }
+
+    // Adds a blend shape: one (x, y, z) position offset per vertex, plus
+    // optional normal offsets in the same layout. Returns the target's index
+    // into the weights; new targets start at weight 0.
+    pub fn add_morph_target(
+        &mut self,
+        position_deltas: &[f32],
+        normal_deltas: Option<&[f32]>,
+    ) -> usize {
+        let vertices = self.vertex_count as usize;
+        assert_eq!(
+            position_deltas.len(),
+            3 * vertices,
+            "expected one position offset per vertex"
+        );
+        if let Some(normals) = normal_deltas {
+            assert_eq!(
+                normals.len(),
+                3 * vertices,
+                "expected one normal offset per vertex"
+            );
+        }
+        assert!(
+            self.morph_target_count() < MAX_MORPH_TARGETS,
+            "a mesh has at most {} morph targets",
+            MAX_MORPH_TARGETS
+        );
+
+        let base = self.bounds;
+        let morph = self.morph.get_or_insert_with(|| {
+            let (mut buffer, mut texture) = (0, 0);
+            unsafe {
+                gl::GenBuffers(1, &mut buffer);
+                gl::GenTextures(1, &mut texture);
+            }
+            MorphTargets {
+                buffer,
+                texture,
+                deltas: Vec::new(),
+                count: 0,
+                weights: [0.0; MAX_MORPH_TARGETS],
+                bounds: base,
+            }
+        });
+
+        // The bounds only know the corners of the base shape, so grow them by
+        // the largest offset in each direction
+        let mut low = vec3(0.0, 0.0, 0.0);
+        let mut high = vec3(0.0, 0.0, 0.0);
+        for (i, offset) in position_deltas.chunks_exact(3).enumerate() {
+            let normal = normal_deltas.map_or([0.0; 3], |n| [n[3 * i], n[3 * i + 1], n[3 * i + 2]]);
+            let offset = vec3(offset[0], offset[1], offset[2]);
+            low = glm::min2(&low, &offset);
+            high = glm::max2(&high, &offset);
+            morph
+                .deltas
+                .extend_from_slice(&[offset.x, offset.y, offset.z, 0.0]);
+            morph
+                .deltas
+                .extend_from_slice(&[normal[0], normal[1], normal[2], 0.0]);
+        }
+        morph.bounds = Aabb::new(
+            glm::min2(&morph.bounds.min, &(base.min + low)),
+            glm::max2(&morph.bounds.max, &(base.max + high)),
+        );
+        morph.count += 1;
+
+        unsafe {
+            gl::BindBuffer(gl::TEXTURE_BUFFER, morph.buffer);
+            gl::BufferData(
+                gl::TEXTURE_BUFFER,
+                mem::size_of_val(morph.deltas.as_slice()) as isize,
+                morph.deltas.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::BindTexture(gl::TEXTURE_BUFFER, morph.texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, gl::RGBA32F, morph.buffer);
+            gl::BindTexture(gl::TEXTURE_BUFFER, 0);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, 0);
+        }
+        morph.count - 1
+    }
+
+    pub fn morph_target_count(&self) -> usize {
+        self.morph.as_ref().map_or(0, |m| m.count)
+    }
+
+    // Blend weight per target, in the order they were added; missing entries
+    // become 0 and extras are ignored. Weights outside [0, 1] extrapolate.
+    pub fn set_morph_weights(&mut self, weights: &[f32]) {
+        if let Some(morph) = &mut self.morph {
+            for (i, weight) in morph.weights[..morph.count].iter_mut().enumerate() {
+                *weight = weights.get(i).copied().unwrap_or(0.0);
+            }
+        }
+    }
+
+    pub fn morph_weights(&self) -> &[f32] {
+        self.morph.as_ref().map_or(&[], |m| &m.weights[..m.count])
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertex_count as usize
+    }
+
+    // Object-space vertex positions as uploaded, without morph targets applied
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+
+    // Three indices into positions/normals per triangle; 0..vertex_count for
+    // meshes not built with `from_indexed`
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.ebo != 0
+    }
+
+    pub fn has_uvs(&self) -> bool {
+        self.uv_layout.is_some()
+    }
+
+    pub fn has_tangents(&self) -> bool {
+        self.tangent_vbo != 0
+    }
+
+    pub(crate) fn uv_layout(&self) -> Option<&UvLayout> {
+        self.uv_layout.as_ref()
+    }
+
+    // Object-space bounding box of the vertex positions. With morph targets it
+    // covers each target at weight 1 on its own, which holds for weights in
+    // [0, 1] that don't stack offsets in the same direction.
+    pub fn bounds(&self) -> Aabb {
+        self.morph.as_ref().map_or(self.bounds, |m| m.bounds)
+    }
+
+    // Sets the morph uniforms on whichever program is in use, so every mesh
+    // shader picks up the blend without the caller knowing about it
+    fn bind_morph_targets(&self) {
+        unsafe {
+            let mut program = 0;
+            gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(program as u32, name.as_ptr());
+            let count = location(c"morphTargetCount");
+            if count < 0 {
+                return;
+            }
+            // Always point the sampler at its own unit: samplers of different
+            // types sharing unit 0 make the draw fail validation
+            gl::Uniform1i(location(c"morphDeltas"), MORPH_TEXTURE_UNIT as i32);
+            let Some(morph) = &self.morph else {
+                gl::Uniform1i(count, 0);
+                return;
+            };
+            gl::Uniform1i(count, morph.count as i32);
+            gl::Uniform1i(location(c"morphVertexCount"), self.vertex_count);
+            gl::Uniform1fv(
+                location(c"morphWeights"),
+                morph.count as i32,
+                morph.weights.as_ptr(),
+            );
+            gl::ActiveTexture(gl::TEXTURE0 + MORPH_TEXTURE_UNIT);
+            gl::BindTexture(gl::TEXTURE_BUFFER, morph.texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+
+    pub(crate) fn vao(&self) -> u32 {
+        self.vao
+    }
+
+    fn bind_for_draw(&self) {
+        self.bind_morph_targets();
+        unsafe {
+            // A disabled attribute reads the current generic value instead
+            if self.color_vbo == 0 {
+                gl::VertexAttrib3f(COLOR_ATTRIBUTE, 1.0, 1.0, 1.0);
+            }
+            // A zero tangent tells the shaders to skip the normal map
+            if self.tangent_vbo == 0 {
+                gl::VertexAttrib4f(TANGENT_ATTRIBUTE, 0.0, 0.0, 0.0, 0.0);
+            }
+            gl::BindVertexArray(self.vao);
+        }
+    }
+
+    pub(crate) fn draw(&self) {
+        self.bind_for_draw();
+        unsafe {
+            for column in 0..4 {
+                let mut identity = [0.0f32; 4];
+                identity[column] = 1.0;
+                gl::VertexAttrib4fv(INSTANCE_ATTRIBUTE + column as u32, identity.as_ptr());
+            }
+            if self.ebo != 0 {
+                gl::DrawElements(
+                    gl::TRIANGLES,
+                    self.indices.len() as i32,
+                    gl::UNSIGNED_INT,
+                    ptr::null(),
+                );
+            } else {
+                gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count);
+            }
+        }
+    }
+
+    // One call drawing `count` copies, for a VAO with the instance matrix
+    // attribute set up by InstancedMesh
+    pub(crate) fn draw_instanced(&self, count: i32) {
+        self.bind_for_draw();
+        unsafe {
+            if self.ebo != 0 {
+                gl::DrawElementsInstanced(
+                    gl::TRIANGLES,
+                    self.indices.len() as i32,
+                    gl::UNSIGNED_INT,
+                    ptr::null(),
+                    count,
+                );
+            } else {
+                gl::DrawArraysInstanced(gl::TRIANGLES, 0, self.vertex_count, count);
+            }
+        }
+    }
+}
+
+impl Drop for Mesh {
+    fn drop(&mut self) {
+        unsafe {
+            for (_, vbo) in &self.custom_vbos {
+                gl::DeleteBuffers(1, vbo);
+            }
+            if let Some(morph) = &self.morph {
+                gl::DeleteTextures(1, &morph.texture);
+                gl::DeleteBuffers(1, &morph.buffer);
+            }
+            if self.uv_vbo != 0 {
+                gl::DeleteBuffers(1, &self.uv_vbo);
+            }
+            if self.tangent_vbo != 0 {
+                gl::DeleteBuffers(1, &self.tangent_vbo);
+            }
+            if self.color_vbo != 0 {
+                gl::DeleteBuffers(1, &self.color_vbo);
+            }
+            if self.ebo != 0 {
+                gl::DeleteBuffers(1, &self.ebo);
+            }
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+        }
+    }
+}
+
+// Per-vertex (tx, ty, tz, w) for a triangle list: the direction of increasing
+// u along the surface, made perpendicular to the normal, with w = -1 where
+// the UVs are mirrored. Vertices sharing a position, normal and UV (the
+// corners of a smooth surface) average their triangles' tangents so the
+// shading doesn't facet.
+fn compute_tangents(vertices: &[f32], uvs: &[f32]) -> Vec<f32> {
+    let count = vertices.len() / FLOATS_PER_VERTEX;
+    let vertex = |i: usize| &vertices[i * FLOATS_PER_VERTEX..(i + 1) * FLOATS_PER_VERTEX];
+    let position = |i: usize| vec3(vertex(i)[0], vertex(i)[1], vertex(i)[2]);
+    let normal = |i: usize| vec3(vertex(i)[3], vertex(i)[4], vertex(i)[5]);
+    let uv = |i: usize| (uvs[2 * i], uvs[2 * i + 1]);
+    let key = |i: usize| -> [u32; FLOATS_PER_VERTEX + 2] {
+        std::array::from_fn(|k| match k {
+            k if k < FLOATS_PER_VERTEX => vertex(i)[k].to_bits(),
+            k => uvs[2 * i + k - FLOATS_PER_VERTEX].to_bits(),
+        })
+    };
+
+    // Summed (tangent, bitangent) per distinct vertex
+    let mut sums: HashMap<_, (Vec3, Vec3)> = HashMap::new();
+    for triangle in 0..count / 3 {
+        let [a, b, c] = [3 * triangle, 3 * triangle + 1, 3 * triangle + 2];
+        let (edge1, edge2) = (position(b) - position(a), position(c) - position(a));
+        let ((u0, v0), (u1, v1), (u2, v2)) = (uv(a), uv(b), uv(c));
+        let (du1, dv1, du2, dv2) = (u1 - u0, v1 - v0, u2 - u0, v2 - v0);
+        let det = du1 * dv2 - du2 * dv1;
+        if det.abs() < 1e-12 {
+            continue;
+        }
+        let tangent = (edge1 * dv2 - edge2 * dv1) / det;
+        let bitangent = (edge2 * du1 - edge1 * du2) / det;
+        for corner in [a, b, c] {
+            let sum = sums
+                .entry(key(corner))
+                .or_insert((Vec3::zeros(), Vec3::zeros()));
+            sum.0 += tangent;
+            sum.1 += bitangent;
+        }
+    }
+
+    let mut tangents = Vec::with_capacity(count * 4);
+    for i in 0..count {
+        let n = normal(i).try_normalize(1e-12).unwrap_or(Vec3::z());
+        let (tangent, bitangent) = sums
+            .get(&key(i))
+            .copied()
+            .unwrap_or((Vec3::zeros(), Vec3::zeros()));
+        // Gram-Schmidt; UVs that don't vary get any perpendicular direction
+        let t = (tangent - n * n.dot(&tangent))
+            .try_normalize(1e-12)
+            .unwrap_or_else(|| {
+                let axis = if n.x.abs() < 0.9 {
+                    Vec3::x()
+                } else {
+                    Vec3::y()
+                };
+                n.cross(&axis).normalize()
+            });
+        let w = if n.cross(&t).dot(&bitangent) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        tangents.extend_from_slice(&[t.x, t.y, t.z, w]);
+    }
+    tangents
+}
+
+// MeshLoader for Wavefront OBJ files, e.g. X3D::load_mesh(path, read_obj).
+// Reads positions, normals and faces (polygons are fanned into triangles and
+// faces without normals shaded flat); texture coordinates and materials are
+// ignored.
+pub fn read_obj(path: &Path) -> Result<Vec<f32>, Box<dyn Error>> {
+    let vertices = std::fs::read_to_string(path)
+        .map_err(MeshError::from)
+        .and_then(|text| crate::obj::parse(&text));
+    Ok(vertices.map_err(|err| format!("{}: {}", path.display(), err))?)
+}
+
+// Where the current mesh came from, so it can be re-read on demand
+pub(crate) struct MeshSource {
+    pub(crate) path: std::path::PathBuf,
+    pub(crate) loader: MeshLoader,
+}
+
+impl MeshSource {
+    pub(crate) fn load(&self) -> Result<Mesh, Box<dyn Error>> {
+        let vertices = (self.loader)(&self.path)?;
+        if vertices.is_empty() || vertices.len() % FLOATS_PER_VERTEX != 0 {
+            return Err(format!(
+                "{}: expected interleaved position + normal data, got {} floats",
+                self.path.display(),
+                vertices.len()
+            )
+            .into());
+        }
+        Ok(Mesh::from_vertices(&vertices))
+    }
+}
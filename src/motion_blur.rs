@@ -0,0 +1,271 @@
+use crate::render_target::FrameTarget;
+use crate::shader::{ShaderError, build_program, with_morph_targets};
+use glm::Mat4;
+use std::ptr;
+
+// Longest blur streak as a fraction of the frame
+const MAX_STREAK: f32 = 0.1;
+
+// Per-object and camera motion blur. Opaque geometry is re-drawn into a
+// velocity buffer using both this frame's and the previous frame's
+// transforms, and the frame is then averaged along each pixel's motion.
+// Particles, foliage and the background don't write velocity, so they only
+// blur where moving geometry passes over them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MotionBlur {
+    // Fraction of the time between frames the virtual shutter stays open;
+    // 0.5 matches a film camera's 180-degree shutter
+    pub strength: f32,
+    // Samples along each streak; more gives smoother blur for fast motion
+    pub samples: u32,
+}
+
+impl Default for MotionBlur {
+    fn default() -> Self {
+        MotionBlur {
+            strength: 0.5,
+            samples: 8,
+        }
+    }
+}
+
+pub(crate) struct MotionBlurPass {
+    // Velocity (RG16F) + depth, drawn by the geometry program
+    velocity_fbo: u32,
+    velocity: u32,
+    depth: u32,
+    // Copy of the frame the blur samples while writing back over it
+    copy_fbo: u32,
+    copy: u32,
+    width: i32,
+    height: i32,
+    velocity_program: u32,
+    blur_program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+}
+
+impl MotionBlurPass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let velocity_program = unsafe {
+            build_program(
+                &with_morph_targets(include_str!("shaders/velocity_vertex.glsl")),
+                include_str!("shaders/velocity_fragment.glsl"),
+            )?
+        };
+        let blur_program = unsafe {
+            build_program(
+                include_str!("shaders/fullscreen_vertex.glsl"),
+                include_str!("shaders/motion_blur_fragment.glsl"),
+            )
+        }
+        .inspect_err(|_| unsafe { gl::DeleteProgram(velocity_program) })?;
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+        Ok(MotionBlurPass {
+            velocity_fbo: 0,
+            velocity: 0,
+            depth: 0,
+            copy_fbo: 0,
+            copy: 0,
+            width: 0,
+            height: 0,
+            velocity_program,
+            blur_program,
+            empty_vao,
+        })
+    }
+
+    // Binds the velocity buffer (reallocated to match `target`) and program;
+    // the caller sets both transforms with `set_model` and draws every opaque
+    // mesh
+    pub(crate) fn begin(
+        &mut self,
+        target: &FrameTarget,
+        view_projection: &Mat4,
+        previous_view_projection: &Mat4,
+    ) {
+        if (target.width, target.height) != (self.width, self.height) {
+            self.free_buffers();
+            self.allocate(target.width.max(1), target.height.max(1));
+        }
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.velocity_fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::ClearColor(0.0, 0.0, 0.0, 0.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            gl::UseProgram(self.velocity_program);
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.velocity_program, name.as_ptr())
+            };
+            gl::UniformMatrix4fv(
+                location(c"viewProjection"),
+                1,
+                gl::FALSE,
+                view_projection.as_ptr(),
+            );
+            gl::UniformMatrix4fv(
+                location(c"previousViewProjection"),
+                1,
+                gl::FALSE,
+                previous_view_projection.as_ptr(),
+            );
+        }
+    }
+
+    pub(crate) fn set_model(&self, model: &Mat4, previous_model: &Mat4) {
+        unsafe {
+            let location = |name: &std::ffi::CStr| {
+                gl::GetUniformLocation(self.velocity_program, name.as_ptr())
+            };
+            gl::UniformMatrix4fv(location(c"model"), 1, gl::FALSE, model.as_ptr());
+            gl::UniformMatrix4fv(
+                location(c"previousModel"),
+                1,
+                gl::FALSE,
+                previous_model.as_ptr(),
+            );
+        }
+    }
+
+    // Copies `target`'s color aside and writes it back blurred along the
+    // velocities, leaving its depth untouched
+    pub(crate) fn composite(&self, params: &MotionBlur, target: &FrameTarget) {
+        unsafe {
+            // The scissor test would clip the blit, and target.bind restores it
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, target.fbo);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.copy_fbo);
+            gl::BlitFramebuffer(
+                target.x,
+                target.y,
+                target.x + target.width,
+                target.y + target.height,
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::COLOR_BUFFER_BIT,
+                gl::NEAREST,
+            );
+        }
+        target.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(self.blur_program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.blur_program, name.as_ptr());
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.copy);
+            gl::Uniform1i(location(c"scene"), 0);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.velocity);
+            gl::Uniform1i(location(c"velocity"), 1);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Uniform1f(location(c"strength"), params.strength.max(0.0));
+            gl::Uniform1i(location(c"samples"), params.samples.clamp(1, 64) as i32);
+            gl::Uniform1f(location(c"maxLength"), MAX_STREAK);
+
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    fn allocate(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        let texture = |internal_format: gl::types::GLenum, format: gl::types::GLenum| unsafe {
+            let mut texture = 0;
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width,
+                height,
+                0,
+                format,
+                gl::FLOAT,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            texture
+        };
+        unsafe {
+            gl::GenFramebuffers(1, &mut self.velocity_fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.velocity_fbo);
+            self.velocity = texture(gl::RG16F, gl::RG);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.velocity,
+                0,
+            );
+            gl::GenRenderbuffers(1, &mut self.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                self.depth,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("Motion blur velocity framebuffer is incomplete");
+            }
+
+            // Float, so HDR frames keep their range through the blur
+            gl::GenFramebuffers(1, &mut self.copy_fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.copy_fbo);
+            self.copy = texture(gl::RGBA16F, gl::RGBA);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                self.copy,
+                0,
+            );
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                eprintln!("Motion blur copy framebuffer is incomplete");
+            }
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+    }
+
+    fn free_buffers(&mut self) {
+        if self.velocity_fbo == 0 {
+            return;
+        }
+        unsafe {
+            gl::DeleteTextures(1, &self.velocity);
+            gl::DeleteTextures(1, &self.copy);
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteFramebuffers(1, &self.velocity_fbo);
+            gl::DeleteFramebuffers(1, &self.copy_fbo);
+        }
+        self.velocity_fbo = 0;
+    }
+}
+
+impl Drop for MotionBlurPass {
+    fn drop(&mut self) {
+        self.free_buffers();
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.velocity_program);
+            gl::DeleteProgram(self.blur_program);
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use crate::mesh::MeshError;
+use glm::{Vec3, vec3};
+
+// Parses Wavefront OBJ text into interleaved [px, py, pz, nx, ny, nz]
+// triangles. Only `v`, `vn` and `f` are used; texture coordinates, groups,
+// smoothing and materials are skipped. Polygons are split into a fan around
+// their first corner, and faces without normals get a flat one.
+pub(crate) fn parse(text: &str) -> Result<Vec<f32>, MeshError> {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut vertices = Vec::new();
+
+    for (number, raw) in text.lines().enumerate() {
+        let error = |message: String| MeshError::Parse {
+            line: number + 1,
+            message,
+        };
+        // Comments may follow data on the same line
+        let line = raw.split('#').next().unwrap_or("").trim();
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("v") => positions.push(
+                parse_vec3(words).ok_or_else(|| error(format!("invalid vertex `{}`", line)))?,
+            ),
+            Some("vn") => normals.push(
+                parse_vec3(words).ok_or_else(|| error(format!("invalid normal `{}`", line)))?,
+            ),
+            Some("f") => {
+                let corners = words
+                    .map(|word| parse_corner(word, positions.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                if corners.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, got {}",
+                        corners.len()
+                    )));
+                }
+
+                let points: Vec<Vec3> = corners.iter().map(|&(p, _)| positions[p]).collect();
+                let flat = face_normal(&points);
+                for i in 1..corners.len() - 1 {
+                    for corner in [corners[0], corners[i], corners[i + 1]] {
+                        let (position, normal) = corner;
+                        let n = normal.map_or(flat, |n| normals[n]);
+                        let p = positions[position];
+                        vertices.extend_from_slice(&[p.x, p.y, p.z, n.x, n.y, n.z]);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if vertices.is_empty() {
+        return Err(MeshError::Empty);
+    }
+    Ok(vertices)
+}
+
+fn parse_vec3<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Vec3> {
+    let mut next = || words.next()?.parse::<f32>().ok();
+    Some(vec3(next()?, next()?, next()?))
+}
+
+// One `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner as zero-based position
+// and normal indices. Negative indices count back from the latest entry.
+fn parse_corner(
+    word: &str,
+    position_count: usize,
+    normal_count: usize,
+) -> Result<(usize, Option<usize>), String> {
+    let mut parts = word.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), position_count)
+        .ok_or_else(|| format!("invalid vertex index in `{}`", word))?;
+    let normal = match parts.nth(1) {
+        Some(index) if !index.is_empty() => Some(
+            resolve_index(index, normal_count)
+                .ok_or_else(|| format!("invalid normal index in `{}`", word))?,
+        ),
+        _ => None,
+    };
+    Ok((position, normal))
+}
+
+fn resolve_index(text: &str, count: usize) -> Option<usize> {
+    let index: i64 = text.parse().ok()?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    (0..count as i64)
+        .contains(&resolved)
+        .then_some(resolved as usize)
+}
+
+// Unit normal of a polygon by Newell's method, which stays stable for
+// slightly non-planar or concave faces. Zero for degenerate ones.
+fn face_normal(points: &[Vec3]) -> Vec3 {
+    let mut normal = Vec3::zeros();
+    for (i, a) in points.iter().enumerate() {
+        let b = &points[(i + 1) % points.len()];
+        normal += vec3(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    let length = normal.norm();
+    if length > f32::EPSILON {
+        normal / length
+    } else {
+        normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Corner positions of parsed vertex data, in order
+    fn positions(vertices: &[f32]) -> Vec<[f32; 3]> {
+        vertices
+            .chunks_exact(6)
+            .map(|v| [v[0], v[1], v[2]])
+            .collect()
+    }
+
+    fn normals(vertices: &[f32]) -> Vec<[f32; 3]> {
+        vertices
+            .chunks_exact(6)
+            .map(|v| [v[3], v[4], v[5]])
+            .collect()
+    }
+
+    fn parse_error(text: &str) -> (usize, String) {
+        match parse(text) {
+            Err(MeshError::Parse { line, message }) => (line, message),
+            other => panic!("expected a parse error, got {other:?}"),
+        }
+    }
+
+    const SQUARE: &str = "
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+    ";
+
+    #[test]
+    fn splits_polygons_into_fans() {
+        let quad = parse(&format!("{SQUARE}f 1 2 3 4")).unwrap();
+        assert_eq!(
+            positions(&quad),
+            [
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ]
+        );
+
+        let pentagon = parse(&format!("{SQUARE}v 0.5 2 0\nf 1 2 3 5 4")).unwrap();
+        assert_eq!(pentagon.len(), 3 * 3 * 6);
+        // Every triangle shares the first corner
+        for triangle in positions(&pentagon).chunks_exact(3) {
+            assert_eq!(triangle[0], [0.0, 0.0, 0.0]);
+        }
+    }
+
+    #[test]
+    fn makes_flat_normals_without_vn() {
+        let vertices = parse(&format!("{SQUARE}f 1 2 3 4\nf 1 4 3")).unwrap();
+        let normals = normals(&vertices);
+        assert!(normals[..6].iter().all(|n| *n == [0.0, 0.0, 1.0]));
+        // Clockwise winding faces the other way
+        assert!(normals[6..].iter().all(|n| *n == [0.0, 0.0, -1.0]));
+    }
+
+    #[test]
+    fn reads_every_corner_form() {
+        let text = format!("{SQUARE}vt 0 0\nvt 1 0\nvt 1 1\nvn 0 1 0\nvn 1 0 0\n");
+        for (face, expected) in [
+            ("f 1 2 3", [[0.0, 0.0, 1.0]; 3]),
+            ("f 1/1 2/2 3/3", [[0.0, 0.0, 1.0]; 3]),
+            (
+                "f 1//1 2//2 3//1",
+                [[0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            ),
+            (
+                "f 1/1/2 2/2/2 3/3/1",
+                [[1.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            ),
+        ] {
+            let vertices = parse(&format!("{text}{face}")).unwrap();
+            assert_eq!(
+                positions(&vertices),
+                [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]],
+                "{face}"
+            );
+            assert_eq!(normals(&vertices), expected, "{face}");
+        }
+    }
+
+    #[test]
+    fn resolves_negative_indices_from_the_latest_entry() {
+        let relative = parse(&format!("{SQUARE}vn 0 0 -1\nf -4//-1 -3//-1 -2//-1")).unwrap();
+        let absolute = parse(&format!("{SQUARE}vn 0 0 -1\nf 1//1 2//1 3//1")).unwrap();
+        assert_eq!(relative, absolute);
+
+        // Relative to the vertices read so far, not the whole file
+        let vertices = parse("v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\nv 5 5 5").unwrap();
+        assert_eq!(
+            positions(&vertices),
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn reports_the_line_of_bad_input() {
+        for (text, line, message) in [
+            ("v 0 0 0\nv 1 x 0", 2, "invalid vertex `v 1 x 0`"),
+            ("v 0 0 0\n\nvn 1 0", 3, "invalid normal `vn 1 0`"),
+            ("v 0 0 0\nf 1 1", 2, "face needs at least 3 vertices, got 2"),
+            ("v 0 0 0\nf 1 1 4", 2, "invalid vertex index in `4`"),
+            ("v 0 0 0\nf 1 1 -2", 2, "invalid vertex index in `-2`"),
+            ("v 0 0 0\nf 1 1 0", 2, "invalid vertex index in `0`"),
+            (
+                "v 0 0 0\nvn 0 0 1\nf 1//1 1//1 1//2",
+                3,
+                "invalid normal index in `1//2`",
+            ),
+        ] {
+            assert_eq!(parse_error(text), (line, message.to_string()), "{text:?}");
+        }
+    }
+
+    #[test]
+    fn rejects_files_without_faces() {
+        assert!(matches!(parse(SQUARE), Err(MeshError::Empty)));
+        assert!(matches!(parse("# just a comment\n"), Err(MeshError::Empty)));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+// Give up waiting on a fence after this long (nanoseconds) rather than hang on
+// a lost context; the frame is then treated as done
+const FENCE_TIMEOUT_NS: u64 = 1_000_000_000;
+
+// Frames whose timestamps may still be pending; older ones are dropped
+const MAX_PENDING_QUERIES: usize = 8;
+// Weight of each new sample in the running latency average
+const LATENCY_SMOOTHING: f64 = 0.1;
+// Frames the frame time statistics are averaged over
+const FRAME_STATS_WINDOW: usize = 60;
+
+// How buffer swaps line up with the display refresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SwapMode {
+    // Wait for vertical blank: no tearing, frame rate capped at the refresh rate
+    #[default]
+    VSync,
+    // Vsync when on time, swap immediately (tearing) when a frame runs late.
+    // Falls back to the driver's behavior without swap_control_tear support.
+    Adaptive,
+    // Never wait: lowest latency and uncapped frame rate, with tearing
+    Immediate,
+}
+
+impl SwapMode {
+    pub(crate) fn interval(self) -> glfw::SwapInterval {
+        match self {
+            SwapMode::VSync => glfw::SwapInterval::Sync(1),
+            SwapMode::Adaptive => glfw::SwapInterval::Adaptive,
+            SwapMode::Immediate => glfw::SwapInterval::None,
+        }
+    }
+}
+
+// Limits how many frames the CPU may queue ahead of the GPU using one fence per
+// swapped frame. Drivers typically allow 2-3 frames, which keeps the GPU busy
+// (throughput) but means input sampled for a frame reaches the screen that many
+// frames later (latency). A limit of 1 keeps at most one frame queued, so input
+// shows up soonest at the cost of the GPU idling while the CPU builds a frame.
+pub(crate) struct FramePacer {
+    max_in_flight: Option<u32>,
+    fences: VecDeque<gl::types::GLsync>,
+}
+
+impl FramePacer {
+    pub(crate) fn new() -> Self {
+        FramePacer {
+            max_in_flight: None,
+            fences: VecDeque::new(),
+        }
+    }
+
+    pub(crate) fn max_in_flight(&self) -> Option<u32> {
+        self.max_in_flight
+    }
+
+    // None leaves queuing to the driver. Sync objects are core since GL 3.2, but
+    // without them the limit is ignored.
+    pub(crate) fn set_max_in_flight(&mut self, max: Option<u32>) {
+        self.max_in_flight = max.map(|n| n.max(1));
+        if self.max_in_flight.is_none() {
+            self.clear();
+        }
+    }
+
+    // Call right after swapping: fences the frame just submitted, then blocks
+    // until no more than the limit are still being processed
+    pub(crate) fn frame_submitted(&mut self) {
+        let Some(max) = self.max_in_flight else {
+            return;
+        };
+        if !gl::FenceSync::is_loaded() {
+            return;
+        }
+        unsafe {
+            let fence = gl::FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+            if !fence.is_null() {
+                self.fences.push_back(fence);
+            }
+            while self.fences.len() > max as usize {
+                let Some(oldest) = self.fences.pop_front() else {
+                    break;
+                };
+                // The flush bit makes sure the fence is actually submitted
+                gl::ClientWaitSync(oldest, gl::SYNC_FLUSH_COMMANDS_BIT, FENCE_TIMEOUT_NS);
+                gl::DeleteSync(oldest);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        for fence in self.fences.drain(..) {
+            unsafe {
+                gl::DeleteSync(fence);
+            }
+        }
+    }
+}
+
+impl Drop for FramePacer {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+// Frame timing averaged over the last 60 frames
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FrameStats {
+    pub fps: f32,
+    // Average time between frames, including any vsync or frame cap wait
+    pub frame_time_ms: f32,
+    // Frames run since the engine started
+    pub frame_count: u64,
+}
+
+// Rolling window of recent frame times behind FrameStats
+pub(crate) struct FrameTimer {
+    frame_times: VecDeque<f32>,
+    frame_count: u64,
+}
+
+impl FrameTimer {
+    pub(crate) fn new() -> Self {
+        FrameTimer {
+            frame_times: VecDeque::with_capacity(FRAME_STATS_WINDOW),
+            frame_count: 0,
+        }
+    }
+
+    // Records one frame's delta time in seconds
+    pub(crate) fn record(&mut self, delta_time: f32) {
+        self.frame_count += 1;
+        if self.frame_times.len() == FRAME_STATS_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta_time);
+    }
+
+    pub(crate) fn stats(&self) -> FrameStats {
+        let total: f32 = self.frame_times.iter().sum();
+        let average = if self.frame_times.is_empty() {
+            0.0
+        } else {
+            total / self.frame_times.len() as f32
+        };
+        FrameStats {
+            fps: if average > 0.0 { 1.0 / average } else { 0.0 },
+            frame_time_ms: average * 1000.0,
+            frame_count: self.frame_count,
+        }
+    }
+}
+
+// Sleeps out whatever is left of a 1/fps second frame budget for a frame that
+// started at `frame_start`; returns at once if the frame already took longer
+pub(crate) fn limit_frame_rate(frame_start: Instant, fps: u32) {
+    let budget = Duration::from_secs_f64(1.0 / fps.max(1) as f64);
+    if let Some(remaining) = budget.checked_sub(frame_start.elapsed()) {
+        std::thread::sleep(remaining);
+    }
+}
+
+// Estimates input-to-display latency: the time from polling input for a frame
+// to the GPU finishing it, swap included. A timestamp query goes in right
+// after each swap, and its GPU time is mapped back to the CPU clock using a
+// reading of both clocks taken at the same moment. This covers the CPU, GPU
+// and swap queue only; the display's own scanout and processing add more,
+// so treat the result as a lower bound.
+pub(crate) struct LatencyMonitor {
+    // Timestamp queries still in flight, with the input time of their frame
+    pending: VecDeque<(u32, Instant)>,
+    spare_queries: Vec<u32>,
+    average: Option<f64>,
+}
+
+impl LatencyMonitor {
+    pub(crate) fn new() -> Self {
+        LatencyMonitor {
+            pending: VecDeque::new(),
+            spare_queries: Vec::new(),
+            average: None,
+        }
+    }
+
+    // Smoothed estimate; None until the first frame has completed or if the
+    // driver lacks timer queries
+    pub(crate) fn latency(&self) -> Option<Duration> {
+        self.average.map(Duration::from_secs_f64)
+    }
+
+    // Call right after swapping, with the time input was polled for the frame
+    pub(crate) fn frame_submitted(&mut self, input_time: Instant) {
+        if !gl::QueryCounter::is_loaded() {
+            return;
+        }
+        unsafe {
+            // GL_TIMESTAMP reads the GPU clock immediately, without waiting
+            let mut gpu_now = 0;
+            gl::GetInteger64v(gl::TIMESTAMP, &mut gpu_now);
+            let cpu_now = Instant::now();
+
+            let query = self.spare_queries.pop().unwrap_or_else(|| {
+                let mut query = 0;
+                gl::GenQueries(1, &mut query);
+                query
+            });
+            gl::QueryCounter(query, gl::TIMESTAMP);
+            self.pending.push_back((query, input_time));
+
+            while let Some(&(query, input_time)) = self.pending.front() {
+                let mut available = 0;
+                gl::GetQueryObjectiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available);
+                if available == 0 && self.pending.len() <= MAX_PENDING_QUERIES {
+                    break;
+                }
+                self.pending.pop_front();
+                self.spare_queries.push(query);
+                if available == 0 {
+                    continue;
+                }
+                let mut gpu_done = 0;
+                gl::GetQueryObjecti64v(query, gl::QUERY_RESULT, &mut gpu_done);
+                // Negative when the frame finished before the clocks were read
+                let since_calibration = (gpu_done - gpu_now) as f64 * 1e-9;
+                let sample = cpu_now.duration_since(input_time).as_secs_f64() + since_calibration;
+                let sample = sample.max(0.0);
+                self.average = Some(match self.average {
+                    Some(average) => average + (sample - average) * LATENCY_SMOOTHING,
+                    None => sample,
+                });
+            }
+        }
+    }
+}
+
+impl Drop for LatencyMonitor {
+    fn drop(&mut self) {
+        let queries = self.pending.drain(..).map(|(query, _)| query);
+        let queries: Vec<u32> = self.spare_queries.drain(..).chain(queries).collect();
+        if !queries.is_empty() {
+            unsafe {
+                gl::DeleteQueries(queries.len() as i32, queries.as_ptr());
+            }
+        }
+    }
+}
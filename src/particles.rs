@@ -0,0 +1,273 @@
+use crate::random::XorShift;
+use crate::shader::{ShaderError, build_program};
+use glm::{Mat4, Vec3, Vec4, vec3};
+use std::mem;
+use std::ptr;
+
+// Floats uploaded per particle: center + size (4), color (4)
+const FLOATS_PER_PARTICLE: usize = 8;
+
+// How an emitter's particles are composited over the scene.
+//
+// `Alpha` is the correct choice for smoke, dust or anything that should darken
+// or occlude what's behind it, but "over" blending is order dependent, so the
+// emitter has to be sorted back-to-front every frame (O(n log n) on the CPU).
+// Leaving `sort` off is cheaper but overlapping particles will pop as the
+// camera moves.
+//
+// `Additive` sums colors instead, which is order independent and needs no
+// sorting. It suits fire, sparks and magic effects, but particles can only
+// brighten the scene and dense clouds saturate to white.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    Alpha { sort: bool },
+    Additive,
+}
+
+struct Particle {
+    position: Vec3,
+    velocity: Vec3,
+    age: f32,
+    lifetime: f32,
+}
+
+// CPU-simulated billboard particles streamed to a dynamic VBO each frame
+pub struct ParticleEmitter {
+    pub origin: Vec3,
+    // Particles spawned per second
+    pub rate: f32,
+    pub lifetime: f32,
+    pub speed: f32,
+    // Half-angle of the emission cone around +Y, in radians
+    pub spread: f32,
+    pub gravity: Vec3,
+    pub size: f32,
+    pub start_color: Vec4,
+    pub end_color: Vec4,
+    blend_mode: BlendMode,
+    max_particles: usize,
+    particles: Vec<Particle>,
+    spawn_accumulator: f32,
+    rng: XorShift,
+    // Instance data staged on the CPU before upload
+    staging: Vec<f32>,
+    shader_program: u32,
+    vao: u32,
+    quad_vbo: u32,
+    instance_vbo: u32,
+}
+
+impl ParticleEmitter {
+    // Fails if the built-in particle shader doesn't compile
+    pub fn new(
+        origin: Vec3,
+        max_particles: usize,
+        blend_mode: BlendMode,
+    ) -> Result<Self, ShaderError> {
+        let shader_program = unsafe {
+            build_program(
+                include_str!("shaders/particle_vertex.glsl"),
+                include_str!("shaders/particle_fragment.glsl"),
+            )
+        }?;
+
+        // Two triangles spanning [-0.5, 0.5]^2, expanded to face the camera in the shader
+        let quad: [f32; 12] = [
+            -0.5, -0.5, 0.5, -0.5, 0.5, 0.5, -0.5, -0.5, 0.5, 0.5, -0.5, 0.5,
+        ];
+
+        let (vao, quad_vbo, instance_vbo) = unsafe {
+            let mut vao = 0;
+            let mut quad_vbo = 0;
+            let mut instance_vbo = 0;
+
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut quad_vbo);
+            gl::GenBuffers(1, &mut instance_vbo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, quad_vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                mem::size_of_val(&quad) as isize,
+                quad.as_ptr() as *const _,
+                gl::STATIC_DRAW,
+            );
+            gl::VertexAttribPointer(
+                0,
+                2,
+                gl::FLOAT,
+                gl::FALSE,
+                2 * mem::size_of::<f32>() as i32,
+                ptr::null(),
+            );
+            gl::EnableVertexAttribArray(0);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, instance_vbo);
+            let stride = (FLOATS_PER_PARTICLE * mem::size_of::<f32>()) as i32;
+            // Center + size
+            gl::VertexAttribPointer(1, 4, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(1);
+            gl::VertexAttribDivisor(1, 1);
+            // Color
+            gl::VertexAttribPointer(
+                2,
+                4,
+                gl::FLOAT,
+                gl::FALSE,
+                stride,
+                (4 * mem::size_of::<f32>()) as *const _,
+            );
+            gl::EnableVertexAttribArray(2);
+            gl::VertexAttribDivisor(2, 1);
+
+            gl::BindVertexArray(0);
+
+            (vao, quad_vbo, instance_vbo)
+        };
+
+        Ok(ParticleEmitter {
+            origin,
+            rate: 100.0,
+            lifetime: 2.0,
+            speed: 1.0,
+            spread: 0.35,
+            gravity: vec3(0.0, -0.5, 0.0),
+            size: 0.08,
+            start_color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            end_color: glm::vec4(1.0, 1.0, 1.0, 0.0),
+            blend_mode,
+            max_particles,
+            particles: Vec::with_capacity(max_particles),
+            spawn_accumulator: 0.0,
+            rng: XorShift::new(0x2545_F491),
+            staging: Vec::with_capacity(max_particles * FLOATS_PER_PARTICLE),
+            shader_program,
+            vao,
+            quad_vbo,
+            instance_vbo,
+        })
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub(crate) fn update(&mut self, delta_time: f32) {
+        // Age and integrate, dropping expired particles
+        let gravity = self.gravity;
+        self.particles.retain_mut(|p| {
+            p.age += delta_time;
+            p.velocity += gravity * delta_time;
+            p.position += p.velocity * delta_time;
+            p.age < p.lifetime
+        });
+
+        self.spawn_accumulator += self.rate.max(0.0) * delta_time;
+        while self.spawn_accumulator >= 1.0 {
+            self.spawn_accumulator -= 1.0;
+            if self.particles.len() >= self.max_particles {
+                continue;
+            }
+            let direction = self.random_direction();
+            let speed = self.speed * self.rng.range(0.7, 1.3);
+            let lifetime = self.lifetime * self.rng.range(0.8, 1.2);
+            self.particles.push(Particle {
+                position: self.origin,
+                velocity: direction * speed,
+                age: 0.0,
+                lifetime,
+            });
+        }
+    }
+
+    // Random unit vector inside the emission cone around +Y
+    fn random_direction(&mut self) -> Vec3 {
+        let angle = self.rng.range(0.0, std::f32::consts::TAU);
+        let tilt = self.rng.range(0.0, self.spread);
+        vec3(
+            tilt.sin() * angle.cos(),
+            tilt.cos(),
+            tilt.sin() * angle.sin(),
+        )
+    }
+
+    // Drawn after opaque geometry with depth writes off, so particles are hidden
+    // by solid objects but don't occlude each other through the depth buffer.
+    pub(crate) fn render(&mut self, view: &Mat4, projection: &Mat4, eye: &Vec3) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        if let BlendMode::Alpha { sort: true } = self.blend_mode {
+            // Farthest first so nearer particles blend over them
+            self.particles.sort_by(|a, b| {
+                let da = glm::distance2(&a.position, eye);
+                let db = glm::distance2(&b.position, eye);
+                db.total_cmp(&da)
+            });
+        }
+
+        self.staging.clear();
+        for p in &self.particles {
+            let t = (p.age / p.lifetime).clamp(0.0, 1.0);
+            let color = glm::lerp(&self.start_color, &self.end_color, t);
+            self.staging
+                .extend_from_slice(&[p.position.x, p.position.y, p.position.z, self.size]);
+            self.staging.extend_from_slice(color.as_slice());
+        }
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.instance_vbo);
+            // Orphan the previous contents so the driver doesn't stall on in-flight draws
+            gl::BufferData(
+                gl::ARRAY_BUFFER,
+                (self.staging.len() * mem::size_of::<f32>()) as isize,
+                self.staging.as_ptr() as *const _,
+                gl::STREAM_DRAW,
+            );
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+
+            gl::UseProgram(self.shader_program);
+            let view_loc = gl::GetUniformLocation(self.shader_program, c"view".as_ptr());
+            let projection_loc =
+                gl::GetUniformLocation(self.shader_program, c"projection".as_ptr());
+            gl::UniformMatrix4fv(view_loc, 1, gl::FALSE, view.as_ptr());
+            gl::UniformMatrix4fv(projection_loc, 1, gl::FALSE, projection.as_ptr());
+
+            gl::Enable(gl::BLEND);
+            match self.blend_mode {
+                BlendMode::Alpha { .. } => gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA),
+                BlendMode::Additive => gl::BlendFunc(gl::SRC_ALPHA, gl::ONE),
+            }
+            gl::DepthMask(gl::FALSE);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArraysInstanced(gl::TRIANGLES, 0, 6, self.particles.len() as i32);
+            gl::BindVertexArray(0);
+
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+        }
+    }
+}
+
+impl Drop for ParticleEmitter {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &self.instance_vbo);
+            gl::DeleteBuffers(1, &self.quad_vbo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.shader_program);
+        }
+    }
+}
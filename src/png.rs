@@ -0,0 +1,745 @@
+use crate::image::Image;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Largest payload of a stored (uncompressed) deflate block
+const MAX_STORED_BLOCK: usize = 65535;
+
+// Writes 8-bit RGBA `pixels` (top row first) as a PNG. The image data is stored
+// uncompressed inside the zlib stream: files are large but any decoder reads
+// them, and no compression library is needed.
+pub(crate) fn write_rgba(path: &Path, width: u32, height: u32, pixels: &[u8]) -> io::Result<()> {
+    let row = width as usize * 4;
+    assert_eq!(
+        pixels.len(),
+        row * height as usize,
+        "pixel buffer size mismatch"
+    );
+
+    // Each scanline is prefixed with filter type 0 (none)
+    let mut raw = Vec::with_capacity((row + 1) * height as usize);
+    for line in pixels.chunks_exact(row) {
+        raw.push(0);
+        raw.extend_from_slice(line);
+    }
+
+    let zlib = zlib_stored(&raw);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, color type 6 (RGBA), deflate, adaptive filter, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    let mut out = BufWriter::new(File::create(path)?);
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+    write_chunk(&mut out, b"IHDR", &header)?;
+    write_chunk(&mut out, b"IDAT", &zlib)?;
+    write_chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+// `raw` as a zlib stream of stored deflate blocks
+fn zlib_stored(raw: &[u8]) -> Vec<u8> {
+    let mut zlib = Vec::with_capacity(raw.len() + raw.len() / MAX_STORED_BLOCK * 5 + 16);
+    zlib.extend_from_slice(&[0x78, 0x01]);
+    let blocks = raw.chunks(MAX_STORED_BLOCK);
+    let count = blocks.len().max(1);
+    if raw.is_empty() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    for (i, block) in blocks.enumerate() {
+        let len = block.len() as u16;
+        zlib.push((i + 1 == count) as u8);
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(raw).to_be_bytes());
+    zlib
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in bytes.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+// Decodes a PNG into RGBA8. Every non-interlaced color type and bit depth is
+// read; 16-bit channels keep their high byte. Chunk CRCs aren't checked.
+pub(crate) fn decode(data: &[u8]) -> Result<Image, String> {
+    if !data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return Err("not a PNG file".to_string());
+    }
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    // Gray or RGB sample values drawn fully transparent (tRNS)
+    let mut transparent: Option<[u16; 3]> = None;
+    let mut compressed = Vec::new();
+    loop {
+        let chunk = data.get(pos..pos + 8).ok_or("truncated PNG")?;
+        let length = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) as usize;
+        let kind = [chunk[4], chunk[5], chunk[6], chunk[7]];
+        let body = data
+            .get(pos + 8..pos + 8 + length)
+            .ok_or("truncated PNG chunk")?;
+        pos += 12 + length;
+        let be16 = |i: usize| body.get(i..i + 2).map(|b| u16::from_be_bytes([b[0], b[1]]));
+        match &kind {
+            b"IHDR" => {
+                let fields = body.get(..13).ok_or("short IHDR chunk")?;
+                let width = u32::from_be_bytes([fields[0], fields[1], fields[2], fields[3]]);
+                let height = u32::from_be_bytes([fields[4], fields[5], fields[6], fields[7]]);
+                let (depth, color_type, interlace) = (fields[8], fields[9], fields[12]);
+                if interlace != 0 {
+                    return Err("interlaced PNGs aren't supported".to_string());
+                }
+                header = Some((width, height, depth, color_type));
+            }
+            b"PLTE" => {
+                palette = body
+                    .chunks_exact(3)
+                    .map(|c| [c[0], c[1], c[2], 255])
+                    .collect();
+            }
+            b"tRNS" => match header.map(|(_, _, _, color_type)| color_type) {
+                Some(3) => {
+                    for (entry, &alpha) in palette.iter_mut().zip(body) {
+                        entry[3] = alpha;
+                    }
+                }
+                Some(0) => transparent = be16(0).map(|g| [g, g, g]),
+                Some(2) => {
+                    transparent = be16(0)
+                        .zip(be16(2))
+                        .zip(be16(4))
+                        .map(|((r, g), b)| [r, g, b]);
+                }
+                _ => {}
+            },
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let (width, height, depth, color_type) = header.ok_or("missing IHDR chunk")?;
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("unknown color type {}", color_type)),
+    };
+    let valid_depth = match color_type {
+        0 => matches!(depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(depth, 1 | 2 | 4 | 8),
+        _ => matches!(depth, 8 | 16),
+    };
+    if !valid_depth {
+        return Err(format!(
+            "invalid bit depth {} for color type {}",
+            depth, color_type
+        ));
+    }
+    if width == 0 || height == 0 || width > 1 << 15 || height > 1 << 15 {
+        return Err(format!("unsupported size {}x{}", width, height));
+    }
+
+    // Skip the 2-byte zlib header; the trailing Adler-32 isn't checked
+    let raw = inflate(compressed.get(2..).ok_or("empty image data")?)?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width as usize * bits_per_pixel).div_ceil(8);
+    // Distance back to the same byte of the previous pixel, for the filters
+    let pixel_bytes = bits_per_pixel.div_ceil(8);
+    let max = (1u32 << depth) - 1;
+    let to_8_bit = |value: u16| match depth {
+        16 => (value >> 8) as u8,
+        _ => (value as u32 * 255 / max) as u8,
+    };
+
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    let mut previous = vec![0u8; stride];
+    let mut current = vec![0u8; stride];
+    for y in 0..height as usize {
+        let start = y * (stride + 1);
+        let line = raw
+            .get(start..start + stride + 1)
+            .ok_or("image data too short")?;
+        current.copy_from_slice(&line[1..]);
+        for i in 0..stride {
+            let a = if i >= pixel_bytes {
+                current[i - pixel_bytes]
+            } else {
+                0
+            };
+            let b = previous[i];
+            let c = if i >= pixel_bytes {
+                previous[i - pixel_bytes]
+            } else {
+                0
+            };
+            let prediction = match line[0] {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                filter => return Err(format!("invalid filter type {}", filter)),
+            };
+            current[i] = current[i].wrapping_add(prediction);
+        }
+
+        // Sample `index` of this row at its native depth
+        let sample = |index: usize| -> u16 {
+            match depth {
+                8 => current[index] as u16,
+                16 => u16::from_be_bytes([current[index * 2], current[index * 2 + 1]]),
+                _ => {
+                    let bit = index * depth as usize;
+                    let shift = 8 - depth as usize - bit % 8;
+                    (current[bit / 8] >> shift) as u16 & max as u16
+                }
+            }
+        };
+        for x in 0..width as usize {
+            let base = x * channels;
+            let pixel = match color_type {
+                3 => *palette
+                    .get(sample(base) as usize)
+                    .ok_or("palette index out of range")?,
+                0 | 4 => {
+                    let gray = sample(base);
+                    let alpha = if color_type == 4 {
+                        to_8_bit(sample(base + 1))
+                    } else if transparent.is_some_and(|t| t[0] == gray) {
+                        0
+                    } else {
+                        255
+                    };
+                    let gray = to_8_bit(gray);
+                    [gray, gray, gray, alpha]
+                }
+                _ => {
+                    let rgb = [sample(base), sample(base + 1), sample(base + 2)];
+                    let alpha = if color_type == 6 {
+                        to_8_bit(sample(base + 3))
+                    } else if transparent == Some(rgb) {
+                        0
+                    } else {
+                        255
+                    };
+                    [to_8_bit(rgb[0]), to_8_bit(rgb[1]), to_8_bit(rgb[2]), alpha]
+                }
+            };
+            rgba.extend_from_slice(&pixel);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    Ok(Image::from_top_rows(width, height, rgba))
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// Base match length and extra bits for length symbols 257-285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// Base distance and extra bits for distance symbols 0-29
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// Order code length code lengths are stored in dynamic blocks
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+// Raw deflate (RFC 1951) decompression
+fn inflate(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader {
+        data,
+        pos: 0,
+        buffer: 0,
+        count: 0,
+    };
+    let mut out = Vec::new();
+    loop {
+        let last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => {
+                // Stored: byte-aligned length, its complement, then the bytes
+                reader.buffer = 0;
+                reader.count = 0;
+                let header = reader.take(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]) as usize;
+                out.extend_from_slice(reader.take(length)?);
+            }
+            1 => {
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &mut out, &literals, &distances)?;
+            }
+            2 => {
+                let literal_count = reader.bits(5)? as usize + 257;
+                let distance_count = reader.bits(5)? as usize + 1;
+                let code_length_count = reader.bits(4)? as usize + 4;
+                let mut code_lengths = [0u8; 19];
+                for &index in &CODE_LENGTH_ORDER[..code_length_count] {
+                    code_lengths[index] = reader.bits(3)? as u8;
+                }
+                let code_lengths = Huffman::new(&code_lengths);
+
+                let mut lengths = vec![0u8; literal_count + distance_count];
+                let mut i = 0;
+                while i < lengths.len() {
+                    let (value, repeat) = match code_lengths.decode(&mut reader)? {
+                        symbol @ 0..=15 => (symbol as u8, 1),
+                        16 => {
+                            let previous = *lengths[..i].last().ok_or("repeat with no length")?;
+                            (previous, 3 + reader.bits(2)? as usize)
+                        }
+                        17 => (0, 3 + reader.bits(3)? as usize),
+                        _ => (0, 11 + reader.bits(7)? as usize),
+                    };
+                    let end = i + repeat;
+                    lengths
+                        .get_mut(i..end)
+                        .ok_or("code lengths overrun")?
+                        .fill(value);
+                    i = end;
+                }
+                let literals = Huffman::new(&lengths[..literal_count]);
+                let distances = Huffman::new(&lengths[literal_count..]);
+                inflate_block(&mut reader, &mut out, &literals, &distances)?;
+            }
+            _ => return Err("invalid deflate block type".to_string()),
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn inflate_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        }
+        if symbol == 256 {
+            return Ok(());
+        }
+        let index = symbol - 257;
+        let length = *LENGTH_BASE.get(index).ok_or("invalid length symbol")? as usize
+            + reader.bits(LENGTH_EXTRA[index] as u32)? as usize;
+        let index = distances.decode(reader)? as usize;
+        let distance = *DISTANCE_BASE.get(index).ok_or("invalid distance symbol")? as usize
+            + reader.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+        let start = out
+            .len()
+            .checked_sub(distance)
+            .ok_or("distance before start of data")?;
+        // Byte by byte, since a match may overlap the bytes it produces
+        for k in 0..length {
+            out.push(out[start + k]);
+        }
+    }
+}
+
+// Reads deflate's least-significant-bit-first bit stream
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitReader<'_> {
+    fn bits(&mut self, n: u32) -> Result<u32, String> {
+        while self.count < n {
+            let byte = *self.data.get(self.pos).ok_or("truncated deflate data")?;
+            self.pos += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1 << n) - 1);
+        self.buffer >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    // Whole bytes following the last one bits were read from
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or("truncated deflate data")?;
+        self.pos += n;
+        Ok(bytes)
+    }
+}
+
+// Canonical Huffman code, decoded one bit at a time by counting codes of
+// each length
+struct Huffman {
+    // Number of codes of each length 0-15
+    counts: [u16; 16],
+    // Symbols ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman { counts, symbols }
+    }
+
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        // First code of the current length, and index of its symbol
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for length in 1..16 {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::image;
+
+    // A PNG with the given IHDR fields and extra chunks before IDAT, whose
+    // scanlines are given with their filter bytes already applied
+    fn encode(
+        width: u32,
+        height: u32,
+        depth: u8,
+        color_type: u8,
+        chunks: &[(&[u8; 4], &[u8])],
+        raw: &[u8],
+    ) -> Vec<u8> {
+        let mut header = Vec::new();
+        header.extend_from_slice(&width.to_be_bytes());
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &header).unwrap();
+        for (kind, data) in chunks {
+            write_chunk(&mut png, kind, data).unwrap();
+        }
+        write_chunk(&mut png, b"IDAT", &zlib_stored(raw)).unwrap();
+        write_chunk(&mut png, b"IEND", &[]).unwrap();
+        png
+    }
+
+    // Applies PNG filter `filter` to every row of `rows` (stride bytes
+    // each, `bpp` bytes per pixel), prefixing each with the filter byte
+    fn filter_rows(
+        rows: &[u8],
+        stride: usize,
+        bpp: usize,
+        filter: impl Fn(usize) -> u8,
+    ) -> Vec<u8> {
+        let mut raw = Vec::new();
+        let zero = vec![0u8; stride];
+        for (y, row) in rows.chunks_exact(stride).enumerate() {
+            let previous = if y == 0 {
+                &zero[..]
+            } else {
+                &rows[(y - 1) * stride..y * stride]
+            };
+            let kind = filter(y);
+            raw.push(kind);
+            for i in 0..stride {
+                let a = if i >= bpp { row[i - bpp] } else { 0 };
+                let b = previous[i];
+                let c = if i >= bpp { previous[i - bpp] } else { 0 };
+                let prediction = match kind {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                raw.push(row[i].wrapping_sub(prediction));
+            }
+        }
+        raw
+    }
+
+    // Deterministic bytes with enough variation to exercise every predictor
+    fn pattern(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 37 + i * i / 5) as u8).collect()
+    }
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn write_rgba_round_trips() {
+        let (width, height) = (7, 5);
+        let pixels = pattern(width * height * 4);
+        let path = std::env::temp_dir().join(format!("x3d-png-test-{}.png", std::process::id()));
+        write_rgba(&path, width as u32, height as u32, &pixels).unwrap();
+        let decoded = image::decode(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            decoded.unwrap(),
+            Image::from_top_rows(width as u32, height as u32, pixels)
+        );
+    }
+
+    #[test]
+    fn write_rgba_spans_several_stored_blocks() {
+        // Over 64 KiB of scanlines, so the zlib stream holds several blocks
+        let (width, height) = (129, 130);
+        let pixels = pattern(width * height * 4);
+        let path = std::env::temp_dir().join(format!("x3d-png-blocks-{}.png", std::process::id()));
+        write_rgba(&path, width as u32, height as u32, &pixels).unwrap();
+        let decoded = image::decode(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            decoded.unwrap(),
+            Image::from_top_rows(width as u32, height as u32, pixels)
+        );
+    }
+
+    #[test]
+    fn decodes_every_filter_type() {
+        let (width, height) = (6, 10);
+        let rgb = pattern(width * height * 3);
+        let expected: Vec<u8> = rgb
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect();
+        let expected = Image::from_top_rows(width as u32, height as u32, expected);
+        // Each filter on its own, then all five mixed row by row
+        for filter in 0..5u8 {
+            let raw = filter_rows(&rgb, width * 3, 3, |_| filter);
+            let png = encode(width as u32, height as u32, 8, 2, &[], &raw);
+            assert_eq!(decode(&png).unwrap(), expected, "filter {filter}");
+        }
+        let raw = filter_rows(&rgb, width * 3, 3, |y| (y % 5) as u8);
+        let png = encode(width as u32, height as u32, 8, 2, &[], &raw);
+        assert_eq!(decode(&png).unwrap(), expected);
+    }
+
+    #[test]
+    fn decodes_palette_with_transparency() {
+        // 2-bit indices, four per byte; 5 pixels leave the last byte partly used
+        let palette = [10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 110, 120];
+        // Alpha for the first two entries only; the rest stay opaque
+        let alpha = [0, 128];
+        let indices = [[0, 1, 2, 3, 1], [3, 2, 1, 0, 2]];
+        let mut raw = Vec::new();
+        for row in indices {
+            raw.push(0);
+            raw.push(row[0] << 6 | row[1] << 4 | row[2] << 2 | row[3]);
+            raw.push(row[4] << 6);
+        }
+        let png = encode(5, 2, 2, 3, &[(b"PLTE", &palette), (b"tRNS", &alpha)], &raw);
+        let colors = [
+            [10, 20, 30, 0],
+            [40, 50, 60, 128],
+            [70, 80, 90, 255],
+            [100, 110, 120, 255],
+        ];
+        let expected = indices
+            .iter()
+            .flatten()
+            .flat_map(|&i| colors[i as usize])
+            .collect();
+        assert_eq!(decode(&png).unwrap(), Image::from_top_rows(5, 2, expected));
+    }
+
+    #[test]
+    fn rejects_palette_index_past_the_palette() {
+        let png = encode(1, 1, 8, 3, &[(b"PLTE", &[1, 2, 3])], &[0, 1]);
+        assert!(decode(&png).is_err());
+    }
+
+    #[test]
+    fn keeps_the_high_byte_of_16_bit_samples() {
+        // One RGBA pixel with 16-bit channels, big-endian
+        let raw = [0, 0x12, 0x34, 0xab, 0xcd, 0xff, 0x00, 0x80, 0x01];
+        let png = encode(1, 1, 16, 6, &[], &raw);
+        let image = decode(&png).unwrap();
+        assert_eq!(image.pixels, [0x12, 0xab, 0xff, 0x80]);
+    }
+
+    #[test]
+    fn applies_16_bit_gray_transparency() {
+        // The tRNS value matches the first pixel's full 16 bits, not the
+        // second's, which only shares its high byte
+        let raw = [0, 0x40, 0x10, 0x40, 0x11];
+        let png = encode(2, 1, 16, 0, &[(b"tRNS", &[0x40, 0x10])], &raw);
+        let image = decode(&png).unwrap();
+        assert_eq!(image.pixels, [0x40, 0x40, 0x40, 0, 0x40, 0x40, 0x40, 255]);
+    }
+
+    #[test]
+    fn scales_low_bit_depth_gray() {
+        // 1-bit gray: set bits are white
+        let png = encode(3, 1, 1, 0, &[], &[0, 0b1010_0000]);
+        let image = decode(&png).unwrap();
+        assert_eq!(
+            image.pixels,
+            [255, 255, 255, 255, 0, 0, 0, 255, 255, 255, 255, 255]
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let rgb = pattern(4 * 4 * 3);
+        let png = encode(4, 4, 8, 2, &[], &filter_rows(&rgb, 12, 3, |y| y as u8));
+        assert!(decode(&png).is_ok());
+        // Cut anywhere before IEND: the signature, a chunk header, the chunk
+        // bodies or the deflate stream
+        let iend = png.len() - 12;
+        for length in 0..iend {
+            assert!(decode(&png[..length]).is_err(), "cut at {length}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        // Interlaced
+        let mut png = encode(1, 1, 8, 2, &[], &[0, 1, 2, 3]);
+        png[8 + 8 + 12] = 1;
+        assert!(decode(&png).is_err());
+        // Bit depth 16 isn't allowed for palettes
+        assert!(decode(&encode(1, 1, 16, 3, &[], &[0, 0, 0])).is_err());
+        // Unknown filter type
+        assert!(decode(&encode(1, 1, 8, 0, &[], &[5, 0])).is_err());
+        assert!(decode(&encode(0, 1, 8, 0, &[], &[])).is_err());
+        assert!(decode(b"\x89PNG\r\n\x1a\n").is_err());
+    }
+
+    #[test]
+    fn inflates_fixed_huffman_blocks() {
+        // zlib level 9, Z_FIXED strategy, raw deflate
+        let compressed = hex("4b4c4a4e8421858cd49c9c7c641200");
+        assert_eq!(
+            inflate(&compressed).unwrap(),
+            b"abcabcabcabc hello hello hello"
+        );
+    }
+
+    #[test]
+    fn inflates_dynamic_huffman_blocks() {
+        // zlib level 9 output, with its header and checksum removed
+        let compressed = hex(concat!(
+            "85d2cb0980301405d1bd55bc12bcd77f3906222e82010dd8be1d8cebd91da68f",
+            "545b2bf9897a44caf98e7a453b73bc7b299db01aeb8075c43a619db12e5857ac",
+            "1b6bf49c594bcc25f6128389c5c4646233319a58cdace69fc758cdac665633ab",
+            "99d5cc6a6635b3da07",
+        ));
+        let expected: String = (0..30)
+            .map(|i| format!("{i} bottles of beer on the wall\n"))
+            .collect();
+        assert_eq!(inflate(&compressed).unwrap(), expected.as_bytes());
+    }
+
+    #[test]
+    fn rejects_malformed_deflate_streams() {
+        // Block type 3 is reserved
+        assert!(inflate(&[0b111]).is_err());
+        // Cut off before the end-of-block code
+        let compressed = hex("4b4c4a4e8421858cd49c9c7c641200");
+        assert!(inflate(&compressed[..compressed.len() - 4]).is_err());
+        assert!(inflate(&[]).is_err());
+    }
+}
@@ -0,0 +1,41 @@
+// Minimal deterministic PRNG for procedural placement and particle spawning
+pub(crate) struct XorShift(u32);
+
+impl XorShift {
+    pub(crate) fn new(seed: u32) -> Self {
+        // Zero is a fixed point of xorshift
+        XorShift(seed.max(1))
+    }
+
+    // Uniform in [0, 1)
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        (self.0 >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // Uniform in [min, max)
+    pub(crate) fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+// Smooth 1D gradient (Perlin) noise in about [-1, 1], one independent curve
+// per seed, with features roughly one unit of `x` apart
+pub(crate) fn gradient_noise(x: f32, seed: u32) -> f32 {
+    let gradient = |i: i32| {
+        // Integer hash of (lattice point, seed) mapped to a slope in [-1, 1]
+        let mut h = (i as u32).wrapping_mul(0x9E37_79B1) ^ seed.wrapping_mul(0x85EB_CA6B);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x2C1B_3C6D);
+        h ^= h >> 12;
+        (h & 0xFFFF) as f32 / 32767.5 - 1.0
+    };
+    let i = x.floor();
+    let t = x - i;
+    let (a, b) = (gradient(i as i32) * t, gradient(i as i32 + 1) * (t - 1.0));
+    // Quintic fade keeps the curve's slope continuous across lattice points
+    let fade = t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+    2.0 * (a + (b - a) * fade)
+}
@@ -0,0 +1,216 @@
+use crate::viewport::Rect;
+use std::ptr;
+
+// Framebuffer the scene is drawn into: the window's default framebuffer (fbo 0)
+// or an offscreen `RenderTarget`, possibly only the x/y/width/height part of it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct FrameTarget {
+    pub(crate) fbo: u32,
+    pub(crate) x: i32,
+    pub(crate) y: i32,
+    pub(crate) width: i32,
+    pub(crate) height: i32,
+    // Framebuffer pixels (x, y, width, height) drawing is scissored to
+    pub(crate) crop: Option<[i32; 4]>,
+}
+
+impl FrameTarget {
+    pub(crate) fn aspect(&self) -> f32 {
+        self.width.max(1) as f32 / self.height.max(1) as f32
+    }
+
+    // This target with drawing limited to `region` (fractions of it)
+    pub(crate) fn cropped(&self, region: &Rect) -> FrameTarget {
+        let area = region.of(self);
+        FrameTarget {
+            crop: Some([area.x, area.y, area.width, area.height]),
+            ..*self
+        }
+    }
+
+    // Binds the framebuffer and sets the viewport to this target's rectangle,
+    // and the scissor test to its crop
+    pub(crate) fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(self.x, self.y, self.width, self.height);
+        }
+        self.apply_crop();
+    }
+
+    // glClear limited to this target's rectangle (and crop)
+    pub(crate) fn clear(&self, mask: gl::types::GLbitfield) {
+        let [mut x0, mut y0] = [self.x, self.y];
+        let [mut x1, mut y1] = [self.x + self.width, self.y + self.height];
+        if let Some([x, y, width, height]) = self.crop {
+            (x0, y0) = (x0.max(x), y0.max(y));
+            (x1, y1) = (x1.min(x + width), y1.min(y + height));
+        }
+        unsafe {
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::Scissor(x0, y0, (x1 - x0).max(0), (y1 - y0).max(0));
+            gl::Clear(mask);
+        }
+        self.apply_crop();
+    }
+
+    fn apply_crop(&self) {
+        unsafe {
+            match self.crop {
+                Some([x, y, width, height]) => {
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(x, y, width, height);
+                }
+                None => gl::Disable(gl::SCISSOR_TEST),
+            }
+        }
+    }
+}
+
+// Offscreen color texture (RGBA8 unless created with `hdr`) + 24-bit depth
+// renderbuffer
+pub(crate) struct RenderTarget {
+    fbo: u32,
+    color: u32,
+    depth: u32,
+    width: i32,
+    height: i32,
+}
+
+impl RenderTarget {
+    // Fails if the size exceeds the driver's limits or the framebuffer is incomplete
+    pub(crate) fn new(width: i32, height: i32) -> Result<Self, String> {
+        Self::with_format(width, height, gl::RGBA8, gl::UNSIGNED_BYTE)
+    }
+
+    // RGBA16F color, so values above 1.0 survive for post-processing
+    pub(crate) fn hdr(width: i32, height: i32) -> Result<Self, String> {
+        Self::with_format(width, height, gl::RGBA16F, gl::FLOAT)
+    }
+
+    fn with_format(
+        width: i32,
+        height: i32,
+        internal_format: gl::types::GLenum,
+        ty: gl::types::GLenum,
+    ) -> Result<Self, String> {
+        let max = max_size();
+        if width <= 0 || height <= 0 || width > max || height > max {
+            return Err(format!(
+                "{width}x{height} render target is outside the supported 1..={max} range"
+            ));
+        }
+
+        let mut target = RenderTarget {
+            fbo: 0,
+            color: 0,
+            depth: 0,
+            width,
+            height,
+        };
+        let complete = unsafe {
+            gl::GenFramebuffers(1, &mut target.fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target.fbo);
+
+            gl::GenTextures(1, &mut target.color);
+            gl::BindTexture(gl::TEXTURE_2D, target.color);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal_format as i32,
+                width,
+                height,
+                0,
+                gl::RGBA,
+                ty,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                target.color,
+                0,
+            );
+
+            gl::GenRenderbuffers(1, &mut target.depth);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, target.depth);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, width, height);
+            gl::FramebufferRenderbuffer(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::RENDERBUFFER,
+                target.depth,
+            );
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            status == gl::FRAMEBUFFER_COMPLETE
+        };
+        if !complete {
+            return Err(format!("{width}x{height} render target is incomplete"));
+        }
+        Ok(target)
+    }
+
+    pub(crate) fn frame_target(&self) -> FrameTarget {
+        FrameTarget {
+            fbo: self.fbo,
+            x: 0,
+            y: 0,
+            width: self.width,
+            height: self.height,
+            crop: None,
+        }
+    }
+
+    pub(crate) fn color_texture(&self) -> u32 {
+        self.color
+    }
+
+    // Tightly packed RGBA8 pixels, bottom row first
+    pub(crate) fn read_rgba(&self) -> Vec<u8> {
+        let mut pixels = vec![0u8; self.width as usize * self.height as usize * 4];
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(
+                0,
+                0,
+                self.width,
+                self.height,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_mut_ptr() as *mut _,
+            );
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        }
+        pixels
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteRenderbuffers(1, &self.depth);
+            gl::DeleteTextures(1, &self.color);
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
+
+// Largest width/height usable for both the color texture and depth renderbuffer
+fn max_size() -> i32 {
+    let mut texture = 0;
+    let mut renderbuffer = 0;
+    unsafe {
+        gl::GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut texture);
+        gl::GetIntegerv(gl::MAX_RENDERBUFFER_SIZE, &mut renderbuffer);
+    }
+    texture.min(renderbuffer)
+}
@@ -0,0 +1,125 @@
+use crate::render_target::{FrameTarget, RenderTarget};
+use crate::shader::{ShaderError, build_program};
+
+// Retro post-process: the scene is drawn at 1/pixel_size of the window
+// resolution and scaled up with nearest-neighbor filtering, optionally with
+// fewer bits per color channel and an ordered-dither pattern to hide banding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pixelation {
+    // Window pixels per scene pixel along each axis
+    pub pixel_size: u32,
+    // Bits kept per color channel (1-8); None leaves colors untouched
+    pub color_bits: Option<u32>,
+    // 4x4 Bayer dithering before quantizing; no effect without `color_bits`
+    pub dither: bool,
+}
+
+impl Default for Pixelation {
+    fn default() -> Self {
+        Pixelation {
+            pixel_size: 4,
+            color_bits: Some(4),
+            dither: true,
+        }
+    }
+}
+
+impl Pixelation {
+    fn color_levels(&self) -> f32 {
+        match self.color_bits {
+            Some(bits) => ((1u32 << bits.clamp(1, 8)) - 1) as f32,
+            None => 0.0,
+        }
+    }
+}
+
+pub(crate) struct PixelationPass {
+    program: u32,
+    // Attribute-less VAO for the fullscreen triangle
+    empty_vao: u32,
+    target: Option<RenderTarget>,
+}
+
+impl PixelationPass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = unsafe {
+            build_program(
+                include_str!("shaders/fullscreen_vertex.glsl"),
+                include_str!("shaders/pixelate_fragment.glsl"),
+            )?
+        };
+        let mut empty_vao = 0;
+        unsafe {
+            gl::GenVertexArrays(1, &mut empty_vao);
+        }
+        Ok(PixelationPass {
+            program,
+            empty_vao,
+            target: None,
+        })
+    }
+
+    // Low-resolution target for a window of `output` size, reallocated when
+    // either changes. None if it can't be created.
+    pub(crate) fn begin(
+        &mut self,
+        params: &Pixelation,
+        output: &FrameTarget,
+    ) -> Option<FrameTarget> {
+        let pixel_size = params.pixel_size.max(1) as i32;
+        let width = (output.width + pixel_size - 1) / pixel_size;
+        let height = (output.height + pixel_size - 1) / pixel_size;
+        let stale = self
+            .target
+            .as_ref()
+            .is_none_or(|t| (t.frame_target().width, t.frame_target().height) != (width, height));
+        if stale {
+            self.target = None;
+            match RenderTarget::new(width.max(1), height.max(1)) {
+                Ok(target) => self.target = Some(target),
+                Err(err) => {
+                    eprintln!("Pixelation disabled: {}", err);
+                    return None;
+                }
+            }
+        }
+        self.target.as_ref().map(RenderTarget::frame_target)
+    }
+
+    // Upscales the low-resolution frame into `output`
+    pub(crate) fn composite(&self, params: &Pixelation, output: &FrameTarget) {
+        let Some(target) = &self.target else {
+            return;
+        };
+        output.bind();
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::UseProgram(self.program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.program, name.as_ptr());
+
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, target.color_texture());
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            gl::Uniform1i(location(c"scene"), 0);
+            gl::Uniform1f(location(c"pixelSize"), params.pixel_size.max(1) as f32);
+            gl::Uniform1f(location(c"colorLevels"), params.color_levels());
+            gl::Uniform1i(location(c"dither"), params.dither as i32);
+
+            gl::BindVertexArray(self.empty_vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+            gl::BindVertexArray(0);
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+impl Drop for PixelationPass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &self.empty_vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use crate::bounds::{Aabb, Frustum};
+use crate::material::Material;
+use crate::mesh::Mesh;
+use crate::texture::Texture;
+use glm::{Mat4, Vec3};
+use std::rc::Rc;
+
+// Share of a node's color a tint replaces unless set otherwise
+const DEFAULT_TINT_STRENGTH: f32 = 0.5;
+
+// Top-level node added with X3D::add_node or Scene::add
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub(crate) usize);
+
+// A program registered with X3D::add_shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ShaderHandle(pub(crate) usize);
+
+// A mesh drawn alongside the main mesh. Top-level nodes are placed in world
+// space by `transform`; children are placed relative to their parent, so
+// moving or hiding a node takes its whole subtree with it.
+pub struct SceneNode {
+    pub mesh: Mesh,
+    pub transform: Mat4,
+    pub children: Vec<SceneNode>,
+    // Program to draw this node with instead of the built-in lit shader
+    pub shader: Option<ShaderHandle>,
+    // Used by the built-in shaders; overrides get the uniforms but may ignore them
+    pub material: Material,
+    // Replaces X3D::set_texture's texture on this node (built-in shaders
+    // only); shared, so several nodes can use one upload
+    pub texture: Option<Rc<Texture>>,
+    pub visible: bool,
+    // Light back faces as their own side of a thin surface rather than with
+    // the front face's normal (built-in shader only)
+    pub two_sided_lighting: bool,
+    // Color mixed over the lit result, e.g. to highlight a picked node, and
+    // the share of it from 0 (none) to 1 (flat tint). Children without a
+    // tint of their own take their parent's. Built-in shaders only.
+    pub tint: Option<Vec3>,
+    pub tint_strength: f32,
+    // World-space shift applied on top of `transform` by the explode view
+    pub(crate) explode_offset: Vec3,
+    // Accumulated world transform and visibility of the ancestors, refreshed
+    // by Scene::update_transforms
+    pub(crate) parent_transform: Mat4,
+    pub(crate) parent_visible: bool,
+    // Nearest ancestor's (tint, strength)
+    pub(crate) parent_tint: Option<(Vec3, f32)>,
+    // World transform in the last presented frame, for motion blur
+    pub(crate) previous_transform: Option<Mat4>,
+}
+
+impl SceneNode {
+    pub fn new(mesh: Mesh) -> Self {
+        SceneNode {
+            mesh,
+            transform: Mat4::identity(),
+            children: Vec::new(),
+            shader: None,
+            material: Material::default(),
+            texture: None,
+            visible: true,
+            two_sided_lighting: false,
+            tint: None,
+            tint_strength: DEFAULT_TINT_STRENGTH,
+            explode_offset: Vec3::zeros(),
+            parent_transform: Mat4::identity(),
+            parent_visible: true,
+            parent_tint: None,
+            previous_transform: None,
+        }
+    }
+
+    pub fn with_transform(mut self, transform: Mat4) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_shader(mut self, shader: ShaderHandle) -> Self {
+        self.shader = Some(shader);
+        self
+    }
+
+    pub fn with_material(mut self, material: Material) -> Self {
+        self.material = material;
+        self
+    }
+
+    pub fn with_texture(mut self, texture: Rc<Texture>) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_two_sided_lighting(mut self, enabled: bool) -> Self {
+        self.two_sided_lighting = enabled;
+        self
+    }
+
+    // Sets or clears the tint, keeping the strength
+    pub fn set_tint(&mut self, tint: Option<Vec3>) {
+        self.tint = tint;
+    }
+
+    pub fn with_tint(mut self, tint: Vec3) -> Self {
+        self.tint = Some(tint);
+        self
+    }
+
+    pub fn with_child(mut self, child: SceneNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    // `transform` under the parents' transforms, plus the explode view's
+    // offset: where the node is drawn. Parent changes show up here once the
+    // scene has been drawn.
+    pub fn world_transform(&self) -> Mat4 {
+        glm::translate(&Mat4::identity(), &self.explode_offset)
+            * self.parent_transform
+            * self.transform
+    }
+
+    pub fn world_bounds(&self) -> Aabb {
+        self.mesh.bounds().transformed(&self.world_transform())
+    }
+
+    // Visible itself and under visible parents
+    pub(crate) fn is_shown(&self) -> bool {
+        self.visible && self.parent_visible
+    }
+
+    pub(crate) fn is_drawn(&self, frustum: &Frustum) -> bool {
+        self.is_shown() && frustum.intersects(&self.world_bounds())
+    }
+
+    // Own tint, else the nearest tinted ancestor's, as (color, strength)
+    pub(crate) fn effective_tint(&self) -> Option<(Vec3, f32)> {
+        self.tint
+            .map(|tint| (tint, self.tint_strength))
+            .or(self.parent_tint)
+    }
+
+    fn update_transforms(
+        &mut self,
+        parent_transform: &Mat4,
+        parent_visible: bool,
+        parent_tint: Option<(Vec3, f32)>,
+    ) {
+        self.parent_transform = *parent_transform;
+        self.parent_visible = parent_visible;
+        self.parent_tint = parent_tint;
+        let world = self.world_transform();
+        let shown = self.is_shown();
+        let tint = self.effective_tint();
+        for child in &mut self.children {
+            child.update_transforms(&world, shown, tint);
+        }
+    }
+
+    fn visit_mut(&mut self, f: &mut impl FnMut(&mut SceneNode)) {
+        f(self);
+        for child in &mut self.children {
+            child.visit_mut(f);
+        }
+    }
+}
+
+// Uploads `tint` as (color, strength) to `program`, which must be in use;
+// None uploads strength 0, leaving the color unchanged
+pub(crate) fn set_tint_uniforms(program: u32, tint: Option<(Vec3, f32)>) {
+    let (color, strength) = tint.unwrap_or((Vec3::zeros(), 0.0));
+    unsafe {
+        let location = |name: &std::ffi::CStr| gl::GetUniformLocation(program, name.as_ptr());
+        gl::Uniform3f(location(c"tint"), color.x, color.y, color.z);
+        gl::Uniform1f(location(c"tintStrength"), strength.clamp(0.0, 1.0));
+    }
+}
+
+// The node tree drawn with the main mesh. The scene itself is the root, at
+// the world origin; its top-level nodes are what NodeIds refer to.
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<SceneNode>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Scene::default()
+    }
+
+    pub fn add(&mut self, node: SceneNode) -> NodeId {
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&SceneNode> {
+        self.nodes.get(id.0)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut SceneNode> {
+        self.nodes.get_mut(id.0)
+    }
+
+    // Top-level nodes; children hang off each one
+    pub fn nodes(&self) -> &[SceneNode] {
+        &self.nodes
+    }
+
+    pub fn into_nodes(self) -> Vec<SceneNode> {
+        self.nodes
+    }
+
+    pub(crate) fn nodes_mut(&mut self) -> &mut [SceneNode] {
+        &mut self.nodes
+    }
+
+    // Every node, parents before their children
+    pub fn iter(&self) -> impl Iterator<Item = &SceneNode> {
+        let mut stack: Vec<&SceneNode> = self.nodes.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    pub(crate) fn visit_mut(&mut self, mut f: impl FnMut(&mut SceneNode)) {
+        for node in &mut self.nodes {
+            node.visit_mut(&mut f);
+        }
+    }
+
+    // The top-level node whose subtree the ray `origin + t * direction` hits
+    // first, testing each shown node's mesh bounds in the node's own space
+    pub fn pick(&self, origin: &Vec3, direction: &Vec3) -> Option<NodeId> {
+        let mut nearest: Option<(f32, NodeId)> = None;
+        for (index, top) in self.nodes.iter().enumerate() {
+            let mut stack = vec![top];
+            while let Some(node) = stack.pop() {
+                stack.extend(&node.children);
+                if !node.is_shown() {
+                    continue;
+                }
+                let Some(inverse) = node.world_transform().try_inverse() else {
+                    continue;
+                };
+                // Unnormalized, so distances stay comparable between nodes
+                let local_origin = inverse.transform_point(&(*origin).into()).coords;
+                let local_direction = inverse.transform_vector(direction);
+                if let Some(t) = node.mesh.bounds().ray_hit(&local_origin, &local_direction)
+                    && nearest.is_none_or(|(best, _)| t < best)
+                {
+                    nearest = Some((t, NodeId(index)));
+                }
+            }
+        }
+        nearest.map(|(_, id)| id)
+    }
+
+    // Pushes each node's world transform and visibility down to its children
+    pub(crate) fn update_transforms(&mut self) {
+        for node in &mut self.nodes {
+            node.update_transforms(&Mat4::identity(), true, None);
+        }
+    }
+}
@@ -0,0 +1,289 @@
+use crate::edges::EdgeDetection;
+use crate::fog::{FogMode, FogParams};
+use crate::lighting::{LightingModel, Outline};
+use crate::retro::Pixelation;
+use glm::{Vec3, vec3};
+use std::error::Error;
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Look-related settings switched together as a named preset. Preset files are
+// plain text with one `key = value` line per field (see `to_text`); missing
+// keys keep their defaults, so hand-written presets only need what they change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub name: String,
+    pub lighting_model: LightingModel,
+    // Light every surface receives regardless of direction
+    pub ambient: f32,
+    // Multiplier on the lit color before fog
+    pub exposure: f32,
+    pub clear_color: [f32; 4],
+    pub shadows: bool,
+    pub fog: Option<FogParams>,
+    pub outline: Option<Outline>,
+    pub edge_detection: Option<EdgeDetection>,
+    pub pixelation: Option<Pixelation>,
+}
+
+impl Default for RenderSettings {
+    // The engine's startup look
+    fn default() -> Self {
+        RenderSettings {
+            name: "Default".to_string(),
+            lighting_model: LightingModel::Lambert,
+            ambient: 0.1,
+            exposure: 1.0,
+            clear_color: [0.1, 0.1, 0.3, 1.0],
+            shadows: false,
+            fog: None,
+            outline: None,
+            edge_detection: None,
+            pixelation: None,
+        }
+    }
+}
+
+impl RenderSettings {
+    // Soft neutral backdrop with shadows, for presenting a model
+    pub fn studio() -> Self {
+        RenderSettings {
+            name: "Studio".to_string(),
+            ambient: 0.2,
+            exposure: 1.1,
+            clear_color: [0.32, 0.33, 0.35, 1.0],
+            shadows: true,
+            ..RenderSettings::default()
+        }
+    }
+
+    // Flat bands with ink edges on white, for reading shape and topology
+    pub fn technical() -> Self {
+        RenderSettings {
+            name: "Technical".to_string(),
+            lighting_model: LightingModel::Toon { bands: 2 },
+            ambient: 0.35,
+            clear_color: [1.0, 1.0, 1.0, 1.0],
+            edge_detection: Some(EdgeDetection::default()),
+            ..RenderSettings::default()
+        }
+    }
+
+    // Bright light and shadows with distance haze
+    pub fn outdoor() -> Self {
+        RenderSettings {
+            name: "Outdoor".to_string(),
+            ambient: 0.25,
+            exposure: 1.2,
+            clear_color: [0.55, 0.7, 0.9, 1.0],
+            shadows: true,
+            fog: Some(FogParams {
+                color: vec3(0.55, 0.7, 0.9),
+                start: 4.0,
+                density: 0.08,
+                mode: FogMode::Exponential,
+                ..FogParams::default()
+            }),
+            ..RenderSettings::default()
+        }
+    }
+
+    pub fn builtin() -> Vec<RenderSettings> {
+        vec![Self::studio(), Self::technical(), Self::outdoor()]
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        std::fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    // Presets without a `name` line are named after the file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let mut settings =
+            Self::parse(&text).map_err(|err| format!("{}: {}", path.display(), err))?;
+        if settings.name.is_empty()
+            && let Some(stem) = path.file_stem()
+        {
+            settings.name = stem.to_string_lossy().into_owned();
+        }
+        Ok(settings)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let vec = |v: &Vec3| format!("{} {} {}", v.x, v.y, v.z);
+        let mut line = |key: &str, value: String| {
+            let _ = writeln!(text, "{key} = {value}");
+        };
+        line("name", self.name.clone());
+        line(
+            "lighting",
+            match self.lighting_model {
+                LightingModel::Lambert => "lambert".to_string(),
+                LightingModel::Toon { bands } => format!("toon {bands}"),
+            },
+        );
+        line("ambient", self.ambient.to_string());
+        line("exposure", self.exposure.to_string());
+        let [r, g, b, a] = self.clear_color;
+        line("clear_color", format!("{r} {g} {b} {a}"));
+        line("shadows", self.shadows.to_string());
+        // fog = <mode> <r g b> <start> <end> <density>
+        line(
+            "fog",
+            self.fog.map_or("none".to_string(), |f| {
+                let mode = match f.mode {
+                    FogMode::Linear => "linear",
+                    FogMode::Exponential => "exponential",
+                    FogMode::ExponentialSquared => "exponential_squared",
+                };
+                format!(
+                    "{mode} {} {} {} {}",
+                    vec(&f.color),
+                    f.start,
+                    f.end,
+                    f.density
+                )
+            }),
+        );
+        // outline = <width> <r g b>
+        line(
+            "outline",
+            self.outline.map_or("none".to_string(), |o| {
+                format!("{} {}", o.width, vec(&o.color))
+            }),
+        );
+        // edges = <r g b> <thickness> <depth threshold> <normal threshold>
+        line(
+            "edges",
+            self.edge_detection.map_or("none".to_string(), |e| {
+                format!(
+                    "{} {} {} {}",
+                    vec(&e.color),
+                    e.thickness,
+                    e.depth_threshold,
+                    e.normal_threshold
+                )
+            }),
+        );
+        // pixelation = <pixel size> <color bits or none> <dither>
+        line(
+            "pixelation",
+            self.pixelation.map_or("none".to_string(), |p| {
+                let bits = p.color_bits.map_or("none".to_string(), |b| b.to_string());
+                format!("{} {bits} {}", p.pixel_size, p.dither)
+            }),
+        );
+        text
+    }
+
+    // Reads the `to_text` format; blank lines and lines starting with '#' are
+    // ignored, as are unknown keys so newer files still load. The name is left
+    // empty if the text doesn't set one.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut settings = RenderSettings {
+            name: String::new(),
+            ..RenderSettings::default()
+        };
+        for (number, raw) in text.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", number + 1))?;
+            let value = value.trim();
+            let words: Vec<&str> = value.split_whitespace().collect();
+            let error = |what: &str| format!("line {}: invalid {what} `{value}`", number + 1);
+            let floats = |words: &[&str]| -> Option<Vec<f32>> {
+                words.iter().map(|w| w.parse().ok()).collect()
+            };
+
+            match key.trim() {
+                "name" => settings.name = value.to_string(),
+                "lighting" => {
+                    settings.lighting_model = match words[..] {
+                        ["lambert"] => LightingModel::Lambert,
+                        ["toon", bands] => LightingModel::Toon {
+                            bands: bands.parse().map_err(|_| error("lighting"))?,
+                        },
+                        _ => return Err(error("lighting")),
+                    }
+                }
+                "ambient" => settings.ambient = value.parse().map_err(|_| error("ambient"))?,
+                "exposure" => settings.exposure = value.parse().map_err(|_| error("exposure"))?,
+                "clear_color" => match floats(&words).as_deref() {
+                    Some(&[r, g, b, a]) => settings.clear_color = [r, g, b, a],
+                    Some(&[r, g, b]) => settings.clear_color = [r, g, b, 1.0],
+                    _ => return Err(error("clear_color")),
+                },
+                "shadows" => settings.shadows = value.parse().map_err(|_| error("shadows"))?,
+                "fog" => {
+                    settings.fog = match words[..] {
+                        ["none"] => None,
+                        [mode, ref rest @ ..] => {
+                            let mode = match mode {
+                                "linear" => FogMode::Linear,
+                                "exponential" => FogMode::Exponential,
+                                "exponential_squared" => FogMode::ExponentialSquared,
+                                _ => return Err(error("fog mode")),
+                            };
+                            match floats(rest).as_deref() {
+                                Some(&[r, g, b, start, end, density]) => Some(FogParams {
+                                    color: vec3(r, g, b),
+                                    start,
+                                    end,
+                                    density,
+                                    mode,
+                                }),
+                                _ => return Err(error("fog")),
+                            }
+                        }
+                        _ => return Err(error("fog")),
+                    }
+                }
+                "outline" => {
+                    settings.outline = match (&words[..], floats(&words).as_deref()) {
+                        (["none"], _) => None,
+                        (_, Some(&[width, r, g, b])) => Some(Outline {
+                            width,
+                            color: vec3(r, g, b),
+                        }),
+                        _ => return Err(error("outline")),
+                    }
+                }
+                "edges" => {
+                    settings.edge_detection = match (&words[..], floats(&words).as_deref()) {
+                        (["none"], _) => None,
+                        (_, Some(&[r, g, b, thickness, depth, normal])) => Some(EdgeDetection {
+                            color: vec3(r, g, b),
+                            thickness,
+                            depth_threshold: depth,
+                            normal_threshold: normal,
+                        }),
+                        _ => return Err(error("edges")),
+                    }
+                }
+                "pixelation" => {
+                    settings.pixelation = match words[..] {
+                        ["none"] => None,
+                        [size, bits, dither] => Some(Pixelation {
+                            pixel_size: size.parse().map_err(|_| error("pixelation"))?,
+                            color_bits: match bits {
+                                "none" => None,
+                                bits => Some(bits.parse().map_err(|_| error("pixelation"))?),
+                            },
+                            dither: dither.parse().map_err(|_| error("pixelation"))?,
+                        }),
+                        _ => return Err(error("pixelation")),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(settings)
+    }
+}
@@ -0,0 +1,285 @@
+use std::ffi::CString;
+use std::fmt;
+use std::path::PathBuf;
+use std::ptr;
+
+#[derive(Debug)]
+pub enum ShaderError {
+    // A stage failed to compile; `shader_type` is the GL enum it was created
+    // with (gl::VERTEX_SHADER, gl::FRAGMENT_SHADER)
+    Compile {
+        shader_type: gl::types::GLenum,
+        log: String,
+    },
+    Link {
+        log: String,
+    },
+    // A shader source file couldn't be read
+    Read {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+}
+
+impl fmt::Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShaderError::Compile { shader_type, log } => {
+                let stage = match *shader_type {
+                    gl::VERTEX_SHADER => "vertex",
+                    gl::FRAGMENT_SHADER => "fragment",
+                    gl::GEOMETRY_SHADER => "geometry",
+                    _ => "unknown",
+                };
+                write!(f, "{stage} shader compilation failed: {}", log.trim_end())
+            }
+            ShaderError::Link { log } => write!(f, "program linking failed: {}", log.trim_end()),
+            ShaderError::Read { path, error } => {
+                write!(f, "couldn't read {}: {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ShaderError {}
+
+pub(crate) unsafe fn compile_shader(src: &str, ty: gl::types::GLenum) -> Result<u32, ShaderError> {
+    unsafe {
+        let c_str = CString::new(src.as_bytes()).map_err(|_| ShaderError::Compile {
+            shader_type: ty,
+            log: "source contains a NUL byte".to_string(),
+        })?;
+        let shader = gl::CreateShader(ty);
+        gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        // Check for compilation errors
+        let mut success = 0;
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+        if success == 0 {
+            let mut len = 0;
+            gl::GetShaderiv(shader, gl::INFO_LOG_LENGTH, &mut len);
+            // The length counts the terminating NUL, which GL writes too
+            let mut buf = vec![0u8; len.max(1) as usize];
+            gl::GetShaderInfoLog(
+                shader,
+                buf.len() as i32,
+                ptr::null_mut(),
+                buf.as_mut_ptr() as *mut _,
+            );
+            gl::DeleteShader(shader);
+            return Err(ShaderError::Compile {
+                shader_type: ty,
+                log: info_log(&buf),
+            });
+        }
+
+        Ok(shader)
+    }
+}
+
+// Links the two stages into a program. The shaders are deleted either way.
+pub(crate) unsafe fn link_program(
+    vertex_shader: u32,
+    fragment_shader: u32,
+) -> Result<u32, ShaderError> {
+    unsafe {
+        let program = gl::CreateProgram();
+        gl::AttachShader(program, vertex_shader);
+        gl::AttachShader(program, fragment_shader);
+        gl::LinkProgram(program);
+
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(fragment_shader);
+
+        // Check for linking errors
+        let mut success = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+        if success == 0 {
+            let mut len = 0;
+            gl::GetProgramiv(program, gl::INFO_LOG_LENGTH, &mut len);
+            // The length counts the terminating NUL, which GL writes too
+            let mut buf = vec![0u8; len.max(1) as usize];
+            gl::GetProgramInfoLog(
+                program,
+                buf.len() as i32,
+                ptr::null_mut(),
+                buf.as_mut_ptr() as *mut _,
+            );
+            gl::DeleteProgram(program);
+            return Err(ShaderError::Link {
+                log: info_log(&buf),
+            });
+        }
+
+        Ok(program)
+    }
+}
+
+// The text of an info log buffer, up to the NUL terminator
+fn info_log(buf: &[u8]) -> String {
+    let end = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..end]).into_owned()
+}
+
+// Compiles and links a vertex/fragment pair, cleaning up whatever was
+// created if any step fails
+pub(crate) unsafe fn build_program(
+    vertex_source: &str,
+    fragment_source: &str,
+) -> Result<u32, ShaderError> {
+    unsafe {
+        let vertex_shader = compile_shader(vertex_source, gl::VERTEX_SHADER)?;
+        let fragment_shader = compile_shader(fragment_source, gl::FRAGMENT_SHADER)
+            .inspect_err(|_| gl::DeleteShader(vertex_shader))?;
+        link_program(vertex_shader, fragment_shader)
+    }
+}
+
+// Vertex shaders that draw Mesh geometry get the morph target code spliced in
+// after their #version line, so they can call applyMorphTargets
+pub(crate) fn with_morph_targets(source: &str) -> String {
+    splice(source, include_str!("shaders/morph.glsl"))
+}
+
+// Fragment shaders that fade into distance fog get its uniforms and
+// fogFactor the same way
+pub(crate) fn with_fog(source: &str) -> String {
+    splice(source, include_str!("shaders/fog.glsl"))
+}
+
+fn splice(source: &str, code: &str) -> String {
+    let (version, body) = source.split_once('\n').unwrap_or((source, ""));
+    format!("{version}\n{code}\n{body}")
+}
+
+// Locations of the built-in lit program's per-frame and per-draw uniforms,
+// looked up once after each link instead of by name every frame. -1 marks a
+// uniform the compiler optimized out.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Uniforms {
+    pub(crate) model: i32,
+    pub(crate) normal_matrix: i32,
+    pub(crate) view: i32,
+    pub(crate) projection: i32,
+    pub(crate) two_sided_lighting: i32,
+    pub(crate) alpha: i32,
+    pub(crate) light_count: i32,
+    pub(crate) light_positions: i32,
+    pub(crate) light_colors: i32,
+    pub(crate) view_pos: i32,
+    pub(crate) lighting_model: i32,
+    pub(crate) toon_bands: i32,
+    pub(crate) flip_normals: i32,
+    pub(crate) ambient_strength: i32,
+    pub(crate) exposure: i32,
+    pub(crate) srgb_textures: i32,
+    pub(crate) use_texture: i32,
+    pub(crate) diffuse_texture: i32,
+    pub(crate) shadows_enabled: i32,
+    pub(crate) shadow_map: i32,
+    pub(crate) light_space_matrix: i32,
+    pub(crate) shadow_bias: i32,
+    pub(crate) shadow_normal_bias: i32,
+    pub(crate) shadow_slope_bias: i32,
+    pub(crate) pcf_radius: i32,
+    pub(crate) pcss_enabled: i32,
+    pub(crate) pcss_scale: i32,
+    pub(crate) pcss_samples: i32,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_bands: i32,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_level: i32,
+}
+
+impl Uniforms {
+    pub(crate) fn new(program: u32) -> Self {
+        let location =
+            |name: &std::ffi::CStr| unsafe { gl::GetUniformLocation(program, name.as_ptr()) };
+        Uniforms {
+            model: location(c"model"),
+            normal_matrix: location(c"normalMatrix"),
+            view: location(c"view"),
+            projection: location(c"projection"),
+            two_sided_lighting: location(c"twoSidedLighting"),
+            alpha: location(c"materialAlpha"),
+            light_count: location(c"lightCount"),
+            light_positions: location(c"lightPositions"),
+            light_colors: location(c"lightColors"),
+            view_pos: location(c"viewPos"),
+            lighting_model: location(c"lightingModel"),
+            toon_bands: location(c"toonBands"),
+            flip_normals: location(c"flipNormals"),
+            ambient_strength: location(c"ambientStrength"),
+            exposure: location(c"exposure"),
+            srgb_textures: location(c"srgbTextures"),
+            use_texture: location(c"useTexture"),
+            diffuse_texture: location(c"diffuseTexture"),
+            shadows_enabled: location(c"shadowsEnabled"),
+            shadow_map: location(c"shadowMap"),
+            light_space_matrix: location(c"lightSpaceMatrix"),
+            shadow_bias: location(c"shadowBias"),
+            shadow_normal_bias: location(c"shadowNormalBias"),
+            shadow_slope_bias: location(c"shadowSlopeBias"),
+            pcf_radius: location(c"pcfRadius"),
+            pcss_enabled: location(c"pcssEnabled"),
+            pcss_scale: location(c"pcssScale"),
+            pcss_samples: location(c"pcssSamples"),
+            #[cfg(feature = "audio")]
+            audio_bands: location(c"audioBands"),
+            #[cfg(feature = "audio")]
+            audio_level: location(c"audioLevel"),
+        }
+    }
+
+    // Uploads `model` and the normal matrix derived from it
+    pub(crate) fn set_model(&self, model: &glm::Mat4) {
+        set_mat4(self.model, model);
+        set_mat3(self.normal_matrix, &crate::transform::normal_matrix(model));
+    }
+}
+
+// Uploads to the current program, skipping uniforms that were optimized out
+pub(crate) fn set_mat4(location: i32, value: &glm::Mat4) {
+    if location != -1 {
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr()) };
+    }
+}
+
+pub(crate) fn set_mat3(location: i32, value: &glm::Mat3) {
+    if location != -1 {
+        unsafe { gl::UniformMatrix3fv(location, 1, gl::FALSE, value.as_ptr()) };
+    }
+}
+
+pub(crate) fn set_int(location: i32, value: i32) {
+    if location != -1 {
+        unsafe { gl::Uniform1i(location, value) };
+    }
+}
+
+pub(crate) fn set_float(location: i32, value: f32) {
+    if location != -1 {
+        unsafe { gl::Uniform1f(location, value) };
+    }
+}
+
+pub(crate) fn set_vec3(location: i32, value: &glm::Vec3) {
+    if location != -1 {
+        unsafe { gl::Uniform3f(location, value.x, value.y, value.z) };
+    }
+}
+
+#[cfg(feature = "audio")]
+pub(crate) fn set_float_array(location: i32, values: &[f32]) {
+    if location != -1 && !values.is_empty() {
+        unsafe { gl::Uniform1fv(location, values.len() as i32, values.as_ptr()) };
+    }
+}
+
+// `values` holds three floats per array element
+pub(crate) fn set_vec3_array(location: i32, values: &[f32]) {
+    if location != -1 && values.len() >= 3 {
+        unsafe { gl::Uniform3fv(location, (values.len() / 3) as i32, values.as_ptr()) };
+    }
+}
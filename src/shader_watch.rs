@@ -0,0 +1,119 @@
+use crate::shader::{ShaderError, build_program, with_fog, with_morph_targets};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// How often the watched files' modification times are checked
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Where debug builds read the main shaders from by default: the crate's own
+// sources, so edits show up without rebuilding. Builds running away from the
+// source tree fall back to the copies embedded at compile time, which release
+// builds always use until set_paths is called.
+const DEFAULT_VERTEX_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/vertex.glsl");
+const DEFAULT_FRAGMENT_PATH: &str =
+    concat!(env!("CARGO_MANIFEST_DIR"), "/src/shaders/fragment.glsl");
+const EMBEDDED_VERTEX: &str = include_str!("shaders/vertex.glsl");
+const EMBEDDED_FRAGMENT: &str = include_str!("shaders/fragment.glsl");
+
+// A shader source file and its modification time when last read
+struct WatchedFile {
+    path: PathBuf,
+    // None if the file couldn't be read
+    modified: Option<SystemTime>,
+    // Used when the file can't be read; only the default paths have one, so
+    // a mistyped user path is an error rather than a silent fallback
+    embedded: Option<&'static str>,
+}
+
+impl WatchedFile {
+    fn new(path: PathBuf, embedded: Option<&'static str>) -> Self {
+        WatchedFile {
+            path,
+            modified: None,
+            embedded,
+        }
+    }
+
+    fn current_modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    // The file's text, or the embedded copy if it can't be read
+    fn read(&mut self) -> Result<String, ShaderError> {
+        self.modified = self.current_modified();
+        std::fs::read_to_string(&self.path).or_else(|error| {
+            self.modified = None;
+            self.embedded
+                .map(str::to_string)
+                .ok_or_else(|| ShaderError::Read {
+                    path: self.path.clone(),
+                    error,
+                })
+        })
+    }
+}
+
+// Hot reloading for the main vertex/fragment program: the sources are read
+// from disk and rebuilt whenever either file changes
+pub(crate) struct ShaderWatch {
+    vertex: WatchedFile,
+    fragment: WatchedFile,
+    // False while release builds are on the default paths, which then aren't
+    // read at all
+    watching: bool,
+    last_check: Instant,
+}
+
+impl ShaderWatch {
+    pub(crate) fn new() -> Self {
+        ShaderWatch {
+            vertex: WatchedFile::new(DEFAULT_VERTEX_PATH.into(), Some(EMBEDDED_VERTEX)),
+            fragment: WatchedFile::new(DEFAULT_FRAGMENT_PATH.into(), Some(EMBEDDED_FRAGMENT)),
+            watching: cfg!(debug_assertions),
+            last_check: Instant::now(),
+        }
+    }
+
+    // Both files must be readable; the current paths are kept otherwise
+    pub(crate) fn set_paths(&mut self, vertex: &Path, fragment: &Path) -> Result<(), ShaderError> {
+        let mut vertex = WatchedFile::new(vertex.to_path_buf(), None);
+        let mut fragment = WatchedFile::new(fragment.to_path_buf(), None);
+        vertex.read()?;
+        fragment.read()?;
+        self.vertex = vertex;
+        self.fragment = fragment;
+        self.watching = true;
+        Ok(())
+    }
+
+    pub(crate) fn paths(&self) -> (&Path, &Path) {
+        (&self.vertex.path, &self.fragment.path)
+    }
+
+    // Compiles and links the current sources, remembering their modification
+    // times
+    pub(crate) fn build(&mut self) -> Result<u32, ShaderError> {
+        let (vertex, fragment) = if self.watching {
+            (self.vertex.read()?, self.fragment.read()?)
+        } else {
+            (EMBEDDED_VERTEX.to_string(), EMBEDDED_FRAGMENT.to_string())
+        };
+        unsafe { build_program(&with_morph_targets(&vertex), &with_fog(&fragment)) }
+    }
+
+    // At most once per CHECK_INTERVAL, rebuilds the program if either file's
+    // modification time has changed. None when nothing needed rebuilding.
+    pub(crate) fn poll(&mut self) -> Option<Result<u32, ShaderError>> {
+        if !self.watching || self.last_check.elapsed() < CHECK_INTERVAL {
+            return None;
+        }
+        self.last_check = Instant::now();
+        let changed = [&self.vertex, &self.fragment].iter().any(|file| {
+            file.current_modified()
+                .is_some_and(|m| Some(m) != file.modified)
+        });
+        changed.then(|| self.build())
+    }
+}
@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D scene;
+// Brightness where pixels start to glow, and the width of the soft knee
+uniform float threshold;
+uniform float knee;
+
+void main()
+{
+    vec3 color = texture(scene, TexCoords).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    // Ramp in over [threshold - knee, threshold + knee] instead of a hard cut
+    float weight = smoothstep(threshold - knee, threshold + knee, brightness);
+    FragColor = vec4(color * weight, 1.0);
+}
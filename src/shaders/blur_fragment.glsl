@@ -0,0 +1,22 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D image;
+// One texel along the blur axis
+uniform vec2 direction;
+
+// 9-tap Gaussian folded into 5 linearly filtered fetches
+const float offsets[3] = float[3](0.0, 1.3846153846, 3.2307692308);
+const float weights[3] = float[3](0.2270270270, 0.3162162162, 0.0702702703);
+
+void main()
+{
+    vec3 color = texture(image, TexCoords).rgb * weights[0];
+    for (int i = 1; i < 3; ++i) {
+        color += texture(image, TexCoords + direction * offsets[i]).rgb * weights[i];
+        color += texture(image, TexCoords - direction * offsets[i]).rgb * weights[i];
+    }
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,48 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 Normal;
+in vec3 FragPos;
+in float Scalar;
+
+uniform vec3 lightPos;
+uniform float scalarMin;
+uniform float scalarMax;
+
+// 0 = viridis, 1 = jet, 2 = grayscale
+uniform int colormap;
+
+// Polynomial fit of matplotlib's viridis
+vec3 viridis(float t)
+{
+    const vec3 c0 = vec3(0.27773, 0.00541, 0.33410);
+    const vec3 c1 = vec3(0.10509, 1.40461, 1.38459);
+    const vec3 c2 = vec3(-0.33086, 0.21485, 0.09510);
+    const vec3 c3 = vec3(-4.63423, -5.79910, -19.33244);
+    const vec3 c4 = vec3(6.22827, 14.17993, 56.69055);
+    const vec3 c5 = vec3(4.77638, -13.74515, -65.35303);
+    const vec3 c6 = vec3(-5.43546, 4.64585, 26.31244);
+    return c0 + t * (c1 + t * (c2 + t * (c3 + t * (c4 + t * (c5 + t * c6)))));
+}
+
+vec3 jet(float t)
+{
+    return clamp(vec3(1.5) - abs(4.0 * t - vec3(3.0, 2.0, 1.0)), 0.0, 1.0);
+}
+
+void main()
+{
+    float t = clamp((Scalar - scalarMin) / max(scalarMax - scalarMin, 1e-6), 0.0, 1.0);
+    vec3 color;
+    if (colormap == 0)
+        color = viridis(t);
+    else if (colormap == 1)
+        color = jet(t);
+    else
+        color = vec3(t);
+
+    // Mostly flat so values read true, with enough shading to show the shape
+    vec3 norm = normalize(Normal);
+    float diff = max(dot(norm, normalize(lightPos - FragPos)), 0.0);
+    FragColor = vec4(color * (0.6 + 0.4 * diff), 1.0);
+}
@@ -0,0 +1,24 @@
+#version 330 core
+// SCALAR_LOCATION is defined by the engine when it compiles this shader
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = SCALAR_LOCATION) in float aScalar;
+
+out vec3 Normal;
+out vec3 FragPos;
+out float Scalar;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = aNormal;
+    applyMorphTargets(position, normal);
+    FragPos = vec3(model * vec4(position, 1.0));
+    Normal = mat3(transpose(inverse(model))) * normal;
+    Scalar = aScalar;
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+}
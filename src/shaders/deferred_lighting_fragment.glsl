@@ -0,0 +1,60 @@
+#version 330 core
+#define MAX_LIGHTS 64
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D gAlbedo;
+uniform sampler2D gEmissive;
+uniform sampler2D gDepth;
+
+uniform int lightCount;
+uniform vec3 lightPositions[MAX_LIGHTS];
+// Already multiplied by intensity
+uniform vec3 lightColors[MAX_LIGHTS];
+
+uniform mat4 view;
+// Camera position in world space
+uniform vec3 viewPos;
+uniform float ambientStrength;
+uniform float exposure;
+
+void main()
+{
+    vec4 albedo = texture(gAlbedo, TexCoords);
+    if (albedo.a == 0.0)
+        discard;
+    gl_FragDepth = texture(gDepth, TexCoords).r;
+
+    // Material parameters ride in the alpha channels
+    vec4 position = texture(gPosition, TexCoords);
+    vec4 normal = texture(gNormal, TexCoords);
+    vec4 emissive = texture(gEmissive, TexCoords);
+    vec3 fragPos = position.xyz;
+    vec3 norm = normalize(normal.xyz);
+    float specularStrength = normal.a;
+    float shininess = emissive.a;
+
+    // Ambient
+    vec3 lighting = vec3(ambientStrength * position.a);
+
+    // Diffuse and Blinn-Phong specular from every light
+    vec3 viewDir = normalize(viewPos - fragPos);
+    vec3 specular = vec3(0.0);
+    for (int i = 0; i < lightCount; ++i) {
+        vec3 lightDir = normalize(lightPositions[i] - fragPos);
+        float diff = max(dot(norm, lightDir), 0.0);
+        lighting += diff * lightColors[i];
+        if (diff > 0.0) {
+            vec3 halfDir = normalize(lightDir + viewDir);
+            specular += pow(max(dot(norm, halfDir), 0.0), shininess) * specularStrength
+                * lightColors[i];
+        }
+    }
+
+    vec3 result = (lighting * albedo.rgb + specular + emissive.rgb) * exposure;
+    float dist = length((view * vec4(fragPos, 1.0)).xyz);
+    FragColor = vec4(mix(result, fogColor, fogFactor(dist)), 1.0);
+}
@@ -0,0 +1,60 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// View-space normal in rgb, view distance in a (0 = background)
+uniform sampler2D normalDepth;
+uniform vec2 texelSize;
+uniform vec3 edgeColor;
+// Distance between samples in pixels
+uniform float thickness;
+// Relative depth step counted as an edge
+uniform float depthThreshold;
+// 1 - cos(angle) between normals counted as a crease
+uniform float normalThreshold;
+
+// Background sits far behind everything so silhouettes always register
+const float BACKGROUND_DEPTH = 1e4;
+
+vec4 fetch(int x, int y)
+{
+    vec4 s = texture(normalDepth, TexCoords + vec2(x, y) * texelSize * thickness);
+    if (s.a <= 0.0)
+        s.a = BACKGROUND_DEPTH;
+    return s;
+}
+
+void main()
+{
+    // 3x3 neighborhood, rows from the bottom
+    vec4 s[9];
+    float nearest = BACKGROUND_DEPTH;
+    for (int y = 0; y < 3; y++) {
+        for (int x = 0; x < 3; x++) {
+            s[y * 3 + x] = fetch(x - 1, y - 1);
+            nearest = min(nearest, s[y * 3 + x].a);
+        }
+    }
+
+    // Sobel kernels; a step of size d between two regions gives a response of 4d
+    vec4 gx = (s[2] + 2.0 * s[5] + s[8]) - (s[0] + 2.0 * s[3] + s[6]);
+    vec4 gy = (s[6] + 2.0 * s[7] + s[8]) - (s[0] + 2.0 * s[1] + s[2]);
+
+    // Depth step relative to the nearest sample so the threshold holds at any distance
+    float depthEdge = length(vec2(gx.a, gy.a)) * 0.25 / nearest;
+
+    // For unit normals |n1 - n2|^2 = 2 (1 - cos(angle))
+    vec3 nx = gx.rgb * 0.25;
+    vec3 ny = gy.rgb * 0.25;
+    float normalEdge = 0.5 * max(dot(nx, nx), dot(ny, ny));
+
+    // Ramp in over half the threshold instead of a hard cut, which antialiases
+    // the lines along with the filtered sampling
+    float coverage = max(
+        smoothstep(0.5 * depthThreshold, depthThreshold, depthEdge),
+        smoothstep(0.5 * normalThreshold, normalThreshold, normalEdge));
+    if (coverage <= 0.0)
+        discard;
+    FragColor = vec4(edgeColor, coverage);
+}
@@ -0,0 +1,22 @@
+// Spliced in after #version by the engine; the uniforms are set by
+// fog::apply_uniforms.
+
+// Distance fog: 0 = off, 1 = linear, 2 = exponential, 3 = exponential squared
+uniform int fogMode;
+uniform vec3 fogColor;
+uniform float fogStart;
+uniform float fogEnd;
+uniform float fogDensity;
+
+// 0.0 for clear, 1.0 for fully fogged, at view-space distance `dist`
+float fogFactor(float dist)
+{
+    if (fogMode == 1)
+        return clamp((dist - fogStart) / (fogEnd - fogStart), 0.0, 1.0);
+    float d = max(dist - fogStart, 0.0) * fogDensity;
+    if (fogMode == 2)
+        return 1.0 - exp(-d);
+    if (fogMode == 3)
+        return 1.0 - exp(-d * d);
+    return 0.0;
+}
@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 FragColor;
+
+in float Height;
+in float Shade;
+in float ViewDistance;
+
+void main()
+{
+    // Darker at the root, lighter at the tip, with a little per-blade variation
+    vec3 root = vec3(0.05, 0.2, 0.03);
+    vec3 tip = mix(vec3(0.35, 0.65, 0.15), vec3(0.55, 0.75, 0.25), Shade);
+    vec3 color = mix(root, tip, Height);
+    FragColor = vec4(mix(color, fogColor, fogFactor(ViewDistance)), 1.0);
+}
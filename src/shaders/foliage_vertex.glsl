@@ -0,0 +1,32 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in mat4 aInstanceModel;
+
+out float Height;
+out float Shade;
+out float ViewDistance;
+
+uniform mat4 view;
+uniform mat4 projection;
+uniform float uTime;
+uniform float windStrength;
+uniform vec2 windDirection;
+
+void main()
+{
+    vec4 worldPos = aInstanceModel * vec4(aPos, 1.0);
+
+    // Phase varies across the field so gusts roll over the grass as a wave
+    float phase = dot(worldPos.xz, windDirection) * 1.5;
+    float gust = sin(uTime * 1.7 + phase) * 0.7 + sin(uTime * 3.1 + phase * 2.3) * 0.3;
+
+    // Sway grows with height so roots stay planted and tips bend the most
+    float bend = aPos.y * aPos.y;
+    worldPos.xz += windDirection * gust * windStrength * bend;
+
+    Height = aPos.y;
+    Shade = fract(sin(float(gl_InstanceID) * 12.9898) * 43758.5453);
+    vec4 viewPos = view * worldPos;
+    ViewDistance = length(viewPos.xyz);
+    gl_Position = projection * viewPos;
+}
@@ -1,23 +1,216 @@
 #version 330 core
+#define MAX_LIGHTS 8
 out vec4 FragColor;
 
 in vec3 Normal;
 in vec3 FragPos;
+in float ViewDistance;
+in vec2 TexCoords;
+in vec3 VertexColor;
+in vec4 Tangent;
 
-uniform vec3 lightPos;
+// Thin surfaces (leaves, cloth, paper): back faces are shaded with the
+// reversed normal, i.e. as the front face of the other side
+uniform bool twoSidedLighting;
+// Point lights, colors already multiplied by intensity. Only light 0 casts
+// shadows; with none, surfaces get just the ambient term.
+uniform int lightCount;
+uniform vec3 lightPositions[MAX_LIGHTS];
+uniform vec3 lightColors[MAX_LIGHTS];
+uniform float ambientStrength;
+uniform vec3 albedo;
+uniform vec3 emissive;
+// Scales ambientStrength for this surface
+uniform float materialAmbient;
+// Blinn-Phong highlights: strength (0 for none) and exponent
+uniform float specularStrength;
+uniform float shininess;
+// Camera position in world space
+uniform vec3 viewPos;
+// Multiplies albedo; meshes without UVs sample a single texel
+uniform bool useTexture;
+uniform sampler2D diffuseTexture;
+// The texture holds sRGB colors to decode first; set with gamma correction
+uniform bool srgbTextures;
+// Tangent-space normal map; only used on meshes with tangents
+uniform bool useNormalMap;
+uniform sampler2D normalMap;
+uniform float exposure;
+// Per-object highlight mixed over the lit color; strength 0 leaves it alone
+uniform vec3 tint;
+uniform float tintStrength;
+// Output alpha; below 1.0 only in the transparent pass, which blends
+uniform float materialAlpha;
+
+// 0 = Lambert, 1 = Toon
+uniform int lightingModel;
+uniform int toonBands;
+
+// Shadow mapping for the primary light
+uniform bool shadowsEnabled;
+uniform sampler2D shadowMap;
+uniform mat4 lightSpaceMatrix;
+uniform float shadowBias;
+uniform float shadowNormalBias;
+uniform float shadowSlopeBias;
+uniform int pcfRadius;
+// Contact-hardening soft shadows; pcssScale converts a light-space depth
+// difference to a penumbra width in shadow-map UV
+uniform bool pcssEnabled;
+uniform float pcssScale;
+uniform int pcssSamples;
+
+// Audio-reactive input (zero unless an audio source is set)
+uniform float audioBands[8];
+uniform float audioLevel;
+
+// Point i of n on a golden-angle spiral filling the unit disk, turned by `angle`
+vec2 vogelDisk(int i, int n, float angle)
+{
+    float r = sqrt((float(i) + 0.5) / float(n));
+    float theta = float(i) * 2.39996323 + angle;
+    return r * vec2(cos(theta), sin(theta));
+}
+
+// PCSS: average the depth of blockers in a search area that could shadow
+// this point, size the penumbra from the blocker-receiver distance, then
+// filter over it. The disk is rotated per pixel to turn banding into noise.
+float softShadow(vec3 projCoords, float bias)
+{
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0));
+    float maxRadius = 64.0 * texelSize.x;
+    float receiver = projCoords.z - bias;
+    float angle = 6.2831853
+        * fract(52.9829189 * fract(dot(gl_FragCoord.xy, vec2(0.06711056, 0.00583715))));
+
+    float searchRadius = clamp(pcssScale * receiver, texelSize.x, maxRadius);
+    float blockerSum = 0.0;
+    int blockers = 0;
+    for (int i = 0; i < pcssSamples; ++i) {
+        vec2 offset = vogelDisk(i, pcssSamples, angle) * searchRadius;
+        float depth = texture(shadowMap, projCoords.xy + offset).r;
+        if (depth < receiver) {
+            blockerSum += depth;
+            ++blockers;
+        }
+    }
+    if (blockers == 0)
+        return 0.0;
+
+    float blocker = blockerSum / float(blockers);
+    float penumbra = clamp(pcssScale * (receiver - blocker), texelSize.x, maxRadius);
+    float shadow = 0.0;
+    for (int i = 0; i < pcssSamples; ++i) {
+        vec2 offset = vogelDisk(i, pcssSamples, angle) * penumbra;
+        shadow += receiver > texture(shadowMap, projCoords.xy + offset).r ? 1.0 : 0.0;
+    }
+    return shadow / float(pcssSamples);
+}
+
+// Returns 1.0 for fully shadowed, 0.0 for fully lit
+float shadowFactor(vec3 norm, vec3 lightDir)
+{
+    float cosTheta = clamp(dot(norm, lightDir), 0.0, 1.0);
+
+    // Push the lookup position off the surface, more so at grazing angles
+    vec3 offsetPos = FragPos + norm * shadowNormalBias * (1.0 - cosTheta);
+    vec4 lightSpacePos = lightSpaceMatrix * vec4(offsetPos, 1.0);
+    vec3 projCoords = lightSpacePos.xyz / lightSpacePos.w * 0.5 + 0.5;
+    if (projCoords.z > 1.0)
+        return 0.0;
+
+    // Slope-scaled bias: tan(theta), clamped so near-parallel surfaces don't explode
+    float slope = clamp(sqrt(1.0 - cosTheta * cosTheta) / max(cosTheta, 1e-4), 0.0, 10.0);
+    float bias = shadowBias + shadowSlopeBias * slope;
+    if (pcssEnabled)
+        return softShadow(projCoords, bias);
+
+    float shadow = 0.0;
+    vec2 texelSize = 1.0 / vec2(textureSize(shadowMap, 0));
+    for (int x = -pcfRadius; x <= pcfRadius; ++x) {
+        for (int y = -pcfRadius; y <= pcfRadius; ++y) {
+            float closest = texture(shadowMap, projCoords.xy + vec2(x, y) * texelSize).r;
+            shadow += projCoords.z - bias > closest ? 1.0 : 0.0;
+        }
+    }
+    float kernel = float(2 * pcfRadius + 1);
+    return shadow / (kernel * kernel);
+}
+
+// Toon shading snaps diffuse terms into flat bands for a cel-shaded look
+float quantize(float diff)
+{
+    if (lightingModel != 1)
+        return diff;
+    float bands = float(max(toonBands, 1));
+    return ceil(diff * bands) / bands;
+}
+
+// Inverse of the sRGB transfer curve
+vec3 srgbToLinear(vec3 color)
+{
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+// The interpolated normal bent by the normal map, whose texels are
+// tangent-space directions turned into world space by the TBN frame
+vec3 mappedNormal(vec3 normal)
+{
+    if (!useNormalMap || dot(Tangent.xyz, Tangent.xyz) < 1e-12)
+        return normal;
+    vec3 tangent = normalize(Tangent.xyz - normal * dot(normal, Tangent.xyz));
+    vec3 bitangent = cross(normal, tangent) * Tangent.w;
+    vec3 texel = texture(normalMap, TexCoords).rgb * 2.0 - 1.0;
+    return normalize(mat3(tangent, bitangent, normal) * texel);
+}
 
 void main()
 {
     // Ambient
-    float ambientStrength = 0.1;
-    vec3 ambient = ambientStrength * vec3(1.0, 1.0, 1.0);
+    vec3 ambient = ambientStrength * materialAmbient * vec3(1.0, 1.0, 1.0);
+
+    // Diffuse and specular. Shadow lookups keep the geometric normal, since
+    // the bias is about the actual surface.
+    vec3 geometric = normalize(Normal);
+    vec3 norm = mappedNormal(geometric);
+    if (twoSidedLighting && !gl_FrontFacing) {
+        geometric = -geometric;
+        norm = -norm;
+    }
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec3 diffuse = vec3(0.0);
+    vec3 specular = vec3(0.0);
+    for (int i = 0; i < lightCount; ++i) {
+        vec3 lightDir = normalize(lightPositions[i] - FragPos);
+        float diff = max(dot(norm, lightDir), 0.0);
+        // Strongest where the normal lines up with the half-way vector
+        // between the light and the eye; none on faces turned from the light
+        vec3 halfDir = normalize(lightDir + viewDir);
+        float spec = diff > 0.0 ? pow(max(dot(norm, halfDir), 0.0), shininess) : 0.0;
+        if (lightingModel == 1)
+            spec = step(0.5, spec);
+        if (i == 0 && shadowsEnabled) {
+            float lit = 1.0 - shadowFactor(geometric, lightDir);
+            diff *= lit;
+            spec *= lit;
+        }
+        diffuse += quantize(diff) * lightColors[i];
+        specular += spec * specularStrength * lightColors[i];
+    }
 
-    // Diffuse
-    vec3 norm = normalize(Normal);
-    vec3 lightDir = normalize(lightPos - FragPos);
-    float diff = max(dot(norm, lightDir), 0.0);
-    vec3 diffuse = diff * vec3(1.0, 1.0, 1.0);
+    vec3 surface = albedo * VertexColor;
+    if (useTexture) {
+        vec3 texel = texture(diffuseTexture, TexCoords).rgb;
+        surface *= srgbTextures ? srgbToLinear(texel) : texel;
+    }
+    vec3 result = (ambient + diffuse) * surface + specular;
 
-    vec3 result = (ambient + diffuse) * vec3(0.5, 0.8, 1.0);
-    FragColor = vec4(result, 1.0);
+    // Pulse with the music: overall brightness follows loudness, bass warms the tint
+    result *= 1.0 + audioLevel * 0.6;
+    result += vec3(0.4, 0.1, 0.0) * audioBands[0] * 0.5;
+    result += emissive;
+    result = mix(result, tint, tintStrength);
+    result *= exposure;
+    result = mix(result, fogColor, fogFactor(ViewDistance));
+    FragColor = vec4(result, materialAlpha);
 }
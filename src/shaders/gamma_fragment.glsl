@@ -0,0 +1,21 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// Copy of the finished frame in linear color
+uniform sampler2D scene;
+
+// The sRGB transfer curve, matching what an sRGB framebuffer applies
+vec3 linearToSrgb(vec3 color)
+{
+    color = clamp(color, 0.0, 1.0);
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055,
+               step(0.0031308, color));
+}
+
+void main()
+{
+    vec4 color = texture(scene, TexCoords);
+    FragColor = vec4(linearToSrgb(color.rgb), color.a);
+}
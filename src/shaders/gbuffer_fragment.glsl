@@ -0,0 +1,68 @@
+#version 330 core
+// The alpha channels carry the material's ambient share, specular strength
+// and shininess
+layout (location = 0) out vec4 gPosition;
+layout (location = 1) out vec4 gNormal;
+layout (location = 2) out vec4 gAlbedo;
+layout (location = 3) out vec4 gEmissive;
+
+in vec3 Normal;
+in vec3 FragPos;
+in vec2 TexCoords;
+in vec3 VertexColor;
+in vec4 Tangent;
+
+uniform vec3 albedo;
+uniform vec3 emissive;
+uniform float materialAmbient;
+uniform float specularStrength;
+uniform float shininess;
+uniform bool useTexture;
+uniform sampler2D diffuseTexture;
+// The texture holds sRGB colors to decode first; set with gamma correction
+uniform bool srgbTextures;
+// Tangent-space normal map; only used on meshes with tangents
+uniform bool useNormalMap;
+uniform sampler2D normalMap;
+// Per-object highlight; the lighting pass has no per-object state, so the
+// surface is faded and the tint carried as emission, which matches the
+// forward mix apart from highlights
+uniform vec3 tint;
+uniform float tintStrength;
+// Shade back faces with the reversed normal, for thin surfaces
+uniform bool twoSidedLighting;
+
+// Inverse of the sRGB transfer curve
+vec3 srgbToLinear(vec3 color)
+{
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+// The interpolated normal bent by the normal map, whose texels are
+// tangent-space directions turned into world space by the TBN frame
+vec3 mappedNormal(vec3 normal)
+{
+    if (!useNormalMap || dot(Tangent.xyz, Tangent.xyz) < 1e-12)
+        return normal;
+    vec3 tangent = normalize(Tangent.xyz - normal * dot(normal, Tangent.xyz));
+    vec3 bitangent = cross(normal, tangent) * Tangent.w;
+    vec3 texel = texture(normalMap, TexCoords).rgb * 2.0 - 1.0;
+    return normalize(mat3(tangent, bitangent, normal) * texel);
+}
+
+void main()
+{
+    vec3 norm = mappedNormal(normalize(Normal));
+    if (twoSidedLighting && !gl_FrontFacing)
+        norm = -norm;
+    gPosition = vec4(FragPos, materialAmbient);
+    gNormal = vec4(norm, specularStrength);
+    // Alpha marks covered pixels so the lighting pass can leave the background alone
+    vec3 surface = albedo * VertexColor;
+    if (useTexture) {
+        vec3 texel = texture(diffuseTexture, TexCoords).rgb;
+        surface *= srgbTextures ? srgbToLinear(texel) : texel;
+    }
+    gAlbedo = vec4(surface * (1.0 - tintStrength), 1.0);
+    gEmissive = vec4(mix(emissive, tint, tintStrength), shininess);
+}
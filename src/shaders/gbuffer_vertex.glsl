@@ -0,0 +1,40 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in vec2 aTexCoord;
+layout (location = 3) in vec3 aColor;
+// Tangent and bitangent sign for normal mapping; zero without UVs
+layout (location = 11) in vec4 aTangent;
+// Per-instance model matrix; the identity outside instanced draws
+layout (location = 12) in mat4 aInstance;
+
+out vec3 Normal;
+out vec3 FragPos;
+out vec2 TexCoords;
+out vec3 VertexColor;
+out vec4 Tangent;
+
+uniform mat4 model;
+// Inverse transpose of model's 3x3 part, computed once per draw on the CPU
+uniform mat3 normalMatrix;
+uniform mat4 view;
+uniform mat4 projection;
+uniform bool flipNormals;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = aNormal;
+    applyMorphTargets(position, normal);
+    TexCoords = aTexCoord;
+    VertexColor = aColor;
+    mat4 world = model * aInstance;
+    FragPos = vec3(world * vec4(position, 1.0));
+    // Instances carry their own scale, so their part is still inverted here
+    Normal = normalMatrix * transpose(inverse(mat3(aInstance))) * normal;
+    if (flipNormals)
+        Normal = -Normal;
+    // Tangents lie along the surface, so they take the plain model transform
+    Tangent = vec4(mat3(world) * aTangent.xyz, aTangent.w);
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+}
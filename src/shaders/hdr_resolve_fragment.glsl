@@ -0,0 +1,11 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+uniform sampler2D scene;
+
+void main()
+{
+    FragColor = vec4(clamp(texture(scene, TexCoords).rgb, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+out float LogLuminance;
+
+in vec2 TexCoords;
+
+uniform sampler2D scene;
+
+void main()
+{
+    vec3 color = texture(scene, TexCoords).rgb;
+    float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    // Log space so the mipmap average gives the geometric mean, which a few
+    // very bright pixels can't dominate; the floor keeps black finite
+    LogLuminance = log(max(luminance, 1e-4));
+}
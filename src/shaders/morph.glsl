@@ -0,0 +1,17 @@
+// Morph targets (blend shapes), spliced in after #version by the engine. For
+// target i and vertex v, texels 2 * (i * morphVertexCount + v) and the one
+// after it hold the position and normal offsets.
+#define MAX_MORPH_TARGETS 8
+uniform int morphTargetCount;
+uniform int morphVertexCount;
+uniform float morphWeights[MAX_MORPH_TARGETS];
+uniform samplerBuffer morphDeltas;
+
+void applyMorphTargets(inout vec3 position, inout vec3 normal)
+{
+    for (int i = 0; i < morphTargetCount; ++i) {
+        int texel = 2 * (i * morphVertexCount + gl_VertexID);
+        position += morphWeights[i] * texelFetch(morphDeltas, texel).xyz;
+        normal += morphWeights[i] * texelFetch(morphDeltas, texel + 1).xyz;
+    }
+}
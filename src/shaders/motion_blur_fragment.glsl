@@ -0,0 +1,30 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// Copy of the frame and its per-pixel velocity (0 where nothing moved)
+uniform sampler2D scene;
+uniform sampler2D velocity;
+// Fraction of the frame interval the shutter is open
+uniform float strength;
+uniform int samples;
+// Longest streak, in texture units, so a camera cut doesn't smear the frame
+uniform float maxLength;
+
+void main()
+{
+    vec2 streak = texture(velocity, TexCoords).rg * strength;
+    float len = length(streak);
+    if (len > maxLength) {
+        streak *= maxLength / len;
+    }
+
+    // Average along the path the surface took, centered on this pixel
+    vec3 sum = vec3(0.0);
+    for (int i = 0; i < samples; ++i) {
+        float t = samples > 1 ? float(i) / float(samples - 1) - 0.5 : 0.0;
+        sum += texture(scene, TexCoords - streak * t).rgb;
+    }
+    FragColor = vec4(sum / float(samples), 1.0);
+}
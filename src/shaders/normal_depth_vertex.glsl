@@ -0,0 +1,23 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+
+out vec3 ViewNormal;
+out float ViewDepth;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = aNormal;
+    applyMorphTargets(position, normal);
+    mat4 modelView = view * model;
+    vec4 viewPos = modelView * vec4(position, 1.0);
+    ViewNormal = mat3(transpose(inverse(modelView))) * normal;
+    // Linear distance along the view axis, valid for both projections
+    ViewDepth = -viewPos.z;
+    gl_Position = projection * viewPos;
+}
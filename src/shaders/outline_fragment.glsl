@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 Normal;
+in vec3 FragPos;
+
+uniform vec3 viewPos;
+uniform vec3 outlineColor;
+
+void main()
+{
+    // Keep only the back of the hull so it reads as a silhouette around the mesh.
+    // Tested against the view direction so it works regardless of winding order.
+    if (dot(Normal, viewPos - FragPos) > 0.0)
+        discard;
+
+    FragColor = vec4(outlineColor, 1.0);
+}
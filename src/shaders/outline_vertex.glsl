@@ -0,0 +1,21 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+
+out vec3 Normal;
+out vec3 FragPos;
+
+uniform mat4 model;
+uniform mat4 view;
+uniform mat4 projection;
+uniform float outlineWidth;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = aNormal;
+    applyMorphTargets(position, normal);
+    Normal = normalize(mat3(transpose(inverse(model))) * normal);
+    FragPos = vec3(model * vec4(position, 1.0)) + Normal * outlineWidth;
+    gl_Position = projection * view * vec4(FragPos, 1.0);
+}
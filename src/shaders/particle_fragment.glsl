@@ -0,0 +1,15 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 Corner;
+in vec4 Color;
+
+void main()
+{
+    // Soft round sprite
+    float falloff = smoothstep(0.5, 0.0, length(Corner));
+    if (falloff <= 0.0)
+        discard;
+
+    FragColor = vec4(Color.rgb, Color.a * falloff);
+}
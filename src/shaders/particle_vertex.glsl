@@ -0,0 +1,22 @@
+#version 330 core
+layout (location = 0) in vec2 aCorner;
+layout (location = 1) in vec4 aCenterSize;
+layout (location = 2) in vec4 aColor;
+
+out vec2 Corner;
+out vec4 Color;
+
+uniform mat4 view;
+uniform mat4 projection;
+
+void main()
+{
+    // Camera right/up are the first two rows of the view matrix's rotation
+    vec3 right = vec3(view[0][0], view[1][0], view[2][0]);
+    vec3 up = vec3(view[0][1], view[1][1], view[2][1]);
+    vec3 worldPos = aCenterSize.xyz + (right * aCorner.x + up * aCorner.y) * aCenterSize.w;
+
+    Corner = aCorner;
+    Color = aColor;
+    gl_Position = projection * view * vec4(worldPos, 1.0);
+}
@@ -0,0 +1,31 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoords;
+
+// Low-resolution scene, sampled with nearest filtering
+uniform sampler2D scene;
+// Window pixels per scene pixel
+uniform float pixelSize;
+// Output levels per channel minus one (2^bits - 1); 0 disables quantization
+uniform float colorLevels;
+uniform bool dither;
+
+// 4x4 ordered-dither thresholds in [0, 1)
+float bayer4(ivec2 p)
+{
+    int index = (p.x & 3) + (p.y & 3) * 4;
+    int bayer[16] = int[16](0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5);
+    return (float(bayer[index]) + 0.5) / 16.0;
+}
+
+void main()
+{
+    vec3 color = texture(scene, TexCoords).rgb;
+    if (colorLevels > 0.0) {
+        // Dither per scene pixel so the pattern is as chunky as the pixels
+        float offset = dither ? bayer4(ivec2(gl_FragCoord.xy / pixelSize)) : 0.5;
+        color = floor(color * colorLevels + offset) / colorLevels;
+    }
+    FragColor = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,15 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+// Per-instance model matrix; the identity outside instanced draws
+layout (location = 12) in mat4 aInstance;
+
+uniform mat4 lightSpaceMatrix;
+uniform mat4 model;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = vec3(0.0);
+    applyMorphTargets(position, normal);
+    gl_Position = lightSpaceMatrix * model * aInstance * vec4(position, 1.0);
+}
@@ -0,0 +1,34 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 Direction;
+
+uniform samplerCube sky;
+uniform float exposure;
+// The faces hold sRGB colors to decode first; set with gamma correction
+uniform bool srgbTextures;
+
+// Height above the horizon (as the direction's y) where fog stops covering
+// the sky
+const float FOG_HORIZON_BAND = 0.25;
+
+// Inverse of the sRGB transfer curve
+vec3 srgbToLinear(vec3 color)
+{
+    return mix(color / 12.92, pow((color + 0.055) / 1.055, vec3(2.4)), step(0.04045, color));
+}
+
+void main()
+{
+    vec3 color = texture(sky, Direction).rgb;
+    if (srgbTextures)
+        color = srgbToLinear(color);
+    color *= exposure;
+    // Fogged geometry fades to fogColor, so the sky does too toward the
+    // horizon and below it
+    if (fogMode != 0) {
+        float height = normalize(Direction).y;
+        color = mix(color, fogColor, 1.0 - smoothstep(0.0, FOG_HORIZON_BAND, height));
+    }
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+out vec3 Direction;
+
+// View without its translation, so the box stays centered on the camera
+uniform mat4 view;
+uniform mat4 projection;
+
+void main()
+{
+    Direction = aPos;
+    vec4 position = projection * view * vec4(aPos, 1.0);
+    // z = w lands every vertex on the far plane, behind all geometry
+    gl_Position = position.xyww;
+}
@@ -0,0 +1,14 @@
+#version 330 core
+out vec2 Velocity;
+
+in vec4 CurrentClip;
+in vec4 PreviousClip;
+
+void main()
+{
+    // Screen movement since the previous frame in texture units (0-1 across
+    // the target); the divide happens per fragment so it stays perspective-correct
+    vec2 current = CurrentClip.xy / CurrentClip.w;
+    vec2 previous = PreviousClip.xy / PreviousClip.w;
+    Velocity = (current - previous) * 0.5;
+}
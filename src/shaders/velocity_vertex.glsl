@@ -0,0 +1,20 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+out vec4 CurrentClip;
+out vec4 PreviousClip;
+
+uniform mat4 model;
+uniform mat4 previousModel;
+uniform mat4 viewProjection;
+uniform mat4 previousViewProjection;
+
+void main()
+{
+    vec3 position = aPos;
+    vec3 normal = vec3(0.0);
+    applyMorphTargets(position, normal);
+    CurrentClip = viewProjection * model * vec4(position, 1.0);
+    PreviousClip = previousViewProjection * previousModel * vec4(position, 1.0);
+    gl_Position = CurrentClip;
+}
@@ -0,0 +1,217 @@
+use crate::render_target::FrameTarget;
+use crate::shader::{ShaderError, build_program, with_morph_targets};
+use glm::{Mat4, Vec3, vec3};
+use std::ptr;
+
+// Tunables for shadow-map sampling. Good values depend on scene scale: too
+// little bias causes acne (self-shadowing stripes), too much detaches shadows
+// from their casters (peter-panning).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShadowParams {
+    // Constant depth offset applied to every comparison
+    pub bias: f32,
+    // World-space offset along the surface normal before projecting into light space
+    pub normal_bias: f32,
+    // Extra depth offset scaled by the slope of the surface relative to the light
+    pub slope_bias: f32,
+    // PCF kernel width in texels (1 = hard shadows, 3 = 3x3, ...); must be odd
+    pub pcf_samples: u32,
+    // Contact-hardening soft shadows in place of the fixed PCF kernel
+    pub soft_shadows: Option<SoftShadows>,
+}
+
+impl Default for ShadowParams {
+    fn default() -> Self {
+        ShadowParams {
+            bias: 0.002,
+            normal_bias: 0.01,
+            slope_bias: 0.002,
+            pcf_samples: 3,
+            soft_shadows: None,
+        }
+    }
+}
+
+// Percentage-closer soft shadows (PCSS): a blocker search finds the average
+// depth of the occluders around each point, and the filter widens with the
+// distance from them to the receiver. Shadows are sharp where an object
+// touches the ground and blur out further away, as under a real area light.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoftShadows {
+    // Apparent size of the light: how much the penumbra widens per world unit
+    // between occluder and receiver, roughly its angular diameter in radians
+    pub light_size: f32,
+    // Taps for each of the blocker search and the filter; more trades speed
+    // for less noise in wide penumbrae
+    pub samples: u32,
+}
+
+impl Default for SoftShadows {
+    fn default() -> Self {
+        SoftShadows {
+            light_size: 0.05,
+            samples: 16,
+        }
+    }
+}
+
+impl SoftShadows {
+    pub const MAX_SAMPLES: u32 = 64;
+
+    // Penumbra width in shadow-map UV per unit of light-space depth
+    // difference, for a light_space_matrix of the given radius
+    pub(crate) fn pcss_scale(&self, radius: f32) -> f32 {
+        let (near, far) = light_depth_range(radius);
+        self.light_size.max(0.0) * (far - near) / (2.0 * radius)
+    }
+}
+
+// Near and far planes of the light's orthographic frustum
+fn light_depth_range(radius: f32) -> (f32, f32) {
+    (0.01, radius * 4.0)
+}
+
+impl ShadowParams {
+    // Largest accepted PCF kernel (7x7)
+    pub const MAX_PCF_SAMPLES: u32 = 7;
+
+    pub(crate) fn pcf_radius(&self) -> i32 {
+        let samples = self.pcf_samples.clamp(1, Self::MAX_PCF_SAMPLES);
+        // Even widths round down to the next odd kernel
+        ((samples - 1) / 2) as i32
+    }
+}
+
+// Depth-only render target for a single directional light
+pub(crate) struct ShadowMap {
+    fbo: u32,
+    depth_texture: u32,
+    program: u32,
+    size: i32,
+}
+
+impl ShadowMap {
+    pub(crate) fn new(size: i32) -> Result<Self, ShaderError> {
+        let program = unsafe {
+            build_program(
+                &with_morph_targets(include_str!("shaders/shadow_vertex.glsl")),
+                include_str!("shaders/shadow_fragment.glsl"),
+            )?
+        };
+
+        let (fbo, depth_texture) = unsafe {
+            let mut depth_texture = 0;
+            gl::GenTextures(1, &mut depth_texture);
+            gl::BindTexture(gl::TEXTURE_2D, depth_texture);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::DEPTH_COMPONENT24 as i32,
+                size,
+                size,
+                0,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                ptr::null(),
+            );
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            // Everything outside the light frustum counts as lit
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                gl::CLAMP_TO_BORDER as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                gl::CLAMP_TO_BORDER as i32,
+            );
+            let border = [1.0f32, 1.0, 1.0, 1.0];
+            gl::TexParameterfv(gl::TEXTURE_2D, gl::TEXTURE_BORDER_COLOR, border.as_ptr());
+            gl::BindTexture(gl::TEXTURE_2D, 0);
+
+            let mut fbo = 0;
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                gl::TEXTURE_2D,
+                depth_texture,
+                0,
+            );
+            gl::DrawBuffer(gl::NONE);
+            gl::ReadBuffer(gl::NONE);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+
+            (fbo, depth_texture)
+        };
+
+        Ok(ShadowMap {
+            fbo,
+            depth_texture,
+            program,
+            size,
+        })
+    }
+
+    // Orthographic projection looking from the light toward the scene center
+    pub(crate) fn light_space_matrix(light_pos: &Vec3, center: &Vec3, radius: f32) -> Mat4 {
+        let direction = (light_pos - center).normalize();
+        let eye = center + direction * radius * 2.0;
+        let up = if direction.y.abs() > 0.99 {
+            vec3(0.0, 0.0, 1.0)
+        } else {
+            vec3(0.0, 1.0, 0.0)
+        };
+        let view = glm::look_at(&eye, center, &up);
+        let (near, far) = light_depth_range(radius);
+        let projection = glm::ortho(-radius, radius, -radius, radius, near, far);
+        projection * view
+    }
+
+    // Binds the depth target and depth-only program; the caller draws casters
+    // with `set_model` and then calls `end`.
+    pub(crate) fn begin(&self, light_space: &Mat4) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.size, self.size);
+            gl::Disable(gl::SCISSOR_TEST);
+            gl::Clear(gl::DEPTH_BUFFER_BIT);
+            gl::UseProgram(self.program);
+            let loc = gl::GetUniformLocation(self.program, c"lightSpaceMatrix".as_ptr());
+            gl::UniformMatrix4fv(loc, 1, gl::FALSE, light_space.as_ptr());
+        }
+    }
+
+    pub(crate) fn set_model(&self, model: &Mat4) {
+        unsafe {
+            let loc = gl::GetUniformLocation(self.program, c"model".as_ptr());
+            gl::UniformMatrix4fv(loc, 1, gl::FALSE, model.as_ptr());
+        }
+    }
+
+    // Rebinds the frame target the scene is being drawn into
+    pub(crate) fn end(&self, target: &FrameTarget) {
+        target.bind();
+    }
+
+    pub(crate) fn bind_texture(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.depth_texture);
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+    }
+}
+
+impl Drop for ShadowMap {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteTextures(1, &self.depth_texture);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
@@ -0,0 +1,163 @@
+use crate::fog::{self, FogParams};
+use crate::image::{self, Image};
+use crate::mesh::Mesh;
+use crate::shader::{ShaderError, build_program, with_fog};
+use crate::texture::TextureError;
+use glm::Mat4;
+use std::path::Path;
+
+// Background drawn from six images on the faces of a cube around the camera,
+// so it turns with the view but never gets closer
+pub struct Skybox {
+    cubemap: u32,
+}
+
+impl Skybox {
+    // Reads the faces in GL's order: +X, -X, +Y, -Y, +Z, -Z (right, left,
+    // top, bottom, front, back), decoded like Texture::load. Each face is
+    // seen from inside the cube, with its top row up (the top face's top row
+    // toward -Z). Faces must be square and all the same size.
+    pub fn load<P: AsRef<Path>>(faces: [P; 6]) -> Result<Skybox, TextureError> {
+        let mut images = Vec::with_capacity(6);
+        for face in &faces {
+            let path = face.as_ref();
+            let data = std::fs::read(path)?;
+            let image = image::decode_bytes(&data)
+                .map_err(|err| TextureError::Decode(format!("{}: {}", path.display(), err)))?;
+            images.push(image);
+        }
+        let images: [Image; 6] = images.try_into().unwrap_or_else(|_| unreachable!());
+        Skybox::from_images(&images)
+    }
+
+    // Like `load`, from already decoded images
+    pub fn from_images(faces: &[Image; 6]) -> Result<Skybox, TextureError> {
+        let size = faces[0].width;
+        if faces
+            .iter()
+            .any(|face| face.width != size || face.height != size)
+        {
+            return Err(TextureError::Decode(
+                "skybox faces must be square and the same size".to_string(),
+            ));
+        }
+
+        let mut cubemap = 0;
+        unsafe {
+            gl::GenTextures(1, &mut cubemap);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, cubemap);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            for (i, face) in faces.iter().enumerate() {
+                // Cube map faces start at the top row, unlike 2D textures
+                let row = size as usize * 4;
+                let pixels: Vec<u8> = face
+                    .pixels
+                    .chunks_exact(row)
+                    .rev()
+                    .flatten()
+                    .copied()
+                    .collect();
+                gl::TexImage2D(
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
+                    0,
+                    gl::RGBA8 as i32,
+                    size as i32,
+                    size as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_ptr() as *const _,
+                );
+            }
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MIN_FILTER,
+                gl::LINEAR as i32,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_CUBE_MAP,
+                gl::TEXTURE_MAG_FILTER,
+                gl::LINEAR as i32,
+            );
+            // Edge clamping hides the seams between faces
+            for wrap in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl::TexParameteri(gl::TEXTURE_CUBE_MAP, wrap, gl::CLAMP_TO_EDGE as i32);
+            }
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+        }
+        Ok(Skybox { cubemap })
+    }
+}
+
+impl Drop for Skybox {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.cubemap);
+        }
+    }
+}
+
+pub(crate) struct SkyboxPass {
+    program: u32,
+    cube: Mesh,
+}
+
+impl SkyboxPass {
+    pub(crate) fn new() -> Result<Self, ShaderError> {
+        let program = unsafe {
+            build_program(
+                include_str!("shaders/skybox_vertex.glsl"),
+                &with_fog(include_str!("shaders/skybox_fragment.glsl")),
+            )?
+        };
+        let (vertices, indices) = crate::create_cube_indexed();
+        Ok(SkyboxPass {
+            program,
+            cube: Mesh::from_indexed(&vertices, &indices),
+        })
+    }
+
+    // Draws `skybox` wherever the depth buffer is still at the far plane, so
+    // call it after the opaque geometry. With `fog` the sky fades into its
+    // color at the horizon.
+    pub(crate) fn render(
+        &self,
+        skybox: &Skybox,
+        view: &Mat4,
+        projection: &Mat4,
+        exposure: f32,
+        srgb_textures: bool,
+        fog: Option<&FogParams>,
+    ) {
+        let rotation = glm::mat3_to_mat4(&glm::mat4_to_mat3(view));
+        unsafe {
+            gl::DepthFunc(gl::LEQUAL);
+            gl::DepthMask(gl::FALSE);
+            gl::UseProgram(self.program);
+            let location =
+                |name: &std::ffi::CStr| gl::GetUniformLocation(self.program, name.as_ptr());
+            gl::UniformMatrix4fv(location(c"view"), 1, gl::FALSE, rotation.as_ptr());
+            gl::UniformMatrix4fv(location(c"projection"), 1, gl::FALSE, projection.as_ptr());
+            gl::Uniform1f(location(c"exposure"), exposure);
+            gl::Uniform1i(location(c"srgbTextures"), srgb_textures as i32);
+            fog::apply_uniforms(self.program, fog);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, skybox.cubemap);
+            gl::Uniform1i(location(c"sky"), 0);
+        }
+        self.cube.draw();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_CUBE_MAP, 0);
+            gl::DepthMask(gl::TRUE);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+}
+
+impl Drop for SkyboxPass {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::image::Image;
+use crate::texture::Texture;
+use glm::{Vec3, vec3};
+
+// Edge length of the generated texture in pixels
+const SIZE: u32 = 512;
+
+// Tints for the UV quadrants 1-4: bottom-left, bottom-right, top-left, top-right
+const QUADRANT_TINTS: [[f32; 3]; 4] = [
+    [1.0, 0.55, 0.55],
+    [0.55, 1.0, 0.55],
+    [0.55, 0.7, 1.0],
+    [1.0, 1.0, 0.5],
+];
+
+// Procedural UV-check texture: a checkerboard with `tiles` squares along each
+// side, for spotting stretching and uneven texel density. Each UV quadrant can
+// be tinted and numbered 1-4 (1 at the UV origin, counting along u then v),
+// so flipped or rotated islands stand out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TestPattern {
+    pub tiles: u32,
+    pub colors: [Vec3; 2],
+    pub quadrants: bool,
+}
+
+impl Default for TestPattern {
+    fn default() -> Self {
+        TestPattern {
+            tiles: 8,
+            colors: [vec3(0.85, 0.85, 0.85), vec3(0.35, 0.35, 0.35)],
+            quadrants: true,
+        }
+    }
+}
+
+impl TestPattern {
+    // Renders the pattern into a size x size image (at least 32 pixels)
+    pub fn image(&self, size: u32) -> Image {
+        let size = size.max(32);
+        let tiles = self.tiles.max(1) as f32;
+        // Image pixels per font pixel, so a digit is about a third of its quadrant
+        let scale = (size / 48).max(1);
+        let (glyph_width, glyph_height) = (GLYPH_WIDTH as u32 * scale, GLYPH_HEIGHT as u32 * scale);
+
+        let mut pixels = Vec::with_capacity(size as usize * size as usize * 4);
+        // Rows are generated bottom first, matching v going up
+        for y in 0..size {
+            for x in 0..size {
+                let u = (x as f32 + 0.5) / size as f32;
+                let v = (y as f32 + 0.5) / size as f32;
+                let checker = ((u * tiles).floor() + (v * tiles).floor()) as u32 % 2;
+                let mut color = self.colors[checker as usize];
+
+                if self.quadrants {
+                    let (right, top) = (x >= size / 2, y >= size / 2);
+                    let quadrant = right as usize + 2 * top as usize;
+                    let [r, g, b] = QUADRANT_TINTS[quadrant];
+                    color = color.component_mul(&vec3(r, g, b));
+
+                    // Digit centered in the quadrant
+                    let left = size / 4 + right as u32 * size / 2 - glyph_width / 2;
+                    let bottom = size / 4 + top as u32 * size / 2 - glyph_height / 2;
+                    if (left..left + glyph_width).contains(&x)
+                        && (bottom..bottom + glyph_height).contains(&y)
+                    {
+                        let column = (x - left) / scale;
+                        let row = GLYPH_HEIGHT as u32 - 1 - (y - bottom) / scale;
+                        let digit = char::from(b'1' + quadrant as u8);
+                        let bits = font::glyph(digit)[row as usize];
+                        if bits & (1 << (GLYPH_WIDTH as u32 - 1 - column)) != 0 {
+                            color = vec3(0.05, 0.05, 0.05);
+                        }
+                    }
+                }
+
+                let byte = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+                pixels.extend_from_slice(&[byte(color.x), byte(color.y), byte(color.z), 255]);
+            }
+        }
+        Image {
+            width: size,
+            height: size,
+            pixels,
+        }
+    }
+
+    pub fn texture(&self) -> Texture {
+        let image = self.image(SIZE);
+        Texture::from_rgba(image.width, image.height, &image.pixels)
+    }
+}
@@ -0,0 +1,77 @@
+use crate::font;
+use crate::lines::LineRenderer;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec2, Vec3, vec2, vec3};
+
+// One string queued with X3D::draw_text
+struct QueuedText {
+    // Top-left corner in pixels from the top-left of the frame
+    position: Vec2,
+    text: String,
+    scale: f32,
+    color: Vec3,
+}
+
+// Screen-space text in the built-in bitmap font, queued during a frame and
+// drawn once over the finished image, e.g. frame rates and instructions.
+// Like DebugDraw the queue only lasts one frame.
+#[derive(Default)]
+pub(crate) struct TextRenderer {
+    queue: Vec<QueuedText>,
+}
+
+impl TextRenderer {
+    pub(crate) fn queue(&mut self, x: f32, y: f32, text: &str, scale: f32, color: Vec3) {
+        if text.is_empty() || scale <= 0.0 {
+            return;
+        }
+        self.queue.push(QueuedText {
+            position: vec2(x, y),
+            text: text.to_string(),
+            scale,
+            color,
+        });
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    // Draws the queue into `target` through an orthographic pixel mapping,
+    // without depth testing so nothing in the scene hides it
+    pub(crate) fn render(&self, lines: &mut LineRenderer, target: &FrameTarget) {
+        if self.queue.is_empty() {
+            return;
+        }
+        target.bind();
+        let size = vec2(target.width.max(1) as f32, target.height.max(1) as f32);
+        let projection = glm::ortho(0.0, size.x, 0.0, size.y, -1.0, 1.0);
+        let at = |p: Vec2| vec3(p.x, p.y, 0.0);
+        for queued in &self.queue {
+            // font::queue_text counts y up from the bottom
+            let origin = vec2(queued.position.x, size.y - queued.position.y);
+            font::queue_text(
+                &queued.text,
+                &origin,
+                queued.scale,
+                &queued.color,
+                lines,
+                &at,
+            );
+        }
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+        lines.flush(&Mat4::identity(), &projection);
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+}
+
+// Size in pixels `text` takes up when drawn at `scale`
+pub(crate) fn text_size(text: &str, scale: f32) -> Vec2 {
+    let (width, height) = font::text_size(text);
+    vec2(width as f32, height as f32) * scale
+}
@@ -0,0 +1,351 @@
+use crate::image::{self, Image, ImageDecoder};
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+
+// EXT_texture_filter_anisotropic / GL 4.6 tokens, not exposed by the gl crate's 4.5 bindings
+const TEXTURE_MAX_ANISOTROPY: gl::types::GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: gl::types::GLenum = 0x84FF;
+
+// Unit the built-in shaders read the surface (diffuse) texture from
+pub(crate) const DIFFUSE_TEXTURE_UNIT: u32 = 0;
+// Unit for a material's normal map, after the shadow map's
+pub(crate) const NORMAL_MAP_TEXTURE_UNIT: u32 = 2;
+
+// Global quality knob applied uniformly to every live texture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureQuality {
+    // Bilinear filtering, no anisotropy
+    Low,
+    // Trilinear filtering, 4x anisotropy
+    #[default]
+    Medium,
+    // Trilinear filtering, 16x anisotropy
+    High,
+}
+
+impl TextureQuality {
+    fn min_filter(self) -> gl::types::GLenum {
+        match self {
+            TextureQuality::Low => gl::LINEAR_MIPMAP_NEAREST,
+            TextureQuality::Medium | TextureQuality::High => gl::LINEAR_MIPMAP_LINEAR,
+        }
+    }
+
+    fn requested_anisotropy(self) -> f32 {
+        match self {
+            TextureQuality::Low => 1.0,
+            TextureQuality::Medium => 4.0,
+            TextureQuality::High => 16.0,
+        }
+    }
+
+    // Anisotropy level actually applied, clamped to what the hardware supports
+    pub fn anisotropy(self) -> f32 {
+        self.requested_anisotropy().min(max_anisotropy())
+    }
+}
+
+// GL objects belong to the context's thread, so the registry of live textures
+// and the current quality setting are tracked per thread.
+thread_local! {
+    static QUALITY: Cell<TextureQuality> = const { Cell::new(TextureQuality::Medium) };
+    static MAX_ANISOTROPY: Cell<Option<f32>> = const { Cell::new(None) };
+    static LIVE_TEXTURES: RefCell<Vec<u32>> = const { RefCell::new(Vec::new()) };
+}
+
+// Hardware anisotropy limit, queried once and cached. Returns 1.0 when the
+// anisotropic filtering extension isn't available.
+pub fn max_anisotropy() -> f32 {
+    MAX_ANISOTROPY.with(|cached| {
+        if let Some(max) = cached.get() {
+            return max;
+        }
+        let max = unsafe { query_max_anisotropy() };
+        cached.set(Some(max));
+        max
+    })
+}
+
+unsafe fn query_max_anisotropy() -> f32 {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        let supported = (0..count.max(0) as u32).any(|i| {
+            let name = gl::GetStringi(gl::EXTENSIONS, i);
+            !name.is_null()
+                && matches!(
+                    CStr::from_ptr(name as *const _).to_bytes(),
+                    b"GL_EXT_texture_filter_anisotropic" | b"GL_ARB_texture_filter_anisotropic"
+                )
+        });
+        if !supported {
+            return 1.0;
+        }
+
+        let mut max = 1.0;
+        gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max);
+        max.max(1.0)
+    }
+}
+
+pub(crate) fn texture_quality() -> TextureQuality {
+    QUALITY.with(Cell::get)
+}
+
+// Stores the new quality and re-applies it to every texture created so far
+pub(crate) fn set_texture_quality(quality: TextureQuality) {
+    QUALITY.with(|q| q.set(quality));
+    LIVE_TEXTURES.with(|live| {
+        for &id in live.borrow().iter() {
+            unsafe { apply_quality(id, quality) };
+        }
+    });
+}
+
+unsafe fn apply_quality(id: u32, quality: TextureQuality) {
+    unsafe {
+        gl::BindTexture(gl::TEXTURE_2D, id);
+        gl::TexParameteri(
+            gl::TEXTURE_2D,
+            gl::TEXTURE_MIN_FILTER,
+            quality.min_filter() as i32,
+        );
+        gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+        if max_anisotropy() > 1.0 {
+            gl::TexParameterf(gl::TEXTURE_2D, TEXTURE_MAX_ANISOTROPY, quality.anisotropy());
+        }
+        gl::BindTexture(gl::TEXTURE_2D, 0);
+    }
+}
+
+type DecodeJob = Box<dyn FnOnce() + Send>;
+
+// Single background thread that decodes images in request order, started on
+// first use. Uploads still happen on the GL thread in TextureHandle::poll.
+// None if the thread couldn't be started.
+fn decode_worker() -> Option<&'static Sender<DecodeJob>> {
+    static WORKER: OnceLock<Option<Sender<DecodeJob>>> = OnceLock::new();
+    WORKER
+        .get_or_init(|| {
+            let (sender, jobs) = mpsc::channel::<DecodeJob>();
+            std::thread::Builder::new()
+                .name("texture-decode".to_string())
+                .spawn(move || {
+                    for job in jobs {
+                        job();
+                    }
+                })
+                .inspect_err(|err| eprintln!("Couldn't start the texture decode thread: {err}"))
+                .ok()?;
+            Some(sender)
+        })
+        .as_ref()
+}
+
+#[derive(Debug)]
+pub enum TextureError {
+    Io(io::Error),
+    // The file was read but isn't an image the built-in decoder understands
+    Decode(String),
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::Io(err) => write!(f, "{}", err),
+            TextureError::Decode(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Error for TextureError {}
+
+impl From<io::Error> for TextureError {
+    fn from(err: io::Error) -> Self {
+        TextureError::Io(err)
+    }
+}
+
+// Equal when they're the same GL texture
+#[derive(Debug, PartialEq, Eq)]
+pub struct Texture {
+    id: u32,
+    width: u32,
+    height: u32,
+}
+
+impl Texture {
+    // Uploads tightly packed RGBA8 pixels (bottom row first, as GL expects)
+    // and generates mipmaps. Filtering follows the global texture quality.
+    pub fn from_rgba(width: u32, height: u32, pixels: &[u8]) -> Texture {
+        let mut id = 0;
+        unsafe {
+            gl::GenTextures(1, &mut id);
+        }
+        LIVE_TEXTURES.with(|live| live.borrow_mut().push(id));
+
+        let mut texture = Texture { id, width, height };
+        texture.upload(width, height, pixels);
+        texture
+    }
+
+    // Reads and uploads an image file right away, decoded by the built-in
+    // decoder (PNG, JPEG, PPM/PGM or TGA). Other formats can be decoded
+    // elsewhere and passed to from_rgba.
+    pub fn load(path: impl AsRef<Path>) -> Result<Texture, TextureError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let image = image::decode_bytes(&data)
+            .map_err(|err| TextureError::Decode(format!("{}: {}", path.display(), err)))?;
+        Ok(Texture::from_rgba(image.width, image.height, &image.pixels))
+    }
+
+    // Reads `path` with the built-in decoder (PNG, JPEG, PPM/PGM or TGA) on
+    // a background thread. See from_file_async_with.
+    pub fn from_file_async(path: impl AsRef<Path>) -> TextureHandle {
+        Self::from_file_async_with(path, image::decode)
+    }
+
+    // Returns at once with a 1x1 gray placeholder while `decoder` runs on the
+    // texture worker thread. Call TextureHandle::poll each frame to upload the
+    // image once it arrives; the texture keeps its GL id, so it can be bound
+    // before then. If decoding fails the placeholder turns magenta. Without a
+    // worker thread the image is decoded on the calling thread instead.
+    pub fn from_file_async_with(path: impl AsRef<Path>, decoder: ImageDecoder) -> TextureHandle {
+        let path = path.as_ref().to_path_buf();
+        let (sender, result) = mpsc::channel();
+        let job_path = path.clone();
+        let job: DecodeJob = Box::new(move || {
+            let image = decoder(&job_path).map_err(|err| err.to_string());
+            // The handle may have been dropped in the meantime
+            let _ = sender.send(image);
+        });
+        let unsent = match decode_worker() {
+            Some(worker) => worker.send(job).err().map(|mpsc::SendError(job)| job),
+            None => Some(job),
+        };
+        if let Some(job) = unsent {
+            // poll still uploads the result as usual
+            job();
+        }
+        TextureHandle {
+            texture: Texture::from_rgba(1, 1, &[128, 128, 128, 255]),
+            path,
+            pending: Some(result),
+        }
+    }
+
+    // Replaces the texture's contents, keeping its id
+    fn upload(&mut self, width: u32, height: u32, pixels: &[u8]) {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * 4,
+            "pixel buffer doesn't match {width}x{height} RGBA"
+        );
+        self.width = width;
+        self.height = height;
+
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::RGBA8 as i32,
+                width as i32,
+                height as i32,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                pixels.as_ptr() as *const _,
+            );
+            gl::GenerateMipmap(gl::TEXTURE_2D);
+            apply_quality(self.id, texture_quality());
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn bind(&self, unit: u32) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_2D, self.id);
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        LIVE_TEXTURES.with(|live| live.borrow_mut().retain(|&id| id != self.id));
+        unsafe {
+            gl::DeleteTextures(1, &self.id);
+        }
+    }
+}
+
+// Texture whose image is still being decoded in the background
+pub struct TextureHandle {
+    texture: Texture,
+    path: PathBuf,
+    pending: Option<Receiver<Result<Image, String>>>,
+}
+
+impl TextureHandle {
+    // The placeholder until the image has been uploaded, then the real texture
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // False while decoding; true once uploaded or failed
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    // Uploads the decoded image if it has arrived. Must be called on the thread
+    // that owns the GL context; returns true on the call that uploads it.
+    pub fn poll(&mut self) -> bool {
+        let Some(result) = &self.pending else {
+            return false;
+        };
+        let image = match result.try_recv() {
+            Ok(image) => image,
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => Err("decode thread stopped".to_string()),
+        };
+        self.pending = None;
+        match image {
+            Ok(image) => {
+                self.texture
+                    .upload(image.width, image.height, &image.pixels);
+                true
+            }
+            Err(err) => {
+                eprintln!("Failed to load texture {}: {}", self.path.display(), err);
+                self.texture.upload(1, 1, &[255, 0, 255, 255]);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use glm::{Mat3, Mat4, Quat, Vec3};
+
+// Position, orientation and size of an object, composed into a model matrix
+// as translation * rotation * scale: scaled about the object's origin first,
+// then rotated, then moved into place
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub position: Vec3,
+    // Unit quaternion
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform {
+            position: Vec3::zeros(),
+            rotation: glm::quat_identity(),
+            scale: Vec3::repeat(1.0),
+        }
+    }
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn to_matrix(&self) -> Mat4 {
+        glm::translation(&self.position)
+            * glm::quat_to_mat4(&self.rotation)
+            * glm::scaling(&self.scale)
+    }
+
+    // Moves by `offset` in world space
+    pub fn translate(&mut self, offset: &Vec3) {
+        self.position += offset;
+    }
+
+    // Turns by `angle` radians about a world-space axis through the object's
+    // position, after the current rotation. A zero axis leaves it unchanged.
+    pub fn rotate_axis_angle(&mut self, axis: &Vec3, angle: f32) {
+        if axis.norm() < 1e-6 {
+            return;
+        }
+        let turn = glm::quat_angle_axis(angle, &axis.normalize());
+        self.rotation = glm::quat_normalize(&(turn * self.rotation));
+    }
+
+    pub fn set_uniform_scale(&mut self, scale: f32) {
+        self.scale = Vec3::repeat(scale);
+    }
+}
+
+// Transforms normals for `model`: the inverse transpose of its 3x3 part,
+// which keeps them perpendicular to surfaces under non-uniform scale where
+// the model matrix itself would tilt them. A degenerate (zero-scaled) model
+// gets its own 3x3 part, as there is no surface left to light correctly.
+pub fn normal_matrix(model: &Mat4) -> Mat3 {
+    let linear = glm::mat4_to_mat3(model);
+    linear
+        .try_inverse()
+        .map_or(linear, |inverse| inverse.transpose())
+}
+
+// World-space position of the camera a view matrix looks from
+pub(crate) fn view_position(view: &Mat4) -> Vec3 {
+    view.try_inverse()
+        .map_or(Vec3::zeros(), |inverse| inverse.column(3).xyz())
+}
+
+impl From<Transform> for Mat4 {
+    fn from(transform: Transform) -> Self {
+        transform.to_matrix()
+    }
+}
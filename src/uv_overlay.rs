@@ -0,0 +1,134 @@
+use crate::lines::LineRenderer;
+use crate::mesh::FLOATS_PER_VERTEX;
+use crate::render_target::FrameTarget;
+use glm::{Mat4, Vec2, Vec3, vec2, vec3};
+
+// CPU copy of a mesh's triangles in UV space, kept for the inspection overlay
+pub(crate) struct UvLayout {
+    triangles: Vec<[Vec2; 3]>,
+    // log2 of each triangle's UV-area / surface-area ratio relative to the
+    // mesh average: 0 is average texel density, +1 twice as dense, -1 half
+    distortion: Vec<f32>,
+}
+
+impl UvLayout {
+    pub(crate) fn new(vertices: &[f32], uvs: &[f32]) -> Self {
+        let positions: Vec<Vec3> = vertices
+            .chunks_exact(FLOATS_PER_VERTEX)
+            .map(|v| vec3(v[0], v[1], v[2]))
+            .collect();
+        let coords: Vec<Vec2> = uvs.chunks_exact(2).map(|uv| vec2(uv[0], uv[1])).collect();
+
+        let mut triangles = Vec::with_capacity(coords.len() / 3);
+        let mut ratios = Vec::with_capacity(coords.len() / 3);
+        for (p, uv) in positions.chunks_exact(3).zip(coords.chunks_exact(3)) {
+            let world_area = glm::cross(&(p[1] - p[0]), &(p[2] - p[0])).norm() * 0.5;
+            let e1 = uv[1] - uv[0];
+            let e2 = uv[2] - uv[0];
+            let uv_area = (e1.x * e2.y - e1.y * e2.x).abs() * 0.5;
+            triangles.push([uv[0], uv[1], uv[2]]);
+            ratios.push(if world_area > 0.0 {
+                uv_area / world_area
+            } else {
+                0.0
+            });
+        }
+
+        // Normalize against the mean so the overlay shows relative stretching
+        let valid: Vec<f32> = ratios.iter().copied().filter(|r| *r > 0.0).collect();
+        let mean = valid.iter().sum::<f32>() / valid.len().max(1) as f32;
+        let distortion = ratios
+            .iter()
+            .map(|r| {
+                if *r > 0.0 && mean > 0.0 {
+                    (r / mean).log2()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        UvLayout {
+            triangles,
+            distortion,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvOverlayMode {
+    // Triangle edges only; overlapping islands show as overdrawn lines
+    Wireframe,
+    // Filled triangles colored by texel density: blue is compressed, green
+    // average, red stretched (saturating at 4x either way)
+    Distortion,
+}
+
+// Draws the layout into a square viewport in the bottom-left corner of
+// `target`. The caller restores the target's viewport afterwards.
+pub(crate) fn render(
+    layout: &UvLayout,
+    mode: UvOverlayMode,
+    lines: &mut LineRenderer,
+    target: &FrameTarget,
+) {
+    let size = (target.width.min(target.height) / 3).max(64);
+    let (x, y) = (target.x + 10, target.y + 10);
+
+    unsafe {
+        gl::Enable(gl::SCISSOR_TEST);
+        gl::Scissor(x, y, size, size);
+        gl::Viewport(x, y, size, size);
+        gl::ClearColor(0.05, 0.05, 0.05, 1.0);
+        gl::Clear(gl::COLOR_BUFFER_BIT);
+        gl::Disable(gl::SCISSOR_TEST);
+        gl::Disable(gl::DEPTH_TEST);
+    }
+
+    // Map [0, 1] UV space onto the viewport with a little padding
+    let projection = glm::ortho(-0.05, 1.05, -0.05, 1.05, -1.0, 1.0);
+    let view = Mat4::identity();
+    let at = |uv: &Vec2| vec3(uv.x, uv.y, 0.0);
+
+    if mode == UvOverlayMode::Distortion {
+        for (tri, d) in layout.triangles.iter().zip(&layout.distortion) {
+            let color = distortion_color(*d);
+            lines.triangle(&at(&tri[0]), &at(&tri[1]), &at(&tri[2]), &color);
+        }
+    }
+
+    let edge = vec3(0.9, 0.9, 0.9);
+    for tri in &layout.triangles {
+        lines.line(&at(&tri[0]), &at(&tri[1]), &edge);
+        lines.line(&at(&tri[1]), &at(&tri[2]), &edge);
+        lines.line(&at(&tri[2]), &at(&tri[0]), &edge);
+    }
+
+    // Unit square border
+    let frame = vec3(0.4, 0.4, 0.4);
+    let corners = [
+        vec2(0.0, 0.0),
+        vec2(1.0, 0.0),
+        vec2(1.0, 1.0),
+        vec2(0.0, 1.0),
+    ];
+    for i in 0..4 {
+        lines.line(&at(&corners[i]), &at(&corners[(i + 1) % 4]), &frame);
+    }
+
+    lines.flush(&view, &projection);
+
+    unsafe {
+        gl::Enable(gl::DEPTH_TEST);
+    }
+}
+
+fn distortion_color(d: f32) -> Vec3 {
+    let t = (d / 2.0).clamp(-1.0, 1.0);
+    let green = vec3(0.1, 0.7, 0.2);
+    if t < 0.0 {
+        glm::lerp(&green, &vec3(0.1, 0.3, 1.0), -t)
+    } else {
+        glm::lerp(&green, &vec3(1.0, 0.15, 0.1), t)
+    }
+}
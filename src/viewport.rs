@@ -0,0 +1,89 @@
+use crate::render_target::FrameTarget;
+
+// How the window is divided into panes, each drawn with its own camera
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ViewportLayout {
+    #[default]
+    Single,
+    // Left and right halves
+    SideBySide,
+    // Top and bottom halves
+    Stacked,
+    // 2x2 grid: top-left, top-right, bottom-left, bottom-right
+    Quad,
+}
+
+impl ViewportLayout {
+    pub fn pane_count(self) -> usize {
+        match self {
+            ViewportLayout::Single => 1,
+            ViewportLayout::SideBySide | ViewportLayout::Stacked => 2,
+            ViewportLayout::Quad => 4,
+        }
+    }
+
+    pub(crate) fn panes(self) -> Vec<Rect> {
+        let pane = |x, y, width, height| Rect {
+            x,
+            y,
+            width,
+            height,
+        };
+        match self {
+            ViewportLayout::Single => vec![pane(0.0, 0.0, 1.0, 1.0)],
+            ViewportLayout::SideBySide => {
+                vec![pane(0.0, 0.0, 0.5, 1.0), pane(0.5, 0.0, 0.5, 1.0)]
+            }
+            ViewportLayout::Stacked => vec![pane(0.0, 0.5, 1.0, 0.5), pane(0.0, 0.0, 1.0, 0.5)],
+            ViewportLayout::Quad => vec![
+                pane(0.0, 0.5, 0.5, 0.5),
+                pane(0.5, 0.5, 0.5, 0.5),
+                pane(0.0, 0.0, 0.5, 0.5),
+                pane(0.5, 0.0, 0.5, 0.5),
+            ],
+        }
+    }
+}
+
+// Rectangle of the window as fractions of its size, with the origin bottom-left
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Rect {
+    pub const FULL: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0,
+    };
+
+    // `point` (fractions of the window, bottom-left origin) relative to this
+    // pane, or None if it lies outside
+    pub(crate) fn local(&self, point: (f32, f32)) -> Option<(f32, f32)> {
+        let u = (point.0 - self.x) / self.width;
+        let v = (point.1 - self.y) / self.height;
+        ((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)).then_some((u, v))
+    }
+
+    // The part of `target` covered by this rectangle, with edges rounded so
+    // neighboring panes share them exactly
+    pub(crate) fn of(&self, target: &FrameTarget) -> FrameTarget {
+        let edge = |origin: i32, size: i32, f: f32| origin + (size as f32 * f).round() as i32;
+        let x0 = edge(target.x, target.width, self.x);
+        let x1 = edge(target.x, target.width, self.x + self.width);
+        let y0 = edge(target.y, target.height, self.y);
+        let y1 = edge(target.y, target.height, self.y + self.height);
+        FrameTarget {
+            x: x0,
+            y: y0,
+            width: x1 - x0,
+            height: y1 - y0,
+            ..*target
+        }
+    }
+}
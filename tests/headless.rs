@@ -0,0 +1,22 @@
+// Needs a GL context, so a display (real or virtual, e.g. Xvfb):
+// `cargo test -- --ignored`
+use x3d::X3D;
+
+#[test]
+#[ignore]
+fn renders_the_default_cube() {
+    let (width, height) = (64, 64);
+    let mut x3d = X3D::new_headless(width, height).expect("headless engine");
+    x3d.render_once();
+    let pixels = x3d.read_pixels();
+    assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+    let center = ((height / 2 * width + width / 2) * 4) as usize;
+    let pixel = &pixels[center..center + 3];
+    let background = x3d.clear_color().map(|c| (c * 255.0).round() as u8);
+    // Allow for rounding in the framebuffer
+    assert!(
+        pixel.iter().zip(background).any(|(a, b)| a.abs_diff(b) > 2),
+        "the center pixel {pixel:?} is the clear color"
+    );
+}